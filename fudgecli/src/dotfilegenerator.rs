@@ -396,21 +396,120 @@ fn write_statement(
 
             format!("if | {{ {} }}", branches)
         }
+        asg::Statement::Match(n) => {
+            let local_expr_from_id = "e0";
+            let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
+            let expr_to_id = write_expression(instance, asg, &n.expr);
+
+            // Expression edge
+            instance
+                .writer
+                .queueline(format!("{} -> {}", expr_from_id, expr_to_id));
+
+            let mut arms = String::new();
+            let branches = n
+                .arms
+                .iter()
+                .map(|arm| (arm_to_string(&arm.pattern, &arm.guard), &arm.body))
+                .chain(n.elsebranch.iter().map(|b| (format!("else"), b)));
+            for (count, (label, branch)) in branches.enumerate() {
+                let local_stmnt_from_id = format!("b{}s0", count);
+                let stmnt_from_id = format!("{}:{}", node_id, local_stmnt_from_id);
+                let stmnt_to_id = format!("{}sb{}", node_id, count);
+
+                if count > 0 {
+                    arms.push_str(" |");
+                }
+                arms.push_str(format!("{} |<{}> then", label, local_stmnt_from_id).as_str());
+
+                // Body
+                if let Some(body) = &branch.body {
+                    write_statementbody(instance, asg, &body, stmnt_to_id.clone());
+
+                    // Edge
+                    instance
+                        .writer
+                        .queueline(format!("{} -> {}", stmnt_from_id, stmnt_to_id));
+                }
+            }
+
+            format!("match |<{}> expr | {{ {} }}", local_expr_from_id, arms)
+        }
+        asg::Statement::While(n) => {
+            let local_expr_from_id = "e0";
+            let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
+            let expr_to_id = write_expression(instance, asg, &n.condition);
+
+            let local_stmnt_from_id = "s0";
+            let stmnt_from_id = format!("{}:{}", node_id, local_stmnt_from_id);
+            let stmnt_to_id = format!("{}sb", node_id);
+
+            // Expression edge
+            instance
+                .writer
+                .queueline(format!("{} -> {}", expr_from_id, expr_to_id));
+
+            // Body
+            if let Some(body) = &n.body.body {
+                write_statementbody(instance, asg, &body, stmnt_to_id.clone());
+
+                // Edge
+                instance
+                    .writer
+                    .queueline(format!("{} -> {}", stmnt_from_id, stmnt_to_id));
+            }
+
+            format!(
+                "while |<{}> expr |<{}> do",
+                local_expr_from_id, local_stmnt_from_id
+            )
+        }
+        asg::Statement::For(n) => {
+            let local_expr_from_id = "e0";
+            let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
+            let expr_to_id = write_expression(instance, asg, &n.iterable);
+
+            let local_stmnt_from_id = "s0";
+            let stmnt_from_id = format!("{}:{}", node_id, local_stmnt_from_id);
+            let stmnt_to_id = format!("{}sb", node_id);
+
+            // Expression edge
+            instance
+                .writer
+                .queueline(format!("{} -> {}", expr_from_id, expr_to_id));
+
+            // Body
+            if let Some(body) = &n.body.body {
+                write_statementbody(instance, asg, &body, stmnt_to_id.clone());
+
+                // Edge
+                instance
+                    .writer
+                    .queueline(format!("{} -> {}", stmnt_from_id, stmnt_to_id));
+            }
+
+            format!(
+                "for {} |<{}> in |<{}> do",
+                n.symbol, local_expr_from_id, local_stmnt_from_id
+            )
+        }
+        asg::Statement::Break => format!("break"),
+        asg::Statement::Continue => format!("continue"),
         asg::Statement::Return(n) => {
-            if let Some(expr) = n.expr {
-                let local_expr_from_id = "e0";
+            let mut label = format!("return");
+            for (i, expr) in n.exprs.iter().enumerate() {
+                let local_expr_from_id = format!("e{}", i);
                 let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
-                let expr_to_id = write_expression(instance, asg, &expr);
+                let expr_to_id = write_expression(instance, asg, expr);
 
                 // Edges
                 instance
                     .writer
                     .queueline(format!("{} -> {}", expr_from_id, expr_to_id));
 
-                format!("return | <{}> expr", local_expr_from_id)
-            } else {
-                format!("return")
+                label += format!(" | <{}> expr", local_expr_from_id).as_str();
             }
+            label
         }
         asg::Statement::Assign(n) => {
             let local_lhs_from_id = "e0";
@@ -446,6 +545,27 @@ fn write_statement(
 
             format!("initalize | {} | <{}> expr", n.symbol, local_expr_from_id)
         }
+        asg::Statement::Destructure(n) => {
+            let local_expr_from_id = "e0";
+            let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
+            let expr_to_id = write_expression(instance, asg, &n.expr);
+
+            // Edges
+            instance
+                .writer
+                .queueline(format!("{} -> {}", expr_from_id, expr_to_id));
+
+            let symbols: Vec<&str> = n
+                .symbols
+                .iter()
+                .map(|s| s.as_ref().map_or("_", |s| s.as_str()))
+                .collect();
+            format!(
+                "destructure | {} | <{}> expr",
+                symbols.join(", "),
+                local_expr_from_id
+            )
+        }
         asg::Statement::ExpressionWrapper(n) => {
             let local_expr_from_id = "e0";
             let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
@@ -556,6 +676,44 @@ fn write_structfield(
     node_id
 }
 
+fn pattern_to_string(pattern: &asg::misc::Pattern) -> String {
+    match pattern {
+        asg::misc::Pattern::Variant(n) if n.bindings.is_empty() => n.name.clone(),
+        asg::misc::Pattern::Variant(n) => {
+            let bindings: Vec<_> = n
+                .bindings
+                .iter()
+                .map(|b| b.as_deref().unwrap_or("_"))
+                .collect();
+            format!("{}({})", n.name, bindings.join(", "))
+        }
+        asg::misc::Pattern::Wildcard => format!("_"),
+        asg::misc::Pattern::Binding(n) => n.clone(),
+        // Expressions of patterns are not written as nodes
+        asg::misc::Pattern::Literal(_) => format!("literal"),
+        asg::misc::Pattern::Range { .. } => format!("range"),
+        asg::misc::Pattern::Struct(fields) => {
+            let fields: Vec<_> = fields
+                .iter()
+                .map(|(name, n)| format!("{} = {}", name, pattern_to_string(n)))
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+        asg::misc::Pattern::Tuple(elements) => {
+            let elements: Vec<_> = elements.iter().map(pattern_to_string).collect();
+            format!("({})", elements.join(", "))
+        }
+    }
+}
+
+fn arm_to_string(
+    pattern: &asg::misc::Pattern,
+    guard: &Option<asg::scope::ExpressionKey>,
+) -> String {
+    let guard = if guard.is_some() { " if guard" } else { "" };
+    escape_string(&format!("case {}{}", pattern_to_string(pattern), guard))
+}
+
 fn write_simple_edge(instance: &mut Instance, from_id: &String, to_id: &String) {
     instance
         .writer
@@ -637,9 +795,21 @@ fn write_expression(
             asg::expressions::Literal::BoolLiteral(n) => {
                 quick_node!(format!("Bool Literal({})", n.value))
             }
+            asg::expressions::Literal::NullLiteral(_) => {
+                quick_node!(format!("Null Literal"))
+            }
             asg::expressions::Literal::IntegerLiteral(n) => {
                 quick_node!(format!("Integer Literal({})", n.data))
             } // TODO: Cast data to correct integer
+            asg::expressions::Literal::FloatLiteral(n) => {
+                quick_node!(format!("Float Literal({})", n.value))
+            }
+            asg::expressions::Literal::CharLiteral(n) => {
+                quick_node!(format!(
+                    "Char Literal({})",
+                    escape_string(&n.value.to_string())
+                ))
+            }
             asg::expressions::Literal::StructLiteral(n) => {
                 let mut count = 0;
                 for field in &n.fields {
@@ -656,6 +826,58 @@ fn write_expression(
 
                 quick_node!(format!("Struct Literal"))
             }
+            asg::expressions::Literal::EnumLiteral(n) => {
+                let mut count = 0;
+                for variant in &n.variants {
+                    for field in &variant.fields {
+                        let field_id = write_structfield(instance, asg, &node_id, field, count);
+
+                        // Edges
+                        instance.writer.queueline(format!(
+                            "{} -> {} [label=\"{} field {}\"]",
+                            node_id, field_id, variant.name, count
+                        ));
+
+                        count += 1;
+                    }
+                }
+
+                let variants: Vec<_> = n.variants.iter().map(|v| v.name.as_str()).collect();
+                quick_node!(format!("Enum Literal | {}", variants.join(" | ")))
+            }
+            asg::expressions::Literal::ArrayLiteral(n) => {
+                let mut count = 0;
+                for element in &n.elements {
+                    let element_id = write_expression(instance, asg, element);
+
+                    // Edges
+                    instance.writer.queueline(format!(
+                        "{} -> {} [label=\"element {}\"]",
+                        node_id, element_id, count
+                    ));
+
+                    count += 1;
+                }
+
+                if n.dynamic {
+                    quick_node!(format!("Dynamic Array Literal"))
+                } else {
+                    quick_node!(format!("Array Literal"))
+                }
+            }
+            asg::expressions::Literal::TupleLiteral(n) => {
+                for (count, element) in n.elements.iter().enumerate() {
+                    let element_id = write_expression(instance, asg, element);
+
+                    // Edges
+                    instance.writer.queueline(format!(
+                        "{} -> {} [label=\"element {}\"]",
+                        node_id, element_id, count
+                    ));
+                }
+
+                quick_node!(format!("Tuple Literal"))
+            }
             asg::expressions::Literal::FunctionLiteral(n) => {
                 let function = instance.state.get_function(&n.functionkey, asg);
                 let name = format!("Function: {}", function.name);
@@ -689,6 +911,7 @@ fn write_expression(
             quick_node!(format!("Builtin({:?})", n.function))
         }
         asg::ExpressionObject::PrimitiveType(n) => quick_node!(format!("Primitive({:?})", n.ptype)),
+        asg::ExpressionObject::ResultType(_) => quick_node!(format!("Result Type")),
         asg::ExpressionObject::SymbolReference(n) => {
             let scope = instance.state.get_current_scope(asg);
 
@@ -777,6 +1000,67 @@ fn write_expression(
 
             quick_node!(format!("if | {{ {} }}", branches))
         }
+        asg::ExpressionObject::Match(n) => {
+            let local_expr_from_id = "e0";
+            let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
+            let expr_to_id = write_expression(instance, asg, &n.expr);
+
+            // Edges
+            instance
+                .writer
+                .queueline(format!("{} -> {}", expr_from_id, expr_to_id));
+
+            let mut arms = String::new();
+            for (count, arm) in n.arms.iter().enumerate() {
+                let local_arm_from_id = format!("b{}e0", count);
+                let arm_from_id = format!("{}:{}", node_id, local_arm_from_id);
+
+                // Arm expressions live in the scope of their arm
+                let mut arm_to_id = String::new();
+                instance.with_scope(&arm.scope, |instance| {
+                    arm_to_id = write_expression(instance, asg, &arm.expr);
+                });
+
+                if count > 0 {
+                    arms.push_str(" |");
+                }
+                arms.push_str(
+                    format!(
+                        "{} |<{}> expr",
+                        arm_to_string(&arm.pattern, &arm.guard),
+                        local_arm_from_id
+                    )
+                    .as_str(),
+                );
+
+                // Edges
+                instance
+                    .writer
+                    .queueline(format!("{} -> {}", arm_from_id, arm_to_id));
+            }
+
+            // Else
+            if let Some(elsebranch) = n.elsebranch {
+                let local_else_from_id = format!("b{}e0", n.arms.len());
+                let else_from_id = format!("{}:{}", node_id, local_else_from_id);
+                let else_to_id = write_expression(instance, asg, &elsebranch);
+
+                if !n.arms.is_empty() {
+                    arms.push_str(" |");
+                }
+                arms.push_str(format!("else |<{}> expr", local_else_from_id).as_str());
+
+                // Edges
+                instance
+                    .writer
+                    .queueline(format!("{} -> {}", else_from_id, else_to_id));
+            }
+
+            quick_node!(format!(
+                "match |<{}> expr | {{ {} }}",
+                local_expr_from_id, arms
+            ))
+        }
         asg::ExpressionObject::Call(n) => {
             let mut label = String::new();
 
@@ -813,6 +1097,22 @@ fn write_expression(
                 count += 1;
             }
 
+            for arg in &n.namedargs {
+                let local_expr_from_id = format!("a{}", count);
+                let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
+
+                label.push_str(format!("|<{}> {}", local_expr_from_id, arg.symbol).as_str());
+
+                let expr_id = write_expression(instance, asg, &arg.expr);
+
+                // Edges
+                instance
+                    .writer
+                    .queueline(format!("{} -> {}", expr_from_id, expr_id));
+
+                count += 1;
+            }
+
             quick_node!(label)
         }
         asg::ExpressionObject::BinOp(n) => {
@@ -836,6 +1136,39 @@ fn write_expression(
                 local_lhs_from_id, n.op, local_rhs_from_id
             ))
         }
+        asg::ExpressionObject::UnaryOp(n) => {
+            let local_expr_from_id = "e0";
+            let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
+            let expr_to_id = write_expression(instance, asg, &n.expr);
+
+            // Edges
+            instance
+                .writer
+                .queueline(format!("{} -> {}", expr_from_id, expr_to_id));
+
+            quick_node!(format!("Unop {:?} |<{}> expr", n.op, local_expr_from_id))
+        }
+        asg::ExpressionObject::Range(n) => {
+            let local_start_from_id = "e0";
+            let start_from_id = format!("{}:{}", node_id, local_start_from_id);
+            let local_end_from_id = "e1";
+            let end_from_id = format!("{}:{}", node_id, local_end_from_id);
+            let start_to_id = write_expression(instance, asg, &n.start);
+            let end_to_id = write_expression(instance, asg, &n.end);
+
+            // Edges
+            instance
+                .writer
+                .queueline(format!("{} -> {}", start_from_id, start_to_id));
+            instance
+                .writer
+                .queueline(format!("{} -> {}", end_from_id, end_to_id));
+
+            quick_node!(format!(
+                "Range |<{}> start |<{}> end",
+                local_start_from_id, local_end_from_id
+            ))
+        }
         asg::ExpressionObject::Subscript(n) => {
             let local_expr_from_id = format!("e0");
             let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
@@ -852,6 +1185,181 @@ fn write_expression(
                 local_expr_from_id, n.symbol
             ))
         }
+        asg::ExpressionObject::StructConstruction(n) => {
+            let local_type_from_id = format!("t");
+            let type_from_id = format!("{}:{}", node_id, local_type_from_id);
+
+            let type_id = write_expression(instance, asg, &n.typeexpr);
+
+            // Edges
+            instance
+                .writer
+                .queueline(format!("{} -> {}", type_from_id, type_id));
+
+            let mut label = format!("Struct Construction |<{}> type", local_type_from_id);
+            for (count, (name, value)) in n.fields.iter().enumerate() {
+                let local_field_from_id = format!("f{}", count);
+                let field_from_id = format!("{}:{}", node_id, local_field_from_id);
+                let field_id = write_expression(instance, asg, value);
+
+                // Edges
+                instance
+                    .writer
+                    .queueline(format!("{} -> {}", field_from_id, field_id));
+
+                label.push_str(&format!(" |<{}> {}", local_field_from_id, name));
+            }
+
+            quick_node!(label)
+        }
+        asg::ExpressionObject::Index(n) => {
+            let local_expr_from_id = "e0";
+            let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
+            let local_index_from_id = "e1";
+            let index_from_id = format!("{}:{}", node_id, local_index_from_id);
+            let expr_to_id = write_expression(instance, asg, &n.expr);
+            let index_to_id = write_expression(instance, asg, &n.index);
+
+            // Edges
+            instance
+                .writer
+                .queueline(format!("{} -> {}", expr_from_id, expr_to_id));
+            instance
+                .writer
+                .queueline(format!("{} -> {}", index_from_id, index_to_id));
+
+            quick_node!(format!(
+                "Index |<{}> expr |<{}> index",
+                local_expr_from_id, local_index_from_id
+            ))
+        }
+        asg::ExpressionObject::DynamicArrayType(n) => {
+            let local_element_from_id = "e0";
+            let element_from_id = format!("{}:{}", node_id, local_element_from_id);
+            let element_to_id = write_expression(instance, asg, &n.element);
+
+            // Edges
+            instance
+                .writer
+                .queueline(format!("{} -> {}", element_from_id, element_to_id));
+
+            quick_node!(format!(
+                "Dynamic Array Type |<{}> element",
+                local_element_from_id
+            ))
+        }
+        asg::ExpressionObject::Slice(n) => {
+            let local_expr_from_id = "e0";
+            let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
+            let local_start_from_id = "e1";
+            let start_from_id = format!("{}:{}", node_id, local_start_from_id);
+            let local_end_from_id = "e2";
+            let end_from_id = format!("{}:{}", node_id, local_end_from_id);
+            let expr_to_id = write_expression(instance, asg, &n.expr);
+            let start_to_id = write_expression(instance, asg, &n.start);
+            let end_to_id = write_expression(instance, asg, &n.end);
+
+            // Edges
+            instance
+                .writer
+                .queueline(format!("{} -> {}", expr_from_id, expr_to_id));
+            instance
+                .writer
+                .queueline(format!("{} -> {}", start_from_id, start_to_id));
+            instance
+                .writer
+                .queueline(format!("{} -> {}", end_from_id, end_to_id));
+
+            quick_node!(format!(
+                "Slice |<{}> expr |<{}> start |<{}> end",
+                local_expr_from_id, local_start_from_id, local_end_from_id
+            ))
+        }
+        asg::ExpressionObject::SliceType(n) => {
+            let local_element_from_id = "e0";
+            let element_from_id = format!("{}:{}", node_id, local_element_from_id);
+            let element_to_id = write_expression(instance, asg, &n.element);
+
+            // Edges
+            instance
+                .writer
+                .queueline(format!("{} -> {}", element_from_id, element_to_id));
+
+            quick_node!(format!("Slice Type |<{}> element", local_element_from_id))
+        }
+        asg::ExpressionObject::OptionalType(n) => {
+            let local_target_from_id = "e0";
+            let target_from_id = format!("{}:{}", node_id, local_target_from_id);
+            let target_to_id = write_expression(instance, asg, &n.target);
+
+            // Edges
+            instance
+                .writer
+                .queueline(format!("{} -> {}", target_from_id, target_to_id));
+
+            quick_node!(format!("Optional Type |<{}> target", local_target_from_id))
+        }
+        asg::ExpressionObject::Reference(n) => {
+            let local_expr_from_id = "e0";
+            let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
+            let expr_to_id = write_expression(instance, asg, &n.expr);
+
+            // Edges
+            instance
+                .writer
+                .queueline(format!("{} -> {}", expr_from_id, expr_to_id));
+
+            quick_node!(format!("Reference |<{}> expr", local_expr_from_id))
+        }
+        asg::ExpressionObject::Dereference(n) => {
+            let local_expr_from_id = "e0";
+            let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
+            let expr_to_id = write_expression(instance, asg, &n.expr);
+
+            // Edges
+            instance
+                .writer
+                .queueline(format!("{} -> {}", expr_from_id, expr_to_id));
+
+            quick_node!(format!("Dereference |<{}> expr", local_expr_from_id))
+        }
+        asg::ExpressionObject::Try(n) => {
+            let local_expr_from_id = "e0";
+            let expr_from_id = format!("{}:{}", node_id, local_expr_from_id);
+            let expr_to_id = write_expression(instance, asg, &n.expr);
+
+            // Edges
+            instance
+                .writer
+                .queueline(format!("{} -> {}", expr_from_id, expr_to_id));
+
+            quick_node!(format!("Try |<{}> expr", local_expr_from_id))
+        }
+        asg::ExpressionObject::FunctionType(n) => {
+            quick_node!(format!("Function Type"));
+
+            for (name, typeexpr) in &n.inparams {
+                let param_id = write_expression(instance, asg, typeexpr);
+
+                // Edges
+                instance.writer.queueline(format!(
+                    "{} -> {} [label=\"in {}\"]",
+                    node_id, param_id, name
+                ));
+            }
+            for (count, typeexpr) in n.outparams.iter().enumerate() {
+                let param_id = write_expression(instance, asg, typeexpr);
+
+                // Edges
+                instance.writer.queueline(format!(
+                    "{} -> {} [label=\"out {}\"]",
+                    node_id, param_id, count
+                ));
+            }
+        }
+        asg::ExpressionObject::TypeParameter(n) => {
+            quick_node!(format!("Type Parameter(${})", n.symbol))
+        }
     };
 
     node_id
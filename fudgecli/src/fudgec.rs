@@ -98,6 +98,17 @@ fn main() {
 
     let grapher_result = grapher::create_graph(&main_ast, &module_asts);
 
+    // TODO: Errors in modules are printed against the main source
+    let main_source = source::Source::from_file(&params.main);
+    output::print_errors(&grapher_result.errors, &main_source);
+    if grapher_result
+        .errors
+        .iter()
+        .any(|err| !error::is_warning(err.id))
+    {
+        return;
+    }
+
     // Generate dotfile for asg
     println!("{}", Color::Green.bold().paint("Generating dotfile..."));
     dotfilegenerator::generate_dotfile(
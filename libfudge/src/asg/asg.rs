@@ -1,6 +1,7 @@
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 
+use crate::error::{self, errors};
 use crate::typesystem::*;
 use crate::utils::objectstore::*;
 pub use crate::utils::*;
@@ -71,6 +72,24 @@ impl Asg {
             .functionstore
             .get(&functionref.function)
     }
+
+    // Finds the function literal a symbol is bound to by a def, if any
+    pub fn get_defined_function(
+        &self,
+        symref: &symboltable::ResolvedSymbolReference,
+    ) -> Option<FunctionRef> {
+        let scope = self.get_scope(&symref.scope);
+        let definition = scope.symboltable.definitions.get(&symref.symbol)?;
+        match &scope.expressions.get(definition).object {
+            ExpressionObject::Literal(expressions::Literal::FunctionLiteral(n)) => {
+                Some(FunctionRef {
+                    module: symref.scope.module,
+                    function: n.functionkey,
+                })
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -104,13 +123,39 @@ pub struct FunctionParameter {
     // This is a bit weird, but since all symbols are added to the
     //  function's scope for lookup, we just reference it here
     pub symref: symboltable::ResolvedSymbolReference,
+    // Default argument, evaluated in the function's scope
+    pub default: Option<ExpressionKey>,
+}
+
+// Value bound to a parameter at a call site
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CallArgument {
+    // Argument expression, in the scope of the call
+    Supplied(ExpressionKey),
+    // Default argument expression, in the scope of the called function
+    Default(ExpressionKey),
+}
+
+// Variable of an enclosing function, used by a function literal
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    pub symref: symboltable::ResolvedSymbolReference,
+    pub byreference: bool,
 }
 
 #[derive(Debug)]
 pub struct Function {
     pub name: String,
     pub scope: ScopeKey,
+    // Type parameters declared in the input parameter types, generic if not empty
+    pub typeparams: Vec<String>,
     pub inparams: Vec<FunctionParameter>,
+    // Type expressions of the returned values
+    pub outparams: Vec<ExpressionKey>,
+    // Explicit capture list, the references belong to the enclosing scope
+    pub capturelist: Vec<(symboltable::SymbolReferenceKey, bool)>,
+    // All captured variables, found by asg processing
+    pub captures: Vec<Capture>,
     pub body: Option<StatementBody>,
 }
 
@@ -118,16 +163,107 @@ impl Function {
     pub fn new(
         name: String,
         scope: ScopeKey,
+        typeparams: Vec<String>,
         inparams: Vec<FunctionParameter>,
+        outparams: Vec<ExpressionKey>,
+        capturelist: Vec<(symboltable::SymbolReferenceKey, bool)>,
         body: Option<StatementBody>,
     ) -> Self {
         Self {
             name,
             scope,
+            typeparams,
             inparams,
+            outparams,
+            capturelist,
+            captures: Vec::new(),
             body,
         }
     }
+
+    // Matches the positional and named arguments of a call to the parameters,
+    //  filling in default arguments for the parameters that were not supplied
+    pub fn resolve_call_arguments(
+        &self,
+        asg: &Asg,
+        call: &expressions::Call,
+    ) -> Result<Vec<CallArgument>, error::Error> {
+        let names: Vec<&String> = self
+            .inparams
+            .iter()
+            .map(|p| {
+                &asg.get_scope(&p.symref.scope)
+                    .symboltable
+                    .declarations
+                    .get(&p.symref.symbol)
+                    .symbol
+            })
+            .collect();
+
+        if call.args.len() > names.len() {
+            return Err(error::Error::at_span(
+                errors::TooManyArguments,
+                call.location.span,
+                format!(
+                    "Function {} takes {} arguments, got {}",
+                    self.name,
+                    names.len(),
+                    call.args.len()
+                ),
+            ));
+        }
+
+        let mut resolved: Vec<Option<CallArgument>> = call
+            .args
+            .iter()
+            .map(|arg| Some(CallArgument::Supplied(*arg)))
+            .collect();
+        resolved.resize(names.len(), None);
+
+        for named in &call.namedargs {
+            let index = names
+                .iter()
+                .position(|name| **name == named.symbol)
+                .ok_or_else(|| {
+                    error::Error::at_span(
+                        errors::UnknownNamedArgument,
+                        named.location.span,
+                        format!(
+                            "Unknown parameter {} in call to {}",
+                            named.symbol, self.name
+                        ),
+                    )
+                })?;
+
+            if resolved[index].is_some() {
+                return Err(error::Error::at_span(
+                    errors::DuplicateNamedArgument,
+                    named.location.span,
+                    format!("Parameter {} is given more than once", named.symbol),
+                ));
+            }
+            resolved[index] = Some(CallArgument::Supplied(named.expr));
+        }
+
+        resolved
+            .into_iter()
+            .enumerate()
+            .map(|(i, arg)| {
+                arg.or(self.inparams[i].default.map(CallArgument::Default))
+                    .ok_or_else(|| {
+                        error::Error::at_span(
+                            errors::MissingArgument,
+                            call.location.span,
+                            format!("Missing argument for parameter {}", names[i]),
+                        )
+                    })
+            })
+            .collect()
+    }
+
+    pub fn is_generic(&self) -> bool {
+        !self.typeparams.is_empty()
+    }
 }
 
 #[derive(Debug)]
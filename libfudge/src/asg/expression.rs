@@ -1,6 +1,8 @@
 use super::*;
 
 use crate::shared::BinaryOperationType;
+use crate::shared::UnaryOperationType;
+use crate::source::SourceLocation;
 use crate::typesystem;
 
 use scope::ExpressionKey;
@@ -13,6 +15,37 @@ pub mod misc {
         pub name: String,
         pub typeexpr: ExpressionKey,
     }
+
+    #[derive(Debug)]
+    pub struct EnumVariant {
+        pub name: String,
+        pub fields: Vec<StructField>,
+    }
+
+    // Variant of an enum, with the symbols its fields are bound to, in order. Ignored
+    //  fields have no symbol, and without any bindings the fields are not bound at all.
+    #[derive(Debug, Clone)]
+    pub struct VariantPattern {
+        pub name: String,
+        pub bindings: Vec<Option<String>>,
+    }
+
+    #[derive(Debug, Clone)]
+    pub enum Pattern {
+        Variant(VariantPattern),
+        Wildcard,
+        // Binds the matched value to the symbol
+        Binding(String),
+        Literal(ExpressionKey),
+        // Half-open, like range expressions
+        Range {
+            start: ExpressionKey,
+            end: ExpressionKey,
+        },
+        // Fields that are not listed are not matched
+        Struct(Vec<(String, Pattern)>),
+        Tuple(Vec<Pattern>),
+    }
 }
 
 pub mod expressions {
@@ -30,17 +63,42 @@ pub mod expressions {
             pub value: bool,
         }
         #[derive(Debug)]
+        pub struct NullLiteral {}
+        #[derive(Debug)]
         pub struct IntegerLiteral {
             pub data: u64,
             pub signed: bool,
         }
         #[derive(Debug)]
+        pub struct FloatLiteral {
+            pub value: f64,
+        }
+        #[derive(Debug)]
+        pub struct CharLiteral {
+            pub value: char,
+        }
+        #[derive(Debug)]
         pub struct StructLiteral {
             pub fields: Vec<misc::StructField>,
         }
         #[derive(Debug)]
+        pub struct EnumLiteral {
+            pub variants: Vec<misc::EnumVariant>,
+        }
+        #[derive(Debug)]
+        pub struct ArrayLiteral {
+            pub elements: Vec<ExpressionKey>,
+            pub dynamic: bool,
+        }
+        #[derive(Debug)]
+        pub struct TupleLiteral {
+            pub elements: Vec<ExpressionKey>,
+        }
+        #[derive(Debug)]
         pub struct FunctionLiteral {
             pub functionkey: FunctionKey,
+            pub location: SourceLocation,
+            pub outputlocation: Option<SourceLocation>,
         }
         #[derive(Debug)]
         pub struct ModuleLiteral {
@@ -52,8 +110,14 @@ pub mod expressions {
     pub enum Literal {
         StringLiteral(literals::StringLiteral),
         BoolLiteral(literals::BoolLiteral),
+        NullLiteral(literals::NullLiteral),
         IntegerLiteral(literals::IntegerLiteral),
+        FloatLiteral(literals::FloatLiteral),
+        CharLiteral(literals::CharLiteral),
         StructLiteral(literals::StructLiteral),
+        EnumLiteral(literals::EnumLiteral),
+        ArrayLiteral(literals::ArrayLiteral),
+        TupleLiteral(literals::TupleLiteral),
         FunctionLiteral(literals::FunctionLiteral),
         ModuleLiteral(literals::ModuleLiteral),
     }
@@ -71,6 +135,7 @@ pub mod expressions {
     #[derive(Debug)]
     pub struct SymbolReference {
         pub symbolref: symboltable::SymbolReferenceKey,
+        pub location: SourceLocation,
     }
 
     #[derive(Debug)]
@@ -79,10 +144,40 @@ pub mod expressions {
         pub elsebranch: Option<ExpressionKey>,
     }
 
+    // Symbols bound by the pattern are declared in the scope of the arm, which the
+    //  guard and expression of the arm belong to
+    #[derive(Debug)]
+    pub struct MatchArm {
+        pub pattern: misc::Pattern,
+        pub guard: Option<ExpressionKey>,
+        pub scope: ScopeKey,
+        pub expr: ExpressionKey,
+        pub location: SourceLocation,
+    }
+
+    #[derive(Debug)]
+    pub struct Match {
+        pub expr: ExpressionKey,
+        pub arms: Vec<MatchArm>,
+        pub elsebranch: Option<ExpressionKey>,
+        pub location: SourceLocation,
+    }
+
+    // The location spans the argument list
     #[derive(Debug)]
     pub struct Call {
         pub callable: ExpressionKey,
+        // Positional arguments, always preceding the named ones
         pub args: Vec<ExpressionKey>,
+        pub namedargs: Vec<NamedArgument>,
+        pub location: SourceLocation,
+    }
+
+    #[derive(Debug)]
+    pub struct NamedArgument {
+        pub symbol: String,
+        pub expr: ExpressionKey,
+        pub location: SourceLocation,
     }
 
     #[derive(Debug)]
@@ -92,11 +187,98 @@ pub mod expressions {
         pub rhs: ExpressionKey,
     }
 
+    #[derive(Debug)]
+    pub struct UnaryOp {
+        pub op: UnaryOperationType,
+        pub expr: ExpressionKey,
+    }
+
+    #[derive(Debug)]
+    pub struct Range {
+        pub start: ExpressionKey,
+        pub end: ExpressionKey,
+    }
+
     #[derive(Debug)]
     pub struct Subscript {
         pub expr: ExpressionKey,
         pub symbol: String,
     }
+
+    // Fields that are not initialized take their default values
+    #[derive(Debug)]
+    pub struct StructConstruction {
+        pub typeexpr: ExpressionKey,
+        pub fields: Vec<(String, ExpressionKey)>,
+    }
+
+    #[derive(Debug)]
+    pub struct Index {
+        pub expr: ExpressionKey,
+        pub index: ExpressionKey,
+        pub location: SourceLocation,
+    }
+
+    // Indexing with a range, produces a view into the indexed array
+    #[derive(Debug)]
+    pub struct Slice {
+        pub expr: ExpressionKey,
+        pub start: ExpressionKey,
+        pub end: ExpressionKey,
+        pub location: SourceLocation,
+    }
+
+    // Address of the referenced value, or the reference type when the expression is a type
+    #[derive(Debug)]
+    pub struct Reference {
+        pub expr: ExpressionKey,
+        pub location: SourceLocation,
+    }
+
+    #[derive(Debug)]
+    pub struct Dereference {
+        pub expr: ExpressionKey,
+        pub location: SourceLocation,
+    }
+
+    // Value of an ok result, error results are returned from the function instead
+    #[derive(Debug)]
+    pub struct Try {
+        pub expr: ExpressionKey,
+        pub location: SourceLocation,
+    }
+
+    #[derive(Debug)]
+    pub struct DynamicArrayType {
+        pub element: ExpressionKey,
+    }
+
+    #[derive(Debug)]
+    pub struct SliceType {
+        pub element: ExpressionKey,
+    }
+
+    #[derive(Debug)]
+    pub struct OptionalType {
+        pub target: ExpressionKey,
+    }
+
+    // Generic built-in enum of the value of an operation that can fail, or its error
+    #[derive(Debug)]
+    pub struct ResultType {}
+
+    // Parameter names are kept for the signature, even though they don't affect the type
+    #[derive(Debug)]
+    pub struct FunctionType {
+        pub inparams: Vec<(String, ExpressionKey)>,
+        pub outparams: Vec<ExpressionKey>,
+    }
+
+    // Stands for the type bound to the parameter at each call of a generic function
+    #[derive(Debug)]
+    pub struct TypeParameter {
+        pub symbol: String,
+    }
 }
 
 #[derive(Debug)]
@@ -106,9 +288,24 @@ pub enum ExpressionObject {
     PrimitiveType(expressions::PrimitiveType),
     SymbolReference(expressions::SymbolReference),
     If(expressions::If),
+    Match(expressions::Match),
     Call(expressions::Call),
     BinOp(expressions::BinOp),
+    UnaryOp(expressions::UnaryOp),
+    Range(expressions::Range),
     Subscript(expressions::Subscript),
+    StructConstruction(expressions::StructConstruction),
+    Index(expressions::Index),
+    Slice(expressions::Slice),
+    Reference(expressions::Reference),
+    Dereference(expressions::Dereference),
+    Try(expressions::Try),
+    DynamicArrayType(expressions::DynamicArrayType),
+    SliceType(expressions::SliceType),
+    OptionalType(expressions::OptionalType),
+    ResultType(expressions::ResultType),
+    FunctionType(expressions::FunctionType),
+    TypeParameter(expressions::TypeParameter),
 }
 
 #[derive(Debug)]
@@ -11,6 +11,12 @@ pub struct Scope {
     pub symboltable: symboltable::SymbolTable,
     pub declarationtypes: HashMap<SymbolKey, crate::typesystem::TypeId>,
     pub expressiontypes: HashMap<ExpressionKey, crate::typesystem::TypeId>,
+    // Types bound to the type parameters of generic functions, per call expression
+    pub typearguments: HashMap<ExpressionKey, Vec<crate::typesystem::TypeId>>,
+    // Expressions of plain values used as optionals, with the optional type they are wrapped in
+    pub optionalvalues: HashMap<ExpressionKey, crate::typesystem::TypeId>,
+    // Result types errors are propagated as by try expressions, the output of the function
+    pub propagatederrors: HashMap<ExpressionKey, crate::typesystem::TypeId>,
 }
 
 impl Scope {
@@ -21,6 +27,9 @@ impl Scope {
             symboltable: symboltable::SymbolTable::new(),
             declarationtypes: HashMap::new(),
             expressiontypes: HashMap::new(),
+            typearguments: HashMap::new(),
+            optionalvalues: HashMap::new(),
+            propagatederrors: HashMap::new(),
         }
     }
 }
@@ -1,5 +1,6 @@
 use super::*;
 
+use crate::source::SourceLocation;
 use scope::ExpressionKey;
 
 pub mod statements {
@@ -17,15 +18,49 @@ pub mod statements {
         pub elsebranch: Option<Branch>,
     }
 
+    // Symbols bound by the pattern are declared in the scope of the body, which the
+    //  guard belongs to as well
+    #[derive(Debug)]
+    pub struct MatchArm {
+        pub pattern: expression::misc::Pattern,
+        pub guard: Option<ExpressionKey>,
+        pub body: Branch,
+        pub location: SourceLocation,
+    }
+
+    #[derive(Debug)]
+    pub struct Match {
+        pub expr: ExpressionKey,
+        pub arms: Vec<MatchArm>,
+        pub elsebranch: Option<Branch>,
+        pub location: SourceLocation,
+    }
+
+    #[derive(Debug)]
+    pub struct While {
+        pub condition: ExpressionKey,
+        pub body: Branch,
+    }
+
+    // The loop symbol is declared in the scope of the body
+    #[derive(Debug)]
+    pub struct For {
+        pub symbol: String,
+        pub iterable: ExpressionKey,
+        pub body: Branch,
+    }
+
+    // One expression per returned value
     #[derive(Debug)]
     pub struct Return {
-        pub expr: Option<ExpressionKey>,
+        pub exprs: Vec<ExpressionKey>,
     }
 
     #[derive(Debug)]
     pub struct Assign {
         pub lhs: ExpressionKey,
         pub rhs: ExpressionKey,
+        pub location: SourceLocation,
     }
 
     #[derive(Debug)]
@@ -34,6 +69,13 @@ pub mod statements {
         pub expr: ExpressionKey,
     }
 
+    // Ignored elements have no symbol
+    #[derive(Debug)]
+    pub struct Destructure {
+        pub symbols: Vec<Option<String>>,
+        pub expr: ExpressionKey,
+    }
+
     #[derive(Debug)]
     pub struct ExpressionWrapper {
         pub expr: ExpressionKey,
@@ -43,8 +85,14 @@ pub mod statements {
 #[derive(Debug)]
 pub enum Statement {
     If(statements::If),
+    Match(statements::Match),
+    While(statements::While),
+    For(statements::For),
+    Break,
+    Continue,
     Return(statements::Return),
     Initialize(statements::Initialize),
+    Destructure(statements::Destructure),
     Assign(statements::Assign),
     ExpressionWrapper(statements::ExpressionWrapper),
 }
@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use super::{objectstore::*, scope::ExpressionKey, *};
+use crate::source::SourceLocation;
 
 pub type SymbolDeclarationStore = HashedObjectStore<StringKey, SymbolDeclaration>;
 pub type SymbolKey = StringKey;
@@ -29,11 +30,46 @@ impl SymbolTable {
 pub struct SymbolDeclaration {
     pub symbol: String,
     pub typeexpr: Option<ExpressionKey>,
+    // Where a def or const was declared, these can not be assigned to
+    pub immutable: Option<SourceLocation>,
+    // Where the symbol was declared, for symbols declared by a declaration statement
+    pub location: Option<SourceLocation>,
 }
 
 impl SymbolDeclaration {
     pub fn new(symbol: String, typeexpr: Option<ExpressionKey>) -> Self {
-        Self { symbol, typeexpr }
+        Self {
+            symbol,
+            typeexpr,
+            immutable: None,
+            location: None,
+        }
+    }
+
+    pub fn new_at(
+        symbol: String,
+        typeexpr: Option<ExpressionKey>,
+        location: SourceLocation,
+    ) -> Self {
+        Self {
+            symbol,
+            typeexpr,
+            immutable: None,
+            location: Some(location),
+        }
+    }
+
+    pub fn new_immutable(
+        symbol: String,
+        typeexpr: Option<ExpressionKey>,
+        location: SourceLocation,
+    ) -> Self {
+        Self {
+            symbol,
+            typeexpr,
+            immutable: Some(location.clone()),
+            location: Some(location),
+        }
     }
 }
 
@@ -48,7 +84,7 @@ pub struct UnresolvedSymbolReference {
     pub symbol: String,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
+#[derive(PartialEq, Eq, Debug, Clone, Hash)]
 pub struct ResolvedSymbolReference {
     pub scope: ScopeRef,
     pub symbol: symboltable::SymbolKey,
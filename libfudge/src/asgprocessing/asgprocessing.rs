@@ -2,10 +2,17 @@ use std::any::type_name;
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+use super::constevaluation::evaluate_definitions;
+use super::exhaustiveness::check_match_coverage;
 use crate::asg::symboltable::SymbolKey;
 use crate::asg::*;
 use crate::utils::objectstore::*;
 
+use crate::error;
+use crate::error::errors;
+use crate::shared::BinaryOperationType;
+use crate::shared::UnaryOperationType;
+use crate::source::SourceLocation;
 use crate::typesystem::*;
 
 fn get_scope<'a>(asg: &'a asg::Asg, scope: &asg::ScopeRef) -> &'a asg::scope::Scope {
@@ -81,19 +88,138 @@ enum TypeConstraint {
         entry: TypeEntryKey,
         id: TypeId,
     },
+    // The argument expression is kept, as arguments are assigned to their parameters
     EqualsCallParam {
         call: TypeEntryKey,
         param: usize,
         arg: TypeEntryKey,
+        scope: ScopeKey,
+        expr: ExpressionKey,
+    },
+    // Value assigned to a target of the type, plain values assigned to optionals are wrapped
+    //  in them. The value expression is kept to record where values are wrapped.
+    AssignedTo {
+        target: TypeEntryKey,
+        value: TypeEntryKey,
+        scope: ScopeKey,
+        expr: ExpressionKey,
+    },
+    // Null literals take the type of the optional they are used as
+    NullOf {
+        entry: TypeEntryKey,
+    },
+    // Type of the value of an optional, once it is narrowed by comparing it to null
+    UnwrappedOf {
+        entry: TypeEntryKey,
+        optional: TypeEntryKey,
     },
     ValueOfExpr {
         entry: TypeEntryKey,
+        scope: ScopeKey,
         expr: ExpressionKey,
     },
     TypeOfSymbol {
         entry: TypeEntryKey,
+        scope: ScopeKey,
         symref: SymbolReferenceKey,
     },
+    // Without a length, the array is dynamic
+    ArrayOf {
+        entry: TypeEntryKey,
+        element: Option<TypeEntryKey>,
+        length: Option<u64>,
+    },
+    ElementOf {
+        entry: TypeEntryKey,
+        array: TypeEntryKey,
+    },
+    SliceOf {
+        entry: TypeEntryKey,
+        array: TypeEntryKey,
+    },
+    TupleOf {
+        entry: TypeEntryKey,
+        elements: Vec<TypeEntryKey>,
+    },
+    // Address of the referenced expression, or the reference type when it is a type. The
+    //  expression is kept to check that it can be referenced.
+    ReferenceOf {
+        entry: TypeEntryKey,
+        target: TypeEntryKey,
+        scope: ScopeKey,
+        expr: ExpressionKey,
+    },
+    // Value the reference refers to, the location of the dereference is kept for reporting
+    DereferenceOf {
+        entry: TypeEntryKey,
+        reference: TypeEntryKey,
+        location: SourceLocation,
+    },
+    // Value of an operand of an arithmetic or ordering operation, optionals are only values
+    //  once compared to null. The operand expression is kept for reporting.
+    OperandOf {
+        entry: TypeEntryKey,
+        operand: TypeEntryKey,
+        scope: ScopeKey,
+        expr: ExpressionKey,
+    },
+    // Value of a tried result, its error must fit the result the function outputs. The
+    //  locations of the function are kept for reporting.
+    TryOf {
+        entry: TypeEntryKey,
+        result: TypeEntryKey,
+        output: TypeEntryKey,
+        scope: ScopeKey,
+        expr: ExpressionKey,
+        function: Option<(SourceLocation, Option<SourceLocation>)>,
+    },
+    FunctionOf {
+        entry: TypeEntryKey,
+        inputparams: Vec<(SymbolKey, TypeEntryKey)>,
+        outputparams: Vec<TypeEntryKey>,
+    },
+    // Type of a field of a struct value, or of a variant of an enum type. The subscripted
+    //  expression is kept to evaluate enum types.
+    FieldOf {
+        entry: TypeEntryKey,
+        value: TypeEntryKey,
+        field: String,
+        scope: ScopeKey,
+        expr: ExpressionKey,
+    },
+    // Value of a struct type with the given fields initialized, the type expression is
+    //  evaluated once the solver runs. Fields are assigned their initializers, and omitted
+    //  fields take their default values.
+    ConstructionOf {
+        entry: TypeEntryKey,
+        fields: Vec<(String, ExpressionKey)>,
+        scope: ScopeKey,
+        expr: ExpressionKey,
+    },
+    // Type of the value returned by calling the function
+    ReturnOf {
+        entry: TypeEntryKey,
+        function: TypeEntryKey,
+    },
+    // Arms of a match on a value, checked once the type of the value is known. Symbols
+    //  bound by a pattern are declared in the scope of its arm, and guarded arms don't
+    //  count towards exhaustiveness.
+    MatchOf {
+        value: TypeEntryKey,
+        arms: Vec<(ScopeKey, expression::misc::Pattern, bool, SourceLocation)>,
+        haselse: bool,
+        location: SourceLocation,
+    },
+    // Call of a generic function, type parameters are bound from the types of the
+    //  supplied arguments. Default arguments don't take part in binding.
+    GenericCall {
+        entry: TypeEntryKey,
+        function: TypeEntryKey,
+        typeparams: Vec<String>,
+        args: Vec<Option<TypeEntryKey>>,
+        scope: ScopeKey,
+        expr: ExpressionKey,
+    },
 }
 
 type TypeEntryStore = IndexedObjectStore<TypeEntry>;
@@ -107,8 +233,25 @@ pub type TypeConstraintKey = usize;
 struct TypeEnvironment {
     types: TypeEntryStore,
     constraints: TypeConstraintStore,
-    symbolmap: HashMap<SymbolKey, TypeEntryKey>,
-    exprmap: HashMap<ExpressionKey, TypeEntryKey>,
+    symbolmap: HashMap<(ScopeKey, SymbolKey), TypeEntryKey>,
+    exprmap: HashMap<(ScopeKey, ExpressionKey), TypeEntryKey>,
+    // All scopes of the function, in the order they were processed
+    scopes: Vec<ScopeKey>,
+    // Returned types of the functions being processed, innermost last. Without declared
+    //  output parameters, the first return statement decides them.
+    outputs: Vec<Option<Vec<TypeEntryKey>>>,
+    // Locations of the functions being processed and of their declared outputs, unknown
+    //  for the main function
+    functionlocations: Vec<Option<(SourceLocation, Option<SourceLocation>)>>,
+    // Types bound to the type parameters of called generic functions
+    typearguments: HashMap<(ScopeKey, ExpressionKey), Vec<TypeId>>,
+    // Symbols compared to null by the conditions of the branches being processed
+    narrowed: Vec<asg::symboltable::ResolvedSymbolReference>,
+    // Plain values used as optionals, with the optional type they are wrapped in
+    optionalvalues: HashMap<(ScopeKey, ExpressionKey), TypeId>,
+    // Results output by the function when a try propagates an error
+    propagatederrors: HashMap<(ScopeKey, ExpressionKey), TypeId>,
+    errors: error::ErrorManager,
 }
 
 impl TypeEnvironment {
@@ -118,9 +261,26 @@ impl TypeEnvironment {
             constraints: TypeConstraintStore::new(),
             symbolmap: HashMap::new(),
             exprmap: HashMap::new(),
+            scopes: Vec::new(),
+            outputs: Vec::new(),
+            functionlocations: Vec::new(),
+            typearguments: HashMap::new(),
+            narrowed: Vec::new(),
+            optionalvalues: HashMap::new(),
+            propagatederrors: HashMap::new(),
+            errors: error::ErrorManager::new(),
         }
     }
 
+    // Types depending on rejected expressions can stay unknown, they are only required to
+    //  be known without errors
+    pub fn has_errors(&self) -> bool {
+        self.errors
+            .get_errors()
+            .iter()
+            .any(|error| !error::is_warning(error.id))
+    }
+
     pub fn get_entry(&self, key: &TypeEntryKey) -> &TypeEntry {
         self.types.get(key)
     }
@@ -129,27 +289,44 @@ impl TypeEnvironment {
         self.types.get_mut(key)
     }
 
-    pub fn add_for_symbol(&mut self, symbolkey: SymbolKey, typeentry: TypeEntry) -> TypeEntryKey {
+    pub fn add_entry(&mut self, typeentry: TypeEntry) -> TypeEntryKey {
+        self.types.add(typeentry)
+    }
+
+    pub fn add_for_symbol(
+        &mut self,
+        scope: ScopeKey,
+        symbolkey: SymbolKey,
+        typeentry: TypeEntry,
+    ) -> TypeEntryKey {
         let key = self.types.add(typeentry);
-        self.symbolmap.insert(symbolkey, key.clone());
+        self.symbolmap.insert((scope, symbolkey), key.clone());
         key
     }
 
-    pub fn get_for_symbol(&self, symbolkey: &SymbolKey) -> TypeEntryKey {
-        self.symbolmap.get(symbolkey).unwrap().clone()
+    pub fn get_for_symbol(&self, scope: &ScopeKey, symbolkey: &SymbolKey) -> TypeEntryKey {
+        self.symbolmap
+            .get(&(*scope, symbolkey.clone()))
+            .unwrap()
+            .clone()
     }
 
-    pub fn get_for_expression(&self, expressionkey: &ExpressionKey) -> TypeEntryKey {
-        self.exprmap.get(expressionkey).unwrap().clone()
+    pub fn get_for_expression(
+        &self,
+        scope: &ScopeKey,
+        expressionkey: &ExpressionKey,
+    ) -> TypeEntryKey {
+        self.exprmap.get(&(*scope, *expressionkey)).unwrap().clone()
     }
 
     pub fn add_for_expression(
         &mut self,
+        scope: ScopeKey,
         exprkey: ExpressionKey,
         typeentry: TypeEntry,
     ) -> TypeEntryKey {
         let key = self.types.add(typeentry);
-        self.exprmap.insert(exprkey, key.clone());
+        self.exprmap.insert((scope, exprkey), key.clone());
         key
     }
 
@@ -162,34 +339,186 @@ impl TypeEnvironment {
     }
 }
 
+fn unsigned_integer_types() -> HashSet<TypeId> {
+    HashSet::from([
+        TypeId::Primitive(PrimitiveType::U8),
+        TypeId::Primitive(PrimitiveType::U16),
+        TypeId::Primitive(PrimitiveType::U32),
+        TypeId::Primitive(PrimitiveType::U64),
+    ])
+}
+
+fn signed_integer_types() -> HashSet<TypeId> {
+    HashSet::from([
+        TypeId::Primitive(PrimitiveType::S8),
+        TypeId::Primitive(PrimitiveType::S16),
+        TypeId::Primitive(PrimitiveType::S32),
+        TypeId::Primitive(PrimitiveType::S64),
+    ])
+}
+
+fn float_types() -> HashSet<TypeId> {
+    HashSet::from([
+        TypeId::Primitive(PrimitiveType::F32),
+        TypeId::Primitive(PrimitiveType::F64),
+    ])
+}
+
+// Operands of arithmetic and ordering operations are used as values, so optionals have to
+//  be compared to null first
+fn process_operand_type(
+    asg: &asg::Asg,
+    scoperef: &ScopeRef,
+    exprkey: &ExpressionKey,
+    typeenv: &mut TypeEnvironment,
+) -> TypeEntryKey {
+    let operand = process_expression_type(asg, scoperef, exprkey, typeenv);
+    let entry = typeenv.add_entry(TypeEntry::Variable(TypeVariable::Free));
+    typeenv.add_constraint(TypeConstraint::OperandOf {
+        entry,
+        operand,
+        scope: scoperef.scope,
+        expr: *exprkey,
+    });
+    entry
+}
+
 fn process_expression_type(
     asg: &asg::Asg,
-    scope: &asg::scope::Scope,
+    scoperef: &ScopeRef,
     exprkey: &ExpressionKey,
     typeenv: &mut TypeEnvironment,
 ) -> TypeEntryKey {
+    let scope = get_scope(asg, scoperef);
     let expression = scope.expressions.get(exprkey);
 
     match &expression.object {
         expression::ExpressionObject::Literal(n) => {
             use expression::expressions::Literal::*;
             match n {
-                IntegerLiteral(_) => {
-                    let mut types = HashSet::new();
-                    // TODO: Limit range based on literal and support signed integer types and defaults
-                    types.insert(TypeId::Primitive(PrimitiveType::U64));
-                    types.insert(TypeId::Primitive(PrimitiveType::U32));
-                    types.insert(TypeId::Primitive(PrimitiveType::U16));
-                    types.insert(TypeId::Primitive(PrimitiveType::U8));
+                IntegerLiteral(n) => {
+                    // TODO: Limit range based on literal and support defaults
+                    let mut types = signed_integer_types();
+                    // Negated literals can only be signed
+                    if !n.signed {
+                        types.extend(unsigned_integer_types());
+                    }
                     typeenv.add_for_expression(
+                        scoperef.scope,
                         exprkey.clone(),
                         TypeEntry::Variable(TypeVariable::new_typeset(types)),
                     )
                 }
+                FloatLiteral(_) => typeenv.add_for_expression(
+                    scoperef.scope,
+                    exprkey.clone(),
+                    TypeEntry::Variable(TypeVariable::new_typeset(float_types())),
+                ),
+                BoolLiteral(_) => typeenv.add_for_expression(
+                    scoperef.scope,
+                    exprkey.clone(),
+                    TypeEntry::Id(TypeId::Primitive(PrimitiveType::Bool)),
+                ),
+                NullLiteral(_) => {
+                    let entry = typeenv.add_for_expression(
+                        scoperef.scope,
+                        exprkey.clone(),
+                        TypeEntry::Variable(TypeVariable::Free),
+                    );
+                    typeenv.add_constraint(TypeConstraint::NullOf { entry });
+                    entry
+                }
+                CharLiteral(_) => typeenv.add_for_expression(
+                    scoperef.scope,
+                    exprkey.clone(),
+                    TypeEntry::Id(TypeId::Primitive(PrimitiveType::Char)),
+                ),
                 StringLiteral(_) => typeenv.add_for_expression(
+                    scoperef.scope,
                     exprkey.clone(),
                     TypeEntry::Id(TypeId::Primitive(PrimitiveType::StaticStringUtf8)),
                 ),
+                ArrayLiteral(n) => {
+                    // All elements must be of the same type
+                    let mut element = None;
+                    for e in &n.elements {
+                        let elementtype = process_expression_type(asg, scoperef, e, typeenv);
+                        match element {
+                            Some(first) => {
+                                typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                                    lhs: first,
+                                    rhs: elementtype,
+                                });
+                            }
+                            None => element = Some(elementtype),
+                        }
+                    }
+
+                    let arraytype = typeenv.add_for_expression(
+                        scoperef.scope,
+                        exprkey.clone(),
+                        TypeEntry::Variable(TypeVariable::Free),
+                    );
+                    typeenv.add_constraint(TypeConstraint::ArrayOf {
+                        entry: arraytype,
+                        element,
+                        length: if n.dynamic {
+                            None
+                        } else {
+                            Some(n.elements.len() as u64)
+                        },
+                    });
+                    arraytype
+                }
+                TupleLiteral(n) => {
+                    if is_type_expression(scope, exprkey) {
+                        for e in &n.elements {
+                            process_expression_type(asg, scoperef, e, typeenv);
+                        }
+                        return typeenv.add_for_expression(
+                            scoperef.scope,
+                            exprkey.clone(),
+                            TypeEntry::Id(TypeId::Type),
+                        );
+                    }
+
+                    process_tupleliteral_type(asg, scoperef, exprkey, n, typeenv).0
+                }
+                FunctionLiteral(n) => {
+                    process_functionliteral_type(asg, scoperef, exprkey, n, typeenv)
+                }
+                StructLiteral(n) => {
+                    for field in &n.fields {
+                        let fieldtype =
+                            process_expression_type(asg, scoperef, &field.typeexpr, typeenv);
+                        typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                            entry: fieldtype,
+                            id: TypeId::Type,
+                        });
+                    }
+
+                    typeenv.add_for_expression(
+                        scoperef.scope,
+                        exprkey.clone(),
+                        TypeEntry::Id(TypeId::Type),
+                    )
+                }
+                EnumLiteral(n) => {
+                    for field in n.variants.iter().flat_map(|v| &v.fields) {
+                        let fieldtype =
+                            process_expression_type(asg, scoperef, &field.typeexpr, typeenv);
+                        typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                            entry: fieldtype,
+                            id: TypeId::Type,
+                        });
+                    }
+
+                    typeenv.add_for_expression(
+                        scoperef.scope,
+                        exprkey.clone(),
+                        TypeEntry::Id(TypeId::Type),
+                    )
+                }
                 _ => todo!(),
                 /*BoolLiteral(_) => TypeVariable::new_primitive(PrimitiveType::Bool),
                 IntegerLiteral(_) => {
@@ -220,195 +549,1788 @@ fn process_expression_type(
         }
         expression::ExpressionObject::PrimitiveType(n) => {
             // All primitive types are of type "Type"
-            typeenv.add_for_expression(exprkey.clone(), TypeEntry::Id(TypeId::Type))
+            typeenv.add_for_expression(scoperef.scope, exprkey.clone(), TypeEntry::Id(TypeId::Type))
+        }
+        expression::ExpressionObject::ResultType(_) => {
+            typeenv.add_for_expression(scoperef.scope, exprkey.clone(), TypeEntry::Id(TypeId::Type))
         }
         expression::ExpressionObject::BuiltInFunction(n) => {
             // TODO: Look up function types of built-ins properly
             typeenv.add_for_expression(
+                scoperef.scope,
                 exprkey.clone(),
                 TypeEntry::Id(TypeId::BuiltInFunction(n.function)),
             )
         }
         expression::ExpressionObject::Call(n) => {
             // Process callable
-            let callabletype = process_expression_type(asg, scope, &n.callable, typeenv);
+            let callabletype = process_expression_type(asg, scoperef, &n.callable, typeenv);
+
+            // Match arguments to parameters, when the called function is known
+            let calledfunction = find_called_function(asg, scoperef, &n.callable);
+            let params = match (calledfunction, n.namedargs.first()) {
+                (Some(function), _) => function.resolve_call_arguments(asg, n),
+                (None, Some(named)) => Err(error::Error::at_span(
+                    errors::UnknownNamedArgument,
+                    named.location.span,
+                    "Named arguments require a known function".to_string(),
+                )),
+                (None, None) => Ok(n
+                    .args
+                    .iter()
+                    .map(|arg| CallArgument::Supplied(*arg))
+                    .collect()),
+            };
+
+            // Arguments that match no parameter are reported, the call still returns
+            //  what the function returns
+            let params = match params {
+                Ok(params) => params,
+                Err(error) => {
+                    typeenv.errors.log_error(error);
+                    let args = n.args.iter().chain(n.namedargs.iter().map(|n| &n.expr));
+                    for argexpr in args {
+                        process_expression_type(asg, scoperef, argexpr, typeenv);
+                    }
+
+                    let returntype = typeenv.add_for_expression(
+                        scoperef.scope,
+                        exprkey.clone(),
+                        TypeEntry::Variable(TypeVariable::Free),
+                    );
+                    typeenv.add_constraint(TypeConstraint::ReturnOf {
+                        entry: returntype,
+                        function: callabletype,
+                    });
+                    return returntype;
+                }
+            };
+
+            // Parameter types of generic functions depend on the arguments
+            if let Some(function) = calledfunction.filter(|f| f.is_generic()) {
+                let args = params
+                    .iter()
+                    .map(|param| match param {
+                        CallArgument::Supplied(argexpr) => {
+                            Some(process_expression_type(asg, scoperef, argexpr, typeenv))
+                        }
+                        CallArgument::Default(_) => None,
+                    })
+                    .collect();
+
+                let returntype = typeenv.add_for_expression(
+                    scoperef.scope,
+                    exprkey.clone(),
+                    TypeEntry::Variable(TypeVariable::Free),
+                );
+                typeenv.add_constraint(TypeConstraint::GenericCall {
+                    entry: returntype,
+                    function: callabletype,
+                    typeparams: function.typeparams.clone(),
+                    args,
+                    scope: scoperef.scope,
+                    expr: exprkey.clone(),
+                });
+                return returntype;
+            }
 
             // Process and constraint args
-            for (i, argexpr) in n.args.iter().enumerate() {
-                let argtype = process_expression_type(asg, scope, argexpr, typeenv);
+            let mut argtypes = Vec::new();
+            for (i, param) in params.iter().enumerate() {
+                // Default arguments belong to the scope of the called function
+                let argexpr = match param {
+                    CallArgument::Supplied(argexpr) => argexpr,
+                    CallArgument::Default(_) => continue,
+                };
+
+                let argtype = process_expression_type(asg, scoperef, argexpr, typeenv);
                 let constraint = TypeConstraint::EqualsCallParam {
                     call: callabletype,
                     param: i,
                     arg: argtype,
+                    scope: scoperef.scope,
+                    expr: *argexpr,
                 };
                 typeenv.add_constraint(constraint);
+                argtypes.push(argtype);
             }
 
-            // TODO: All calls return null for now
-            typeenv.add_for_expression(exprkey.clone(), TypeEntry::Id(TypeId::Null))
-        }
-        expression::ExpressionObject::SymbolReference(n) => {
-            // We don't know yet the type of sym refs, we do lookup later
-            let tv = typeenv
-                .add_for_expression(exprkey.clone(), TypeEntry::Variable(TypeVariable::Free));
-            typeenv.add_constraint(TypeConstraint::TypeOfSymbol {
-                entry: tv,
-                symref: n.symbolref,
-            });
-            tv
-        }
-        _ => todo!(),
-        /*
-        expression::ExpressionObject::SymbolReference(n) => {
-            let symref = scope.symboltable.references.get(&n.symbolref);
-            TypeVariable::new_symboltype(symref.clone())
-        }
-        expression::ExpressionObject::If(n) => {
-            let mut branch_expressions = Vec::new();
-
-            for b in &n.branches {
-                branch_expressions.push(b.1);
+            if let expression::ExpressionObject::BuiltInFunction(f) =
+                &scope.expressions.get(&n.callable).object
+            {
+                if f.function != BuiltInFunction::PrintFormat {
+                    return process_array_builtin_call(
+                        scoperef, exprkey, f.function, &argtypes, typeenv,
+                    );
+                }
             }
 
-            if let Some(e) = n.elsebranch {
-                branch_expressions.push(e);
+            if let expression::ExpressionObject::BuiltInFunction(_) =
+                &scope.expressions.get(&n.callable).object
+            {
+                return typeenv.add_for_expression(
+                    scoperef.scope,
+                    exprkey.clone(),
+                    TypeEntry::Id(TypeId::Null),
+                );
             }
 
-            TypeVariable::new_expression_types(branch_expressions)
+            let returntype = typeenv.add_for_expression(
+                scoperef.scope,
+                exprkey.clone(),
+                TypeEntry::Variable(TypeVariable::Free),
+            );
+            typeenv.add_constraint(TypeConstraint::ReturnOf {
+                entry: returntype,
+                function: callabletype,
+            });
+            returntype
         }
-        expression::ExpressionObject::BinOp(n) => {
-            // TODO: For now, assume all operators have to be same type
-            let mut ops = Vec::new();
-            ops.push(n.lhs);
-            ops.push(n.rhs);
+        expression::ExpressionObject::BinOp(n) => match n.op {
+            BinaryOperationType::And | BinaryOperationType::Or => {
+                // Logical operations only work on bools. The rhs of an and is only
+                //  evaluated if the lhs holds, so it sees the symbols the lhs narrows.
+                let outer = typeenv.narrowed.len();
+                for operand in [&n.lhs, &n.rhs] {
+                    let operandtype = process_expression_type(asg, scoperef, operand, typeenv);
+                    typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                        entry: operandtype,
+                        id: TypeId::Primitive(PrimitiveType::Bool),
+                    });
+
+                    if n.op == BinaryOperationType::And && operand == &n.lhs {
+                        typeenv
+                            .narrowed
+                            .extend(find_narrowed_symbols(asg, scoperef, &n.lhs));
+                    }
+                }
+                typeenv.narrowed.truncate(outer);
 
-            TypeVariable::new_expression_types(ops)
-        }
-        expression::ExpressionObject::Subscript(_) => todo!(),*/
-    }
-}
+                typeenv.add_for_expression(
+                    scoperef.scope,
+                    exprkey.clone(),
+                    TypeEntry::Id(TypeId::Primitive(PrimitiveType::Bool)),
+                )
+            }
+            BinaryOperationType::ShiftLeft | BinaryOperationType::ShiftRight => {
+                let lhstype = process_operand_type(asg, scoperef, &n.lhs, typeenv);
+                let rhstype = process_operand_type(asg, scoperef, &n.rhs, typeenv);
+
+                // Result has the type of the shifted value
+                let mut types = signed_integer_types();
+                types.extend(unsigned_integer_types());
+                let resulttype = typeenv.add_for_expression(
+                    scoperef.scope,
+                    exprkey.clone(),
+                    TypeEntry::Variable(TypeVariable::new_typeset(types)),
+                );
+                typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                    lhs: resulttype,
+                    rhs: lhstype,
+                });
 
-fn eval_expression_as_type(
-    asg: &asg::Asg,
-    scope: &asg::scope::Scope,
-    exprkey: &ExpressionKey,
-) -> TypeId {
-    let expression = scope.expressions.get(exprkey);
+                // Shift amount can be any unsigned type, independent of the shifted value
+                let amount = scope.expressions.get(&n.rhs);
+                if let expression::ExpressionObject::Literal(
+                    expression::expressions::Literal::IntegerLiteral(_),
+                ) = &amount.object
+                {
+                    typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                        entry: rhstype,
+                        id: TypeId::Primitive(PrimitiveType::U32),
+                    });
+                } else {
+                    let amounttype = typeenv.add_entry(TypeEntry::Variable(
+                        TypeVariable::new_typeset(unsigned_integer_types()),
+                    ));
+                    typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                        lhs: amounttype,
+                        rhs: rhstype,
+                    });
+                }
 
-    match &expression.object {
-        expression::ExpressionObject::PrimitiveType(n) => TypeId::Primitive(n.ptype),
-        _ => panic!("Cannot currently evaulate non-built in type literal expressions"),
-    }
-}
+                resulttype
+            }
+            _ => {
+                // Optionals can be compared for equality, to null as well
+                let (lhstype, rhstype) = match n.op {
+                    BinaryOperationType::Equals | BinaryOperationType::NotEquals => (
+                        process_expression_type(asg, scoperef, &n.lhs, typeenv),
+                        process_expression_type(asg, scoperef, &n.rhs, typeenv),
+                    ),
+                    _ => (
+                        process_operand_type(asg, scoperef, &n.lhs, typeenv),
+                        process_operand_type(asg, scoperef, &n.rhs, typeenv),
+                    ),
+                };
 
-fn process_function(
-    asg: &mut asg::Asg,
-    modulekey: &asg::ModuleKey,
-    functionkey: &asg::FunctionKey,
-) {
-    fn can_unify_var_id(a: &TypeVariable, b: &TypeId) -> bool {
-        match a {
-            TypeVariable::Free => true,
-            TypeVariable::TypeSet(n) => {
-                assert!(
-                    n.contains(&b),
-                    "Failed unify, typeSet did not contain type: {:?}, {:?}",
-                    n,
-                    a
+                // Both operands must be of the same type
+                typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                    lhs: lhstype,
+                    rhs: rhstype,
+                });
+
+                let mut types = signed_integer_types();
+                types.extend(unsigned_integer_types());
+                match n.op {
+                    BinaryOperationType::Add
+                    | BinaryOperationType::Sub
+                    | BinaryOperationType::Mul
+                    | BinaryOperationType::Div
+                    | BinaryOperationType::Mod => types.extend(float_types()),
+                    BinaryOperationType::BitAnd
+                    | BinaryOperationType::BitOr
+                    | BinaryOperationType::BitXor => (),
+                    _ => {
+                        // Comparisons
+                        return typeenv.add_for_expression(
+                            scoperef.scope,
+                            exprkey.clone(),
+                            TypeEntry::Id(TypeId::Primitive(PrimitiveType::Bool)),
+                        );
+                    }
+                }
+
+                let resulttype = typeenv.add_for_expression(
+                    scoperef.scope,
+                    exprkey.clone(),
+                    TypeEntry::Variable(TypeVariable::new_typeset(types)),
                 );
-                true
-            }
-        }
-    }
+                typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                    lhs: resulttype,
+                    rhs: lhstype,
+                });
 
-    fn resolve_substitutions(key: &TypeEntryKey, typeenv: &TypeEnvironment) -> TypeEntryKey {
-        let mut key = key;
-        loop {
-            let entry = typeenv.get_entry(key);
-            match entry {
-                TypeEntry::Substituted(n) => key = n,
-                _ => return *key,
+                resulttype
             }
-            return *key;
-        }
-    }
+        },
+        expression::ExpressionObject::UnaryOp(n) => match n.op {
+            UnaryOperationType::Neg | UnaryOperationType::BitNot => {
+                let operandtype = process_operand_type(asg, scoperef, &n.expr, typeenv);
+
+                // Result has the same type as the operand, limited to types supporting the operation
+                let types = if n.op == UnaryOperationType::Neg {
+                    let mut types = signed_integer_types();
+                    types.extend(float_types());
+                    types
+                } else {
+                    let mut types = signed_integer_types();
+                    types.extend(unsigned_integer_types());
+                    types
+                };
+                let resulttype = typeenv.add_for_expression(
+                    scoperef.scope,
+                    exprkey.clone(),
+                    TypeEntry::Variable(TypeVariable::new_typeset(types)),
+                );
+                typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                    lhs: resulttype,
+                    rhs: operandtype,
+                });
 
-    let mut typeenv = TypeEnvironment::new();
-    let typeenv = &mut typeenv;
+                resulttype
+            }
+            UnaryOperationType::Not => {
+                let operandtype = process_expression_type(asg, scoperef, &n.expr, typeenv);
+                typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                    entry: operandtype,
+                    id: TypeId::Primitive(PrimitiveType::Bool),
+                });
 
-    // Decls
-    {
-        let module = asg.modulestore.get(&modulekey);
-        let function = module.functionstore.get(&functionkey);
+                typeenv.add_for_expression(
+                    scoperef.scope,
+                    exprkey.clone(),
+                    TypeEntry::Id(TypeId::Primitive(PrimitiveType::Bool)),
+                )
+            }
+        },
+        expression::ExpressionObject::Range(n) => {
+            let starttype = process_expression_type(asg, scoperef, &n.start, typeenv);
+            let endtype = process_expression_type(asg, scoperef, &n.end, typeenv);
+
+            // Ranges are not first-class values yet, so the range takes the integer type
+            //  of its bounds
+            let mut types = signed_integer_types();
+            types.extend(unsigned_integer_types());
+            let rangetype = typeenv.add_for_expression(
+                scoperef.scope,
+                exprkey.clone(),
+                TypeEntry::Variable(TypeVariable::new_typeset(types)),
+            );
+            typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                lhs: rangetype,
+                rhs: starttype,
+            });
+            typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                lhs: rangetype,
+                rhs: endtype,
+            });
 
-        let scope = module.scopestore.get(&function.scope);
-        let decls = &scope.symboltable.declarations;
-        for symkey in decls.keys() {
-            let decl = decls.get(symkey);
-            if let Some(typeexpr) = &decl.typeexpr {
-                // Process type for type expression
-                let exprtype = process_expression_type(asg, &scope, &typeexpr, typeenv);
+            rangetype
+        }
+        expression::ExpressionObject::Index(n) => {
+            let exprtype = process_expression_type(asg, scoperef, &n.expr, typeenv);
+            let indextype = process_expression_type(asg, scoperef, &n.index, typeenv);
 
-                // Constrain it to "Type"
+            if is_type_expression(scope, &n.expr) {
+                // Indexing a type gives an array type, with the index as its length
                 typeenv.add_constraint(TypeConstraint::EqualsTypeId {
-                    entry: exprtype,
-                    id: TypeId::Type,
+                    entry: indextype,
+                    id: TypeId::Primitive(PrimitiveType::U64),
                 });
+                return typeenv.add_for_expression(
+                    scoperef.scope,
+                    exprkey.clone(),
+                    TypeEntry::Id(TypeId::Type),
+                );
+            }
 
-                // Add entry for symbol
-                let entry =
-                    typeenv.add_for_symbol(symkey.clone(), TypeEntry::Variable(TypeVariable::Free));
-
-                // Constrain the type of the symbol to the _value_ of the expression
-                typeenv.add_constraint(TypeConstraint::ValueOfExpr {
-                    entry,
-                    expr: typeexpr.clone(),
+            // Indices can be any unsigned type
+            let index = scope.expressions.get(&n.index);
+            if let expression::ExpressionObject::Literal(
+                expression::expressions::Literal::IntegerLiteral(_),
+            ) = &index.object
+            {
+                typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                    entry: indextype,
+                    id: TypeId::Primitive(PrimitiveType::U32),
                 });
             } else {
-                todo!();
+                let unsignedtype = typeenv.add_entry(TypeEntry::Variable(
+                    TypeVariable::new_typeset(unsigned_integer_types()),
+                ));
+                typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                    lhs: unsignedtype,
+                    rhs: indextype,
+                });
             }
+
+            let elementtype = typeenv.add_for_expression(
+                scoperef.scope,
+                exprkey.clone(),
+                TypeEntry::Variable(TypeVariable::Free),
+            );
+            typeenv.add_constraint(TypeConstraint::ElementOf {
+                entry: elementtype,
+                array: exprtype,
+            });
+            elementtype
         }
-    }
+        expression::ExpressionObject::Slice(n) => {
+            let exprtype = process_expression_type(asg, scoperef, &n.expr, typeenv);
+            let starttype = process_expression_type(asg, scoperef, &n.start, typeenv);
+            let endtype = process_expression_type(asg, scoperef, &n.end, typeenv);
+
+            // Both bounds have the same unsigned type
+            let boundtype = typeenv.add_entry(TypeEntry::Variable(TypeVariable::new_typeset(
+                unsigned_integer_types(),
+            )));
+            typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                lhs: boundtype,
+                rhs: starttype,
+            });
+            typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                lhs: boundtype,
+                rhs: endtype,
+            });
 
-    // Statements
-    {
-        let module = asg.modulestore.get(&modulekey);
-        let function = module.functionstore.get(&functionkey);
+            let slicetype = typeenv.add_for_expression(
+                scoperef.scope,
+                exprkey.clone(),
+                TypeEntry::Variable(TypeVariable::Free),
+            );
+            typeenv.add_constraint(TypeConstraint::SliceOf {
+                entry: slicetype,
+                array: exprtype,
+            });
+            slicetype
+        }
+        expression::ExpressionObject::Reference(n) => {
+            let targettype = process_expression_type(asg, scoperef, &n.expr, typeenv);
 
-        if let Some(body) = &function.body {
-            let scope = module.scopestore.get(&body.scope_nonowned);
-            for stmnt in &body.statements {
-                match stmnt {
-                    Statement::If(_) => todo!(),
-                    Statement::Return(_) => todo!(),
-                    Statement::Initialize(n) => {
-                        let symkey = SymbolKey::from_str(n.symbol.as_str()); // TODO: Don't need complete symbol in n
+            let referencetype = typeenv.add_for_expression(
+                scoperef.scope,
+                exprkey.clone(),
+                TypeEntry::Variable(TypeVariable::Free),
+            );
+            typeenv.add_constraint(TypeConstraint::ReferenceOf {
+                entry: referencetype,
+                target: targettype,
+                scope: scoperef.scope,
+                expr: n.expr,
+            });
+            referencetype
+        }
+        expression::ExpressionObject::Dereference(n) => {
+            let referencetype = process_expression_type(asg, scoperef, &n.expr, typeenv);
 
-                        // Add type for initialization expression
-                        let rhs = process_expression_type(asg, &scope, &n.expr, typeenv);
+            let targettype = typeenv.add_for_expression(
+                scoperef.scope,
+                exprkey.clone(),
+                TypeEntry::Variable(TypeVariable::Free),
+            );
+            typeenv.add_constraint(TypeConstraint::DereferenceOf {
+                entry: targettype,
+                reference: referencetype,
+                location: n.location.clone(),
+            });
+            targettype
+        }
+        expression::ExpressionObject::Try(n) => {
+            let resulttype = process_expression_type(asg, scoperef, &n.expr, typeenv);
 
-                        // Symbol declaration should have been added earlier
-                        let lhs = typeenv.get_for_symbol(&symkey);
+            let valuetype = typeenv.add_for_expression(
+                scoperef.scope,
+                exprkey.clone(),
+                TypeEntry::Variable(TypeVariable::Free),
+            );
 
-                        // Add constraint for symboltype and expression
-                        typeenv.add_constraint(TypeConstraint::EqualsEntry { lhs, rhs });
-                    }
-                    Statement::Assign(_) => todo!(),
-                    Statement::ExpressionWrapper(n) => {
-                        process_expression_type(asg, &scope, &n.expr, typeenv);
-                    }
+            // Errors are returned from the function, so it must output a result
+            let function = typeenv.functionlocations.last().unwrap().clone();
+            let output = match typeenv.outputs.last() {
+                Some(Some(outputs)) if outputs.len() == 1 => outputs[0],
+                Some(Some(_)) => {
+                    let label = "The function returns several values";
+                    let error = create_try_error(&n.location, &function, String::new(), label);
+                    typeenv.errors.log_error(error);
+                    return valuetype;
                 }
-            }
-        }
-    }
+                _ => {
+                    let label = "The function does not return a value";
+                    let error = create_try_error(&n.location, &function, String::new(), label);
+                    typeenv.errors.log_error(error);
+                    return valuetype;
+                }
+            };
 
-    // At this point we should be ready to start processing type constraints
-    let mut iteration = 0;
-    loop {
-        let last_constraints = typeenv.constraints.keys();
+            typeenv.add_constraint(TypeConstraint::TryOf {
+                entry: valuetype,
+                result: resulttype,
+                output,
+                scope: scoperef.scope,
+                expr: exprkey.clone(),
+                function,
+            });
+            valuetype
+        }
+        expression::ExpressionObject::DynamicArrayType(n) => {
+            let elementtype = process_expression_type(asg, scoperef, &n.element, typeenv);
+            typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                entry: elementtype,
+                id: TypeId::Type,
+            });
+
+            typeenv.add_for_expression(scoperef.scope, exprkey.clone(), TypeEntry::Id(TypeId::Type))
+        }
+        expression::ExpressionObject::SliceType(n) => {
+            let elementtype = process_expression_type(asg, scoperef, &n.element, typeenv);
+            typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                entry: elementtype,
+                id: TypeId::Type,
+            });
+
+            typeenv.add_for_expression(scoperef.scope, exprkey.clone(), TypeEntry::Id(TypeId::Type))
+        }
+        expression::ExpressionObject::OptionalType(n) => {
+            let targettype = process_expression_type(asg, scoperef, &n.target, typeenv);
+            typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                entry: targettype,
+                id: TypeId::Type,
+            });
+
+            typeenv.add_for_expression(scoperef.scope, exprkey.clone(), TypeEntry::Id(TypeId::Type))
+        }
+        expression::ExpressionObject::FunctionType(n) => {
+            let paramexprs = n.inparams.iter().map(|(_, e)| e).chain(&n.outparams);
+            for typeexpr in paramexprs {
+                let paramtype = process_expression_type(asg, scoperef, typeexpr, typeenv);
+                typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                    entry: paramtype,
+                    id: TypeId::Type,
+                });
+            }
+
+            typeenv.add_for_expression(scoperef.scope, exprkey.clone(), TypeEntry::Id(TypeId::Type))
+        }
+        expression::ExpressionObject::TypeParameter(_) => {
+            typeenv.add_for_expression(scoperef.scope, exprkey.clone(), TypeEntry::Id(TypeId::Type))
+        }
+        expression::ExpressionObject::SymbolReference(n) => {
+            // Optionals compared to null are read as their value in the narrowed branch
+            let narrowed = resolve_reference_expression(asg, scoperef, exprkey)
+                .filter(|reference| typeenv.narrowed.contains(reference));
+            if narrowed.is_some() {
+                let optional = typeenv.add_entry(TypeEntry::Variable(TypeVariable::Free));
+                typeenv.add_constraint(TypeConstraint::TypeOfSymbol {
+                    entry: optional,
+                    scope: scoperef.scope,
+                    symref: n.symbolref,
+                });
+
+                let tv = typeenv.add_for_expression(
+                    scoperef.scope,
+                    exprkey.clone(),
+                    TypeEntry::Variable(TypeVariable::Free),
+                );
+                typeenv.add_constraint(TypeConstraint::UnwrappedOf {
+                    entry: tv,
+                    optional,
+                });
+                return tv;
+            }
+
+            // We don't know yet the type of sym refs, we do lookup later
+            let tv = typeenv.add_for_expression(
+                scoperef.scope,
+                exprkey.clone(),
+                TypeEntry::Variable(TypeVariable::Free),
+            );
+            typeenv.add_constraint(TypeConstraint::TypeOfSymbol {
+                entry: tv,
+                scope: scoperef.scope,
+                symref: n.symbolref,
+            });
+            tv
+        }
+        expression::ExpressionObject::Subscript(n) => {
+            let valuetype = process_expression_type(asg, scoperef, &n.expr, typeenv);
+
+            let fieldtype = typeenv.add_for_expression(
+                scoperef.scope,
+                exprkey.clone(),
+                TypeEntry::Variable(TypeVariable::Free),
+            );
+            typeenv.add_constraint(TypeConstraint::FieldOf {
+                entry: fieldtype,
+                value: valuetype,
+                field: n.symbol.clone(),
+                scope: scoperef.scope,
+                expr: n.expr,
+            });
+            fieldtype
+        }
+        expression::ExpressionObject::StructConstruction(n) => {
+            let typetype = process_expression_type(asg, scoperef, &n.typeexpr, typeenv);
+            typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                entry: typetype,
+                id: TypeId::Type,
+            });
+
+            for (_, value) in &n.fields {
+                process_expression_type(asg, scoperef, value, typeenv);
+            }
+
+            let structtype = typeenv.add_for_expression(
+                scoperef.scope,
+                exprkey.clone(),
+                TypeEntry::Variable(TypeVariable::Free),
+            );
+            typeenv.add_constraint(TypeConstraint::ConstructionOf {
+                entry: structtype,
+                fields: n.fields.clone(),
+                scope: scoperef.scope,
+                expr: n.typeexpr,
+            });
+            structtype
+        }
+        expression::ExpressionObject::Match(n) => {
+            let valuetype = process_expression_type(asg, scoperef, &n.expr, typeenv);
+
+            // Every arm gives a value of the same type
+            let resulttype = typeenv.add_for_expression(
+                scoperef.scope,
+                exprkey.clone(),
+                TypeEntry::Variable(TypeVariable::Free),
+            );
+            for arm in &n.arms {
+                let armscope = ScopeRef::new(scoperef.module, arm.scope);
+                process_declarations(asg, &armscope, typeenv);
+                process_match_arm(asg, &armscope, &arm.pattern, &arm.guard, typeenv);
+                let armtype = process_expression_type(asg, &armscope, &arm.expr, typeenv);
+                typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                    lhs: resulttype,
+                    rhs: armtype,
+                });
+            }
+            if let Some(elsebranch) = &n.elsebranch {
+                let elsetype = process_expression_type(asg, scoperef, elsebranch, typeenv);
+                typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                    lhs: resulttype,
+                    rhs: elsetype,
+                });
+            }
+
+            typeenv.add_constraint(TypeConstraint::MatchOf {
+                value: valuetype,
+                arms: n
+                    .arms
+                    .iter()
+                    .map(|arm| {
+                        (
+                            arm.scope,
+                            arm.pattern.clone(),
+                            arm.guard.is_some(),
+                            arm.location.clone(),
+                        )
+                    })
+                    .collect(),
+                haselse: n.elsebranch.is_some(),
+                location: n.location.clone(),
+            });
+            resulttype
+        }
+        expression::ExpressionObject::If(n) => {
+            // Every branch gives a value of the same type, so one has to be taken
+            let elsebranch = n
+                .elsebranch
+                .as_ref()
+                .expect("If expression needs an else branch to give a value");
+
+            let resulttype = typeenv.add_for_expression(
+                scoperef.scope,
+                exprkey.clone(),
+                TypeEntry::Variable(TypeVariable::Free),
+            );
+            for (condition, value) in &n.branches {
+                process_condition(asg, scoperef, condition, typeenv);
+                let valuetype = process_expression_type(asg, scoperef, value, typeenv);
+                typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                    lhs: resulttype,
+                    rhs: valuetype,
+                });
+            }
+            let elsetype = process_expression_type(asg, scoperef, elsebranch, typeenv);
+            typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                lhs: resulttype,
+                rhs: elsetype,
+            });
+
+            resulttype
+        }
+    }
+}
+
+// Resolves the symbol a symbol reference expression refers to, before the reference
+//  itself is resolved
+fn resolve_reference_expression(
+    asg: &asg::Asg,
+    scoperef: &ScopeRef,
+    exprkey: &ExpressionKey,
+) -> Option<asg::symboltable::ResolvedSymbolReference> {
+    let scope = get_scope(asg, scoperef);
+    let reference = match &scope.expressions.get(exprkey).object {
+        expression::ExpressionObject::SymbolReference(n) => {
+            match scope.symboltable.references.get(&n.symbolref) {
+                SymbolReference::UnresolvedReference(n) => lookup_symbol(asg, n, scoperef),
+                SymbolReference::ResolvedReference(n) => {
+                    SymbolReference::ResolvedReference(n.clone())
+                }
+            }
+        }
+        _ => return None,
+    };
+
+    match reference {
+        SymbolReference::ResolvedReference(n) => Some(n),
+        SymbolReference::UnresolvedReference(_) => None,
+    }
+}
+
+// Defs and consts can not be assigned to, neither can the fields or elements of values
+//  stored in them
+fn check_assignment_target(
+    asg: &asg::Asg,
+    scoperef: &ScopeRef,
+    exprkey: &ExpressionKey,
+    location: &SourceLocation,
+    typeenv: &mut TypeEnvironment,
+) {
+    match &get_scope(asg, scoperef).expressions.get(exprkey).object {
+        expression::ExpressionObject::Subscript(n) => {
+            check_assignment_target(asg, scoperef, &n.expr, location, typeenv);
+        }
+        expression::ExpressionObject::Index(n) => {
+            check_assignment_target(asg, scoperef, &n.expr, location, typeenv);
+        }
+        expression::ExpressionObject::Slice(n) => {
+            check_assignment_target(asg, scoperef, &n.expr, location, typeenv);
+        }
+        // Writing through a reference changes the referenced variable, which is always a var
+        expression::ExpressionObject::Dereference(_) => (),
+        expression::ExpressionObject::SymbolReference(_) => {
+            if let Some(reference) = resolve_reference_expression(asg, scoperef, exprkey) {
+                let decl = get_scope(asg, &reference.scope)
+                    .symboltable
+                    .declarations
+                    .get(&reference.symbol);
+                if let Some(declared) = &decl.immutable {
+                    typeenv.errors.log_error(
+                        error::Error::at_span(
+                            errors::AssignmentToImmutable,
+                            location.span,
+                            format!(
+                                "Cannot assign to {}, it is declared as def or const",
+                                decl.symbol
+                            ),
+                        )
+                        .with_label(declared.span, format!("{} is declared here", decl.symbol)),
+                    );
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+// Only variables can be referenced, defs and consts have no storage to refer to and
+//  could be assigned to through the reference
+fn check_reference_target(asg: &asg::Asg, scoperef: &ScopeRef, exprkey: &ExpressionKey) {
+    match &get_scope(asg, scoperef).expressions.get(exprkey).object {
+        expression::ExpressionObject::SymbolReference(_) => {
+            if let Some(reference) = resolve_reference_expression(asg, scoperef, exprkey) {
+                let decl = get_scope(asg, &reference.scope)
+                    .symboltable
+                    .declarations
+                    .get(&reference.symbol);
+                if let Some(declared) = &decl.immutable {
+                    panic!(
+                        "Cannot reference {}, it is declared as def or const at {}",
+                        decl.symbol, declared
+                    );
+                }
+            }
+        }
+        _ => panic!("Only variables can be referenced"),
+    }
+}
+
+// Location of a value for reporting, place expressions are located by the symbol they
+//  are rooted in
+fn find_value_location(
+    asg: &asg::Asg,
+    scoperef: &ScopeRef,
+    exprkey: &ExpressionKey,
+) -> Option<SourceLocation> {
+    match &get_scope(asg, scoperef).expressions.get(exprkey).object {
+        expression::ExpressionObject::SymbolReference(n) => Some(n.location.clone()),
+        expression::ExpressionObject::Subscript(n) => find_value_location(asg, scoperef, &n.expr),
+        expression::ExpressionObject::Index(n) => Some(n.location.clone()),
+        expression::ExpressionObject::Slice(n) => Some(n.location.clone()),
+        expression::ExpressionObject::Dereference(n) => Some(n.location.clone()),
+        expression::ExpressionObject::Call(n) => Some(n.location.clone()),
+        expression::ExpressionObject::Match(n) => Some(n.location.clone()),
+        expression::ExpressionObject::Reference(n) => Some(n.location.clone()),
+        expression::ExpressionObject::Literal(
+            expression::expressions::Literal::FunctionLiteral(n),
+        ) => Some(n.location.clone()),
+        expression::ExpressionObject::If(n) => n
+            .branches
+            .iter()
+            .map(|(_, value)| value)
+            .chain(&n.elsebranch)
+            .find_map(|value| find_value_location(asg, scoperef, value)),
+        _ => None,
+    }
+}
+
+fn create_optional_operand_error(
+    asg: &asg::Asg,
+    scoperef: &ScopeRef,
+    exprkey: &ExpressionKey,
+    optional: &TypeId,
+) -> error::Error {
+    // Literals and operations never give optionals
+    let location = find_value_location(asg, scoperef, exprkey).unwrap();
+    let message = match resolve_reference_expression(asg, scoperef, exprkey) {
+        Some(reference) => {
+            let decl = get_scope(asg, &reference.scope)
+                .symboltable
+                .declarations
+                .get(&reference.symbol);
+            format!(
+                "Cannot use {} as a value, it can be null unless compared to null first",
+                decl.symbol
+            )
+        }
+        None => format!(
+            "Cannot use {} as a value, it can be null unless compared to null first",
+            optional.to_string()
+        ),
+    };
+    error::Error::at_span(errors::OptionalUsedAsValue, location.span, message)
+}
+
+// Reports a try in a function not returning a result, with a label at the declared outputs
+//  of the function, or at the function when it declares none
+fn create_try_error(
+    location: &SourceLocation,
+    function: &Option<(SourceLocation, Option<SourceLocation>)>,
+    details: String,
+    label: &str,
+) -> error::Error {
+    let error = error::Error::at_span(
+        errors::TryOutsideResultFunction,
+        location.span,
+        format!(
+            "Try can only be used in functions returning a result{}",
+            details
+        ),
+    );
+    match function {
+        Some((_, Some(outputs))) => error.with_label(outputs.span, label.to_string()),
+        Some((function, None)) => error.with_label(function.span, label.to_string()),
+        None => error,
+    }
+}
+
+// Finds the function a callable expression refers to, if it names one directly
+fn find_called_function<'a>(
+    asg: &'a asg::Asg,
+    scoperef: &ScopeRef,
+    callable: &ExpressionKey,
+) -> Option<&'a asg::Function> {
+    let reference = resolve_reference_expression(asg, scoperef, callable)?;
+    asg.get_defined_function(&reference)
+        .map(|f| asg.get_function(&f))
+}
+
+// Array built-ins work on dynamic arrays of any element type, so they are constrained
+//  here instead of through the signature of the callable. The length is also available
+//  for static arrays and slices.
+fn process_array_builtin_call(
+    scoperef: &ScopeRef,
+    exprkey: &ExpressionKey,
+    function: BuiltInFunction,
+    argtypes: &[TypeEntryKey],
+    typeenv: &mut TypeEnvironment,
+) -> TypeEntryKey {
+    let argcount = if function == BuiltInFunction::ArrayPush {
+        2
+    } else {
+        1
+    };
+    assert!(
+        argtypes.len() == argcount,
+        "{} expects {} arguments, got {}",
+        function.to_str(),
+        argcount,
+        argtypes.len()
+    );
+
+    let resulttype = match function {
+        BuiltInFunction::ArrayLen => TypeEntry::Id(TypeId::Primitive(PrimitiveType::U64)),
+        BuiltInFunction::ArrayPop => TypeEntry::Variable(TypeVariable::Free),
+        BuiltInFunction::ArrayPush | BuiltInFunction::ArrayClear => TypeEntry::Id(TypeId::Null),
+        _ => panic!("{} is not an array built-in", function.to_str()),
+    };
+    let result = typeenv.add_for_expression(scoperef.scope, exprkey.clone(), resulttype);
+
+    if function == BuiltInFunction::ArrayLen {
+        let element = typeenv.add_entry(TypeEntry::Variable(TypeVariable::Free));
+        typeenv.add_constraint(TypeConstraint::ElementOf {
+            entry: element,
+            array: argtypes[0],
+        });
+        return result;
+    }
+
+    // Pushed and popped values are elements of the array
+    let element = match function {
+        BuiltInFunction::ArrayPush => Some(argtypes[1]),
+        BuiltInFunction::ArrayPop => Some(result),
+        _ => None,
+    };
+    typeenv.add_constraint(TypeConstraint::ArrayOf {
+        entry: argtypes[0],
+        element,
+        length: None,
+    });
+
+    result
+}
+
+// Returns the entries of the tuple and of each of its elements
+fn process_tupleliteral_type(
+    asg: &asg::Asg,
+    scoperef: &ScopeRef,
+    exprkey: &ExpressionKey,
+    literal: &expression::expressions::literals::TupleLiteral,
+    typeenv: &mut TypeEnvironment,
+) -> (TypeEntryKey, Vec<TypeEntryKey>) {
+    let elements: Vec<_> = literal
+        .elements
+        .iter()
+        .map(|e| process_expression_type(asg, scoperef, e, typeenv))
+        .collect();
+
+    let tupletype = typeenv.add_for_expression(
+        scoperef.scope,
+        exprkey.clone(),
+        TypeEntry::Variable(TypeVariable::Free),
+    );
+    typeenv.add_constraint(TypeConstraint::TupleOf {
+        entry: tupletype,
+        elements: elements.clone(),
+    });
+
+    (tupletype, elements)
+}
+
+// Function literals are processed along with the function they are defined in, so types
+//  of captured variables are known in both
+fn process_functionliteral_type(
+    asg: &asg::Asg,
+    scoperef: &ScopeRef,
+    exprkey: &ExpressionKey,
+    literal: &expression::expressions::literals::FunctionLiteral,
+    typeenv: &mut TypeEnvironment,
+) -> TypeEntryKey {
+    let functionkey = &literal.functionkey;
+    let function = asg.get_function(&FunctionRef {
+        module: scoperef.module,
+        function: *functionkey,
+    });
+    let functionscope = ScopeRef::new(scoperef.module, function.scope);
+
+    // Parameters are declarations of the function scope
+    process_declarations(asg, &functionscope, typeenv);
+
+    let mut inputparams = Vec::new();
+    for param in &function.inparams {
+        let paramtype = typeenv.get_for_symbol(&param.symref.scope.scope, &param.symref.symbol);
+        if let Some(default) = &param.default {
+            let defaulttype = process_expression_type(asg, &functionscope, default, typeenv);
+            typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                lhs: paramtype,
+                rhs: defaulttype,
+            });
+        }
+        inputparams.push((param.symref.symbol.clone(), paramtype));
+    }
+
+    let outputparams = if function.outparams.is_empty() {
+        None
+    } else {
+        let mut outputparams = Vec::new();
+        for typeexpr in &function.outparams {
+            let exprtype = process_expression_type(asg, &functionscope, typeexpr, typeenv);
+            typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                entry: exprtype,
+                id: TypeId::Type,
+            });
+
+            let entry = typeenv.add_entry(TypeEntry::Variable(TypeVariable::Free));
+            typeenv.add_constraint(TypeConstraint::ValueOfExpr {
+                entry,
+                scope: function.scope,
+                expr: typeexpr.clone(),
+            });
+            outputparams.push(entry);
+        }
+        Some(outputparams)
+    };
+
+    typeenv.outputs.push(outputparams);
+    typeenv.functionlocations.push(Some((
+        literal.location.clone(),
+        literal.outputlocation.clone(),
+    )));
+    if let Some(body) = &function.body {
+        process_statement_body(asg, &scoperef.module, body, typeenv);
+    }
+    typeenv.functionlocations.pop();
+    let outputparams = typeenv.outputs.pop().unwrap().unwrap_or_default();
+
+    let functiontype = typeenv.add_for_expression(
+        scoperef.scope,
+        exprkey.clone(),
+        TypeEntry::Variable(TypeVariable::Free),
+    );
+    typeenv.add_constraint(TypeConstraint::FunctionOf {
+        entry: functiontype,
+        inputparams,
+        outputparams,
+    });
+    functiontype
+}
+
+// Whether the expression can only be evaluated to a type, like `u32` or `u32[4]`
+fn is_type_expression(scope: &asg::scope::Scope, exprkey: &ExpressionKey) -> bool {
+    match &scope.expressions.get(exprkey).object {
+        expression::ExpressionObject::PrimitiveType(_) => true,
+        expression::ExpressionObject::Index(n) => is_type_expression(scope, &n.expr),
+        expression::ExpressionObject::DynamicArrayType(_) => true,
+        expression::ExpressionObject::SliceType(_) => true,
+        expression::ExpressionObject::OptionalType(_) => true,
+        expression::ExpressionObject::Reference(n) => is_type_expression(scope, &n.expr),
+        expression::ExpressionObject::FunctionType(_) => true,
+        expression::ExpressionObject::TypeParameter(_) => true,
+        expression::ExpressionObject::Literal(expression::expressions::Literal::TupleLiteral(
+            n,
+        )) => n.elements.iter().all(|e| is_type_expression(scope, e)),
+        _ => false,
+    }
+}
+
+// A "[..]" type annotation only says the array is dynamic, the element type is inferred
+fn is_dynamic_array_marker(scope: &asg::scope::Scope, exprkey: &ExpressionKey) -> bool {
+    match &scope.expressions.get(exprkey).object {
+        expression::ExpressionObject::Literal(expression::expressions::Literal::ArrayLiteral(
+            n,
+        )) => n.dynamic && n.elements.is_empty(),
+        _ => false,
+    }
+}
+
+fn eval_expression_as_type(
+    asg: &asg::Asg,
+    scoperef: &ScopeRef,
+    exprkey: &ExpressionKey,
+) -> Result<TypeId, error::Error> {
+    let scope = get_scope(asg, scoperef);
+    let expression = scope.expressions.get(exprkey);
+
+    match &expression.object {
+        expression::ExpressionObject::PrimitiveType(n) => Ok(TypeId::Primitive(n.ptype)),
+        expression::ExpressionObject::ResultType(_) => {
+            Ok(TypeId::Enum(EnumDefinition::new_generic_result()))
+        }
+        expression::ExpressionObject::Index(n) => {
+            let element = eval_expression_as_type(asg, scoperef, &n.expr)?;
+            match &scope.expressions.get(&n.index).object {
+                expression::ExpressionObject::Literal(
+                    expression::expressions::Literal::IntegerLiteral(length),
+                ) => Ok(TypeId::new_array(element, length.data)),
+                _ => panic!("Array lengths must be integer literals"),
+            }
+        }
+        expression::ExpressionObject::DynamicArrayType(n) => Ok(TypeId::new_dynamic_array(
+            eval_expression_as_type(asg, scoperef, &n.element)?,
+        )),
+        expression::ExpressionObject::SliceType(n) => Ok(TypeId::new_slice(
+            eval_expression_as_type(asg, scoperef, &n.element)?,
+        )),
+        expression::ExpressionObject::Reference(n) => Ok(TypeId::new_reference(
+            eval_expression_as_type(asg, scoperef, &n.expr)?,
+        )),
+        expression::ExpressionObject::OptionalType(n) => Ok(TypeId::new_optional(
+            eval_expression_as_type(asg, scoperef, &n.target)?,
+        )),
+        expression::ExpressionObject::FunctionType(n) => Ok(TypeId::Function(FunctionSignature {
+            inputparams: n
+                .inparams
+                .iter()
+                .map(|(name, e)| {
+                    Ok((
+                        SymbolKey::from_str(name),
+                        eval_expression_as_type(asg, scoperef, e)?,
+                    ))
+                })
+                .collect::<Result<_, _>>()?,
+            outputparams: n
+                .outparams
+                .iter()
+                .map(|e| eval_expression_as_type(asg, scoperef, e))
+                .collect::<Result<_, _>>()?,
+        })),
+        expression::ExpressionObject::TypeParameter(n) => {
+            Ok(TypeId::TypeParameter(n.symbol.clone()))
+        }
+        expression::ExpressionObject::SymbolReference(_) => {
+            // Named types are evaluated from the expression they are defined as
+            let reference = resolve_reference_expression(asg, scoperef, exprkey)
+                .expect("Symbol could not be resolved!");
+            let definition = asg
+                .get_scope(&reference.scope)
+                .symboltable
+                .definitions
+                .get(&reference.symbol)
+                .unwrap_or_else(|| panic!("Symbol {:?} is not defined as a type", reference));
+            eval_expression_as_type(asg, &reference.scope, definition)
+        }
+        expression::ExpressionObject::Literal(expression::expressions::Literal::StructLiteral(
+            n,
+        )) => Ok(TypeId::Struct(StructDefinition::new(
+            n.fields
+                .iter()
+                .map(|field| {
+                    Ok((
+                        SymbolKey::from_str(&field.name),
+                        eval_expression_as_type(asg, scoperef, &field.typeexpr)?,
+                    ))
+                })
+                .collect::<Result<_, _>>()?,
+        ))),
+        expression::ExpressionObject::Literal(expression::expressions::Literal::EnumLiteral(n)) => {
+            Ok(TypeId::Enum(EnumDefinition::new(
+                n.variants
+                    .iter()
+                    .map(|variant| {
+                        Ok(EnumVariant {
+                            name: SymbolKey::from_str(&variant.name),
+                            fields: variant
+                                .fields
+                                .iter()
+                                .map(|field| {
+                                    Ok((
+                                        SymbolKey::from_str(&field.name),
+                                        eval_expression_as_type(asg, scoperef, &field.typeexpr)?,
+                                    ))
+                                })
+                                .collect::<Result<_, _>>()?,
+                        })
+                    })
+                    .collect::<Result<_, _>>()?,
+            )))
+        }
+        expression::ExpressionObject::Call(n) => {
+            // Instantiation of a generic struct or enum
+            let arguments = n
+                .args
+                .iter()
+                .map(|arg| eval_expression_as_type(asg, scoperef, arg))
+                .collect::<Result<Vec<_>, _>>()?;
+            let create_error = |message: String| {
+                error::Error::at_span(errors::InvalidTypeInstantiation, n.location.span, message)
+            };
+            match eval_expression_as_type(asg, scoperef, &n.callable)? {
+                TypeId::Struct(definition) => definition
+                    .instantiate(&arguments)
+                    .map(TypeId::Struct)
+                    .map_err(create_error),
+                TypeId::Enum(definition) => definition
+                    .instantiate(&arguments)
+                    .map(TypeId::Enum)
+                    .map_err(create_error),
+                t => Err(create_error(format!(
+                    "Cannot instantiate non-struct type {}",
+                    t.to_string()
+                ))),
+            }
+        }
+        expression::ExpressionObject::Literal(expression::expressions::Literal::TupleLiteral(
+            n,
+        )) => Ok(TypeId::Tuple(
+            n.elements
+                .iter()
+                .map(|e| eval_expression_as_type(asg, scoperef, e))
+                .collect::<Result<_, _>>()?,
+        )),
+        _ => panic!("Cannot currently evaulate non-built in type literal expressions"),
+    }
+}
+
+// Struct and enum types, including each instance of a generic one, are interned in the
+//  type store of the module using them
+fn intern_struct_types(typestore: &mut TypeStore, typeid: &TypeId) {
+    match typeid {
+        TypeId::Enum(definition) => {
+            for (_, fieldtype) in definition.variants.iter().flat_map(|v| &v.fields) {
+                intern_struct_types(typestore, fieldtype);
+            }
+            if !definition.is_generic() {
+                typestore.add(typeid.clone());
+            }
+        }
+        TypeId::Struct(definition) => {
+            for (_, fieldtype) in &definition.fields {
+                intern_struct_types(typestore, fieldtype);
+            }
+            if !definition.is_generic() {
+                typestore.add(typeid.clone());
+            }
+        }
+        TypeId::Array { element, .. }
+        | TypeId::DynamicArray { element }
+        | TypeId::Slice { element }
+        | TypeId::Reference(element) => intern_struct_types(typestore, element),
+        TypeId::Tuple(elements) => {
+            for element in elements {
+                intern_struct_types(typestore, element);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn process_declarations(asg: &asg::Asg, scoperef: &ScopeRef, typeenv: &mut TypeEnvironment) {
+    typeenv.scopes.push(scoperef.scope);
+
+    let scope = get_scope(asg, scoperef);
+    let decls = &scope.symboltable.declarations;
+    for symkey in decls.keys() {
+        let decl = decls.get(symkey);
+        if let Some(typeexpr) = decl
+            .typeexpr
+            .as_ref()
+            .filter(|e| is_dynamic_array_marker(scope, e))
+        {
+            typeenv.add_for_expression(
+                scoperef.scope,
+                typeexpr.clone(),
+                TypeEntry::Id(TypeId::Type),
+            );
+
+            let entry = typeenv.add_for_symbol(
+                scoperef.scope,
+                symkey.clone(),
+                TypeEntry::Variable(TypeVariable::Free),
+            );
+            typeenv.add_constraint(TypeConstraint::ArrayOf {
+                entry,
+                element: None,
+                length: None,
+            });
+        } else if let Some(typeexpr) = &decl.typeexpr {
+            // Process type for type expression
+            let exprtype = process_expression_type(asg, scoperef, &typeexpr, typeenv);
+
+            // Constrain it to "Type"
+            typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                entry: exprtype,
+                id: TypeId::Type,
+            });
+
+            // Add entry for symbol
+            let entry = typeenv.add_for_symbol(
+                scoperef.scope,
+                symkey.clone(),
+                TypeEntry::Variable(TypeVariable::Free),
+            );
+
+            // Constrain the type of the symbol to the _value_ of the expression
+            typeenv.add_constraint(TypeConstraint::ValueOfExpr {
+                entry,
+                scope: scoperef.scope,
+                expr: typeexpr.clone(),
+            });
+        } else {
+            // Without a type expression, the type is inferred from how the symbol is used
+            typeenv.add_for_symbol(
+                scoperef.scope,
+                symkey.clone(),
+                TypeEntry::Variable(TypeVariable::Free),
+            );
+        }
+    }
+}
+
+// Symbols the condition compares to null, "x != null", they hold a value in the branch.
+//  Both operands of an and hold, so the symbols of either are narrowed.
+fn find_narrowed_symbols(
+    asg: &asg::Asg,
+    scoperef: &ScopeRef,
+    condition: &ExpressionKey,
+) -> Vec<asg::symboltable::ResolvedSymbolReference> {
+    let scope = get_scope(asg, scoperef);
+    let is_null = |exprkey: &ExpressionKey| {
+        matches!(
+            scope.expressions.get(exprkey).object,
+            expression::ExpressionObject::Literal(expression::expressions::Literal::NullLiteral(_))
+        )
+    };
+
+    match &scope.expressions.get(condition).object {
+        expression::ExpressionObject::BinOp(n) if n.op == BinaryOperationType::NotEquals => {
+            let narrowed = if is_null(&n.rhs) {
+                resolve_reference_expression(asg, scoperef, &n.lhs)
+            } else if is_null(&n.lhs) {
+                resolve_reference_expression(asg, scoperef, &n.rhs)
+            } else {
+                None
+            };
+            narrowed.into_iter().collect()
+        }
+        expression::ExpressionObject::BinOp(n) if n.op == BinaryOperationType::And => {
+            let mut narrowed = find_narrowed_symbols(asg, scoperef, &n.lhs);
+            narrowed.extend(find_narrowed_symbols(asg, scoperef, &n.rhs));
+            narrowed
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn process_condition(
+    asg: &asg::Asg,
+    scoperef: &ScopeRef,
+    condition: &ExpressionKey,
+    typeenv: &mut TypeEnvironment,
+) {
+    let condtype = process_expression_type(asg, scoperef, condition, typeenv);
+    typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+        entry: condtype,
+        id: TypeId::Primitive(PrimitiveType::Bool),
+    });
+}
+
+// Types the expressions of the pattern and the guard of a match arm, in the scope of the
+//  arm. The pattern itself is checked against the matched value by MatchOf.
+fn process_match_arm(
+    asg: &asg::Asg,
+    armscope: &ScopeRef,
+    pattern: &expression::misc::Pattern,
+    guard: &Option<ExpressionKey>,
+    typeenv: &mut TypeEnvironment,
+) {
+    use expression::misc::Pattern;
+    match pattern {
+        Pattern::Variant(_) | Pattern::Wildcard | Pattern::Binding(_) => (),
+        Pattern::Literal(n) => {
+            process_expression_type(asg, armscope, n, typeenv);
+        }
+        Pattern::Range { start, end } => {
+            let starttype = process_expression_type(asg, armscope, start, typeenv);
+            let endtype = process_expression_type(asg, armscope, end, typeenv);
+            typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                lhs: starttype,
+                rhs: endtype,
+            });
+        }
+        Pattern::Struct(fields) => {
+            for (_, n) in fields {
+                process_match_arm(asg, armscope, n, &None, typeenv);
+            }
+        }
+        Pattern::Tuple(elements) => {
+            for n in elements {
+                process_match_arm(asg, armscope, n, &None, typeenv);
+            }
+        }
+    }
+
+    if let Some(guard) = guard {
+        process_condition(asg, armscope, guard, typeenv);
+    }
+}
+
+// Constrains the symbols and literals of the pattern to the types of the values they match
+fn check_pattern_type(
+    typeenv: &mut TypeEnvironment,
+    scope: &ScopeKey,
+    pattern: &expression::misc::Pattern,
+    id: &TypeId,
+) {
+    use expression::misc::Pattern;
+    match (pattern, id) {
+        (Pattern::Wildcard, _) => (),
+        (Pattern::Binding(symbol), _) => {
+            let entry = typeenv.get_for_symbol(scope, &SymbolKey::from_str(symbol));
+            typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                entry,
+                id: id.clone(),
+            });
+        }
+        (Pattern::Literal(n), _) => {
+            let entry = typeenv.get_for_expression(scope, n);
+            typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                entry,
+                id: id.clone(),
+            });
+        }
+        (Pattern::Range { start, .. }, _) => {
+            let ordered = unsigned_integer_types().contains(id)
+                || signed_integer_types().contains(id)
+                || id.is_primitive(&PrimitiveType::Char);
+            assert!(
+                ordered,
+                "Range patterns can only match integers and chars, got {}",
+                id.to_string()
+            );
+
+            // The end is constrained to the start when processing the arm
+            let entry = typeenv.get_for_expression(scope, start);
+            typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                entry,
+                id: id.clone(),
+            });
+        }
+        (Pattern::Struct(fields), TypeId::Struct(definition)) => {
+            for (field, n) in fields {
+                match definition.get_field(&SymbolKey::from_str(field)) {
+                    Some(fieldid) => check_pattern_type(typeenv, scope, n, fieldid),
+                    None => panic!("Struct has no field {}", field),
+                }
+            }
+        }
+        (Pattern::Struct(_), n) => {
+            panic!("Cannot match struct pattern on type {}", n.to_string())
+        }
+        (Pattern::Tuple(elements), TypeId::Tuple(ids)) => {
+            assert!(
+                elements.len() == ids.len(),
+                "Tuple pattern has {} elements, matched tuple has {}",
+                elements.len(),
+                ids.len()
+            );
+            for (n, elementid) in elements.iter().zip(ids) {
+                check_pattern_type(typeenv, scope, n, elementid);
+            }
+        }
+        (Pattern::Tuple(_), n) => panic!("Cannot match tuple pattern on type {}", n.to_string()),
+        (Pattern::Variant(pattern), TypeId::Enum(definition)) => {
+            let variant = match definition.get_variant(&SymbolKey::from_str(&pattern.name)) {
+                Some((_, variant)) => variant,
+                None => panic!("Enum has no variant {}", pattern.name),
+            };
+            assert!(
+                pattern.bindings.is_empty() || pattern.bindings.len() == variant.fields.len(),
+                "Variant {} has {} fields, got {} bindings",
+                pattern.name,
+                variant.fields.len(),
+                pattern.bindings.len()
+            );
+
+            // Bound symbols take the types of the fields
+            for (binding, (_, fieldid)) in pattern.bindings.iter().zip(&variant.fields) {
+                if let Some(binding) = binding {
+                    let entry = typeenv.get_for_symbol(scope, &SymbolKey::from_str(binding));
+                    typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                        entry,
+                        id: fieldid.clone(),
+                    });
+                }
+            }
+        }
+        (Pattern::Variant(pattern), n) => panic!(
+            "Cannot match variant {} on non-enum type {}",
+            pattern.name,
+            n.to_string()
+        ),
+    }
+}
+
+fn process_branch(
+    asg: &asg::Asg,
+    modulekey: &asg::ModuleKey,
+    branch: &statements::Branch,
+    typeenv: &mut TypeEnvironment,
+) {
+    process_declarations(asg, &ScopeRef::new(*modulekey, branch.scope), typeenv);
+    if let Some(body) = &branch.body {
+        process_statement_body(asg, modulekey, body, typeenv);
+    }
+}
+
+fn process_statement_body(
+    asg: &asg::Asg,
+    modulekey: &asg::ModuleKey,
+    body: &StatementBody,
+    typeenv: &mut TypeEnvironment,
+) {
+    let scoperef = &ScopeRef::new(*modulekey, body.scope_nonowned);
+    for stmnt in &body.statements {
+        match stmnt {
+            Statement::If(n) => {
+                for (condition, branch) in &n.branches {
+                    process_condition(asg, scoperef, condition, typeenv);
+
+                    let outer = typeenv.narrowed.len();
+                    typeenv
+                        .narrowed
+                        .extend(find_narrowed_symbols(asg, scoperef, condition));
+                    process_branch(asg, modulekey, branch, typeenv);
+                    typeenv.narrowed.truncate(outer);
+                }
+
+                if let Some(elsebranch) = &n.elsebranch {
+                    process_branch(asg, modulekey, elsebranch, typeenv);
+                }
+            }
+            Statement::Match(n) => {
+                let valuetype = process_expression_type(asg, scoperef, &n.expr, typeenv);
+                for arm in &n.arms {
+                    process_branch(asg, modulekey, &arm.body, typeenv);
+
+                    let armscope = ScopeRef::new(*modulekey, arm.body.scope);
+                    process_match_arm(asg, &armscope, &arm.pattern, &arm.guard, typeenv);
+                }
+
+                if let Some(elsebranch) = &n.elsebranch {
+                    process_branch(asg, modulekey, elsebranch, typeenv);
+                }
+
+                typeenv.add_constraint(TypeConstraint::MatchOf {
+                    value: valuetype,
+                    arms: n
+                        .arms
+                        .iter()
+                        .map(|arm| {
+                            (
+                                arm.body.scope,
+                                arm.pattern.clone(),
+                                arm.guard.is_some(),
+                                arm.location.clone(),
+                            )
+                        })
+                        .collect(),
+                    haselse: n.elsebranch.is_some(),
+                    location: n.location.clone(),
+                });
+            }
+            Statement::While(n) => {
+                process_condition(asg, scoperef, &n.condition, typeenv);
+                process_branch(asg, modulekey, &n.body, typeenv);
+            }
+            Statement::For(n) => {
+                let iterable = get_scope(asg, scoperef).expressions.get(&n.iterable);
+                let elementtype = match &iterable.object {
+                    expression::ExpressionObject::Range(_) => {
+                        process_expression_type(asg, scoperef, &n.iterable, typeenv)
+                    }
+                    // Arrays, dynamic arrays and slices are iterated over by element
+                    _ => {
+                        let arraytype =
+                            process_expression_type(asg, scoperef, &n.iterable, typeenv);
+                        let elementtype =
+                            typeenv.add_entry(TypeEntry::Variable(TypeVariable::Free));
+                        typeenv.add_constraint(TypeConstraint::ElementOf {
+                            entry: elementtype,
+                            array: arraytype,
+                        });
+                        elementtype
+                    }
+                };
+
+                process_branch(asg, modulekey, &n.body, typeenv);
+
+                // The loop symbol takes the values of the range or the elements of the array
+                let symkey = SymbolKey::from_str(n.symbol.as_str());
+                let symtype = typeenv.get_for_symbol(&n.body.scope, &symkey);
+                typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                    lhs: symtype,
+                    rhs: elementtype,
+                });
+            }
+            Statement::Break | Statement::Continue => (),
+            Statement::Return(n) => {
+                let exprtypes: Vec<_> = n
+                    .exprs
+                    .iter()
+                    .map(|expr| process_expression_type(asg, scoperef, expr, typeenv))
+                    .collect();
+
+                let outputs = match typeenv.outputs.last() {
+                    Some(Some(outputs)) => outputs.clone(),
+                    _ => {
+                        *typeenv.outputs.last_mut().unwrap() = Some(exprtypes);
+                        continue;
+                    }
+                };
+
+                if exprtypes.len() == 1 && outputs.len() > 1 {
+                    // A single tuple can be returned for several output parameters
+                    typeenv.add_constraint(TypeConstraint::TupleOf {
+                        entry: exprtypes[0],
+                        elements: outputs,
+                    });
+                } else {
+                    assert!(
+                        exprtypes.len() == outputs.len(),
+                        "Expected {} returned values, got {}",
+                        outputs.len(),
+                        exprtypes.len()
+                    );
+                    for ((output, exprtype), expr) in
+                        outputs.into_iter().zip(exprtypes).zip(&n.exprs)
+                    {
+                        typeenv.add_constraint(TypeConstraint::AssignedTo {
+                            target: output,
+                            value: exprtype,
+                            scope: scoperef.scope,
+                            expr: *expr,
+                        });
+                    }
+                }
+            }
+            Statement::Initialize(n) => {
+                let symkey = SymbolKey::from_str(n.symbol.as_str()); // TODO: Don't need complete symbol in n
+
+                // Add type for initialization expression
+                let rhs = process_expression_type(asg, scoperef, &n.expr, typeenv);
+
+                // Symbol declaration should have been added earlier
+                let lhs = typeenv.get_for_symbol(&scoperef.scope, &symkey);
+
+                // Add constraint for symboltype and expression, without a declared type
+                //  the symbol takes the type of the expression as is
+                let decl = get_scope(asg, scoperef)
+                    .symboltable
+                    .declarations
+                    .get(&symkey);
+                if decl.typeexpr.is_some() {
+                    typeenv.add_constraint(TypeConstraint::AssignedTo {
+                        target: lhs,
+                        value: rhs,
+                        scope: scoperef.scope,
+                        expr: n.expr,
+                    });
+                } else {
+                    typeenv.add_constraint(TypeConstraint::EqualsEntry { lhs, rhs });
+                }
+            }
+            Statement::Destructure(n) => {
+                let scope = get_scope(asg, scoperef);
+
+                // Elements of tuple literals are tied to the symbols directly, so they can be
+                //  inferred from how the symbols are used. Otherwise, the symbols take the
+                //  element types once the tuple type is known.
+                let elements = match &scope.expressions.get(&n.expr).object {
+                    expression::ExpressionObject::Literal(
+                        expression::expressions::Literal::TupleLiteral(literal),
+                    ) => {
+                        assert!(
+                            literal.elements.len() == n.symbols.len(),
+                            "Cannot destructure {} values into {} symbols",
+                            literal.elements.len(),
+                            n.symbols.len()
+                        );
+                        process_tupleliteral_type(asg, scoperef, &n.expr, literal, typeenv).1
+                    }
+                    _ => {
+                        let rhs = process_expression_type(asg, scoperef, &n.expr, typeenv);
+                        let elements: Vec<_> = n
+                            .symbols
+                            .iter()
+                            .map(|_| typeenv.add_entry(TypeEntry::Variable(TypeVariable::Free)))
+                            .collect();
+                        typeenv.add_constraint(TypeConstraint::TupleOf {
+                            entry: rhs,
+                            elements: elements.clone(),
+                        });
+                        elements
+                    }
+                };
+
+                for (symbol, rhs) in n.symbols.iter().zip(elements) {
+                    if let Some(symbol) = symbol {
+                        let symkey = SymbolKey::from_str(symbol.as_str());
+                        let lhs = typeenv.get_for_symbol(&scoperef.scope, &symkey);
+                        typeenv.add_constraint(TypeConstraint::EqualsEntry { lhs, rhs });
+                    }
+                }
+            }
+            Statement::Assign(n) => {
+                check_assignment_target(asg, scoperef, &n.lhs, &n.location, typeenv);
+
+                // Narrowing only applies to reads, an assigned optional takes any optional
+                let narrowed = typeenv.narrowed.clone();
+                if let expression::ExpressionObject::SymbolReference(_) =
+                    &get_scope(asg, scoperef).expressions.get(&n.lhs).object
+                {
+                    let target = resolve_reference_expression(asg, scoperef, &n.lhs);
+                    typeenv
+                        .narrowed
+                        .retain(|symbol| Some(symbol) != target.as_ref());
+                }
+                let lhs = process_expression_type(asg, scoperef, &n.lhs, typeenv);
+                typeenv.narrowed = narrowed;
+                let rhs = process_expression_type(asg, scoperef, &n.rhs, typeenv);
+                typeenv.add_constraint(TypeConstraint::AssignedTo {
+                    target: lhs,
+                    value: rhs,
+                    scope: scoperef.scope,
+                    expr: n.rhs,
+                });
+            }
+            Statement::ExpressionWrapper(n) => {
+                process_expression_type(asg, scoperef, &n.expr, typeenv);
+            }
+        }
+    }
+}
+
+// Numeric literals that were never constrained to a single type get a default one,
+//  the same ones the treewalker uses. Returns whether any type was defaulted.
+fn default_numeric_types(typeenv: &mut TypeEnvironment) -> bool {
+    let mut defaulted = false;
+    for key in typeenv.types.keys() {
+        let entry = typeenv.get_entry_mut(&key);
+        if let TypeEntry::Variable(TypeVariable::TypeSet(types)) = entry {
+            let default = [PrimitiveType::U32, PrimitiveType::S32, PrimitiveType::F64]
+                .map(TypeId::Primitive)
+                .iter()
+                .find(|t| types.contains(t))
+                .cloned();
+            if let Some(default) = default {
+                *entry = TypeEntry::Id(default);
+                defaulted = true;
+            }
+        }
+    }
+    defaulted
+}
+
+fn process_function(
+    asg: &mut asg::Asg,
+    modulekey: &asg::ModuleKey,
+    functionkey: &asg::FunctionKey,
+) -> Vec<error::Error> {
+    fn can_unify_var_id(a: &TypeVariable, b: &TypeId) -> bool {
+        match a {
+            TypeVariable::Free => true,
+            // Checked for each instantiation instead
+            TypeVariable::TypeSet(_) if matches!(b, TypeId::TypeParameter(_)) => true,
+            TypeVariable::TypeSet(n) => {
+                assert!(
+                    n.contains(&b),
+                    "Failed unify, typeSet did not contain type: {:?}, {:?}",
+                    n,
+                    a
+                );
+                true
+            }
+        }
+    }
+
+    fn resolve_substitutions(key: &TypeEntryKey, typeenv: &TypeEnvironment) -> TypeEntryKey {
+        let mut key = key;
+        loop {
+            let entry = typeenv.get_entry(key);
+            match entry {
+                TypeEntry::Substituted(n) => key = n,
+                _ => return *key,
+            }
+        }
+    }
+
+    let mut typeenv = TypeEnvironment::new();
+    let typeenv = &mut typeenv;
+
+    // Decls and statements, including those of nested scopes
+    {
+        let module = asg.modulestore.get(&modulekey);
+        let function = module.functionstore.get(&functionkey);
+
+        process_declarations(asg, &ScopeRef::new(*modulekey, function.scope), typeenv);
+
+        typeenv.outputs.push(None);
+        typeenv.functionlocations.push(None);
+        if let Some(body) = &function.body {
+            process_statement_body(asg, modulekey, body, typeenv);
+        }
+        typeenv.functionlocations.pop();
+        typeenv.outputs.pop();
+    }
+
+    // At this point we should be ready to start processing type constraints
+    let mut iteration = 0;
+
+    // Values assigned to targets of unknown types are assumed not to be wrapped in optionals
+    //  once nothing else can be resolved, before array literals are made static
+    let mut resolve_assignments = false;
+
+    // Array literals are only made static once nothing else requires them to be dynamic
+    let mut resolve_static_arrays = false;
+    loop {
+        let last_constraints = typeenv.constraints.keys();
 
         println!("Iteration {}: {:?}", iteration, typeenv);
 
+        // Some constraints have to wait for other types to be resolved first, if nothing
+        //  but those are left we are stuck
+        let mut processed = 0;
+        let mut deferred = 0;
+
         for constraintkey in typeenv.constraints.keys() {
             fn resolve_type<'a>(key: &TypeEntryKey, typeenv: &'a TypeEnvironment) -> &'a TypeId {
                 let subst = typeenv.get_entry(&resolve_substitutions(key, typeenv));
@@ -425,14 +2347,24 @@ fn process_function(
             let c = typeenv.remove_constraint(&constraintkey);
 
             // Note: Make sure to resolve substitutions for keys, to avoid unifying stale variables
+            if c.is_some() {
+                processed += 1;
+            }
             match c {
                 Some(n) => match n {
                     TypeConstraint::EqualsEntry {
                         lhs: lhskey,
                         rhs: rhskey,
                     } => {
-                        let lhs = typeenv.get_entry(&resolve_substitutions(&lhskey, &typeenv));
-                        let rhs = typeenv.get_entry(&resolve_substitutions(&rhskey, &typeenv));
+                        // Unify the resolved entries, so whole substitution chains are updated
+                        let lhskey = resolve_substitutions(&lhskey, &typeenv);
+                        let rhskey = resolve_substitutions(&rhskey, &typeenv);
+                        if lhskey == rhskey {
+                            continue;
+                        }
+
+                        let lhs = typeenv.get_entry(&lhskey);
+                        let rhs = typeenv.get_entry(&rhskey);
 
                         match (lhs, rhs) {
                             (TypeEntry::Id(lhs), TypeEntry::Id(rhs)) => {
@@ -490,138 +2422,1063 @@ fn process_function(
                                     }
                                 }
                             }
-                            (_, TypeEntry::Substituted(_)) | (TypeEntry::Substituted(_), _) => {
+                            (_, TypeEntry::Substituted(_)) | (TypeEntry::Substituted(_), _) => {
+                                panic!("Substitutions not allowed!")
+                            }
+                        }
+                    }
+                    TypeConstraint::EqualsTypeId { entry, id } => {
+                        let entry = resolve_substitutions(&entry, &typeenv);
+                        let lhs = typeenv.get_entry(&entry);
+                        match lhs {
+                            TypeEntry::Id(n) => {
+                                assert!(*n == id, "Type mismatch: {:?}, {:?}", n, id);
+                            }
+                            TypeEntry::Variable(n) => {
+                                if can_unify_var_id(&n, &id) {
+                                    // Typecheck passed, assign the variable to typeid
+                                    // TODO: Typeid cloned here :(
+                                    *typeenv.get_entry_mut(&entry) = TypeEntry::Id(id.clone());
+                                } else {
+                                    panic!("Cannot unify {:?} and {:?}", n, id)
+                                }
+                            }
+                            TypeEntry::Substituted(_) => panic!("Substitutions not allowed!"),
+                        };
+                    }
+                    TypeConstraint::EqualsCallParam {
+                        call: callentrykey,
+                        param: paramindex,
+                        arg: argentrykey,
+                        scope,
+                        expr,
+                    } => {
+                        let callentrykey = resolve_substitutions(&callentrykey, &typeenv);
+                        if let TypeEntry::Variable(_) = typeenv.get_entry(&callentrykey) {
+                            // Wait for the type of the callable to be known
+                            typeenv.add_constraint(TypeConstraint::EqualsCallParam {
+                                call: callentrykey,
+                                param: paramindex,
+                                arg: argentrykey,
+                                scope,
+                                expr,
+                            });
+                            deferred += 1;
+                            continue;
+                        }
+                        let callabletype = resolve_type(&callentrykey, &typeenv);
+
+                        match callabletype {
+                            TypeId::BuiltInFunction(n) => match n {
+                                BuiltInFunction::ArrayLen
+                                | BuiltInFunction::ArrayPush
+                                | BuiltInFunction::ArrayPop
+                                | BuiltInFunction::ArrayClear
+                                | BuiltInFunction::ArrayNew
+                                | BuiltInFunction::Allocate => {
+                                    // Constrained when processing the call
+                                }
+                                BuiltInFunction::PrintFormat => {
+                                    // Generate new constraints for each argument
+                                    // Print format first arg is string, rest "Any"
+                                    let paramentry = if paramindex == 0 {
+                                        Some(TypeId::Primitive(PrimitiveType::StaticStringUtf8))
+                                    } else {
+                                        None
+                                    };
+                                    match paramentry {
+                                        Some(n) => {
+                                            // Type known, add concrete constraint
+                                            typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                                                entry: argentrykey,
+                                                id: n,
+                                            });
+                                        }
+                                        None => {
+                                            // Type any, no new contraints necessary
+                                        }
+                                    }
+                                }
+                            },
+                            TypeId::Function(signature) => {
+                                let id = match signature.inputparams.get(paramindex) {
+                                    Some((_, id)) => id.clone(),
+                                    None => panic!(
+                                        "Too many arguments, expected {}",
+                                        signature.inputparams.len()
+                                    ),
+                                };
+                                let target = typeenv.add_entry(TypeEntry::Id(id));
+                                typeenv.add_constraint(TypeConstraint::AssignedTo {
+                                    target,
+                                    value: argentrykey,
+                                    scope,
+                                    expr,
+                                });
+                            }
+                            TypeId::Type => {
+                                // Generic struct instantiation, the arguments are types
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                                    entry: argentrykey,
+                                    id: TypeId::Type,
+                                });
+                            }
+                            n => panic!("Cannot call non-function type {:?}", n),
+                        }
+                    }
+                    TypeConstraint::ValueOfExpr { entry, scope, expr } => {
+                        let scoperef = ScopeRef::new(*modulekey, scope);
+                        let typeid = match eval_expression_as_type(asg, &scoperef, &expr) {
+                            Ok(typeid) => typeid,
+                            Err(error) => {
+                                // The symbol is reported and left without a known type
+                                typeenv.errors.log_error(error);
+                                continue;
+                            }
+                        };
+
+                        // Generic structs and enums can only be named through an instantiation
+                        let generic = match &typeid {
+                            TypeId::Struct(definition) => definition.is_generic(),
+                            TypeId::Enum(definition) => definition.is_generic(),
+                            _ => false,
+                        };
+                        if generic {
+                            let instantiated = matches!(
+                                get_scope(asg, &scoperef).expressions.get(&expr).object,
+                                expression::ExpressionObject::Call(_)
+                            );
+                            assert!(
+                                instantiated,
+                                "Generic type requires type arguments: {}",
+                                typeid.to_string()
+                            );
+                        }
+                        intern_struct_types(
+                            &mut asg.modulestore.get_mut(&modulekey).typestore,
+                            &typeid,
+                        );
+
+                        let entry = resolve_substitutions(&entry, &typeenv);
+                        let lhs = typeenv.get_entry(&entry);
+
+                        match lhs {
+                            TypeEntry::Id(n) => {
+                                assert!(*n == typeid, "Type mismatch: {:?}, {:?}", n, typeid);
+                            }
+                            TypeEntry::Variable(n) => {
+                                if can_unify_var_id(&n, &typeid) {
+                                    // Typecheck passed, assign the variable to typeid
+                                    *typeenv.get_entry_mut(&entry) = TypeEntry::Id(typeid);
+                                } else {
+                                    panic!("Cannot unify {:?} and {:?}", n, typeid)
+                                }
+                            }
+                            TypeEntry::Substituted(_) => panic!("Substitutions not allowed!"),
+                        };
+                    }
+                    TypeConstraint::TypeOfSymbol {
+                        entry,
+                        scope: scopekey,
+                        symref: symrefkey,
+                    } => {
+                        let module = asg.modulestore.get(&modulekey);
+                        let scope = module.scopestore.get(&scopekey);
+                        let symref = scope.symboltable.references.get(&symrefkey);
+
+                        match symref {
+                            SymbolReference::ResolvedReference(_) => {
+                                panic!("Symbol was already resolved!")
+                            }
+                            SymbolReference::UnresolvedReference(n) => {
+                                // Lookup symbol
+                                let scoperef = ScopeRef::new(*modulekey, scopekey);
+                                let resolved_ref = lookup_symbol(asg, n, &scoperef);
+
+                                match &resolved_ref {
+                                    SymbolReference::ResolvedReference(n) => {
+                                        // Queue a new constraint with the resolved symbol entry
+                                        let rhs = typeenv.get_for_symbol(&n.scope.scope, &n.symbol);
+                                        typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                                            lhs: entry,
+                                            rhs: rhs,
+                                        });
+                                    }
+                                    SymbolReference::UnresolvedReference(_) => {
+                                        panic!("Symbol could not be resolved!")
+                                    }
+                                }
+
+                                // Update reference in asg
+                                let modmut = asg.modulestore.get_mut(&modulekey);
+                                let scopemut = modmut.scopestore.get_mut(&scoperef.scope);
+                                let symrefmod = scopemut.symboltable.references.get_mut(&symrefkey);
+                                *symrefmod = resolved_ref;
+                            }
+                        }
+                    }
+                    TypeConstraint::ArrayOf {
+                        entry,
+                        element,
+                        length,
+                    } => {
+                        let entry = resolve_substitutions(&entry, &typeenv);
+                        let element = element.map(|e| resolve_substitutions(&e, &typeenv));
+                        let elementid = match element.map(|e| typeenv.get_entry(&e)) {
+                            Some(TypeEntry::Id(n)) => Some(n.clone()),
+                            _ => None,
+                        };
+
+                        match (typeenv.get_entry(&entry), elementid) {
+                            (
+                                TypeEntry::Id(TypeId::Array {
+                                    element: arrayelement,
+                                    length: arraylength,
+                                }),
+                                _,
+                            ) => {
+                                assert!(
+                                    Some(*arraylength) == length,
+                                    "Array length mismatch: {}, {:?}",
+                                    arraylength,
+                                    length
+                                );
+                                if let Some(element) = element {
+                                    typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                                        entry: element,
+                                        id: *arrayelement.clone(),
+                                    });
+                                }
+                            }
+                            (
+                                TypeEntry::Id(TypeId::DynamicArray {
+                                    element: arrayelement,
+                                }),
+                                _,
+                            ) => {
+                                // Static literals are accepted as initial contents
+                                if let Some(element) = element {
+                                    typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                                        entry: element,
+                                        id: *arrayelement.clone(),
+                                    });
+                                }
+                            }
+                            (TypeEntry::Id(n), _) => panic!("Type mismatch: {:?}, array", n),
+                            (TypeEntry::Variable(_), Some(_))
+                                if length.is_some() && !resolve_static_arrays =>
+                            {
+                                // The literal could still be used as a dynamic array
+                                typeenv.add_constraint(TypeConstraint::ArrayOf {
+                                    entry,
+                                    element,
+                                    length,
+                                });
+                                deferred += 1;
+                            }
+                            (TypeEntry::Variable(n), Some(elementid)) => {
+                                // Arrays also required to be dynamic elsewhere become dynamic
+                                let dynamic = length.is_none()
+                                    || typeenv.constraints.values().iter().any(|c| match c {
+                                        Some(TypeConstraint::ArrayOf {
+                                            entry: other,
+                                            length: None,
+                                            ..
+                                        }) => resolve_substitutions(other, &typeenv) == entry,
+                                        _ => false,
+                                    });
+                                let id = match length {
+                                    Some(length) if !dynamic => {
+                                        TypeId::new_array(elementid, length)
+                                    }
+                                    _ => TypeId::new_dynamic_array(elementid),
+                                };
+                                if can_unify_var_id(&n, &id) {
+                                    *typeenv.get_entry_mut(&entry) = TypeEntry::Id(id);
+                                }
+                            }
+                            (TypeEntry::Variable(_), None) => {
+                                // Wait for either the array or the element type to be known
+                                typeenv.add_constraint(TypeConstraint::ArrayOf {
+                                    entry,
+                                    element,
+                                    length,
+                                });
+                                deferred += 1;
+                            }
+                            (TypeEntry::Substituted(_), _) => {
+                                panic!("Substitutions not allowed!")
+                            }
+                        }
+                    }
+                    TypeConstraint::ElementOf { entry, array } => {
+                        let array = resolve_substitutions(&array, &typeenv);
+                        match typeenv.get_entry(&array) {
+                            TypeEntry::Id(TypeId::Array { element, .. })
+                            | TypeEntry::Id(TypeId::DynamicArray { element })
+                            | TypeEntry::Id(TypeId::Slice { element }) => {
+                                let id = *element.clone();
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId { entry, id });
+                            }
+                            TypeEntry::Id(n) => panic!("Cannot index non-array type {:?}", n),
+                            TypeEntry::Variable(_) => {
+                                // Wait for the array type to be known
+                                typeenv.add_constraint(TypeConstraint::ElementOf { entry, array });
+                                deferred += 1;
+                            }
+                            TypeEntry::Substituted(_) => panic!("Substitutions not allowed!"),
+                        }
+                    }
+                    TypeConstraint::SliceOf { entry, array } => {
+                        let array = resolve_substitutions(&array, &typeenv);
+                        match typeenv.get_entry(&array) {
+                            TypeEntry::Id(TypeId::Array { element, .. })
+                            | TypeEntry::Id(TypeId::DynamicArray { element })
+                            | TypeEntry::Id(TypeId::Slice { element }) => {
+                                let id = TypeId::new_slice(*element.clone());
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId { entry, id });
+                            }
+                            TypeEntry::Id(n) => panic!("Cannot slice non-array type {:?}", n),
+                            TypeEntry::Variable(_) => {
+                                // Wait for the array type to be known
+                                typeenv.add_constraint(TypeConstraint::SliceOf { entry, array });
+                                deferred += 1;
+                            }
+                            TypeEntry::Substituted(_) => panic!("Substitutions not allowed!"),
+                        }
+                    }
+                    TypeConstraint::ReferenceOf {
+                        entry,
+                        target,
+                        scope,
+                        expr,
+                    } => {
+                        let target = resolve_substitutions(&target, &typeenv);
+                        match typeenv.get_entry(&target) {
+                            // Reference types are types themselves
+                            TypeEntry::Id(TypeId::Type) => {
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                                    entry,
+                                    id: TypeId::Type,
+                                });
+                            }
+                            TypeEntry::Id(n) => {
+                                let id = TypeId::new_reference(n.clone());
+                                check_reference_target(
+                                    asg,
+                                    &ScopeRef::new(*modulekey, scope),
+                                    &expr,
+                                );
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId { entry, id });
+                            }
+                            TypeEntry::Variable(_) => {
+                                // Wait for the type of the referenced value to be known
+                                typeenv.add_constraint(TypeConstraint::ReferenceOf {
+                                    entry,
+                                    target,
+                                    scope,
+                                    expr,
+                                });
+                                deferred += 1;
+                            }
+                            TypeEntry::Substituted(_) => panic!("Substitutions not allowed!"),
+                        }
+                    }
+                    TypeConstraint::DereferenceOf {
+                        entry,
+                        reference,
+                        location,
+                    } => {
+                        let reference = resolve_substitutions(&reference, &typeenv);
+                        match typeenv.get_entry(&reference) {
+                            TypeEntry::Id(TypeId::Reference(target)) => {
+                                let id = *target.clone();
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId { entry, id });
+                            }
+                            TypeEntry::Id(n @ TypeId::Optional(_)) => {
+                                let message = format!(
+                                    "Cannot dereference {}, it can be null unless compared to null first",
+                                    n.to_string()
+                                );
+                                typeenv.errors.log_error(error::Error::at_span(
+                                    errors::OptionalUsedAsValue,
+                                    location.span,
+                                    message,
+                                ));
+                            }
+                            TypeEntry::Id(n) => {
+                                let message = format!(
+                                    "Cannot dereference non-reference type {}",
+                                    n.to_string()
+                                );
+                                typeenv.errors.log_error(error::Error::at_span(
+                                    errors::DereferenceOfNonReference,
+                                    location.span,
+                                    message,
+                                ));
+                            }
+                            TypeEntry::Variable(_) => {
+                                // Wait for the reference type to be known
+                                typeenv.add_constraint(TypeConstraint::DereferenceOf {
+                                    entry,
+                                    reference,
+                                    location,
+                                });
+                                deferred += 1;
+                            }
+                            TypeEntry::Substituted(_) => panic!("Substitutions not allowed!"),
+                        }
+                    }
+                    TypeConstraint::TryOf {
+                        entry,
+                        result,
+                        output,
+                        scope,
+                        expr,
+                        function,
+                    } => {
+                        let result = resolve_substitutions(&result, &typeenv);
+                        let output = resolve_substitutions(&output, &typeenv);
+                        match (typeenv.get_entry(&result), typeenv.get_entry(&output)) {
+                            (TypeEntry::Id(resultid), TypeEntry::Id(outputid)) => {
+                                let get_result_types = |id: &TypeId| match id {
+                                    TypeId::Enum(definition) => definition
+                                        .get_result_types()
+                                        .map(|(v, e)| (v.clone(), e.clone())),
+                                    _ => None,
+                                };
+                                let (value, error) =
+                                    get_result_types(resultid).unwrap_or_else(|| {
+                                        panic!(
+                                            "Only results can be tried, not {}",
+                                            resultid.to_string()
+                                        )
+                                    });
+                                let outputerror = match get_result_types(outputid) {
+                                    Some((_, outputerror)) => outputerror,
+                                    None => {
+                                        let location = match &get_scope(
+                                            asg,
+                                            &ScopeRef::new(*modulekey, scope),
+                                        )
+                                        .expressions
+                                        .get(&expr)
+                                        .object
+                                        {
+                                            ExpressionObject::Try(n) => n.location.clone(),
+                                            _ => panic!("Tried expression is not a try!"),
+                                        };
+                                        let output = outputid.to_string();
+                                        let error = create_try_error(
+                                            &location,
+                                            &function,
+                                            format!(", not {}", output),
+                                            &format!("The function returns {}", output),
+                                        );
+                                        typeenv.errors.log_error(error);
+                                        continue;
+                                    }
+                                };
+                                assert!(
+                                    outputerror == error,
+                                    "Cannot propagate error {} from a function with error {}",
+                                    error.to_string(),
+                                    outputerror.to_string()
+                                );
+
+                                // Kept to build the returned error result
+                                let outputid = outputid.clone();
+                                typeenv.propagatederrors.insert((scope, expr), outputid);
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                                    entry,
+                                    id: value,
+                                });
+                            }
+                            (TypeEntry::Substituted(_), _) | (_, TypeEntry::Substituted(_)) => {
+                                panic!("Substitutions not allowed!")
+                            }
+                            _ => {
+                                // Wait for the result and the output to be known
+                                typeenv.add_constraint(TypeConstraint::TryOf {
+                                    entry,
+                                    result,
+                                    output,
+                                    scope,
+                                    expr,
+                                    function,
+                                });
+                                deferred += 1;
+                            }
+                        }
+                    }
+                    TypeConstraint::AssignedTo {
+                        target,
+                        value,
+                        scope,
+                        expr,
+                    } => {
+                        let target = resolve_substitutions(&target, &typeenv);
+                        let value = resolve_substitutions(&value, &typeenv);
+                        let is_null = matches!(
+                            get_scope(asg, &ScopeRef::new(*modulekey, scope))
+                                .expressions
+                                .get(&expr)
+                                .object,
+                            expression::ExpressionObject::Literal(
+                                expression::expressions::Literal::NullLiteral(_)
+                            )
+                        );
+
+                        match (typeenv.get_entry(&target), typeenv.get_entry(&value)) {
+                            // Plain values, including numeric literals, are wrapped
+                            (
+                                TypeEntry::Id(TypeId::Optional(optional)),
+                                TypeEntry::Id(_) | TypeEntry::Variable(TypeVariable::TypeSet(_)),
+                            ) if !matches!(
+                                typeenv.get_entry(&value),
+                                TypeEntry::Id(TypeId::Optional(_))
+                            ) =>
+                            {
+                                let id = *optional.clone();
+                                typeenv
+                                    .optionalvalues
+                                    .insert((scope, expr), TypeId::new_optional(id.clone()));
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                                    entry: value,
+                                    id,
+                                });
+                            }
+                            // Wait for the types to tell if the value is wrapped, unless
+                            //  nothing else is left to resolve
+                            (
+                                TypeEntry::Id(TypeId::Optional(_))
+                                | TypeEntry::Variable(TypeVariable::Free),
+                                TypeEntry::Variable(_),
+                            )
+                            | (TypeEntry::Variable(TypeVariable::Free), TypeEntry::Id(_))
+                                if !is_null && !resolve_assignments =>
+                            {
+                                typeenv.add_constraint(TypeConstraint::AssignedTo {
+                                    target,
+                                    value,
+                                    scope,
+                                    expr,
+                                });
+                                deferred += 1;
+                            }
+                            _ => {
+                                typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                                    lhs: target,
+                                    rhs: value,
+                                });
+                            }
+                        }
+                    }
+                    TypeConstraint::OperandOf {
+                        entry,
+                        operand,
+                        scope,
+                        expr,
+                    } => {
+                        let operand = resolve_substitutions(&operand, &typeenv);
+                        match typeenv.get_entry(&operand) {
+                            TypeEntry::Id(n @ TypeId::Optional(_)) => {
+                                let scoperef = ScopeRef::new(*modulekey, scope);
+                                let error = create_optional_operand_error(asg, &scoperef, &expr, n);
+                                typeenv.errors.log_error(error);
+                            }
+                            // Wait for the operand to tell if it is an optional, unless nothing
+                            //  else is left to resolve
+                            TypeEntry::Variable(TypeVariable::Free) if !resolve_assignments => {
+                                typeenv.add_constraint(TypeConstraint::OperandOf {
+                                    entry,
+                                    operand,
+                                    scope,
+                                    expr,
+                                });
+                                deferred += 1;
+                            }
+                            // Type sets only hold primitive types
+                            _ => {
+                                typeenv.add_constraint(TypeConstraint::EqualsEntry {
+                                    lhs: entry,
+                                    rhs: operand,
+                                });
+                            }
+                        }
+                    }
+                    TypeConstraint::NullOf { entry } => {
+                        let entry = resolve_substitutions(&entry, &typeenv);
+                        match typeenv.get_entry(&entry) {
+                            TypeEntry::Id(TypeId::Optional(_)) => (),
+                            TypeEntry::Id(n) => panic!(
+                                "Null can only be used as a value of an optional type, not {}",
+                                n.to_string()
+                            ),
+                            TypeEntry::Variable(_) => {
+                                // Wait for the optional the null is used as
+                                typeenv.add_constraint(TypeConstraint::NullOf { entry });
+                                deferred += 1;
+                            }
+                            TypeEntry::Substituted(_) => panic!("Substitutions not allowed!"),
+                        }
+                    }
+                    TypeConstraint::UnwrappedOf { entry, optional } => {
+                        let optional = resolve_substitutions(&optional, &typeenv);
+                        match typeenv.get_entry(&optional) {
+                            TypeEntry::Id(TypeId::Optional(target)) => {
+                                let id = *target.clone();
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId { entry, id });
+                            }
+                            TypeEntry::Id(n) => panic!(
+                                "Only optionals can be compared to null, not {}",
+                                n.to_string()
+                            ),
+                            TypeEntry::Variable(_) => {
+                                // Wait for the type of the optional to be known
+                                typeenv.add_constraint(TypeConstraint::UnwrappedOf {
+                                    entry,
+                                    optional,
+                                });
+                                deferred += 1;
+                            }
+                            TypeEntry::Substituted(_) => panic!("Substitutions not allowed!"),
+                        }
+                    }
+                    TypeConstraint::TupleOf { entry, elements } => {
+                        let entry = resolve_substitutions(&entry, &typeenv);
+                        let elementids: Option<Vec<TypeId>> = elements
+                            .iter()
+                            .map(
+                                |e| match typeenv.get_entry(&resolve_substitutions(e, &typeenv)) {
+                                    TypeEntry::Id(n) => Some(n.clone()),
+                                    _ => None,
+                                },
+                            )
+                            .collect();
+
+                        match (typeenv.get_entry(&entry), elementids) {
+                            (TypeEntry::Id(TypeId::Tuple(ids)), _) => {
+                                assert!(
+                                    ids.len() == elements.len(),
+                                    "Tuple length mismatch: {}, {}",
+                                    ids.len(),
+                                    elements.len()
+                                );
+                                for (element, id) in elements.iter().zip(ids.clone()) {
+                                    typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                                        entry: *element,
+                                        id,
+                                    });
+                                }
+                            }
+                            (TypeEntry::Id(n), _) => panic!("Type mismatch: {:?}, tuple", n),
+                            (TypeEntry::Variable(_), Some(ids)) => {
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                                    entry,
+                                    id: TypeId::Tuple(ids),
+                                });
+                            }
+                            (TypeEntry::Variable(_), None) => {
+                                // Wait for either the tuple or all element types to be known
+                                typeenv.add_constraint(TypeConstraint::TupleOf { entry, elements });
+                                deferred += 1;
+                            }
+                            (TypeEntry::Substituted(_), _) => {
                                 panic!("Substitutions not allowed!")
                             }
                         }
                     }
-                    TypeConstraint::EqualsTypeId { entry, id } => {
-                        let lhs = typeenv.get_entry(&resolve_substitutions(&entry, &typeenv));
-                        match lhs {
-                            TypeEntry::Id(n) => {
-                                assert!(*n == id, "Type mismatch: {:?}, {:?}", n, id);
+                    TypeConstraint::FunctionOf {
+                        entry,
+                        inputparams,
+                        outputparams,
+                    } => {
+                        let resolve_id = |key: &TypeEntryKey| match typeenv
+                            .get_entry(&resolve_substitutions(key, &typeenv))
+                        {
+                            TypeEntry::Id(n) => Some(n.clone()),
+                            _ => None,
+                        };
+                        let inputids: Option<Vec<_>> = inputparams
+                            .iter()
+                            .map(|(symbol, key)| resolve_id(key).map(|id| (symbol.clone(), id)))
+                            .collect();
+                        let outputids: Option<Vec<_>> =
+                            outputparams.iter().map(|key| resolve_id(key)).collect();
+
+                        match (inputids, outputids) {
+                            (Some(inputparams), Some(outputparams)) => {
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                                    entry,
+                                    id: TypeId::Function(FunctionSignature {
+                                        inputparams,
+                                        outputparams,
+                                    }),
+                                });
                             }
-                            TypeEntry::Variable(n) => {
-                                if can_unify_var_id(&n, &id) {
-                                    // Typecheck passed, assign the variable to typeid
-                                    // TODO: Typeid cloned here :(
-                                    *typeenv.get_entry_mut(&entry) = TypeEntry::Id(id.clone());
-                                } else {
-                                    panic!("Cannot unify {:?} and {:?}", n, id)
-                                }
+                            _ => {
+                                // Wait for all parameter types to be known
+                                typeenv.add_constraint(TypeConstraint::FunctionOf {
+                                    entry,
+                                    inputparams,
+                                    outputparams,
+                                });
+                                deferred += 1;
                             }
-                            TypeEntry::Substituted(_) => panic!("Substitutions not allowed!"),
-                        };
+                        }
                     }
-                    TypeConstraint::EqualsCallParam {
-                        call: callentrykey,
-                        param: paramindex,
-                        arg: argentrykey,
+                    TypeConstraint::FieldOf {
+                        entry,
+                        value,
+                        field,
+                        scope,
+                        expr,
                     } => {
-                        let callabletype =
-                            resolve_type(&resolve_substitutions(&callentrykey, &typeenv), &typeenv);
-
-                        match callabletype {
-                            TypeId::BuiltInFunction(n) => match n {
-                                BuiltInFunction::PrintFormat => {
-                                    // Generate new constraints for each argument
-                                    // Print format first arg is string, rest "Any"
-                                    let paramentry = if paramindex == 0 {
-                                        Some(TypeId::Primitive(PrimitiveType::StaticStringUtf8))
-                                    } else {
-                                        None
-                                    };
-                                    match paramentry {
-                                        Some(n) => {
-                                            // Type known, add concrete constraint
-                                            typeenv.add_constraint(TypeConstraint::EqualsTypeId {
-                                                entry: argentrykey,
-                                                id: n,
-                                            });
-                                        }
-                                        None => {
-                                            // Type any, no new contraints necessary
+                        let value = resolve_substitutions(&value, &typeenv);
+                        match typeenv.get_entry(&value) {
+                            TypeEntry::Id(TypeId::Struct(definition)) => {
+                                let id = match definition.get_field(&SymbolKey::from_str(&field)) {
+                                    Some(id) => id.clone(),
+                                    None => panic!("Struct has no field {}", field),
+                                };
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId { entry, id });
+                            }
+                            TypeEntry::Id(TypeId::Type) => {
+                                // Variants without fields are values of the enum, the others
+                                //  construct one when called
+                                let scoperef = ScopeRef::new(*modulekey, scope);
+                                let definition =
+                                    match eval_expression_as_type(asg, &scoperef, &expr) {
+                                        Ok(TypeId::Enum(definition)) => definition,
+                                        Err(error) => {
+                                            typeenv.errors.log_error(error);
+                                            continue;
                                         }
-                                    }
-                                }
-                            },
-                            _ => panic!("Only built-in function calls supported!"),
+                                        Ok(n) => panic!(
+                                            "Cannot access variant {} of non-enum type {}",
+                                            field,
+                                            n.to_string()
+                                        ),
+                                    };
+                                assert!(
+                                    !definition.is_generic(),
+                                    "Generic enum requires type arguments"
+                                );
+                                let variant =
+                                    match definition.get_variant(&SymbolKey::from_str(&field)) {
+                                        Some((_, variant)) => variant.clone(),
+                                        None => panic!("Enum has no variant {}", field),
+                                    };
+                                let enumtype = TypeId::Enum(definition);
+                                let id = if variant.fields.is_empty() {
+                                    enumtype
+                                } else {
+                                    TypeId::Function(FunctionSignature {
+                                        inputparams: variant.fields,
+                                        outputparams: vec![enumtype],
+                                    })
+                                };
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId { entry, id });
+                            }
+                            TypeEntry::Id(n) => {
+                                panic!("Cannot access field {} of non-struct type {:?}", field, n)
+                            }
+                            TypeEntry::Variable(_) => {
+                                // Wait for the struct type to be known
+                                typeenv.add_constraint(TypeConstraint::FieldOf {
+                                    entry,
+                                    value,
+                                    field,
+                                    scope,
+                                    expr,
+                                });
+                                deferred += 1;
+                            }
+                            TypeEntry::Substituted(_) => panic!("Substitutions not allowed!"),
                         }
                     }
-                    TypeConstraint::ValueOfExpr { entry, expr } => {
-                        let module = asg.modulestore.get(&modulekey);
-                        let function = module.functionstore.get(&functionkey);
-                        let scope = module.scopestore.get(&function.scope);
-                        let typeid = eval_expression_as_type(asg, scope, &expr);
+                    TypeConstraint::ConstructionOf {
+                        entry,
+                        fields,
+                        scope,
+                        expr,
+                    } => {
+                        let scoperef = ScopeRef::new(*modulekey, scope);
+                        let definition = match eval_expression_as_type(asg, &scoperef, &expr) {
+                            Ok(TypeId::Struct(definition)) => definition,
+                            Err(error) => {
+                                typeenv.errors.log_error(error);
+                                continue;
+                            }
+                            Ok(n) => panic!("Cannot construct non-struct type {}", n.to_string()),
+                        };
+                        assert!(
+                            !definition.is_generic(),
+                            "Generic struct requires type arguments"
+                        );
 
-                        let lhs = typeenv.get_entry(&resolve_substitutions(&entry, &typeenv));
+                        for (field, value) in &fields {
+                            let id = match definition.get_field(&SymbolKey::from_str(field)) {
+                                Some(id) => id.clone(),
+                                None => panic!("Struct has no field {}", field),
+                            };
+                            let target = typeenv.add_entry(TypeEntry::Id(id));
+                            let valuetype = typeenv.get_for_expression(&scope, value);
+                            typeenv.add_constraint(TypeConstraint::AssignedTo {
+                                target,
+                                value: valuetype,
+                                scope,
+                                expr: *value,
+                            });
+                        }
+                        for (symbol, id) in &definition.fields {
+                            let initialized = fields
+                                .iter()
+                                .any(|(field, _)| SymbolKey::from_str(field) == *symbol);
+                            assert!(
+                                initialized || id.has_default_value(),
+                                "Field {} of type {} has no default value and must be initialized",
+                                symbol,
+                                id.to_string()
+                            );
+                        }
 
-                        match lhs {
-                            TypeEntry::Id(n) => {
-                                assert!(*n == typeid, "Type mismatch: {:?}, {:?}", n, typeid);
+                        typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                            entry,
+                            id: TypeId::Struct(definition),
+                        });
+                    }
+                    TypeConstraint::ReturnOf { entry, function } => {
+                        let function = resolve_substitutions(&function, &typeenv);
+                        match typeenv.get_entry(&function) {
+                            TypeEntry::Id(TypeId::Function(signature)) => {
+                                let id = signature.return_type();
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId { entry, id });
                             }
-                            TypeEntry::Variable(n) => {
-                                if can_unify_var_id(&n, &typeid) {
-                                    // Typecheck passed, assign the variable to typeid
-                                    *typeenv.get_entry_mut(&entry) = TypeEntry::Id(typeid);
-                                } else {
-                                    panic!("Cannot unify {:?} and {:?}", n, typeid)
-                                }
+                            TypeEntry::Id(TypeId::Type) => {
+                                // Instantiating a generic struct gives a type
+                                typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                                    entry,
+                                    id: TypeId::Type,
+                                });
+                            }
+                            TypeEntry::Id(n) => panic!("Cannot call non-function type {:?}", n),
+                            TypeEntry::Variable(_) => {
+                                // Wait for the signature of the function to be known
+                                typeenv
+                                    .add_constraint(TypeConstraint::ReturnOf { entry, function });
+                                deferred += 1;
+                            }
+                            TypeEntry::Substituted(_) => panic!("Substitutions not allowed!"),
+                        }
+                    }
+                    TypeConstraint::MatchOf {
+                        value,
+                        arms,
+                        haselse,
+                        location,
+                    } => {
+                        let value = resolve_substitutions(&value, &typeenv);
+                        let id = match typeenv.get_entry(&value) {
+                            TypeEntry::Id(n) => n.clone(),
+                            TypeEntry::Variable(_) => {
+                                // Wait for the type of the matched value to be known
+                                typeenv.add_constraint(TypeConstraint::MatchOf {
+                                    value,
+                                    arms,
+                                    haselse,
+                                    location,
+                                });
+                                deferred += 1;
+                                continue;
                             }
                             TypeEntry::Substituted(_) => panic!("Substitutions not allowed!"),
                         };
+
+                        for (scope, pattern, _, _) in &arms {
+                            check_pattern_type(typeenv, scope, pattern, &id);
+                        }
+
+                        let coverage = check_match_coverage(
+                            asg,
+                            &arms
+                                .iter()
+                                .map(|(scope, pattern, guarded, _)| {
+                                    (ScopeRef::new(*modulekey, *scope), pattern, *guarded)
+                                })
+                                .collect::<Vec<_>>(),
+                            &id,
+                        );
+
+                        for index in coverage.unreachable_arms {
+                            typeenv.errors.log_error(error::Error::at_span(
+                                errors::UnreachableMatchArm,
+                                arms[index].3.span,
+                                format!("Match arm {} is unreachable", index + 1),
+                            ));
+                        }
+
+                        if haselse && coverage.exhaustive {
+                            typeenv.errors.log_error(error::Error::at_span(
+                                errors::UnreachableElseBranch,
+                                location.span,
+                                "Else branch of match is unreachable".to_string(),
+                            ));
+                        }
+
+                        if !haselse && !coverage.exhaustive {
+                            let error = match &id {
+                                // The missing variants are listed after the last arm
+                                TypeId::Enum(_) => error::Error::at_span(
+                                    errors::NonExhaustiveMatch,
+                                    location.span,
+                                    "Match is not exhaustive".to_string(),
+                                )
+                                .with_label(
+                                    arms.last().map_or(location.span, |arm| arm.3.span),
+                                    format!(
+                                        "Missing variants: {}",
+                                        coverage.missing_variants.join(", ")
+                                    ),
+                                ),
+                                n => error::Error::at_span(
+                                    errors::NonExhaustiveMatch,
+                                    location.span,
+                                    format!(
+                                        "Match on {} is not exhaustive, needs an else branch",
+                                        n.to_string()
+                                    ),
+                                ),
+                            };
+                            typeenv.errors.log_error(error);
+                        }
                     }
-                    TypeConstraint::TypeOfSymbol {
+                    TypeConstraint::GenericCall {
                         entry,
-                        symref: symrefkey,
+                        function,
+                        typeparams,
+                        args,
+                        scope,
+                        expr,
                     } => {
-                        // TODO: This wont work with multiple scope in a function
-                        let module = asg.modulestore.get(&modulekey);
-                        let function = module.functionstore.get(&functionkey);
-                        let scope = module.scopestore.get(&function.scope);
-                        let symref = scope.symboltable.references.get(&symrefkey);
-
-                        match symref {
-                            SymbolReference::ResolvedReference(_) => {
-                                panic!("Symbol was already resolved!")
+                        let resolve_id = |key: &TypeEntryKey| match typeenv
+                            .get_entry(&resolve_substitutions(key, &typeenv))
+                        {
+                            TypeEntry::Id(n) => Some(n.clone()),
+                            _ => None,
+                        };
+                        let signature = match resolve_id(&function) {
+                            Some(TypeId::Function(signature)) => signature,
+                            Some(n) => panic!("Cannot call non-function type {:?}", n),
+                            None => {
+                                // Wait for the signature to be known
+                                typeenv.add_constraint(TypeConstraint::GenericCall {
+                                    entry,
+                                    function,
+                                    typeparams,
+                                    args,
+                                    scope,
+                                    expr,
+                                });
+                                deferred += 1;
+                                continue;
                             }
-                            SymbolReference::UnresolvedReference(n) => {
-                                // Lookup symbol
-                                let scoperef = ScopeRef::new(*modulekey, function.scope);
-                                let resolved_ref = lookup_symbol(asg, n, &scoperef);
+                        };
 
-                                match &resolved_ref {
-                                    SymbolReference::ResolvedReference(n) => {
-                                        // Queue a new constraint with the resolved symbol entry
-                                        let rhs = typeenv.get_for_symbol(&n.symbol);
-                                        typeenv.add_constraint(TypeConstraint::EqualsEntry {
-                                            lhs: entry,
-                                            rhs: rhs,
-                                        });
-                                    }
-                                    SymbolReference::UnresolvedReference(_) => {
-                                        panic!("Symbol could not be resolved!")
-                                    }
-                                }
+                        // Bind from the known argument types first, so the others, like
+                        //  numeric literals, can take the bound types
+                        let mut bindings = HashMap::new();
+                        let params = signature.inputparams.iter().map(|(_, id)| id);
+                        let conflict = params.clone().zip(&args).find_map(|(paramtype, arg)| {
+                            let argid = arg.as_ref().and_then(|key| resolve_id(key))?;
+                            paramtype.bind_type_parameters(&argid, &mut bindings).err()
+                        });
+                        if let Some(message) = conflict {
+                            // The call is reported and returns nothing known
+                            let location = match &get_scope(asg, &ScopeRef::new(*modulekey, scope))
+                                .expressions
+                                .get(&expr)
+                                .object
+                            {
+                                ExpressionObject::Call(n) => n.location.clone(),
+                                _ => panic!("Generic call is not a call expression!"),
+                            };
+                            typeenv.errors.log_error(error::Error::at_span(
+                                errors::ConflictingTypeArguments,
+                                location.span,
+                                message,
+                            ));
+                            continue;
+                        }
 
-                                // Update reference in asg
-                                let modmut = asg.modulestore.get_mut(&modulekey);
-                                let scopemut = modmut.scopestore.get_mut(&scoperef.scope);
-                                let symrefmod = scopemut.symboltable.references.get_mut(&symrefkey);
-                                *symrefmod = resolved_ref;
+                        let mut unknown = Vec::new();
+                        for (paramtype, arg) in params.zip(&args) {
+                            if let Some(key) = arg.filter(|key| resolve_id(key).is_none()) {
+                                unknown.push((key, paramtype));
                             }
                         }
+                        if unknown
+                            .iter()
+                            .any(|(_, paramtype)| !paramtype.is_bound(&bindings))
+                        {
+                            // Wait for more argument types to be known
+                            typeenv.add_constraint(TypeConstraint::GenericCall {
+                                entry,
+                                function,
+                                typeparams,
+                                args,
+                                scope,
+                                expr,
+                            });
+                            deferred += 1;
+                            continue;
+                        }
+                        for (key, paramtype) in unknown {
+                            typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                                entry: key,
+                                id: paramtype.substitute(&bindings),
+                            });
+                        }
+
+                        let typearguments = typeparams
+                            .iter()
+                            .map(|typeparam| match bindings.get(typeparam) {
+                                Some(id) => id.clone(),
+                                None => panic!("Cannot infer type parameter ${}", typeparam),
+                            })
+                            .collect();
+                        typeenv.typearguments.insert((scope, expr), typearguments);
+
+                        typeenv.add_constraint(TypeConstraint::EqualsTypeId {
+                            entry,
+                            id: signature.return_type().substitute(&bindings),
+                        });
                     }
                 },
                 None => {}
             };
         }
 
+        // Only waiting constraints were left, try to get things moving by defaulting numbers
+        if processed > 0 && processed == deferred && !resolve_assignments {
+            resolve_assignments = true;
+        } else if processed > 0 && processed == deferred && !resolve_static_arrays {
+            resolve_static_arrays = true;
+        } else if processed > 0 && processed == deferred && !default_numeric_types(typeenv) {
+            assert!(
+                typeenv.has_errors(),
+                "Cannot infer types, unresolved constraints left: {:?}",
+                typeenv.constraints.values()
+            );
+            break;
+        }
+
         // TODO: Bleh, using a queue would make this check cleaner
         let constraints = typeenv.constraints.keys();
         if constraints == last_constraints {
             assert!(
-                typeenv.constraints.values().iter().all(|x| x.is_none()),
+                typeenv.has_errors() || typeenv.constraints.values().iter().all(|x| x.is_none()),
                 "Unresolved constraints left: {:?}",
                 typeenv.constraints.values()
             );
@@ -631,44 +3488,44 @@ fn process_function(
         iteration += 1;
     }
 
-    let scopekey = {
-        let module = asg.modulestore.get(&modulekey);
-        let function = module.functionstore.get(&functionkey);
-        function.scope
-    };
+    default_numeric_types(typeenv);
 
     println!("Type processing done!");
-    println!("  Declaration types:");
-    {
-        let module = asg.modulestore.get(&modulekey);
-        let scope = module.scopestore.get(&scopekey);
-        let decls = &scope.symboltable.declarations;
-        for d in decls.keys() {
-            println!(
-                "    {}: {:?}",
-                decls.get(d).symbol,
-                typeenv.get_entry(&resolve_substitutions(&typeenv.get_for_symbol(d), typeenv))
-            );
+    for scopekey in typeenv.scopes.clone() {
+        println!("  Scope {}:", scopekey);
+        println!("    Declaration types:");
+        {
+            let module = asg.modulestore.get(&modulekey);
+            let scope = module.scopestore.get(&scopekey);
+            let decls = &scope.symboltable.declarations;
+            for d in decls.keys() {
+                println!(
+                    "      {}: {:?}",
+                    decls.get(d).symbol,
+                    typeenv.get_entry(&resolve_substitutions(
+                        &typeenv.get_for_symbol(&scopekey, d),
+                        typeenv
+                    ))
+                );
+            }
         }
-    }
-    println!("  Expression types:");
-    {
-        let module = asg.modulestore.get(&modulekey);
-        let scope = module.scopestore.get(&scopekey);
-        for e in scope.expressions.keys() {
-            println!(
-                "    e{}: {:?}",
-                e,
-                typeenv.get_entry(&resolve_substitutions(
-                    &typeenv.get_for_expression(&e),
-                    typeenv
-                ))
-            );
+        println!("    Expression types:");
+        {
+            let module = asg.modulestore.get(&modulekey);
+            let scope = module.scopestore.get(&scopekey);
+            for e in scope.expressions.keys() {
+                println!(
+                    "      e{}: {:?}",
+                    e,
+                    typeenv.get_entry(&resolve_substitutions(
+                        &typeenv.get_for_expression(&scopekey, &e),
+                        typeenv
+                    ))
+                );
+            }
         }
-    }
 
-    // Write back result to asg
-    {
+        // Write back result to asg
         let mut decltypes = HashMap::new();
         {
             let module = asg.modulestore.get(&modulekey);
@@ -676,10 +3533,13 @@ fn process_function(
 
             let decls = &scope.symboltable.declarations;
             for d in decls.keys() {
-                let e =
-                    typeenv.get_entry(&resolve_substitutions(&typeenv.get_for_symbol(d), typeenv));
+                let e = typeenv.get_entry(&resolve_substitutions(
+                    &typeenv.get_for_symbol(&scopekey, d),
+                    typeenv,
+                ));
                 match e {
                     TypeEntry::Id(n) => decltypes.insert(d.clone(), n.clone()),
+                    _ if typeenv.has_errors() => None,
                     _ => panic!(
                         "Unresolved type for symbol {:?}",
                         scope.symboltable.declarations.get(&d)
@@ -700,11 +3560,12 @@ fn process_function(
 
             for expkey in scope.expressions.keys() {
                 let e = typeenv.get_entry(&resolve_substitutions(
-                    &typeenv.get_for_expression(&expkey),
+                    &typeenv.get_for_expression(&scopekey, &expkey),
                     typeenv,
                 ));
                 match e {
                     TypeEntry::Id(n) => exprtypes.insert(expkey, n.clone()),
+                    _ if typeenv.has_errors() => None,
                     _ => panic!(
                         "Unresolved type for expression {:?}",
                         scope.expressions.get(&expkey)
@@ -718,13 +3579,380 @@ fn process_function(
             .scopestore
             .get_mut(&scopekey)
             .expressiontypes = exprtypes;
+
+        let typearguments = typeenv
+            .typearguments
+            .iter()
+            .filter(|((scope, _), _)| *scope == scopekey)
+            .map(|((_, expr), typeargs)| (*expr, typeargs.clone()))
+            .collect();
+        asg.modulestore
+            .get_mut(&modulekey)
+            .scopestore
+            .get_mut(&scopekey)
+            .typearguments = typearguments;
+
+        let optionalvalues = typeenv
+            .optionalvalues
+            .iter()
+            .filter(|((scope, _), _)| *scope == scopekey)
+            .map(|((_, expr), typeid)| (*expr, typeid.clone()))
+            .collect();
+        asg.modulestore
+            .get_mut(&modulekey)
+            .scopestore
+            .get_mut(&scopekey)
+            .optionalvalues = optionalvalues;
+
+        let propagatederrors = typeenv
+            .propagatederrors
+            .iter()
+            .filter(|((scope, _), _)| *scope == scopekey)
+            .map(|((_, expr), typeid)| (*expr, typeid.clone()))
+            .collect();
+        asg.modulestore
+            .get_mut(&modulekey)
+            .scopestore
+            .get_mut(&scopekey)
+            .propagatederrors = propagatederrors;
+    }
+
+    typeenv.errors.get_errors().clone()
+}
+
+// Finds the function a scope belongs to, scopes outside of any function have no owner
+fn find_owning_function(
+    asg: &asg::Asg,
+    functionscopes: &HashMap<ScopeKey, FunctionKey>,
+    scoperef: &ScopeRef,
+) -> Option<FunctionKey> {
+    let mut iter = Some(*scoperef);
+    while let Some(scoperef) = iter {
+        if let Some(function) = functionscopes.get(&scoperef.scope) {
+            return Some(*function);
+        }
+        iter = get_scope(asg, &scoperef).parent;
+    }
+    None
+}
+
+// Function literals capture every variable of an enclosing function they refer to, either
+//  directly or through function literals nested in them. Variables listed explicitly keep
+//  their capture mode, everything else is captured by value.
+fn process_captures(asg: &mut asg::Asg, modulekey: &asg::ModuleKey) -> Vec<error::Error> {
+    let module = asg.modulestore.get(modulekey);
+    let functionscopes: HashMap<_, _> = module
+        .functionstore
+        .keys()
+        .map(|key| (module.functionstore.get(&key).scope, key))
+        .collect();
+
+    // Resolve the explicit capture lists in the scope the literal is defined in
+    let mut explicit = HashMap::new();
+    for functionkey in module.functionstore.keys() {
+        let function = asg
+            .modulestore
+            .get(modulekey)
+            .functionstore
+            .get(&functionkey);
+        let parent = match get_scope(asg, &ScopeRef::new(*modulekey, function.scope)).parent {
+            Some(parent) => parent,
+            None => continue,
+        };
+
+        let mut captures = Vec::new();
+        for (symrefkey, byreference) in function.capturelist.clone() {
+            let symref = get_scope(asg, &parent)
+                .symboltable
+                .references
+                .get(&symrefkey);
+            let resolved = match symref {
+                SymbolReference::UnresolvedReference(n) => lookup_symbol(asg, n, &parent),
+                SymbolReference::ResolvedReference(_) => symref.clone(),
+            };
+            let resolved = match resolved {
+                SymbolReference::ResolvedReference(n) => n,
+                SymbolReference::UnresolvedReference(n) => {
+                    panic!("Symbol could not be resolved: {}", n.symbol)
+                }
+            };
+
+            let owner = find_owning_function(asg, &functionscopes, &resolved.scope);
+            if owner.is_none() || owner == Some(functionkey) {
+                panic!(
+                    "Cannot capture {}, it is not a local variable",
+                    resolved.symbol
+                );
+            }
+
+            *get_scope_mut(asg, &parent)
+                .symboltable
+                .references
+                .get_mut(&symrefkey) = SymbolReference::ResolvedReference(resolved.clone());
+            captures.push(Capture {
+                symref: resolved,
+                byreference,
+            });
+        }
+        explicit.insert(functionkey, captures);
+    }
+
+    // Collect the variables referenced by each function, including its nested scopes
+    let module = asg.modulestore.get(modulekey);
+    let mut referenced: HashMap<FunctionKey, Vec<ResolvedSymbolReference>> = HashMap::new();
+    for scopekey in module.scopestore.keys() {
+        let scoperef = ScopeRef::new(*modulekey, scopekey);
+        let owner = match find_owning_function(asg, &functionscopes, &scoperef) {
+            Some(owner) => owner,
+            None => continue,
+        };
+        let references = &module.scopestore.get(&scopekey).symboltable.references;
+        for symrefkey in references.keys() {
+            if let SymbolReference::ResolvedReference(n) = references.get(&symrefkey) {
+                referenced.entry(owner).or_default().push(n.clone());
+            }
+        }
+    }
+
+    let nested: HashMap<FunctionKey, Vec<FunctionKey>> =
+        module
+            .functionstore
+            .keys()
+            .fold(HashMap::new(), |mut nested, key| {
+                let scope = module.functionstore.get(&key).scope;
+                if let Some(parent) = get_scope(asg, &ScopeRef::new(*modulekey, scope)).parent {
+                    if let Some(owner) = find_owning_function(asg, &functionscopes, &parent) {
+                        nested.entry(owner).or_default().push(key);
+                    }
+                }
+                nested
+            });
+
+    // Captures of nested literals propagate outwards, repeat until nothing changes
+    let mut captures: HashMap<FunctionKey, Vec<Capture>> = HashMap::new();
+    loop {
+        let mut changed = false;
+        for functionkey in module.functionstore.keys() {
+            let mut candidates = explicit.get(&functionkey).cloned().unwrap_or_default();
+            for symref in referenced.get(&functionkey).into_iter().flatten() {
+                candidates.push(Capture {
+                    symref: symref.clone(),
+                    byreference: false,
+                });
+            }
+            for inner in nested.get(&functionkey).into_iter().flatten() {
+                candidates.extend(captures.get(inner).cloned().unwrap_or_default());
+            }
+
+            let mut result: Vec<Capture> = Vec::new();
+            for candidate in candidates {
+                let owner = find_owning_function(asg, &functionscopes, &candidate.symref.scope);
+                if owner.is_none() || owner == Some(functionkey) {
+                    continue;
+                }
+
+                // Types only exist at compile time, there is nothing to capture
+                let decltype = get_scope(asg, &candidate.symref.scope)
+                    .declarationtypes
+                    .get(&candidate.symref.symbol);
+                if decltype == Some(&TypeId::Type) {
+                    continue;
+                }
+
+                // Functions without captures are referred to directly
+                if let Some(defined) = asg.get_defined_function(&candidate.symref) {
+                    let definedcaptures = captures.get(&defined.function);
+                    if defined.function == functionkey
+                        || definedcaptures.map_or(true, |c| c.is_empty())
+                    {
+                        continue;
+                    }
+                }
+
+                // The first entry wins, explicit captures are listed first
+                if !result.iter().any(|c| c.symref == candidate.symref) {
+                    result.push(candidate);
+                }
+            }
+
+            if captures.get(&functionkey) != Some(&result) {
+                captures.insert(functionkey, result);
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    let module = asg.modulestore.get_mut(modulekey);
+    for (functionkey, captures) in captures {
+        module.functionstore.get_mut(&functionkey).captures = captures;
+    }
+
+    // References to variables of a function cannot be returned from it
+    let module = asg.modulestore.get(modulekey);
+    let mut errors = Vec::new();
+    for functionkey in module.functionstore.keys() {
+        if let Some(body) = &module.functionstore.get(&functionkey).body {
+            check_returned_references(
+                asg,
+                modulekey,
+                &functionscopes,
+                &functionkey,
+                body,
+                &mut errors,
+            );
+        }
+    }
+    errors
+}
+
+// Reports a reference outliving the local variable of the returning function it refers to
+fn create_outliving_reference_error(
+    asg: &asg::Asg,
+    location: SourceLocation,
+    target: &ResolvedSymbolReference,
+    message: &str,
+) -> error::Error {
+    let decl = get_scope(asg, &target.scope)
+        .symboltable
+        .declarations
+        .get(&target.symbol);
+    let error = error::Error::at_span(
+        errors::ReferenceOutlivesVariable,
+        location.span,
+        format!("{} {} outlives the variable", message, decl.symbol),
+    );
+    match &decl.location {
+        Some(declared) => error.with_label(
+            declared.span,
+            format!(
+                "{} is declared here, it does not exist after returning",
+                decl.symbol
+            ),
+        ),
+        None => error,
+    }
+}
+
+// Returned references, and closures capturing by reference, must not refer to variables
+//  of the returning function
+fn check_returned_references(
+    asg: &asg::Asg,
+    modulekey: &asg::ModuleKey,
+    functionscopes: &HashMap<ScopeKey, FunctionKey>,
+    functionkey: &FunctionKey,
+    body: &StatementBody,
+    errors: &mut Vec<error::Error>,
+) {
+    let scoperef = ScopeRef::new(*modulekey, body.scope_nonowned);
+    let scope = get_scope(asg, &scoperef);
+    for stmnt in &body.statements {
+        let branches: Vec<&statements::Branch> = match stmnt {
+            Statement::If(n) => n
+                .branches
+                .iter()
+                .map(|(_, branch)| branch)
+                .chain(n.elsebranch.iter())
+                .collect(),
+            Statement::Match(n) => n
+                .arms
+                .iter()
+                .map(|arm| &arm.body)
+                .chain(n.elsebranch.iter())
+                .collect(),
+            Statement::While(n) => vec![&n.body],
+            Statement::For(n) => vec![&n.body],
+            Statement::Return(n) => {
+                for expr in &n.exprs {
+                    if let expression::ExpressionObject::Reference(n) =
+                        &scope.expressions.get(expr).object
+                    {
+                        let target = resolve_reference_expression(asg, &scoperef, &n.expr);
+                        if let Some(target) = target.filter(|target| {
+                            find_owning_function(asg, functionscopes, &target.scope)
+                                == Some(*functionkey)
+                        }) {
+                            errors.push(create_outliving_reference_error(
+                                asg,
+                                n.location.clone(),
+                                &target,
+                                "Reference to",
+                            ));
+                        }
+                        continue;
+                    }
+
+                    let returned = match &scope.expressions.get(expr).object {
+                        expression::ExpressionObject::Literal(
+                            expression::expressions::Literal::FunctionLiteral(n),
+                        ) => Some(n.functionkey),
+                        expression::ExpressionObject::SymbolReference(n) => {
+                            match scope.symboltable.references.get(&n.symbolref) {
+                                SymbolReference::ResolvedReference(n) => {
+                                    asg.get_defined_function(n).map(|f| f.function)
+                                }
+                                SymbolReference::UnresolvedReference(_) => None,
+                            }
+                        }
+                        _ => None,
+                    };
+
+                    let returned = match returned {
+                        Some(returned) => asg.get_function(&FunctionRef {
+                            module: *modulekey,
+                            function: returned,
+                        }),
+                        None => continue,
+                    };
+                    for capture in returned.captures.iter().filter(|c| c.byreference) {
+                        let owner =
+                            find_owning_function(asg, functionscopes, &capture.symref.scope);
+                        if owner == Some(*functionkey) {
+                            // Returned functions are always named or literals
+                            let location = find_value_location(asg, &scoperef, expr).unwrap();
+                            errors.push(create_outliving_reference_error(
+                                asg,
+                                location,
+                                &capture.symref,
+                                "Captured reference to",
+                            ));
+                        }
+                    }
+                }
+                Vec::new()
+            }
+            _ => Vec::new(),
+        };
+
+        for branch in branches {
+            if let Some(body) = &branch.body {
+                check_returned_references(
+                    asg,
+                    modulekey,
+                    functionscopes,
+                    functionkey,
+                    body,
+                    errors,
+                );
+            }
+        }
     }
 }
 
-pub fn process_asg(mut asg: asg::Asg) -> asg::Asg {
+pub fn process_asg(mut asg: asg::Asg) -> (asg::Asg, Vec<error::Error>) {
     let module = asg.global_module.clone();
     let function = asg.main.clone();
-    process_function(&mut asg, &module, &function);
+    let mut errors = process_function(&mut asg, &module, &function);
+
+    // Later passes need the types of every expression
+    if errors.iter().any(|error| !error::is_warning(error.id)) {
+        return (asg, errors);
+    }
+    errors.extend(process_captures(&mut asg, &module));
+    errors.extend(evaluate_definitions(&mut asg, &module));
 
-    asg
+    (asg, errors)
 }
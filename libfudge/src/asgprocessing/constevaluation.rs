@@ -0,0 +1,461 @@
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::asg::*;
+use crate::error::{self, errors};
+use crate::source::SourceLocation;
+use crate::utils::objectstore::*;
+
+use crate::shared::BinaryOperationType;
+use crate::shared::UnaryOperationType;
+use crate::typesystem::*;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Constant {
+    Bool(bool),
+    Char(char),
+    String(String),
+    // Integers are stored as the bits of a u64, sign extended for signed types
+    Integer(u64),
+    Float(f64),
+}
+
+// Expressions like types and functions are known at compile time, but are not values
+//  that can be folded into a literal, those evaluate to None
+type EvalResult = Result<Option<Constant>, EvalError>;
+
+// Why a def can not be evaluated, with the runtime symbol it depends on when there is one
+#[derive(Debug, Clone)]
+struct EvalError {
+    reason: String,
+    dependency: Option<(String, Option<SourceLocation>)>,
+}
+
+impl From<&str> for EvalError {
+    fn from(reason: &str) -> Self {
+        reason.to_string().into()
+    }
+}
+
+impl From<String> for EvalError {
+    fn from(reason: String) -> Self {
+        EvalError {
+            reason,
+            dependency: None,
+        }
+    }
+}
+
+fn is_signed(ptype: PrimitiveType) -> bool {
+    matches!(
+        ptype,
+        PrimitiveType::S8 | PrimitiveType::S16 | PrimitiveType::S32 | PrimitiveType::S64
+    )
+}
+
+// Wraps the result of a 64 bit operation to the width of the type
+fn wrap_integer(ptype: PrimitiveType, value: u64) -> u64 {
+    match ptype {
+        PrimitiveType::U8 => value as u8 as u64,
+        PrimitiveType::U16 => value as u16 as u64,
+        PrimitiveType::U32 => value as u32 as u64,
+        PrimitiveType::S8 => value as i8 as i64 as u64,
+        PrimitiveType::S16 => value as i16 as i64 as u64,
+        PrimitiveType::S32 => value as i32 as i64 as u64,
+        _ => value,
+    }
+}
+
+// Rounds the result of a 64 bit operation to the precision of the type
+fn round_float(ptype: PrimitiveType, value: f64) -> f64 {
+    match ptype {
+        PrimitiveType::F32 => value as f32 as f64,
+        _ => value,
+    }
+}
+
+fn evaluate_integer_binop(
+    ptype: PrimitiveType,
+    op: BinaryOperationType,
+    lhs: u64,
+    rhs: u64,
+) -> EvalResult {
+    use BinaryOperationType::*;
+
+    let signed = is_signed(ptype);
+    if matches!(op, Div | Mod) && rhs == 0 {
+        return Err("division by zero".into());
+    }
+
+    let value = match op {
+        Add => lhs.wrapping_add(rhs),
+        Sub => lhs.wrapping_sub(rhs),
+        Mul => lhs.wrapping_mul(rhs),
+        Div if signed => (lhs as i64).wrapping_div(rhs as i64) as u64,
+        Div => lhs / rhs,
+        Mod if signed => (lhs as i64).wrapping_rem(rhs as i64) as u64,
+        Mod => lhs % rhs,
+        BitAnd => lhs & rhs,
+        BitOr => lhs | rhs,
+        BitXor => lhs ^ rhs,
+        ShiftLeft => lhs.wrapping_shl(rhs as u32),
+        ShiftRight if signed => (lhs as i64).wrapping_shr(rhs as u32) as u64,
+        ShiftRight => lhs.wrapping_shr(rhs as u32),
+        _ => {
+            let ordering = if signed {
+                (lhs as i64).cmp(&(rhs as i64))
+            } else {
+                lhs.cmp(&rhs)
+            };
+            return Ok(Some(Constant::Bool(match op {
+                Equals => ordering.is_eq(),
+                NotEquals => ordering.is_ne(),
+                LessThan => ordering.is_lt(),
+                LessThanOrEq => ordering.is_le(),
+                GreaterThan => ordering.is_gt(),
+                GreaterThanOrEq => ordering.is_ge(),
+                _ => return Err(format!("unsupported integer operation {:?}", op).into()),
+            })));
+        }
+    };
+
+    Ok(Some(Constant::Integer(wrap_integer(ptype, value))))
+}
+
+fn evaluate_float_binop(
+    ptype: PrimitiveType,
+    op: BinaryOperationType,
+    lhs: f64,
+    rhs: f64,
+) -> EvalResult {
+    use BinaryOperationType::*;
+
+    let value = match op {
+        Add => lhs + rhs,
+        Sub => lhs - rhs,
+        Mul => lhs * rhs,
+        Div => lhs / rhs,
+        Mod => lhs % rhs,
+        Equals => return Ok(Some(Constant::Bool(lhs == rhs))),
+        NotEquals => return Ok(Some(Constant::Bool(lhs != rhs))),
+        LessThan => return Ok(Some(Constant::Bool(lhs < rhs))),
+        LessThanOrEq => return Ok(Some(Constant::Bool(lhs <= rhs))),
+        GreaterThan => return Ok(Some(Constant::Bool(lhs > rhs))),
+        GreaterThanOrEq => return Ok(Some(Constant::Bool(lhs >= rhs))),
+        _ => return Err(format!("unsupported float operation {:?}", op).into()),
+    };
+
+    Ok(Some(Constant::Float(round_float(ptype, value))))
+}
+
+struct ConstEvaluator<'a> {
+    asg: &'a Asg,
+    // Evaluated definitions, by the scope and expression they are defined by
+    values: HashMap<(ScopeRef, ExpressionKey), Option<Constant>>,
+    // Definitions currently being evaluated, to catch cyclic definitions
+    active: HashSet<(ScopeRef, ExpressionKey)>,
+}
+
+impl<'a> ConstEvaluator<'a> {
+    fn new(asg: &'a Asg) -> Self {
+        Self {
+            asg,
+            values: HashMap::new(),
+            active: HashSet::new(),
+        }
+    }
+
+    fn evaluate_definition(&mut self, scoperef: &ScopeRef, exprkey: &ExpressionKey) -> EvalResult {
+        let key = (*scoperef, *exprkey);
+        if let Some(value) = self.values.get(&key) {
+            return Ok(value.clone());
+        }
+        if !self.active.insert(key) {
+            return Err("the definition refers to itself".into());
+        }
+
+        let value = self.evaluate_expression(scoperef, exprkey);
+
+        self.active.remove(&key);
+        if let Ok(value) = &value {
+            self.values.insert(key, value.clone());
+        }
+        value
+    }
+
+    fn evaluate_symbol_reference(
+        &mut self,
+        scoperef: &ScopeRef,
+        symrefkey: &symboltable::SymbolReferenceKey,
+    ) -> EvalResult {
+        let scope = self.asg.get_scope(scoperef);
+        let symref = match scope.symboltable.references.get(symrefkey) {
+            SymbolReference::ResolvedReference(n) => n,
+            SymbolReference::UnresolvedReference(n) => {
+                panic!("Symbol could not be resolved: {}", n.symbol)
+            }
+        };
+
+        // Only other definitions are known at compile time
+        let defscope = self.asg.get_scope(&symref.scope);
+        match defscope.symboltable.definitions.get(&symref.symbol) {
+            Some(definition) => self.evaluate_definition(&symref.scope, definition),
+            None => {
+                let decl = defscope.symboltable.declarations.get(&symref.symbol);
+                Err(EvalError {
+                    reason: format!("it depends on the runtime state of {}", decl.symbol),
+                    dependency: Some((decl.symbol.clone(), decl.location.clone())),
+                })
+            }
+        }
+    }
+
+    fn evaluate_expression(&mut self, scoperef: &ScopeRef, exprkey: &ExpressionKey) -> EvalResult {
+        let scope = self.asg.get_scope(scoperef);
+        let exprtype = scope.expressiontypes.get(exprkey);
+
+        match &scope.expressions.get(exprkey).object {
+            ExpressionObject::Literal(n) => match n {
+                expressions::Literal::BoolLiteral(n) => Ok(Some(Constant::Bool(n.value))),
+                expressions::Literal::CharLiteral(n) => Ok(Some(Constant::Char(n.value))),
+                expressions::Literal::StringLiteral(n) => {
+                    Ok(Some(Constant::String(n.string.clone())))
+                }
+                expressions::Literal::IntegerLiteral(n) => Ok(Some(Constant::Integer(n.data))),
+                expressions::Literal::FloatLiteral(n) => Ok(Some(Constant::Float(n.value))),
+                expressions::Literal::NullLiteral(_) => Ok(None),
+                expressions::Literal::ArrayLiteral(expressions::literals::ArrayLiteral {
+                    elements,
+                    ..
+                })
+                | expressions::Literal::TupleLiteral(expressions::literals::TupleLiteral {
+                    elements,
+                }) => {
+                    for element in elements {
+                        self.evaluate_expression(scoperef, element)?;
+                    }
+                    Ok(None)
+                }
+                // Function literals are constant, their captures are bound where they are declared
+                expressions::Literal::StructLiteral(_)
+                | expressions::Literal::EnumLiteral(_)
+                | expressions::Literal::FunctionLiteral(_)
+                | expressions::Literal::ModuleLiteral(_) => Ok(None),
+            },
+            ExpressionObject::BuiltInFunction(_)
+            | ExpressionObject::PrimitiveType(_)
+            | ExpressionObject::DynamicArrayType(_)
+            | ExpressionObject::SliceType(_)
+            | ExpressionObject::OptionalType(_)
+            | ExpressionObject::ResultType(_)
+            | ExpressionObject::FunctionType(_)
+            | ExpressionObject::TypeParameter(_) => Ok(None),
+            ExpressionObject::SymbolReference(n) => {
+                self.evaluate_symbol_reference(scoperef, &n.symbolref)
+            }
+            // Instantiating a generic struct or enum is the only call known at compile time
+            ExpressionObject::Call(_) if exprtype == Some(&TypeId::Type) => Ok(None),
+            ExpressionObject::Call(_) => {
+                Err("it depends on a call, calls are evaluated at runtime".into())
+            }
+            // Only the taken branch is evaluated, the others may depend on anything
+            ExpressionObject::If(n) => {
+                for (condition, value) in &n.branches {
+                    match self.evaluate_expression(scoperef, condition)? {
+                        Some(Constant::Bool(true)) => {
+                            return self.evaluate_expression(scoperef, value)
+                        }
+                        Some(Constant::Bool(false)) => (),
+                        _ => {
+                            return Err("the condition of an if is not known at compile time".into())
+                        }
+                    }
+                }
+                match &n.elsebranch {
+                    Some(elsebranch) => self.evaluate_expression(scoperef, elsebranch),
+                    None => Ok(None),
+                }
+            }
+            ExpressionObject::Match(_) => {
+                Err("it depends on a match, matches are evaluated at runtime".into())
+            }
+            ExpressionObject::BinOp(n) => {
+                let lhs = self.evaluate_expression(scoperef, &n.lhs)?;
+                let rhs = self.evaluate_expression(scoperef, &n.rhs)?;
+                let operandtype = scope.expressiontypes.get(&n.lhs);
+
+                match (lhs, rhs, operandtype) {
+                    (
+                        Some(Constant::Integer(lhs)),
+                        Some(Constant::Integer(rhs)),
+                        Some(TypeId::Primitive(ptype)),
+                    ) => evaluate_integer_binop(*ptype, n.op, lhs, rhs),
+                    (
+                        Some(Constant::Float(lhs)),
+                        Some(Constant::Float(rhs)),
+                        Some(TypeId::Primitive(ptype)),
+                    ) => evaluate_float_binop(*ptype, n.op, lhs, rhs),
+                    (Some(Constant::Bool(lhs)), Some(Constant::Bool(rhs)), _) => {
+                        Ok(Some(Constant::Bool(match n.op {
+                            BinaryOperationType::And => lhs && rhs,
+                            BinaryOperationType::Or => lhs || rhs,
+                            BinaryOperationType::Equals => lhs == rhs,
+                            BinaryOperationType::NotEquals => lhs != rhs,
+                            _ => {
+                                return Err(format!("unsupported bool operation {:?}", n.op).into())
+                            }
+                        })))
+                    }
+                    (Some(Constant::Char(lhs)), Some(Constant::Char(rhs)), _) => {
+                        Ok(Some(Constant::Bool(match n.op {
+                            BinaryOperationType::Equals => lhs == rhs,
+                            BinaryOperationType::NotEquals => lhs != rhs,
+                            BinaryOperationType::LessThan => lhs < rhs,
+                            BinaryOperationType::LessThanOrEq => lhs <= rhs,
+                            BinaryOperationType::GreaterThan => lhs > rhs,
+                            BinaryOperationType::GreaterThanOrEq => lhs >= rhs,
+                            _ => {
+                                return Err(format!("unsupported char operation {:?}", n.op).into())
+                            }
+                        })))
+                    }
+                    _ => Ok(None),
+                }
+            }
+            ExpressionObject::UnaryOp(n) => {
+                let operand = self.evaluate_expression(scoperef, &n.expr)?;
+
+                match (operand, exprtype) {
+                    (Some(Constant::Integer(value)), Some(TypeId::Primitive(ptype))) => {
+                        let value = match n.op {
+                            UnaryOperationType::Neg => value.wrapping_neg(),
+                            UnaryOperationType::BitNot => !value,
+                            UnaryOperationType::Not => {
+                                return Err("unsupported integer operation Not".into())
+                            }
+                        };
+                        Ok(Some(Constant::Integer(wrap_integer(*ptype, value))))
+                    }
+                    (Some(Constant::Float(value)), _) if n.op == UnaryOperationType::Neg => {
+                        Ok(Some(Constant::Float(-value)))
+                    }
+                    (Some(Constant::Bool(value)), _) if n.op == UnaryOperationType::Not => {
+                        Ok(Some(Constant::Bool(!value)))
+                    }
+                    _ => Ok(None),
+                }
+            }
+            ExpressionObject::Range(n) => {
+                self.evaluate_expression(scoperef, &n.start)?;
+                self.evaluate_expression(scoperef, &n.end)?;
+                Ok(None)
+            }
+            ExpressionObject::Subscript(n) => {
+                self.evaluate_expression(scoperef, &n.expr)?;
+                Ok(None)
+            }
+            // Reference types are known at compile time, addresses of values are not
+            ExpressionObject::Reference(_) if exprtype == Some(&TypeId::Type) => Ok(None),
+            ExpressionObject::Reference(_) | ExpressionObject::Dereference(_) => {
+                Err("it depends on a reference, addresses are taken at runtime".into())
+            }
+            ExpressionObject::Try(_) => {
+                Err("it depends on a try, errors are propagated at runtime".into())
+            }
+            ExpressionObject::StructConstruction(n) => {
+                for (_, value) in &n.fields {
+                    self.evaluate_expression(scoperef, value)?;
+                }
+                Ok(None)
+            }
+            ExpressionObject::Index(n) => {
+                self.evaluate_expression(scoperef, &n.expr)?;
+                self.evaluate_expression(scoperef, &n.index)?;
+                Ok(None)
+            }
+            ExpressionObject::Slice(n) => {
+                self.evaluate_expression(scoperef, &n.expr)?;
+                self.evaluate_expression(scoperef, &n.start)?;
+                self.evaluate_expression(scoperef, &n.end)?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+fn create_literal(constant: Constant) -> ExpressionObject {
+    let literal = match constant {
+        Constant::Bool(value) => {
+            expressions::Literal::BoolLiteral(expressions::literals::BoolLiteral { value })
+        }
+        Constant::Char(value) => {
+            expressions::Literal::CharLiteral(expressions::literals::CharLiteral { value })
+        }
+        Constant::String(string) => {
+            expressions::Literal::StringLiteral(expressions::literals::StringLiteral { string })
+        }
+        Constant::Integer(data) => {
+            expressions::Literal::IntegerLiteral(expressions::literals::IntegerLiteral {
+                data,
+                signed: (data as i64) < 0,
+            })
+        }
+        Constant::Float(value) => {
+            expressions::Literal::FloatLiteral(expressions::literals::FloatLiteral { value })
+        }
+    };
+
+    ExpressionObject::Literal(literal)
+}
+
+// Folds the initializers of defs into literals. Defs are computed at compile time, so they
+//  can only depend on literals and other defs, other defs are reported.
+pub fn evaluate_definitions(asg: &mut Asg, modulekey: &ModuleKey) -> Vec<error::Error> {
+    let mut folded = Vec::new();
+    let mut errors = Vec::new();
+    {
+        let mut evaluator = ConstEvaluator::new(asg);
+        let module = asg.modulestore.get(modulekey);
+        for scopekey in module.scopestore.keys() {
+            let scoperef = ScopeRef::new(*modulekey, scopekey);
+            let symboltable = &module.scopestore.get(&scopekey).symboltable;
+            for (symbol, definition) in &symboltable.definitions {
+                match evaluator.evaluate_definition(&scoperef, definition) {
+                    Ok(Some(constant)) => folded.push((scoperef, *definition, constant)),
+                    Ok(None) => (),
+                    Err(e) => {
+                        // Defs that can fail to evaluate are declared by a statement
+                        let decl = symboltable.declarations.get(symbol);
+                        let location = decl.location.as_ref().unwrap();
+                        let mut error = error::Error::at_span(
+                            errors::DefinitionNotConstant,
+                            location.span,
+                            format!(
+                                "Cannot evaluate def {} at compile time, {}",
+                                decl.symbol, e.reason
+                            ),
+                        );
+                        if let Some((symbol, Some(location))) = e.dependency {
+                            error = error.with_label(
+                                location.span,
+                                format!("{} is only known at runtime", symbol),
+                            );
+                        }
+                        errors.push(error);
+                    }
+                }
+            }
+        }
+    }
+
+    for (scoperef, definition, constant) in folded {
+        let scope = asg
+            .modulestore
+            .get_mut(&scoperef.module)
+            .scopestore
+            .get_mut(&scoperef.scope);
+        scope.expressions.get_mut(&definition).object = create_literal(constant);
+    }
+
+    errors
+}
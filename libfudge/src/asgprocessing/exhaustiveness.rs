@@ -0,0 +1,251 @@
+use crate::asg::symboltable::SymbolKey;
+use crate::asg::*;
+use crate::utils::objectstore::*;
+
+use crate::typesystem::*;
+
+// Matches are checked by the usefulness of patterns: a pattern is useful after a list of
+//  arms if it matches a value that none of the arms match. A match is exhaustive if a
+//  wildcard is not useful after its unguarded arms, and an arm is unreachable if it is
+//  not useful after the unguarded arms preceding it.
+
+#[derive(Debug, Clone, PartialEq)]
+enum Constructor {
+    // Index of the variant, fields of variants can only be bound so they are not matched
+    Variant(usize),
+    Bool(bool),
+    // Tuples and structs, which have a single constructor with a value for each element
+    //  or field
+    Single,
+    // Integer and char literals
+    Value(u64),
+    // Ranges and other literals, which are never assumed to match the same values
+    Opaque,
+}
+
+impl Constructor {
+    fn covers(&self, other: &Constructor) -> bool {
+        *self != Constructor::Opaque && self == other
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Space {
+    Any,
+    Constructed(Constructor, Vec<Space>),
+}
+
+// Types of the values of the constructor, in order
+fn field_types(constructor: &Constructor, id: &TypeId) -> Vec<TypeId> {
+    match (constructor, id) {
+        (Constructor::Single, TypeId::Tuple(ids)) => ids.clone(),
+        (Constructor::Single, TypeId::Struct(definition)) => definition
+            .fields
+            .iter()
+            .map(|(_, fieldid)| fieldid.clone())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Constructors of every value of the type, None if there are too many to list
+fn all_constructors(id: &TypeId) -> Option<Vec<Constructor>> {
+    match id {
+        TypeId::Enum(definition) => Some(
+            (0..definition.variants.len())
+                .map(Constructor::Variant)
+                .collect(),
+        ),
+        TypeId::Primitive(PrimitiveType::Bool) => {
+            Some(vec![Constructor::Bool(false), Constructor::Bool(true)])
+        }
+        TypeId::Tuple(_) | TypeId::Struct(_) => Some(vec![Constructor::Single]),
+        _ => None,
+    }
+}
+
+fn create_space(
+    asg: &Asg,
+    scoperef: &ScopeRef,
+    pattern: &expression::misc::Pattern,
+    id: &TypeId,
+) -> Space {
+    use expression::misc::Pattern;
+    match (pattern, id) {
+        (Pattern::Wildcard, _) | (Pattern::Binding(_), _) => Space::Any,
+        (Pattern::Variant(n), TypeId::Enum(definition)) => {
+            match definition.get_variant(&SymbolKey::from_str(&n.name)) {
+                Some((variant, _)) => Space::Constructed(Constructor::Variant(variant), Vec::new()),
+                None => Space::Constructed(Constructor::Opaque, Vec::new()),
+            }
+        }
+        (Pattern::Literal(n), _) => {
+            let constructor = match &asg.get_scope(scoperef).expressions.get(n).object {
+                ExpressionObject::Literal(expressions::Literal::BoolLiteral(n)) => {
+                    Constructor::Bool(n.value)
+                }
+                ExpressionObject::Literal(expressions::Literal::IntegerLiteral(n)) => {
+                    Constructor::Value(n.data)
+                }
+                ExpressionObject::Literal(expressions::Literal::CharLiteral(n)) => {
+                    Constructor::Value(n.value as u64)
+                }
+                _ => Constructor::Opaque,
+            };
+            Space::Constructed(constructor, Vec::new())
+        }
+        (Pattern::Tuple(elements), TypeId::Tuple(ids)) => Space::Constructed(
+            Constructor::Single,
+            elements
+                .iter()
+                .zip(ids)
+                .map(|(n, elementid)| create_space(asg, scoperef, n, elementid))
+                .collect(),
+        ),
+        // Fields that are not listed match any value
+        (Pattern::Struct(fields), TypeId::Struct(definition)) => Space::Constructed(
+            Constructor::Single,
+            definition
+                .fields
+                .iter()
+                .map(|(symbol, fieldid)| {
+                    match fields
+                        .iter()
+                        .find(|(field, _)| SymbolKey::from_str(field) == *symbol)
+                    {
+                        Some((_, n)) => create_space(asg, scoperef, n, fieldid),
+                        None => Space::Any,
+                    }
+                })
+                .collect(),
+        ),
+        _ => Space::Constructed(Constructor::Opaque, Vec::new()),
+    }
+}
+
+// Rows whose first space matches the constructor, with that space replaced by the
+//  spaces of its fields
+fn specialize(rows: &[Vec<Space>], constructor: &Constructor, arity: usize) -> Vec<Vec<Space>> {
+    rows.iter()
+        .filter_map(|row| {
+            let fields = match &row[0] {
+                Space::Any => vec![Space::Any; arity],
+                Space::Constructed(n, fields) if n.covers(constructor) => fields.clone(),
+                Space::Constructed(..) => return None,
+            };
+            Some(fields.into_iter().chain(row[1..].iter().cloned()).collect())
+        })
+        .collect()
+}
+
+// Rows matching any value in their first column, without that column
+fn default_rows(rows: &[Vec<Space>]) -> Vec<Vec<Space>> {
+    rows.iter()
+        .filter(|row| matches!(row[0], Space::Any))
+        .map(|row| row[1..].to_vec())
+        .collect()
+}
+
+fn is_useful(rows: &[Vec<Space>], vector: &[Space], ids: &[TypeId]) -> bool {
+    if vector.is_empty() {
+        return rows.is_empty();
+    }
+
+    let specialize_vector = |fields: Vec<Space>| -> Vec<Space> {
+        fields
+            .into_iter()
+            .chain(vector[1..].iter().cloned())
+            .collect()
+    };
+    let specialize_ids = |constructor: &Constructor| -> Vec<TypeId> {
+        field_types(constructor, &ids[0])
+            .into_iter()
+            .chain(ids[1..].iter().cloned())
+            .collect()
+    };
+
+    match &vector[0] {
+        Space::Constructed(constructor, fields) => is_useful(
+            &specialize(rows, constructor, fields.len()),
+            &specialize_vector(fields.clone()),
+            &specialize_ids(constructor),
+        ),
+        Space::Any => {
+            // Only if the arms list every constructor, the wildcard has to be useful for
+            //  one of them. Otherwise it is useful for a missing one, unless the arms
+            //  matching anything cover the remaining columns.
+            let constructors = all_constructors(&ids[0]).filter(|constructors| {
+                constructors.iter().all(|constructor| {
+                    rows.iter().any(|row| match &row[0] {
+                        Space::Constructed(n, _) => n.covers(constructor),
+                        Space::Any => false,
+                    })
+                })
+            });
+            match constructors {
+                Some(constructors) => constructors.iter().any(|constructor| {
+                    let arity = field_types(constructor, &ids[0]).len();
+                    is_useful(
+                        &specialize(rows, constructor, arity),
+                        &specialize_vector(vec![Space::Any; arity]),
+                        &specialize_ids(constructor),
+                    )
+                }),
+                None => is_useful(&default_rows(rows), &vector[1..], &ids[1..]),
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MatchCoverage {
+    // Indices of the arms that can never be taken
+    pub unreachable_arms: Vec<usize>,
+    // Whether every value is matched by unguarded arms
+    pub exhaustive: bool,
+    // Variants of a matched enum that are not matched by unguarded arms
+    pub missing_variants: Vec<String>,
+}
+
+// Checks the arms of a match on a value of the type, guarded arms match no value for sure
+//  but can still be unreachable
+pub fn check_match_coverage(
+    asg: &Asg,
+    arms: &[(ScopeRef, &expression::misc::Pattern, bool)],
+    id: &TypeId,
+) -> MatchCoverage {
+    let ids = [id.clone()];
+
+    let mut rows: Vec<Vec<Space>> = Vec::new();
+    let mut unreachable_arms = Vec::new();
+    for (index, (scoperef, pattern, guarded)) in arms.iter().enumerate() {
+        let row = vec![create_space(asg, scoperef, pattern, id)];
+        if !is_useful(&rows, &row, &ids) {
+            unreachable_arms.push(index);
+        }
+        if !guarded {
+            rows.push(row);
+        }
+    }
+
+    let exhaustive = !is_useful(&rows, &[Space::Any], &ids);
+    let missing_variants = match id {
+        TypeId::Enum(definition) => definition
+            .variants
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| {
+                let vector = [Space::Constructed(Constructor::Variant(*index), Vec::new())];
+                is_useful(&rows, &vector, &ids)
+            })
+            .map(|(_, variant)| variant.name.to_string())
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    MatchCoverage {
+        unreachable_arms,
+        exhaustive,
+        missing_variants,
+    }
+}
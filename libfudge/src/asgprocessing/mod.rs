@@ -1,8 +1,17 @@
 pub mod asgprocessing;
 pub use asgprocessing::*;
 
+pub mod constevaluation;
+pub use constevaluation::*;
+
+pub mod exhaustiveness;
+pub use exhaustiveness::*;
+
 pub mod symbolresolution;
 pub use symbolresolution::*;
 
 pub mod typechecking;
 pub use typechecking::*;
+
+#[cfg(test)]
+mod test;
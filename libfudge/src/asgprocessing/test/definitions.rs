@@ -0,0 +1,169 @@
+use super::utils::*;
+
+use crate::asg::symboltable::SymbolKey;
+use crate::asg::*;
+use crate::error::*;
+use crate::source::SourceSpan;
+use crate::utils::objectstore::*;
+
+// Initializer of a def in the global module, after folding
+fn get_definition<'a>(asg: &'a Asg, symbol: &str) -> &'a ExpressionObject {
+    let module = asg.get_module(&asg.global_module);
+    module
+        .scopestore
+        .keys()
+        .map(|scopekey| module.scopestore.get(&scopekey))
+        .find_map(|scope| {
+            let definition = scope
+                .symboltable
+                .definitions
+                .get(&SymbolKey::from_str(symbol))?;
+            Some(&scope.expressions.get(definition).object)
+        })
+        .unwrap()
+}
+
+fn assert_folded_integer(asg: &Asg, symbol: &str, expected: u64) {
+    match get_definition(asg, symbol) {
+        ExpressionObject::Literal(expressions::Literal::IntegerLiteral(n)) => {
+            assert_eq!(n.data, expected)
+        }
+        n => panic!("Def {} was not folded to an integer: {:?}", symbol, n),
+    }
+}
+
+fn assert_folded_bool(asg: &Asg, symbol: &str, expected: bool) {
+    match get_definition(asg, symbol) {
+        ExpressionObject::Literal(expressions::Literal::BoolLiteral(n)) => {
+            assert_eq!(n.value, expected)
+        }
+        n => panic!("Def {} was not folded to a bool: {:?}", symbol, n),
+    }
+}
+
+#[test]
+fn test_fold_arithmetic() {
+    let result = process_asg(
+        "\
+            def a : #primitives.u32 = 2 + 3 * 4\n\
+            def b : #primitives.u8 = 200 + 100\n\
+            def c : #primitives.s32 = 0 - 7\n\
+        ",
+    );
+    assert_folded_integer(&result.asg, "a", 14);
+    assert_folded_integer(&result.asg, "b", 44);
+    assert_folded_integer(&result.asg, "c", -7i64 as u64);
+}
+
+#[test]
+fn test_fold_other_definitions() {
+    let result = process_asg(
+        "\
+            def b : #primitives.u32 = a * 2\n\
+            def a : #primitives.u32 = 5\n\
+            def c = b > a and true\n\
+        ",
+    );
+    assert_folded_integer(&result.asg, "b", 10);
+    assert_folded_bool(&result.asg, "c", true);
+}
+
+#[test]
+fn test_fold_taken_branch() {
+    let result = process_asg(
+        "\
+            var x : #primitives.u32 = 1\n\
+            def a : #primitives.u32 = if 1 > 2 => x else if true => 3 else x\n\
+        ",
+    );
+    assert_folded_integer(&result.asg, "a", 3);
+}
+
+#[test]
+fn test_wrong_definition_of_variable() {
+    let result = process_asg_with_errors(
+        "\
+            var x : #primitives.u32 = 1\n\
+            def b = x + 1\n\
+        ",
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::DefinitionNotConstant)],
+    );
+
+    // Points at the def, with a label at the variable it depends on
+    let error = &result.errors[0];
+    assert_eq!(
+        error.message,
+        "Cannot evaluate def b at compile time, it depends on the runtime state of x"
+    );
+    assert_eq!(error.source_span, SourceSpan { pos: 32, len: 1 });
+    assert_eq!(error.labels[0].0, SourceSpan { pos: 4, len: 1 });
+    assert_eq!(error.labels[0].1, "x is only known at runtime");
+}
+
+#[test]
+fn test_wrong_definition_of_call() {
+    let result = process_asg_with_errors(
+        "\
+            def f =\n\
+                \tfunc() -> #primitives.u32 do\n\
+                    \t\treturn 1\n\
+                \tend\n\
+            def a = f()\n\
+        ",
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::DefinitionNotConstant)],
+    );
+    assert_eq!(
+        result.errors[0].message,
+        "Cannot evaluate def a at compile time, it depends on a call, calls are evaluated at runtime"
+    );
+    assert!(result.errors[0].labels.is_empty());
+}
+
+#[test]
+fn test_wrong_definition_of_variable_in_taken_branch() {
+    let result = process_asg_with_errors(
+        "\
+            var x : #primitives.u32 = 1\n\
+            def a : #primitives.u32 = if false => 3 else x\n\
+        ",
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::DefinitionNotConstant)],
+    );
+    assert_eq!(result.errors[0].labels[0].0, SourceSpan { pos: 4, len: 1 });
+}
+
+#[test]
+fn test_wrong_cyclic_definition() {
+    let result = process_asg_with_errors(
+        "\
+            def a : #primitives.u32 = b + 1\n\
+            def b : #primitives.u32 = a + 1\n\
+        ",
+    );
+
+    // Both defs are reported, the one evaluated first finds the cycle
+    expect_error_ids(
+        &result.errors,
+        &[
+            new_error_id(errors::DefinitionNotConstant),
+            new_error_id(errors::DefinitionNotConstant),
+        ],
+    );
+    assert!(result.errors[0]
+        .message
+        .ends_with("the definition refers to itself"));
+}
+
+#[test]
+#[should_panic(expected = "If expression needs an else branch to give a value")]
+fn test_wrong_if_without_else() {
+    process_asg("def a : #primitives.u32 = if true => 3\n");
+}
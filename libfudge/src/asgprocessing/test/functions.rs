@@ -0,0 +1,83 @@
+use super::utils::*;
+
+use crate::error::*;
+use crate::source::SourceSpan;
+
+const FIXTURE: &str = "\
+    def f =\n\
+        \tfunc(a : #primitives.u32, b : #primitives.u32) -> #primitives.u32 do\n\
+            \t\treturn a + b\n\
+        \tend";
+
+#[test]
+fn test_named_arguments() {
+    process_asg(&append_to_fixture(FIXTURE, "var x = f(b = 1, a = 2)"));
+}
+
+#[test]
+fn test_wrong_unknown_named_argument() {
+    let result = process_asg_with_errors(&append_to_fixture(FIXTURE, "var x = f(1, c = 2)"));
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::UnknownNamedArgument)],
+    );
+
+    // Points at the named argument
+    let error = &result.errors[0];
+    assert_eq!(error.message, "Unknown parameter c in call to global.f");
+    assert_eq!(error.source_span, SourceSpan { pos: 111, len: 5 });
+}
+
+#[test]
+fn test_wrong_duplicate_named_argument() {
+    let result = process_asg_with_errors(&append_to_fixture(FIXTURE, "var x = f(1, a = 2)"));
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::DuplicateNamedArgument)],
+    );
+    assert_eq!(
+        result.errors[0].source_span,
+        SourceSpan { pos: 111, len: 5 }
+    );
+}
+
+#[test]
+fn test_wrong_too_many_arguments() {
+    let result = process_asg_with_errors(&append_to_fixture(FIXTURE, "var x = f(1, 2, 3)"));
+    expect_error_ids(&result.errors, &[new_error_id(errors::TooManyArguments)]);
+
+    // Points at the argument list
+    assert_eq!(
+        result.errors[0].source_span,
+        SourceSpan { pos: 107, len: 9 }
+    );
+}
+
+#[test]
+fn test_wrong_missing_argument() {
+    let result = process_asg_with_errors(&append_to_fixture(FIXTURE, "var x = f(b = 1)"));
+    expect_error_ids(&result.errors, &[new_error_id(errors::MissingArgument)]);
+    assert_eq!(result.errors[0].message, "Missing argument for parameter a");
+}
+
+#[test]
+fn test_wrong_conflicting_type_arguments() {
+    let result = process_asg_with_errors(
+        "\
+        def g =\n\
+            \tfunc(a : $T, b : $T) -> $T do\n\
+                \t\treturn a\n\
+            \tend\n\
+        var x : #primitives.u32 = 1\n\
+        var y : #primitives.bool = true\n\
+        var z = g(x, y)",
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::ConflictingTypeArguments)],
+    );
+    assert_eq!(
+        result.errors[0].source_span,
+        SourceSpan { pos: 124, len: 6 }
+    );
+}
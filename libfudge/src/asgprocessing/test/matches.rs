@@ -0,0 +1,247 @@
+use super::utils::*;
+
+use crate::error::*;
+use crate::source::SourceSpan;
+
+static SHAPE_FIXTURE: &str = "\
+    def Shape =\n\
+        \tenum begin\n\
+            \t\tcase none\n\
+            \t\tcase circle(radius : #primitives.u32)\n\
+            \t\tcase rect(w : #primitives.u32, h : #primitives.u32)\n\
+        \tend\n\
+    var s : Shape = Shape.circle(2)\n\
+    var a : #primitives.u32 = 0\n\
+    ";
+
+#[test]
+fn test_match_all_variants() {
+    process_asg(
+        append_to_fixture(
+            SHAPE_FIXTURE,
+            "\
+                a =\n\
+                    \tmatch s\n\
+                    \tcase none => 0\n\
+                    \tcase circle(r) => r\n\
+                    \tcase rect(w, _) => w\n\
+            ",
+        )
+        .as_str(),
+    );
+}
+
+#[test]
+fn test_match_with_else() {
+    process_asg(
+        append_to_fixture(
+            SHAPE_FIXTURE,
+            "\
+                match s\n\
+                case circle(r) then\n\
+                    \ta = r\n\
+                else\n\
+                    \ta = 1\n\
+                end\n\
+            ",
+        )
+        .as_str(),
+    );
+}
+
+#[test]
+fn test_wrong_match_missing_variants() {
+    let result = process_asg_with_errors(
+        append_to_fixture(
+            SHAPE_FIXTURE,
+            "\
+                match s\n\
+                case circle(r) then\n\
+                    \ta = r\n\
+                end\n\
+            ",
+        )
+        .as_str(),
+    );
+    expect_error_ids(&result.errors, &[new_error_id(errors::NonExhaustiveMatch)]);
+    assert_eq!(result.errors[0].message, "Match is not exhaustive");
+    assert_eq!(result.errors[0].labels[0].1, "Missing variants: none, rect");
+}
+
+#[test]
+fn test_wrong_match_expression_missing_variant() {
+    let result = process_asg_with_errors(
+        append_to_fixture(
+            SHAPE_FIXTURE,
+            "\
+                a =\n\
+                    \tmatch s\n\
+                    \tcase none => 0\n\
+                    \tcase circle(r) => r\n\
+            ",
+        )
+        .as_str(),
+    );
+    expect_error_ids(&result.errors, &[new_error_id(errors::NonExhaustiveMatch)]);
+    assert_eq!(result.errors[0].message, "Match is not exhaustive");
+    assert_eq!(result.errors[0].labels[0].1, "Missing variants: rect");
+}
+
+#[test]
+fn test_wrong_match_guarded_arm_is_not_exhaustive() {
+    let result = process_asg_with_errors(
+        append_to_fixture(
+            SHAPE_FIXTURE,
+            "\
+                a =\n\
+                    \tmatch s\n\
+                    \tcase none => 0\n\
+                    \tcase circle(r) if r > 1 => r\n\
+                    \tcase rect(w, _) => w\n\
+            ",
+        )
+        .as_str(),
+    );
+    expect_error_ids(&result.errors, &[new_error_id(errors::NonExhaustiveMatch)]);
+    assert_eq!(result.errors[0].message, "Match is not exhaustive");
+    assert_eq!(result.errors[0].labels[0].1, "Missing variants: circle");
+}
+
+#[test]
+fn test_match_tuple_patterns() {
+    process_asg(
+        "\
+            var t : (#primitives.u32, #primitives.bool) = (5, true)\n\
+            var a : #primitives.u32 =\n\
+                \tmatch t\n\
+                \tcase (_, false) => 0\n\
+                \tcase (n, true) => n\n\
+        ",
+    );
+}
+
+#[test]
+fn test_wrong_match_tuple_patterns_missing_value() {
+    let result = process_asg_with_errors(
+        "\
+            var t : (#primitives.u32, #primitives.bool) = (5, true)\n\
+            var a : #primitives.u32 =\n\
+                \tmatch t\n\
+                \tcase (0, _) => 0\n\
+                \tcase (n, true) => n\n\
+        ",
+    );
+    expect_error_ids(&result.errors, &[new_error_id(errors::NonExhaustiveMatch)]);
+    assert_eq!(
+        result.errors[0].message,
+        "Match on (u32, bool) is not exhaustive, needs an else branch"
+    );
+}
+
+#[test]
+fn test_match_bool_literals() {
+    process_asg(
+        "\
+            var b : #primitives.bool = true\n\
+            var a : #primitives.u32 =\n\
+                \tmatch b\n\
+                \tcase true => 1\n\
+                \tcase false => 0\n\
+        ",
+    );
+}
+
+#[test]
+fn test_wrong_match_integer_literals() {
+    let result = process_asg_with_errors(
+        "\
+            var n : #primitives.u32 = 1\n\
+            var a : #primitives.u32 =\n\
+                \tmatch n\n\
+                \tcase 0 => 1\n\
+                \tcase 1 => 0\n\
+        ",
+    );
+    expect_error_ids(&result.errors, &[new_error_id(errors::NonExhaustiveMatch)]);
+    assert_eq!(
+        result.errors[0].message,
+        "Match on u32 is not exhaustive, needs an else branch"
+    );
+}
+
+#[test]
+fn test_match_struct_pattern_with_variants() {
+    process_asg(
+        append_to_fixture(
+            SHAPE_FIXTURE,
+            "\
+            def P =\n\
+                \tstruct begin\n\
+                    \t\tvar shape : Shape\n\
+                    \t\tvar filled : #primitives.bool\n\
+                \tend\n\
+            var p : P = P { shape = s, filled = true }\n\
+            a =\n\
+                \tmatch p\n\
+                \tcase { filled = false } => 0\n\
+                \tcase { shape = circle(r) } => r\n\
+                \tcase { shape = rect(w, _) } => w\n\
+                \tcase { filled = true } => 1\n\
+        ",
+        )
+        .as_str(),
+    );
+}
+
+#[test]
+fn test_wrong_match_unreachable_arm() {
+    let result = process_asg_with_errors(
+        append_to_fixture(
+            SHAPE_FIXTURE,
+            "\
+                a =\n\
+                    \tmatch s\n\
+                    \tcase circle(r) => r\n\
+                    \tcase _ => 0\n\
+                    \tcase none => 1\n\
+            ",
+        )
+        .as_str(),
+    );
+    expect_error_ids(&result.errors, &[new_error_id(errors::UnreachableMatchArm)]);
+    assert_eq!(result.errors[0].message, "Match arm 3 is unreachable");
+    assert_eq!(
+        result.errors[0].source_span,
+        SourceSpan { pos: 244, len: 9 }
+    );
+}
+
+#[test]
+fn test_wrong_match_unreachable_else() {
+    let result = process_asg_with_errors(
+        append_to_fixture(
+            SHAPE_FIXTURE,
+            "\
+                match s\n\
+                case none then\n\
+                    \ta = 0\n\
+                case circle(r) then\n\
+                    \ta = r\n\
+                case rect(w, _) then\n\
+                    \ta = w\n\
+                else\n\
+                    \ta = 1\n\
+                end\n\
+            ",
+        )
+        .as_str(),
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::UnreachableElseBranch)],
+    );
+    assert_eq!(
+        result.errors[0].source_span,
+        SourceSpan { pos: 196, len: 7 }
+    );
+}
@@ -0,0 +1,9 @@
+pub mod definitions;
+pub mod functions;
+pub mod matches;
+pub mod mutability;
+pub mod optionals;
+pub mod references;
+pub mod results;
+pub mod structs;
+pub mod utils;
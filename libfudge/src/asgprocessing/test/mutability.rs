@@ -0,0 +1,116 @@
+use super::utils::*;
+
+use crate::error::*;
+use crate::source::SourceSpan;
+
+#[test]
+fn test_assign_to_var() {
+    process_asg(
+        "\
+        var x = 5\n\
+        x = 6\n\
+        y := 1\n\
+        y = x",
+    );
+}
+
+// Short-hand declarations are vars, whether or not they are re-assigned
+#[test]
+fn test_assign_to_inferred_declaration() {
+    process_asg(
+        "\
+        x : #primitives.u32 = 5\n\
+        x = x + 1\n\
+        y := x\n\
+        y = 2",
+    );
+}
+
+#[test]
+fn test_wrong_assign_to_def() {
+    let result = process_asg_with_errors(
+        "\
+        def x = 5\n\
+        x = 6",
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::AssignmentToImmutable)],
+    );
+
+    // Points at the whole assignment, with a label at the declared symbol
+    let error = &result.errors[0];
+    assert_eq!(error.source_span, SourceSpan { pos: 10, len: 5 });
+    assert_eq!(error.labels.len(), 1);
+    assert_eq!(error.labels[0].0, SourceSpan { pos: 4, len: 1 });
+}
+
+#[test]
+fn test_wrong_assign_to_const() {
+    let result = process_asg_with_errors(
+        "\
+        const x : #primitives.u32 = 5\n\
+        x = x + 1",
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::AssignmentToImmutable)],
+    );
+    assert_eq!(result.errors[0].source_span, SourceSpan { pos: 30, len: 9 });
+    assert_eq!(result.errors[0].labels[0].0, SourceSpan { pos: 6, len: 1 });
+}
+
+#[test]
+fn test_wrong_assign_to_field_of_const() {
+    let result = process_asg_with_errors(
+        "\
+        def P =\n\
+            \tstruct begin\n\
+                \t\tvar a : #primitives.u32\n\
+            \tend\n\
+        var q : P\n\
+        const p = q\n\
+        p.a = 2",
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::AssignmentToImmutable)],
+    );
+}
+
+#[test]
+fn test_assign_to_element_of_var() {
+    process_asg(
+        "\
+        var a : #primitives.u32[2] = [1, 2]\n\
+        a[0] = 5",
+    );
+}
+
+#[test]
+fn test_wrong_assign_to_element_of_const() {
+    let result = process_asg_with_errors(
+        "\
+        var q : #primitives.u32[2] = [1, 2]\n\
+        const a = q\n\
+        a[0] = 5",
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::AssignmentToImmutable)],
+    );
+    assert_eq!(result.errors[0].labels[0].0, SourceSpan { pos: 42, len: 1 });
+}
+
+#[test]
+fn test_wrong_assign_to_nested_element_of_def() {
+    let result = process_asg_with_errors(
+        "\
+        def a : #primitives.u32[2][2] = [[1, 2], [3, 4]]\n\
+        a[1][0] = 5",
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::AssignmentToImmutable)],
+    );
+}
@@ -0,0 +1,180 @@
+use super::utils::*;
+
+use crate::error::*;
+use crate::source::SourceSpan;
+
+static OPTIONAL_FIXTURE: &str = "\
+    var x : ?#primitives.u32 = 5\n\
+    var z : ?#primitives.u32 = null\n\
+    var r : ?&#primitives.u32 = null\n\
+    var b : #primitives.bool = true\n\
+    var y : #primitives.u32 = 0\n\
+    ";
+
+#[test]
+fn test_narrowed_read() {
+    process_asg(
+        append_to_fixture(
+            OPTIONAL_FIXTURE,
+            "\
+                if x != null then\n\
+                    \ty = x + 1\n\
+                end\n\
+            ",
+        )
+        .as_str(),
+    );
+}
+
+#[test]
+fn test_narrowed_by_and() {
+    process_asg(
+        append_to_fixture(
+            OPTIONAL_FIXTURE,
+            "\
+                if b and x != null and null != z then\n\
+                    \ty = x + z\n\
+                end\n\
+            ",
+        )
+        .as_str(),
+    );
+}
+
+#[test]
+fn test_narrowed_in_rhs_of_and() {
+    process_asg(
+        append_to_fixture(
+            OPTIONAL_FIXTURE,
+            "\
+                if r != null and *r > 3 then\n\
+                    \ty = *r\n\
+                end\n\
+            ",
+        )
+        .as_str(),
+    );
+}
+
+#[test]
+fn test_assign_to_narrowed() {
+    process_asg(
+        append_to_fixture(
+            OPTIONAL_FIXTURE,
+            "\
+                if x != null then\n\
+                    \ty = x\n\
+                    \tx = null\n\
+                    \tx = z\n\
+                    \tx = 3\n\
+                end\n\
+            ",
+        )
+        .as_str(),
+    );
+}
+
+#[test]
+fn test_wrong_dereference_optional() {
+    let result = process_asg_with_errors(append_to_fixture(OPTIONAL_FIXTURE, "y = *r\n").as_str());
+    expect_error_ids(&result.errors, &[new_error_id(errors::OptionalUsedAsValue)]);
+
+    // Points at the dereference
+    let error = &result.errors[0];
+    assert_eq!(
+        error.message,
+        "Cannot dereference ?&u32, it can be null unless compared to null first"
+    );
+    assert_eq!(error.source_span, SourceSpan { pos: 159, len: 2 });
+}
+
+#[test]
+fn test_wrong_dereference_optional_narrowed_by_or() {
+    let result = process_asg_with_errors(
+        append_to_fixture(
+            OPTIONAL_FIXTURE,
+            "\
+                if r != null or b then\n\
+                    \ty = *r\n\
+                end\n\
+            ",
+        )
+        .as_str(),
+    );
+    expect_error_ids(&result.errors, &[new_error_id(errors::OptionalUsedAsValue)]);
+    assert_eq!(
+        result.errors[0].source_span,
+        SourceSpan { pos: 183, len: 2 }
+    );
+}
+
+#[test]
+fn test_wrong_dereference_optional_outside_narrowed_branch() {
+    let result = process_asg_with_errors(
+        append_to_fixture(
+            OPTIONAL_FIXTURE,
+            "\
+                if r != null then\n\
+                    \ty = *r\n\
+                else\n\
+                    \ty = *r + 1\n\
+                end\n\
+            ",
+        )
+        .as_str(),
+    );
+    expect_error_ids(&result.errors, &[new_error_id(errors::OptionalUsedAsValue)]);
+    assert_eq!(
+        result.errors[0].source_span,
+        SourceSpan { pos: 191, len: 2 }
+    );
+}
+
+#[test]
+fn test_wrong_dereference_non_reference() {
+    let result = process_asg_with_errors(append_to_fixture(OPTIONAL_FIXTURE, "y = *y\n").as_str());
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::DereferenceOfNonReference)],
+    );
+    assert_eq!(
+        result.errors[0].message,
+        "Cannot dereference non-reference type u32"
+    );
+}
+
+#[test]
+fn test_wrong_operand_optional() {
+    let result =
+        process_asg_with_errors(append_to_fixture(OPTIONAL_FIXTURE, "y = x + 1\n").as_str());
+    expect_error_ids(&result.errors, &[new_error_id(errors::OptionalUsedAsValue)]);
+
+    // Points at the optional symbol
+    let error = &result.errors[0];
+    assert_eq!(
+        error.message,
+        "Cannot use x as a value, it can be null unless compared to null first"
+    );
+    assert_eq!(error.source_span, SourceSpan { pos: 159, len: 1 });
+}
+
+#[test]
+fn test_wrong_operand_optional_outside_narrowed_branch() {
+    let result = process_asg_with_errors(
+        append_to_fixture(
+            OPTIONAL_FIXTURE,
+            "\
+                if z != null then\n\
+                    \ty = z\n\
+                end\n\
+                b = z > 3\n\
+            ",
+        )
+        .as_str(),
+    );
+    expect_error_ids(&result.errors, &[new_error_id(errors::OptionalUsedAsValue)]);
+    assert_eq!(
+        result.errors[0].source_span,
+        SourceSpan { pos: 188, len: 1 }
+    );
+}
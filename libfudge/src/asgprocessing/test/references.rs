@@ -0,0 +1,118 @@
+use super::utils::*;
+
+use crate::error::*;
+use crate::source::SourceSpan;
+
+#[test]
+fn test_return_reference_parameter() {
+    process_asg(
+        "\
+            def f =\n\
+                \tfunc(r : &#primitives.u32) -> &#primitives.u32 do\n\
+                    \t\treturn r\n\
+                \tend\n\
+            var x : #primitives.u32 = 1\n\
+            var y = f(&x)\n\
+        ",
+    );
+}
+
+#[test]
+fn test_return_capture_by_value() {
+    process_asg(
+        "\
+            def make_counter =\n\
+                \tfunc() do\n\
+                    \t\tvar count : #primitives.u32 = 0\n\
+                    \t\tdef counter =\n\
+                        \t\t\tfunc[count]() -> #primitives.u32 do\n\
+                            \t\t\t\treturn count + 1\n\
+                        \t\t\tend\n\
+                    \t\treturn counter\n\
+                \tend\n\
+            var counter = make_counter()\n\
+        ",
+    );
+}
+
+#[test]
+fn test_wrong_return_local_reference() {
+    let result = process_asg_with_errors(
+        "\
+            def f =\n\
+                \tfunc() -> &#primitives.u32 do\n\
+                    \t\tvar local : #primitives.u32 = 5\n\
+                    \t\treturn &local\n\
+                \tend\n\
+            var r = f()\n\
+        ",
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::ReferenceOutlivesVariable)],
+    );
+
+    // Points at the reference, with a label at the local
+    let error = &result.errors[0];
+    assert_eq!(error.message, "Reference to local outlives the variable");
+    assert_eq!(error.source_span, SourceSpan { pos: 82, len: 6 });
+    assert_eq!(error.labels[0].0, SourceSpan { pos: 45, len: 5 });
+}
+
+#[test]
+fn test_wrong_return_local_reference_from_branch() {
+    let result = process_asg_with_errors(
+        "\
+            def f =\n\
+                \tfunc(b : #primitives.bool) -> &#primitives.u32 do\n\
+                    \t\tvar local : #primitives.u32 = 5\n\
+                    \t\tif b then\n\
+                        \t\t\treturn &local\n\
+                    \t\tend\n\
+                    \t\treturn &local\n\
+                \tend\n\
+            var r = f(true)\n\
+        ",
+    );
+
+    // Every returned reference is reported
+    expect_error_ids(
+        &result.errors,
+        &[
+            new_error_id(errors::ReferenceOutlivesVariable),
+            new_error_id(errors::ReferenceOutlivesVariable),
+        ],
+    );
+}
+
+#[test]
+fn test_wrong_return_reference_capture() {
+    let result = process_asg_with_errors(
+        "\
+            def make_counter =\n\
+                \tfunc() do\n\
+                    \t\tvar count : #primitives.u32 = 0\n\
+                    \t\tdef counter =\n\
+                        \t\t\tfunc[&count]() -> #primitives.u32 do\n\
+                            \t\t\t\tcount = count + 1\n\
+                            \t\t\t\treturn count\n\
+                        \t\t\tend\n\
+                    \t\treturn counter\n\
+                \tend\n\
+            var counter = make_counter()\n\
+        ",
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::ReferenceOutlivesVariable)],
+    );
+
+    // Points at the returned function
+    let error = &result.errors[0];
+    assert_eq!(
+        error.message,
+        "Captured reference to count outlives the variable"
+    );
+    assert_eq!(error.source_span, SourceSpan { pos: 175, len: 7 });
+    assert_eq!(error.labels[0].0, SourceSpan { pos: 36, len: 5 });
+}
@@ -0,0 +1,138 @@
+use super::utils::*;
+
+use crate::error::*;
+use crate::source::SourceSpan;
+
+static RESULT_FIXTURE: &str = "\
+    def Parsed = #result(#primitives.u32, #primitives.u32)\n\
+    def Checked = #result(#primitives.u32, #primitives.bool)\n\
+    def half =\n\
+        \tfunc(n : #primitives.u32) -> Parsed do\n\
+            \t\tif n % 2 == 1 then\n\
+                \t\t\treturn Parsed.error(1)\n\
+            \t\tend\n\
+            \t\treturn Parsed.ok(n / 2)\n\
+        \tend\n\
+    ";
+
+#[test]
+fn test_try_in_result_function() {
+    process_asg(
+        append_to_fixture(
+            RESULT_FIXTURE,
+            "\
+                def quarter =\n\
+                    \tfunc(n : #primitives.u32) -> Parsed do\n\
+                        \t\treturn Parsed.ok(try half(try half(n)))\n\
+                    \tend\n\
+            ",
+        )
+        .as_str(),
+    );
+}
+
+#[test]
+fn test_wrong_try_in_non_result_function() {
+    let result = process_asg_with_errors(
+        append_to_fixture(
+            RESULT_FIXTURE,
+            "\
+                def f =\n\
+                    \tfunc() -> #primitives.u32 do\n\
+                        \t\treturn try half(3)\n\
+                    \tend\n\
+            ",
+        )
+        .as_str(),
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::TryOutsideResultFunction)],
+    );
+
+    // Points at the try, with a label at the output of the function
+    let error = &result.errors[0];
+    assert_eq!(
+        error.message,
+        "Try can only be used in functions returning a result, not u32"
+    );
+    assert_eq!(error.source_span, SourceSpan { pos: 295, len: 11 });
+    assert_eq!(error.labels[0].0, SourceSpan { pos: 264, len: 18 });
+    assert_eq!(error.labels[0].1, "The function returns u32");
+}
+
+#[test]
+fn test_wrong_try_in_function_without_output() {
+    let result = process_asg_with_errors(
+        append_to_fixture(
+            RESULT_FIXTURE,
+            "\
+                def f =\n\
+                    \tfunc(n : #primitives.u32) do\n\
+                        \t\tvar h = try half(n)\n\
+                    \tend\n\
+            ",
+        )
+        .as_str(),
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::TryOutsideResultFunction)],
+    );
+
+    // Without outputs, the label is at the function
+    let error = &result.errors[0];
+    assert_eq!(error.source_span, SourceSpan { pos: 296, len: 11 });
+    assert_eq!(error.labels[0].0, SourceSpan { pos: 257, len: 4 });
+    assert_eq!(error.labels[0].1, "The function does not return a value");
+}
+
+#[test]
+fn test_wrong_try_outside_function() {
+    let result = process_asg_with_errors(
+        append_to_fixture(RESULT_FIXTURE, "var h = try half(2)\n").as_str(),
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::TryOutsideResultFunction)],
+    );
+    assert_eq!(
+        result.errors[0].source_span,
+        SourceSpan { pos: 256, len: 11 }
+    );
+    assert!(result.errors[0].labels.is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Cannot propagate error u32 from a function with error bool")]
+fn test_wrong_try_mismatched_error() {
+    process_asg(
+        append_to_fixture(
+            RESULT_FIXTURE,
+            "\
+                def f =\n\
+                    \tfunc(n : #primitives.u32) -> Checked do\n\
+                        \t\treturn Checked.ok(try half(n))\n\
+                    \tend\n\
+            ",
+        )
+        .as_str(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Only results can be tried, not u32")]
+fn test_wrong_try_non_result() {
+    process_asg(
+        append_to_fixture(
+            RESULT_FIXTURE,
+            "\
+                def f =\n\
+                    \tfunc(n : #primitives.u32) -> Parsed do\n\
+                        \t\treturn Parsed.ok(try n)\n\
+                    \tend\n\
+            ",
+        )
+        .as_str(),
+    );
+}
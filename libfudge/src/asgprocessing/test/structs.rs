@@ -0,0 +1,143 @@
+use super::utils::*;
+
+use crate::error::*;
+use crate::source::SourceSpan;
+
+static STRUCT_FIXTURE: &str = "\
+    def A =\n\
+        \tstruct begin\n\
+            \t\tvar a : #primitives.u32\n\
+            \t\tvar b : ?#primitives.u8\n\
+        \tend\n\
+    def F =\n\
+        \tstruct begin\n\
+            \t\tvar f : func(x : #primitives.u32) -> #primitives.u32\n\
+        \tend\n\
+    ";
+
+static GENERIC_STRUCT_FIXTURE: &str = "\
+    def Pair =\n\
+        \tstruct begin\n\
+            \t\tvar first : $T\n\
+            \t\tvar second : $T\n\
+        \tend\n\
+    def Entry =\n\
+        \tstruct begin\n\
+            \t\tvar value : Pair($V)\n\
+            \t\tvar key : $K\n\
+        \tend\n\
+    ";
+
+#[test]
+fn test_struct_construction() {
+    process_asg(
+        append_to_fixture(
+            STRUCT_FIXTURE,
+            "\
+                var x = A { b = 2, a = 1 }\n\
+                var y : A = A { a = x.a }\n\
+            ",
+        )
+        .as_str(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Struct has no field c")]
+fn test_wrong_struct_construction_unknown_field() {
+    process_asg(append_to_fixture(STRUCT_FIXTURE, "var x = A { a = 1, c = 2 }\n").as_str());
+}
+
+#[test]
+#[should_panic(expected = "Field f of type")]
+fn test_wrong_struct_construction_missing_field() {
+    process_asg(append_to_fixture(STRUCT_FIXTURE, "var x = F {}\n").as_str());
+}
+
+#[test]
+#[should_panic(expected = "Type mismatch")]
+fn test_wrong_struct_construction_field_type() {
+    process_asg(append_to_fixture(STRUCT_FIXTURE, "var x = A { a = true }\n").as_str());
+}
+
+#[test]
+#[should_panic(expected = "Type mismatch")]
+fn test_wrong_struct_construction_declared_type() {
+    process_asg(
+        append_to_fixture(STRUCT_FIXTURE, "var x : #primitives.u32 = A { a = 1 }\n").as_str(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Cannot construct non-struct type u32")]
+fn test_wrong_construction_of_non_struct() {
+    process_asg("var x = #primitives.u32 { a = 1 }\n");
+}
+
+// Type arguments bind to the type parameters in order of first use in the fields
+#[test]
+fn test_generic_struct_type_parameter_order() {
+    process_asg(
+        append_to_fixture(
+            GENERIC_STRUCT_FIXTURE,
+            "\
+            var e : Entry(#primitives.u8, #primitives.u32)\n\
+            v : #primitives.u8 = e.value.first\n\
+            k : #primitives.u32 = e.key\n\
+            ",
+        )
+        .as_str(),
+    );
+}
+
+#[test]
+#[should_panic(expected = "Type mismatch")]
+fn test_wrong_generic_struct_type_parameter_order() {
+    process_asg(
+        append_to_fixture(
+            GENERIC_STRUCT_FIXTURE,
+            "\
+            var e : Entry(#primitives.u8, #primitives.u32)\n\
+            k : #primitives.u8 = e.key\n\
+            ",
+        )
+        .as_str(),
+    );
+}
+
+#[test]
+fn test_wrong_generic_struct_type_argument_count() {
+    let result = process_asg_with_errors(
+        append_to_fixture(
+            GENERIC_STRUCT_FIXTURE,
+            "var p : Pair(#primitives.u32, #primitives.u32)\n",
+        )
+        .as_str(),
+    );
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::InvalidTypeInstantiation)],
+    );
+    assert_eq!(result.errors[0].message, "Expected 1 type arguments, got 2");
+    assert_eq!(
+        result.errors[0].source_span,
+        SourceSpan { pos: 147, len: 34 }
+    );
+}
+
+#[test]
+fn test_wrong_instantiation_of_non_struct() {
+    let result = process_asg_with_errors("var x : #primitives.u32(#primitives.u8)\n");
+    expect_error_ids(
+        &result.errors,
+        &[new_error_id(errors::InvalidTypeInstantiation)],
+    );
+    assert_eq!(
+        result.errors[0].message,
+        "Cannot instantiate non-struct type u32"
+    );
+    assert_eq!(
+        result.errors[0].source_span,
+        SourceSpan { pos: 23, len: 16 }
+    );
+}
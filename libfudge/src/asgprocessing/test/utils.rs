@@ -0,0 +1,42 @@
+use crate::error;
+use crate::grapher;
+use crate::parser;
+use crate::parser::tokenstream::TokenStream;
+use crate::scanner;
+use crate::source::*;
+
+// TODO: Copy of function in parser test utils, move to common utils file
+pub fn expect_error_ids(errors: &Vec<error::Error>, expected_error_ids: &[error::ErrorId]) {
+    assert_eq!(
+        errors.len(),
+        expected_error_ids.len(),
+        "Found a different error count than expected!"
+    );
+
+    for i in 0..expected_error_ids.len() {
+        assert_eq!(errors[i].id, expected_error_ids[i]);
+    }
+}
+
+// Runs the source through the grapher and the asg processing, the source itself has to
+//  scan and parse without errors
+pub fn process_asg_with_errors(source: &str) -> grapher::GrapherResult {
+    let source = Source::from_str(source);
+
+    let scanner_result = scanner::tokenize(&source);
+    let parser_result = parser::parse(&mut TokenStream::new(&scanner_result.tokens, &source), true);
+    assert!(scanner_result.errors.is_empty() && parser_result.errors.is_empty());
+
+    grapher::create_graph(&parser_result.ast, &Vec::new())
+}
+
+pub fn process_asg(source: &str) -> grapher::GrapherResult {
+    let result = process_asg_with_errors(source);
+    let error_ids = result.errors.iter().map(|x| x.id).collect::<Vec<_>>();
+    assert_eq!(error_ids, &[]);
+    result
+}
+
+pub fn append_to_fixture(fixture: &str, appendix: &str) -> String {
+    format!("{}\n{}", fixture, appendix)
+}
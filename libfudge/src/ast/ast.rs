@@ -10,6 +10,8 @@ use std::hash::{Hash, Hasher};
 use crate::typesystem::*;
 
 use crate::shared::BinaryOperationType;
+use crate::shared::UnaryOperationType;
+use crate::source::SourceLocation;
 
 use crate::utils::stringstore::StringStore;
 pub use crate::utils::StringKey as SymbolRef;
@@ -20,6 +22,8 @@ use crate::utils::objectstore::*;
 pub enum BuiltInObject {
     Function(BuiltInFunction),
     PrimitiveType(PrimitiveType),
+    // Generic enum of a value or an error, "#result"
+    ResultType,
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -160,12 +164,39 @@ impl Ast {
 
         return None;
     }
+
+    // Type parameters used in a subtree, in order of first appearance
+    pub fn collect_type_parameters(&self, noderef: &NodeRef, symbols: &mut Vec<SymbolRef>) {
+        let node = self.get_node(noderef);
+        if let Node::TypeParameter(n) = node {
+            if !symbols.contains(&n.symbol) {
+                symbols.push(n.symbol.clone());
+            }
+        }
+
+        visit_children(node, |childref| {
+            self.collect_type_parameters(childref, symbols);
+            return true;
+        });
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SymbolDeclarationType {
+    // Computed at compile time
     Def,
+    // Computed once at declaration, cannot be re-assigned
+    Const,
     Var,
+    // Short-hand "a := 5" or "a : T = 5", declares a var
+    Inferred,
+}
+
+// How a function literal captures a local variable of the enclosing function
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CaptureType {
+    Value,
+    Reference,
 }
 
 // Declares enums and data structs associated with ast nodes
@@ -281,62 +312,197 @@ declare_nodes!(
     BooleanLiteral {
         value: bool,
     },
+    // Absent value of an optional type
+    NullLiteral,
     IntegerLiteral {
         value: u64,
-        // TODO: What does this mean? How does the literal know if it's signed or not?
+        // Set by the parser for literals that are directly negated, i.e. "-5"
         signed: bool,
     },
     // TODO: BigIntegerLiteral
+    FloatLiteral {
+        value: f64,
+    },
+    CharacterLiteral { value: char },
     StringLiteral { text: String },
     StructLiteral {
         fields: Vec<NodeRef>,
     },
+    // A trailing ".." makes the literal a dynamic array
+    ArrayLiteral {
+        elements: Vec<NodeRef>,
+        dynamic: bool,
+    },
+    // Parenthesized, comma-separated list of at least two expressions
+    TupleLiteral {
+        elements: Vec<NodeRef>,
+    },
     StructField {
         symbol: SymbolRef,
         typeexpr: NodeRef,
     },
+    EnumLiteral {
+        variants: Vec<NodeRef>,
+    },
+    // Value of the struct type expression, "T { field = expr, ... }", omitted fields
+    //  take their default values
+    StructConstruction {
+        expr: NodeRef,
+        fields: Vec<(SymbolRef, NodeRef)>,
+    },
+    // Payload fields are StructField nodes
+    EnumVariant {
+        symbol: SymbolRef,
+        fields: Vec<NodeRef>,
+    },
     FunctionLiteral {
+        // Explicit capture list, other used locals are captured by value
+        captures: Vec<(SymbolRef, CaptureType)>,
         inputparams: Vec<NodeRef>,
         outputparams: Vec<NodeRef>,
         body: NodeRef,
+        // The locations of the func keyword and of the outputs, starting at the arrow, are
+        //  kept to report returned captures and tries in functions not returning results
+        location: SourceLocation,
+        outputlocation: Option<SourceLocation>,
+    },
+    // Function literal without a body, i.e. the type of a function parameter
+    FunctionType {
+        inputparams: Vec<NodeRef>,
+        outputparams: Vec<NodeRef>,
     },
     InputParameter {
         symbol: SymbolRef,
         typeexpr: NodeRef,
+        defaultexpr: Option<NodeRef>,
     },
     OutputParameter { typeexpr: NodeRef },
     BuiltInObjectReference {
         object: BuiltInObject,
     },
-    SymbolReference { symbol: SymbolRef },
+    // Optionals read without comparing them to null are reported after parsing, so the
+    //  location is kept
+    SymbolReference { symbol: SymbolRef, location: SourceLocation },
+    // "$" label for a type, declared by using it in the input parameters of a function
+    TypeParameter { symbol: SymbolRef },
     IfStatement { branches: Vec<(NodeRef, NodeRef)>, elsebranch: Option<NodeRef> },
     IfExpression { branches: Vec<(NodeRef, NodeRef)>, elsebranch: Option<NodeRef>  },
-    ReturnStatement { expr: Option<NodeRef> },
-    AssignStatement { lhs: NodeRef, rhs: NodeRef },
+    // Unreachable arms and else branches are reported after parsing, so the locations are
+    //  kept. The location of a match spans its matched expression, that of an arm its
+    //  pattern and guard.
+    MatchStatement { expr: NodeRef, arms: Vec<NodeRef>, elsebranch: Option<NodeRef>, location: SourceLocation },
+    MatchExpression { expr: NodeRef, arms: Vec<NodeRef>, elsebranch: Option<NodeRef>, location: SourceLocation },
+    // The body is a statement body for match statements and an expression otherwise,
+    //  the arm is only taken if the optional guard expression holds as well
+    MatchArm { pattern: NodeRef, guard: Option<NodeRef>, body: NodeRef, location: SourceLocation },
+    // Variant name followed by optional bindings for its fields, bindings named _ are stored as None
+    VariantPattern { symbol: SymbolRef, bindings: Vec<Option<SymbolRef>> },
+    // "_", matches anything without binding it
+    WildcardPattern,
+    // Binds the matched value to the symbol, only valid nested in other patterns
+    BindingPattern { symbol: SymbolRef },
+    // Matches values equal to the literal expression
+    LiteralPattern { value: NodeRef },
+    // Matches values in the half-open range, like range expressions
+    RangePattern { start: NodeRef, end: NodeRef },
+    // Matches the listed fields of a struct, "{ field = pattern, ... }", other fields are ignored
+    StructPattern { fields: Vec<(SymbolRef, NodeRef)> },
+    // Matches each element of a tuple, "(pattern, pattern, ...)"
+    TuplePattern { elements: Vec<NodeRef> },
+    ReturnStatement { exprs: Vec<NodeRef> },
+    WhileStatement { condition: NodeRef, body: NodeRef },
+    ForStatement { symbol: SymbolRef, iterable: NodeRef, body: NodeRef },
+    BreakStatement,
+    ContinueStatement,
+    // Assignments to defs and consts are rejected after parsing, so the location is kept
+    AssignStatement { lhs: NodeRef, rhs: NodeRef, location: SourceLocation },
     ArgumentList {
         args: Vec<NodeRef>,
     },
+    // Argument supplied by parameter name, "symbol = expr". Arguments are matched to the
+    //  parameters after parsing, so the locations of the argument and of the argument list
+    //  of a call are kept.
+    NamedArgument {
+        symbol: SymbolRef,
+        expr: NodeRef,
+        location: SourceLocation,
+    },
     // TODO: Can this be generalized to parameterized symbol reference?
     //  The same syntax is used for function calls, type parameteters etc
     CallOperation {
         expr: NodeRef,
         arglist: NodeRef,
+        location: SourceLocation,
     },
     BinaryOperation {
         optype: BinaryOperationType,
         lhs: NodeRef,
         rhs: NodeRef,
     },
+    // Half-open range of values, start included and end excluded
+    RangeExpression {
+        start: NodeRef,
+        end: NodeRef,
+    },
+    UnaryOperation {
+        optype: UnaryOperationType,
+        expr: NodeRef,
+    },
     SymbolDeclaration {
         symbol: SymbolRef,
         decltype: SymbolDeclarationType,
         typeexpr: Option<NodeRef>,
         initexpr: Option<NodeRef>,
+        location: SourceLocation,
+    },
+    // Declares one symbol per element of a tuple, symbols named _ are ignored and stored as None
+    DestructuringDeclaration {
+        symbols: Vec<Option<SymbolRef>>,
+        decltype: SymbolDeclarationType,
+        initexpr: NodeRef,
+        location: SourceLocation,
     },
     SubScript {
         expr: NodeRef,
         field: SymbolRef,
     },
+    // Indexing is checked at runtime, so the location is kept for reporting
+    IndexExpression {
+        expr: NodeRef,
+        index: NodeRef,
+        location: SourceLocation,
+    },
+    // Element type followed by [..]
+    DynamicArrayType {
+        expr: NodeRef,
+    },
+    // Element type followed by [&]
+    SliceType {
+        expr: NodeRef,
+    },
+    // Address of the value, or the reference type of a type, "&expr". References are
+    //  checked to not outlive their variable after parsing, so the location is kept.
+    ReferenceExpression {
+        expr: NodeRef,
+        location: SourceLocation,
+    },
+    // Value the reference refers to, "*expr". Dereferences are checked after parsing, so
+    //  the location is kept.
+    DereferenceExpression {
+        expr: NodeRef,
+        location: SourceLocation,
+    },
+    // Type holding either a value of the type expression or null, "?expr"
+    OptionalType {
+        expr: NodeRef,
+    },
+    // Value of an ok result, an error result is returned from the function instead, "try expr".
+    //  The enclosing function is checked to return a result after parsing, so the location
+    //  is kept.
+    TryExpression {
+        expr: NodeRef,
+        location: SourceLocation,
+    },
 );
 
 macro_rules! as_node {
@@ -389,10 +555,22 @@ impl ChildCollector for nodes::BooleanLiteral {
     fn collect_children(&self, _collector: &mut Vec<NodeRef>) {}
 }
 
+impl ChildCollector for nodes::NullLiteral {
+    fn collect_children(&self, _collector: &mut Vec<NodeRef>) {}
+}
+
 impl ChildCollector for nodes::IntegerLiteral {
     fn collect_children(&self, _collector: &mut Vec<NodeRef>) {}
 }
 
+impl ChildCollector for nodes::FloatLiteral {
+    fn collect_children(&self, _collector: &mut Vec<NodeRef>) {}
+}
+
+impl ChildCollector for nodes::CharacterLiteral {
+    fn collect_children(&self, _collector: &mut Vec<NodeRef>) {}
+}
+
 impl ChildCollector for nodes::StringLiteral {
     fn collect_children(&self, _collector: &mut Vec<NodeRef>) {}
 }
@@ -405,12 +583,53 @@ impl ChildCollector for nodes::StructLiteral {
     }
 }
 
+impl ChildCollector for nodes::ArrayLiteral {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        for n in &self.elements {
+            collector.push(*n);
+        }
+    }
+}
+
+impl ChildCollector for nodes::TupleLiteral {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        for n in &self.elements {
+            collector.push(*n);
+        }
+    }
+}
+
 impl ChildCollector for nodes::StructField {
     fn collect_children(&self, collector: &mut Vec<NodeRef>) {
         collector.push(self.typeexpr);
     }
 }
 
+impl ChildCollector for nodes::StructConstruction {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.expr);
+        for (_, n) in &self.fields {
+            collector.push(*n);
+        }
+    }
+}
+
+impl ChildCollector for nodes::EnumLiteral {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        for n in &self.variants {
+            collector.push(*n);
+        }
+    }
+}
+
+impl ChildCollector for nodes::EnumVariant {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        for n in &self.fields {
+            collector.push(*n);
+        }
+    }
+}
+
 impl ChildCollector for nodes::FunctionLiteral {
     fn collect_children(&self, collector: &mut Vec<NodeRef>) {
         for n in &self.inputparams {
@@ -424,9 +643,23 @@ impl ChildCollector for nodes::FunctionLiteral {
     }
 }
 
+impl ChildCollector for nodes::FunctionType {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        for n in &self.inputparams {
+            collector.push(*n);
+        }
+        for n in &self.outputparams {
+            collector.push(*n);
+        }
+    }
+}
+
 impl ChildCollector for nodes::InputParameter {
     fn collect_children(&self, collector: &mut Vec<NodeRef>) {
         collector.push(self.typeexpr);
+        if let Some(n) = &self.defaultexpr {
+            collector.push(*n);
+        }
     }
 }
 
@@ -444,6 +677,10 @@ impl ChildCollector for nodes::SymbolReference {
     fn collect_children(&self, _collector: &mut Vec<NodeRef>) {}
 }
 
+impl ChildCollector for nodes::TypeParameter {
+    fn collect_children(&self, _collector: &mut Vec<NodeRef>) {}
+}
+
 impl ChildCollector for nodes::IfStatement {
     fn collect_children(&self, collector: &mut Vec<NodeRef>) {
         for case in &self.branches {
@@ -468,14 +705,111 @@ impl ChildCollector for nodes::IfExpression {
     }
 }
 
+impl ChildCollector for nodes::MatchStatement {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.expr);
+        for n in &self.arms {
+            collector.push(*n);
+        }
+        if let Some(n) = &self.elsebranch {
+            collector.push(*n);
+        }
+    }
+}
+
+impl ChildCollector for nodes::MatchExpression {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.expr);
+        for n in &self.arms {
+            collector.push(*n);
+        }
+        if let Some(n) = &self.elsebranch {
+            collector.push(*n);
+        }
+    }
+}
+
+impl ChildCollector for nodes::MatchArm {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.pattern);
+        if let Some(n) = &self.guard {
+            collector.push(*n);
+        }
+        collector.push(self.body);
+    }
+}
+
+impl ChildCollector for nodes::VariantPattern {
+    fn collect_children(&self, _collector: &mut Vec<NodeRef>) {}
+}
+
+impl ChildCollector for nodes::WildcardPattern {
+    fn collect_children(&self, _collector: &mut Vec<NodeRef>) {}
+}
+
+impl ChildCollector for nodes::BindingPattern {
+    fn collect_children(&self, _collector: &mut Vec<NodeRef>) {}
+}
+
+impl ChildCollector for nodes::LiteralPattern {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.value);
+    }
+}
+
+impl ChildCollector for nodes::RangePattern {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.start);
+        collector.push(self.end);
+    }
+}
+
+impl ChildCollector for nodes::StructPattern {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        for (_, n) in &self.fields {
+            collector.push(*n);
+        }
+    }
+}
+
+impl ChildCollector for nodes::TuplePattern {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        for n in &self.elements {
+            collector.push(*n);
+        }
+    }
+}
+
 impl ChildCollector for nodes::ReturnStatement {
     fn collect_children(&self, collector: &mut Vec<NodeRef>) {
-        if let Some(n) = &self.expr {
+        for n in &self.exprs {
             collector.push(*n);
         }
     }
 }
 
+impl ChildCollector for nodes::WhileStatement {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.condition);
+        collector.push(self.body);
+    }
+}
+
+impl ChildCollector for nodes::ForStatement {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.iterable);
+        collector.push(self.body);
+    }
+}
+
+impl ChildCollector for nodes::BreakStatement {
+    fn collect_children(&self, _collector: &mut Vec<NodeRef>) {}
+}
+
+impl ChildCollector for nodes::ContinueStatement {
+    fn collect_children(&self, _collector: &mut Vec<NodeRef>) {}
+}
+
 impl ChildCollector for nodes::AssignStatement {
     fn collect_children(&self, collector: &mut Vec<NodeRef>) {
         collector.push(self.lhs);
@@ -491,6 +825,12 @@ impl ChildCollector for nodes::ArgumentList {
     }
 }
 
+impl ChildCollector for nodes::NamedArgument {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.expr);
+    }
+}
+
 impl ChildCollector for nodes::CallOperation {
     fn collect_children(&self, collector: &mut Vec<NodeRef>) {
         collector.push(self.expr);
@@ -505,6 +845,19 @@ impl ChildCollector for nodes::BinaryOperation {
     }
 }
 
+impl ChildCollector for nodes::RangeExpression {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.start);
+        collector.push(self.end);
+    }
+}
+
+impl ChildCollector for nodes::UnaryOperation {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.expr);
+    }
+}
+
 impl ChildCollector for nodes::SymbolDeclaration {
     fn collect_children(&self, collector: &mut Vec<NodeRef>) {
         if let Some(n) = &self.typeexpr {
@@ -516,12 +869,61 @@ impl ChildCollector for nodes::SymbolDeclaration {
     }
 }
 
+impl ChildCollector for nodes::DestructuringDeclaration {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.initexpr);
+    }
+}
+
 impl ChildCollector for nodes::SubScript {
     fn collect_children(&self, collector: &mut Vec<NodeRef>) {
         collector.push(self.expr);
     }
 }
 
+impl ChildCollector for nodes::IndexExpression {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.expr);
+        collector.push(self.index);
+    }
+}
+
+impl ChildCollector for nodes::DynamicArrayType {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.expr);
+    }
+}
+
+impl ChildCollector for nodes::SliceType {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.expr);
+    }
+}
+
+impl ChildCollector for nodes::ReferenceExpression {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.expr);
+    }
+}
+
+impl ChildCollector for nodes::DereferenceExpression {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.expr);
+    }
+}
+
+impl ChildCollector for nodes::OptionalType {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.expr);
+    }
+}
+
+impl ChildCollector for nodes::TryExpression {
+    fn collect_children(&self, collector: &mut Vec<NodeRef>) {
+        collector.push(self.expr);
+    }
+}
+
 struct AstPrinter<'a> {
     ast: &'a Ast,
     left_padding: u32,
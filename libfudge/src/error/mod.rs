@@ -15,6 +15,7 @@ pub mod errors {
     pub use LayoutErrorType::*;
     pub use MajorErrorType::*;
     pub use MinorErrorType::*;
+    pub use WarningType::*;
 
     #[derive(Clone, Copy, Debug, PartialEq)]
     pub enum FatalErrorType {
@@ -32,6 +33,26 @@ pub mod errors {
         UnknownCompilerDirective,
         ModuleAlreadyDeclared,
         ModuleDeclarationInMain,
+        InvalidNumericLiteral,
+        InvalidCharacterLiteral,
+        BreakOutsideLoop,
+        ContinueOutsideLoop,
+        PositionalArgumentAfterNamed,
+        DuplicateNamedArgument,
+        CapturesOnFunctionType,
+        DuplicateFieldInitializer,
+        AssignmentToImmutable,
+        NonExhaustiveMatch,
+        UnknownNamedArgument,
+        TooManyArguments,
+        MissingArgument,
+        ConflictingTypeArguments,
+        DefinitionNotConstant,
+        OptionalUsedAsValue,
+        DereferenceOfNonReference,
+        ReferenceOutlivesVariable,
+        TryOutsideResultFunction,
+        InvalidTypeInstantiation,
     }
 
     #[derive(Clone, Copy, Debug, PartialEq)]
@@ -51,6 +72,12 @@ pub mod errors {
         MismatchedAlignment,
         ExpectedNewLine,
     }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum WarningType {
+        UnreachableMatchArm,
+        UnreachableElseBranch,
+    }
 }
 
 const FATAL_ERROR_THRESHOLD: usize = 1;
@@ -63,6 +90,7 @@ pub enum ErrorId {
     MajorError(errors::MajorErrorType),
     MinorError(errors::MinorErrorType),
     LayoutError(errors::LayoutErrorType),
+    Warning(errors::WarningType),
 }
 
 pub trait ErrorIdConstructor {
@@ -93,6 +121,12 @@ impl ErrorIdConstructor for errors::LayoutErrorType {
     }
 }
 
+impl ErrorIdConstructor for errors::WarningType {
+    fn create_id(&self) -> ErrorId {
+        ErrorId::Warning(*self)
+    }
+}
+
 pub fn new_error_id<T: ErrorIdConstructor>(t: T) -> ErrorId {
     t.create_id()
 }
@@ -111,6 +145,9 @@ pub fn error_label(id: ErrorId) -> &'static str {
         ErrorId::LayoutError(_e) => {
             return "Error";
         }
+        ErrorId::Warning(_e) => {
+            return "Warning";
+        }
     }
 }
 
@@ -128,14 +165,23 @@ pub fn error_code(id: ErrorId) -> String {
         ErrorId::LayoutError(e) => {
             return format!("L{:03}", e as i32);
         }
+        ErrorId::Warning(e) => {
+            return format!("W{:03}", e as i32);
+        }
     }
 }
 
+pub fn is_warning(id: ErrorId) -> bool {
+    matches!(id, ErrorId::Warning(_))
+}
+
 #[derive(Clone, Debug)]
 pub struct Error {
     pub id: ErrorId,
     pub message: String,
     pub source_span: source::SourceSpan,
+    // Secondary spans that explain the error, like the declaration of an assigned symbol
+    pub labels: Vec<(source::SourceSpan, String)>,
 
     pub backtrace: Option<Backtrace>,
 }
@@ -150,6 +196,7 @@ impl Error {
             id: new_error_id(t),
             message,
             source_span,
+            labels: Vec::new(),
             backtrace: None,
         }
     }
@@ -159,8 +206,14 @@ impl Error {
     pub fn at_token<T: ErrorIdConstructor>(t: T, token: &token::Token, message: String) -> Error {
         Self::at_span(t, token.source_span, message)
     }
+
+    pub fn with_label(mut self, source_span: source::SourceSpan, message: String) -> Error {
+        self.labels.push((source_span, message));
+        self
+    }
 }
 
+#[derive(Debug)]
 pub struct ErrorData {
     fatal_error_count: usize,
     major_error_count: usize,
@@ -168,6 +221,7 @@ pub struct ErrorData {
     pub errors: Vec<Error>,
 }
 
+#[derive(Debug)]
 pub struct ErrorManager {
     reached_error_limit: bool,
     pub error_data: ErrorData,
@@ -225,6 +279,8 @@ impl ErrorManager {
                     self.reached_error_limit = true;
                 }
             }
+            // Warnings don't count towards the error limit
+            ErrorId::Warning(_e) => (),
         }
         return id;
     }
@@ -178,7 +178,15 @@ impl<'a> Grapher<'a> {
         self.state.pop_scope();
 
         // Note: main should not be available for symbol lookup, so don't add it to any scope
-        let function = asg::Function::new("main".into(), mainscope, Vec::new(), body);
+        let function = asg::Function::new(
+            "main".into(),
+            mainscope,
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            Vec::new(),
+            body,
+        );
 
         let functionkey = self
             .state
@@ -281,9 +289,10 @@ pub fn create_graph<'a>(main_ast: &'a ast::Ast, module_asts: &'a Vec<ast::Ast>)
 
     let grapher = Grapher::new(&context);
 
-    let (asg, errors) = grapher.create_asg();
+    let (asg, mut errors) = grapher.create_asg();
 
-    let asg = asgprocessing::process_asg(asg);
+    let (asg, processing_errors) = asgprocessing::process_asg(asg);
+    errors.extend(processing_errors);
 
     return GrapherResult {
         asg: asg,
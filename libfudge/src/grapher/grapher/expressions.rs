@@ -17,15 +17,34 @@ impl<'a> Grapher<'a> {
         match self.context.get_ast(astkey).get_node(node) {
             ast::Node::StringLiteral(n) => self.parse_stringliteral(astkey, n),
             ast::Node::BooleanLiteral(n) => self.parse_booleanliteral(astkey, n),
+            ast::Node::NullLiteral(n) => self.parse_nullliteral(astkey, n),
             ast::Node::IntegerLiteral(n) => self.parse_integerliteral(astkey, n),
+            ast::Node::FloatLiteral(n) => self.parse_floatliteral(astkey, n),
+            ast::Node::CharacterLiteral(n) => self.parse_characterliteral(astkey, n),
             ast::Node::StructLiteral(n) => self.parse_structliteral(astkey, n),
+            ast::Node::EnumLiteral(n) => self.parse_enumliteral(astkey, n),
+            ast::Node::ArrayLiteral(n) => self.parse_arrayliteral(astkey, n),
+            ast::Node::TupleLiteral(n) => self.parse_tupleliteral(astkey, n),
             ast::Node::FunctionLiteral(n) => self.parse_functionliteral(astkey, n),
             ast::Node::BuiltInObjectReference(n) => self.parse_builtinobjectreference(astkey, n),
             ast::Node::SymbolReference(n) => self.parse_symbolreference(astkey, n),
             ast::Node::IfExpression(n) => self.parse_ifexpression(astkey, n),
+            ast::Node::MatchExpression(n) => self.parse_matchexpression(astkey, n),
             ast::Node::CallOperation(n) => self.parse_calloperation(astkey, n),
             ast::Node::BinaryOperation(n) => self.parse_binaryoperation(astkey, n),
+            ast::Node::UnaryOperation(n) => self.parse_unaryoperation(astkey, n),
+            ast::Node::RangeExpression(n) => self.parse_rangeexpression(astkey, n),
             ast::Node::SubScript(n) => self.parse_subscript(astkey, n),
+            ast::Node::StructConstruction(n) => self.parse_structconstruction(astkey, n),
+            ast::Node::IndexExpression(n) => self.parse_indexexpression(astkey, n),
+            ast::Node::ReferenceExpression(n) => self.parse_referenceexpression(astkey, n),
+            ast::Node::DereferenceExpression(n) => self.parse_dereferenceexpression(astkey, n),
+            ast::Node::TryExpression(n) => self.parse_tryexpression(astkey, n),
+            ast::Node::DynamicArrayType(n) => self.parse_dynamicarraytype(astkey, n),
+            ast::Node::SliceType(n) => self.parse_slicetype(astkey, n),
+            ast::Node::OptionalType(n) => self.parse_optionaltype(astkey, n),
+            ast::Node::FunctionType(n) => self.parse_functiontype(astkey, n),
+            ast::Node::TypeParameter(n) => self.parse_typeparameter(astkey, n),
             n => {
                 panic!("{:?} is not a valid expression!", n);
             }
@@ -63,6 +82,17 @@ impl<'a> Grapher<'a> {
         ))
     }
 
+    pub fn parse_nullliteral(
+        &mut self,
+        _astkey: ast::AstKey,
+        _ast_lit: &ast::nodes::NullLiteral,
+    ) -> ExpressionKey {
+        let literal = asg::expressions::literals::NullLiteral {};
+        self.add_expression(asg::ExpressionObject::Literal(
+            asg::expressions::Literal::NullLiteral(literal),
+        ))
+    }
+
     pub fn parse_integerliteral(
         &mut self,
         _astkey: ast::AstKey,
@@ -77,6 +107,32 @@ impl<'a> Grapher<'a> {
         ))
     }
 
+    pub fn parse_floatliteral(
+        &mut self,
+        _astkey: ast::AstKey,
+        ast_lit: &ast::nodes::FloatLiteral,
+    ) -> ExpressionKey {
+        let literal = asg::expressions::literals::FloatLiteral {
+            value: ast_lit.value,
+        };
+        self.add_expression(asg::ExpressionObject::Literal(
+            asg::expressions::Literal::FloatLiteral(literal),
+        ))
+    }
+
+    pub fn parse_characterliteral(
+        &mut self,
+        _astkey: ast::AstKey,
+        ast_lit: &ast::nodes::CharacterLiteral,
+    ) -> ExpressionKey {
+        let literal = asg::expressions::literals::CharLiteral {
+            value: ast_lit.value,
+        };
+        self.add_expression(asg::ExpressionObject::Literal(
+            asg::expressions::Literal::CharLiteral(literal),
+        ))
+    }
+
     pub fn parse_structliteral(
         &mut self,
         astkey: ast::AstKey,
@@ -100,6 +156,75 @@ impl<'a> Grapher<'a> {
         ))
     }
 
+    pub fn parse_enumliteral(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_lit: &ast::nodes::EnumLiteral,
+    ) -> ExpressionKey {
+        let mut variants = Vec::new();
+
+        let ast = self.context.get_ast(astkey);
+        for v in &ast_lit.variants {
+            let ev = ast::as_node!(ast, EnumVariant, &v);
+            let mut fields = Vec::new();
+            for f in &ev.fields {
+                let sf = ast::as_node!(ast, StructField, &f);
+                fields.push(asg::misc::StructField {
+                    name: ast.get_symbol(&sf.symbol).unwrap().clone(),
+                    typeexpr: self.parse_expression(astkey, &sf.typeexpr),
+                });
+            }
+            variants.push(asg::misc::EnumVariant {
+                name: ast.get_symbol(&ev.symbol).unwrap().clone(),
+                fields,
+            });
+        }
+
+        let literal = asg::expressions::literals::EnumLiteral { variants };
+
+        self.add_expression(asg::ExpressionObject::Literal(
+            asg::expressions::Literal::EnumLiteral(literal),
+        ))
+    }
+
+    pub fn parse_arrayliteral(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_lit: &ast::nodes::ArrayLiteral,
+    ) -> ExpressionKey {
+        let mut elements = Vec::new();
+        for e in &ast_lit.elements {
+            elements.push(self.parse_expression(astkey, e));
+        }
+
+        let literal = asg::expressions::literals::ArrayLiteral {
+            elements,
+            dynamic: ast_lit.dynamic,
+        };
+
+        self.add_expression(asg::ExpressionObject::Literal(
+            asg::expressions::Literal::ArrayLiteral(literal),
+        ))
+    }
+
+    pub fn parse_tupleliteral(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_lit: &ast::nodes::TupleLiteral,
+    ) -> ExpressionKey {
+        let elements = ast_lit
+            .elements
+            .iter()
+            .map(|e| self.parse_expression(astkey, e))
+            .collect();
+
+        let literal = asg::expressions::literals::TupleLiteral { elements };
+
+        self.add_expression(asg::ExpressionObject::Literal(
+            asg::expressions::Literal::TupleLiteral(literal),
+        ))
+    }
+
     pub fn parse_functionliteral(
         &mut self,
         astkey: ast::AstKey,
@@ -123,6 +248,31 @@ impl<'a> Grapher<'a> {
         //  know what symbol this will be bound to, try to figure something out
         let name = create_function_name(&self.state);
 
+        // Captured symbols are looked up from the scope the literal is defined in
+        let capturelist = ast_lit
+            .captures
+            .iter()
+            .map(|(symbol, capturetype)| {
+                let symbol = ast.get_symbol(symbol).unwrap().clone();
+                let symbolref = self.state.get_current_scope().symboltable.references.add(
+                    asg::symboltable::SymbolReference::UnresolvedReference(
+                        asg::symboltable::UnresolvedSymbolReference { symbol },
+                    ),
+                );
+                (symbolref, *capturetype == ast::CaptureType::Reference)
+            })
+            .collect();
+
+        // Using a type parameter in the input parameters declares it
+        let mut typeparams = Vec::new();
+        for inparam in &ast_lit.inputparams {
+            ast.collect_type_parameters(inparam, &mut typeparams);
+        }
+        let typeparams = typeparams
+            .iter()
+            .map(|symbol| ast.get_symbol(symbol).unwrap().clone())
+            .collect();
+
         let scope = self.state.create_scope();
 
         // Make sure the scope includes parsing parameters
@@ -136,10 +286,10 @@ impl<'a> Grapher<'a> {
             let typeexpr = self.parse_expression(astkey, &inparam.typeexpr);
 
             let symdecl = self.state.edit_scope(&scope).symboltable.declarations.add(
-                asg::symboltable::SymbolDeclaration {
-                    symbol: ast.get_symbol(&inparam.symbol).unwrap().clone(),
-                    typeexpr: Some(typeexpr),
-                },
+                asg::symboltable::SymbolDeclaration::new(
+                    ast.get_symbol(&inparam.symbol).unwrap().clone(),
+                    Some(typeexpr),
+                ),
             );
 
             let symref = asg::symboltable::ResolvedSymbolReference {
@@ -150,16 +300,38 @@ impl<'a> Grapher<'a> {
                 symbol: symdecl,
             };
 
-            inparams.push(asg::FunctionParameter { symref });
+            let default = inparam
+                .defaultexpr
+                .as_ref()
+                .map(|n| self.parse_expression(astkey, n));
+
+            inparams.push(asg::FunctionParameter { symref, default });
         }
 
+        let outparams = ast_lit
+            .outputparams
+            .iter()
+            .map(|outparam| {
+                let outparam = as_node!(ast, OutputParameter, outparam);
+                self.parse_expression(astkey, &outparam.typeexpr)
+            })
+            .collect();
+
         // Parse body
         let statementbody =
             self.parse_statement_body(astkey, as_node!(ast, StatementBody, &ast_lit.body));
 
         self.state.pop_scope();
 
-        let function = asg::Function::new(name, scope, inparams, statementbody);
+        let function = asg::Function::new(
+            name,
+            scope,
+            typeparams,
+            inparams,
+            outparams,
+            capturelist,
+            statementbody,
+        );
 
         let functionkey = self
             .state
@@ -168,7 +340,11 @@ impl<'a> Grapher<'a> {
             .add(function);
 
         // Create literal expression
-        let literal = asg::expressions::literals::FunctionLiteral { functionkey };
+        let literal = asg::expressions::literals::FunctionLiteral {
+            functionkey,
+            location: ast_lit.location.clone(),
+            outputlocation: ast_lit.outputlocation.clone(),
+        };
         self.add_expression(asg::ExpressionObject::Literal(
             asg::expressions::Literal::FunctionLiteral(literal),
         ))
@@ -188,6 +364,9 @@ impl<'a> Grapher<'a> {
             ast::BuiltInObject::PrimitiveType(n) => {
                 asg::ExpressionObject::PrimitiveType(asg::expressions::PrimitiveType { ptype: *n })
             }
+            ast::BuiltInObject::ResultType => {
+                asg::ExpressionObject::ResultType(asg::expressions::ResultType {})
+            }
         };
         self.add_expression(expr)
     }
@@ -206,8 +385,10 @@ impl<'a> Grapher<'a> {
                 asg::symboltable::UnresolvedSymbolReference { symbol },
             ),
         );
-        let expr =
-            asg::ExpressionObject::SymbolReference(asg::expressions::SymbolReference { symbolref });
+        let expr = asg::ExpressionObject::SymbolReference(asg::expressions::SymbolReference {
+            symbolref,
+            location: ast_symref.location.clone(),
+        });
         self.add_expression(expr)
     }
 
@@ -238,6 +419,115 @@ impl<'a> Grapher<'a> {
         self.add_expression(asg::ExpressionObject::If(ifexpr))
     }
 
+    // Declares the symbols bound by the pattern in the current scope, which is the scope
+    //  of the arm, so literal expressions of the pattern belong to it as well
+    pub fn parse_pattern(
+        &mut self,
+        astkey: ast::AstKey,
+        node: &ast::NodeRef,
+    ) -> asg::misc::Pattern {
+        let ast = self.context.get_ast(astkey);
+        match ast.get_node(node) {
+            ast::Node::VariantPattern(n) => {
+                let bindings: Vec<Option<String>> = n
+                    .bindings
+                    .iter()
+                    .map(|s| s.as_ref().map(|s| ast.get_symbol(s).unwrap().clone()))
+                    .collect();
+
+                let scope = self.state.get_current_scope();
+                for symbol_name in bindings.iter().flatten() {
+                    let symbol_decl =
+                        asg::symboltable::SymbolDeclaration::new(symbol_name.clone(), None);
+                    scope.symboltable.declarations.add(symbol_decl);
+                }
+
+                asg::misc::Pattern::Variant(asg::misc::VariantPattern {
+                    name: ast.get_symbol(&n.symbol).unwrap().clone(),
+                    bindings,
+                })
+            }
+            ast::Node::WildcardPattern(_) => asg::misc::Pattern::Wildcard,
+            ast::Node::BindingPattern(n) => {
+                let symbol_name = ast.get_symbol(&n.symbol).unwrap().clone();
+
+                let symbol_decl =
+                    asg::symboltable::SymbolDeclaration::new(symbol_name.clone(), None);
+                let scope = self.state.get_current_scope();
+                scope.symboltable.declarations.add(symbol_decl);
+
+                asg::misc::Pattern::Binding(symbol_name)
+            }
+            ast::Node::LiteralPattern(n) => {
+                asg::misc::Pattern::Literal(self.parse_expression(astkey, &n.value))
+            }
+            ast::Node::RangePattern(n) => asg::misc::Pattern::Range {
+                start: self.parse_expression(astkey, &n.start),
+                end: self.parse_expression(astkey, &n.end),
+            },
+            ast::Node::StructPattern(n) => asg::misc::Pattern::Struct(
+                n.fields
+                    .iter()
+                    .map(|(s, p)| {
+                        (
+                            ast.get_symbol(s).unwrap().clone(),
+                            self.parse_pattern(astkey, p),
+                        )
+                    })
+                    .collect(),
+            ),
+            ast::Node::TuplePattern(n) => asg::misc::Pattern::Tuple(
+                n.elements
+                    .iter()
+                    .map(|p| self.parse_pattern(astkey, p))
+                    .collect(),
+            ),
+            n => panic!("{:?} is not a valid pattern!", n),
+        }
+    }
+
+    pub fn parse_matchexpression(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_matchexpr: &ast::nodes::MatchExpression,
+    ) -> ExpressionKey {
+        let ast = self.context.get_ast(astkey);
+        let expr = self.parse_expression(astkey, &ast_matchexpr.expr);
+
+        let mut arms = Vec::new();
+        for arm in &ast_matchexpr.arms {
+            let arm = as_node!(ast, MatchArm, arm);
+
+            let scope = self.state.create_scope();
+            self.state.push_scope(&scope);
+            let pattern = self.parse_pattern(astkey, &arm.pattern);
+            let guard = arm.guard.map(|g| self.parse_expression(astkey, &g));
+            let armexpr = self.parse_expression(astkey, &arm.body);
+            self.state.pop_scope();
+
+            arms.push(asg::expressions::MatchArm {
+                pattern,
+                guard,
+                scope,
+                expr: armexpr,
+                location: arm.location.clone(),
+            });
+        }
+
+        let elsebranch = ast_matchexpr
+            .elsebranch
+            .map(|eb| self.parse_expression(astkey, &eb));
+
+        let matchexpr = asg::expressions::Match {
+            expr,
+            arms,
+            elsebranch,
+            location: ast_matchexpr.location.clone(),
+        };
+
+        self.add_expression(asg::ExpressionObject::Match(matchexpr))
+    }
+
     pub fn parse_calloperation(
         &mut self,
         astkey: ast::AstKey,
@@ -246,16 +536,33 @@ impl<'a> Grapher<'a> {
         let callable = self.parse_expression(astkey, &ast_callop.expr);
 
         let mut args = Vec::new();
+        let mut namedargs = Vec::new();
 
         let ast = self.context.get_ast(astkey);
         let ast_arglist = as_node!(ast, ArgumentList, &ast_callop.arglist);
 
         for arg in &ast_arglist.args {
+            // Named arguments are matched to parameters when the callee is known
+            if let ast::Node::NamedArgument(n) = ast.get_node(arg) {
+                let expr = self.parse_expression(astkey, &n.expr);
+                namedargs.push(asg::expressions::NamedArgument {
+                    symbol: ast.get_symbol(&n.symbol).unwrap().clone(),
+                    expr,
+                    location: n.location.clone(),
+                });
+                continue;
+            }
+
             let expr = self.parse_expression(astkey, &arg);
             args.push(expr);
         }
 
-        let callexpr = asg::expressions::Call { callable, args };
+        let callexpr = asg::expressions::Call {
+            callable,
+            args,
+            namedargs,
+            location: ast_callop.location.clone(),
+        };
 
         self.add_expression(asg::ExpressionObject::Call(callexpr))
     }
@@ -275,6 +582,33 @@ impl<'a> Grapher<'a> {
         self.add_expression(asg::ExpressionObject::BinOp(binopexpr))
     }
 
+    pub fn parse_unaryoperation(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_unop: &ast::nodes::UnaryOperation,
+    ) -> ExpressionKey {
+        let expr = self.parse_expression(astkey, &ast_unop.expr);
+
+        let op = ast_unop.optype;
+
+        let unopexpr = asg::expressions::UnaryOp { op, expr };
+
+        self.add_expression(asg::ExpressionObject::UnaryOp(unopexpr))
+    }
+
+    pub fn parse_rangeexpression(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_range: &ast::nodes::RangeExpression,
+    ) -> ExpressionKey {
+        let start = self.parse_expression(astkey, &ast_range.start);
+        let end = self.parse_expression(astkey, &ast_range.end);
+
+        let rangeexpr = asg::expressions::Range { start, end };
+
+        self.add_expression(asg::ExpressionObject::Range(rangeexpr))
+    }
+
     pub fn parse_subscript(
         &mut self,
         astkey: ast::AstKey,
@@ -289,4 +623,191 @@ impl<'a> Grapher<'a> {
 
         self.add_expression(asg::ExpressionObject::Subscript(subscriptexpr))
     }
+
+    pub fn parse_structconstruction(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_construction: &ast::nodes::StructConstruction,
+    ) -> ExpressionKey {
+        let typeexpr = self.parse_expression(astkey, &ast_construction.expr);
+
+        let ast = self.context.get_ast(astkey);
+        let fields = ast_construction
+            .fields
+            .iter()
+            .map(|(s, e)| {
+                (
+                    ast.get_symbol(s).unwrap().clone(),
+                    self.parse_expression(astkey, e),
+                )
+            })
+            .collect();
+
+        let constructionexpr = asg::expressions::StructConstruction { typeexpr, fields };
+
+        self.add_expression(asg::ExpressionObject::StructConstruction(constructionexpr))
+    }
+
+    pub fn parse_indexexpression(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_index: &ast::nodes::IndexExpression,
+    ) -> ExpressionKey {
+        let expr = self.parse_expression(astkey, &ast_index.expr);
+
+        // Indexing with a range creates a slice
+        let ast = self.context.get_ast(astkey);
+        if let ast::Node::RangeExpression(range) = ast.get_node(&ast_index.index) {
+            let start = self.parse_expression(astkey, &range.start);
+            let end = self.parse_expression(astkey, &range.end);
+
+            let sliceexpr = asg::expressions::Slice {
+                expr,
+                start,
+                end,
+                location: ast_index.location.clone(),
+            };
+
+            return self.add_expression(asg::ExpressionObject::Slice(sliceexpr));
+        }
+
+        let index = self.parse_expression(astkey, &ast_index.index);
+
+        let indexexpr = asg::expressions::Index {
+            expr,
+            index,
+            location: ast_index.location.clone(),
+        };
+
+        self.add_expression(asg::ExpressionObject::Index(indexexpr))
+    }
+
+    pub fn parse_referenceexpression(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_reference: &ast::nodes::ReferenceExpression,
+    ) -> ExpressionKey {
+        let expr = self.parse_expression(astkey, &ast_reference.expr);
+
+        let referenceexpr = asg::expressions::Reference {
+            expr,
+            location: ast_reference.location.clone(),
+        };
+
+        self.add_expression(asg::ExpressionObject::Reference(referenceexpr))
+    }
+
+    pub fn parse_dereferenceexpression(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_dereference: &ast::nodes::DereferenceExpression,
+    ) -> ExpressionKey {
+        let expr = self.parse_expression(astkey, &ast_dereference.expr);
+
+        let dereferenceexpr = asg::expressions::Dereference {
+            expr,
+            location: ast_dereference.location.clone(),
+        };
+
+        self.add_expression(asg::ExpressionObject::Dereference(dereferenceexpr))
+    }
+
+    pub fn parse_tryexpression(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_try: &ast::nodes::TryExpression,
+    ) -> ExpressionKey {
+        let expr = self.parse_expression(astkey, &ast_try.expr);
+
+        let tryexpr = asg::expressions::Try {
+            expr,
+            location: ast_try.location.clone(),
+        };
+
+        self.add_expression(asg::ExpressionObject::Try(tryexpr))
+    }
+
+    pub fn parse_dynamicarraytype(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_type: &ast::nodes::DynamicArrayType,
+    ) -> ExpressionKey {
+        let element = self.parse_expression(astkey, &ast_type.expr);
+
+        let typeexpr = asg::expressions::DynamicArrayType { element };
+
+        self.add_expression(asg::ExpressionObject::DynamicArrayType(typeexpr))
+    }
+
+    pub fn parse_slicetype(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_type: &ast::nodes::SliceType,
+    ) -> ExpressionKey {
+        let element = self.parse_expression(astkey, &ast_type.expr);
+
+        let typeexpr = asg::expressions::SliceType { element };
+
+        self.add_expression(asg::ExpressionObject::SliceType(typeexpr))
+    }
+
+    pub fn parse_optionaltype(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_type: &ast::nodes::OptionalType,
+    ) -> ExpressionKey {
+        let target = self.parse_expression(astkey, &ast_type.expr);
+
+        let typeexpr = asg::expressions::OptionalType { target };
+
+        self.add_expression(asg::ExpressionObject::OptionalType(typeexpr))
+    }
+
+    pub fn parse_functiontype(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_type: &ast::nodes::FunctionType,
+    ) -> ExpressionKey {
+        let ast = self.context.get_ast(astkey);
+
+        let inparams = ast_type
+            .inputparams
+            .iter()
+            .map(|inparam| {
+                let inparam = as_node!(ast, InputParameter, inparam);
+                let symbol = ast.get_symbol(&inparam.symbol).unwrap().clone();
+                (symbol, self.parse_expression(astkey, &inparam.typeexpr))
+            })
+            .collect();
+
+        let outparams = ast_type
+            .outputparams
+            .iter()
+            .map(|outparam| {
+                let outparam = as_node!(ast, OutputParameter, outparam);
+                self.parse_expression(astkey, &outparam.typeexpr)
+            })
+            .collect();
+
+        let typeexpr = asg::expressions::FunctionType {
+            inparams,
+            outparams,
+        };
+
+        self.add_expression(asg::ExpressionObject::FunctionType(typeexpr))
+    }
+
+    pub fn parse_typeparameter(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_type: &ast::nodes::TypeParameter,
+    ) -> ExpressionKey {
+        let ast = self.context.get_ast(astkey);
+
+        let typeexpr = asg::expressions::TypeParameter {
+            symbol: ast.get_symbol(&ast_type.symbol).unwrap().clone(),
+        };
+
+        self.add_expression(asg::ExpressionObject::TypeParameter(typeexpr))
+    }
 }
@@ -19,7 +19,15 @@ impl<'a> Grapher<'a> {
             }
             ast::Node::StatementBody(_n) => todo!(), // TODO: Can this happen?
             ast::Node::SymbolDeclaration(n) => self.parse_symboldeclaration(astkey, n),
+            ast::Node::DestructuringDeclaration(n) => {
+                Some(self.parse_destructuringdeclaration(astkey, n))
+            }
             ast::Node::IfStatement(n) => Some(self.parse_ifstatement(astkey, n)),
+            ast::Node::MatchStatement(n) => Some(self.parse_matchstatement(astkey, n)),
+            ast::Node::WhileStatement(n) => Some(self.parse_whilestatement(astkey, n)),
+            ast::Node::ForStatement(n) => Some(self.parse_forstatement(astkey, n)),
+            ast::Node::BreakStatement(_) => Some(asg::Statement::Break),
+            ast::Node::ContinueStatement(_) => Some(asg::Statement::Continue),
             ast::Node::ReturnStatement(n) => Some(self.parse_returnstatement(astkey, n)),
             ast::Node::AssignStatement(n) => Some(self.parse_assignstatement(astkey, n)),
             ast::Node::CallOperation(_n) => Some(self.parse_expressionwrapper(astkey, node)),
@@ -76,7 +84,23 @@ impl<'a> Grapher<'a> {
 
         self.state.current_symdecl_name = old_symdecl_name;
 
-        let symbol_decl = asg::symboltable::SymbolDeclaration::new(symbol_name.clone(), type_expr);
+        let symbol_decl = match ast_symdecl.decltype {
+            ast::SymbolDeclarationType::Def | ast::SymbolDeclarationType::Const => {
+                asg::symboltable::SymbolDeclaration::new_immutable(
+                    symbol_name.clone(),
+                    type_expr,
+                    ast_symdecl.location.clone(),
+                )
+            }
+            // Short-hand declarations are vars
+            ast::SymbolDeclarationType::Var | ast::SymbolDeclarationType::Inferred => {
+                asg::symboltable::SymbolDeclaration::new_at(
+                    symbol_name.clone(),
+                    type_expr,
+                    ast_symdecl.location.clone(),
+                )
+            }
+        };
 
         let scope = self.state.get_current_scope();
 
@@ -84,23 +108,62 @@ impl<'a> Grapher<'a> {
 
         // Handle initialization
         if let Some(initexpr) = init_expr {
-            // Defs are registered on scope directly
+            // Defs are registered on scope directly, so they can be evaluated at compile
+            //  time. Consts are initialized in order, like vars.
             if ast_symdecl.decltype == ast::SymbolDeclarationType::Def {
                 scope.symboltable.definitions.insert(symbolkey, initexpr);
-
-                return None;
-            } else {
-                let initstmt = asg::statements::Initialize {
-                    symbol: symbol_name,
-                    expr: initexpr,
-                };
-                return Some(asg::Statement::Initialize(initstmt));
             }
+
+            let initstmt = asg::statements::Initialize {
+                symbol: symbol_name,
+                expr: initexpr,
+            };
+            return Some(asg::Statement::Initialize(initstmt));
         };
 
         None
     }
 
+    // Destructured defs are initialized like vars, there is no expression to register per symbol
+    pub fn parse_destructuringdeclaration(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_destructdecl: &ast::nodes::DestructuringDeclaration,
+    ) -> Statement {
+        let ast = self.context.get_ast(astkey);
+
+        let expr = self.parse_expression(astkey, &ast_destructdecl.initexpr);
+
+        let symbols: Vec<Option<String>> = ast_destructdecl
+            .symbols
+            .iter()
+            .map(|s| s.as_ref().map(|s| ast.get_symbol(s).unwrap().clone()))
+            .collect();
+
+        let scope = self.state.get_current_scope();
+        for symbol_name in symbols.iter().flatten() {
+            let symbol_decl = match ast_destructdecl.decltype {
+                ast::SymbolDeclarationType::Def | ast::SymbolDeclarationType::Const => {
+                    asg::symboltable::SymbolDeclaration::new_immutable(
+                        symbol_name.clone(),
+                        None,
+                        ast_destructdecl.location.clone(),
+                    )
+                }
+                ast::SymbolDeclarationType::Var | ast::SymbolDeclarationType::Inferred => {
+                    asg::symboltable::SymbolDeclaration::new_at(
+                        symbol_name.clone(),
+                        None,
+                        ast_destructdecl.location.clone(),
+                    )
+                }
+            };
+            scope.symboltable.declarations.add(symbol_decl);
+        }
+
+        asg::Statement::Destructure(asg::statements::Destructure { symbols, expr })
+    }
+
     pub fn parse_ifstatement(
         &mut self,
         astkey: ast::AstKey,
@@ -159,14 +222,122 @@ impl<'a> Grapher<'a> {
         asg::Statement::If(ifstmt)
     }
 
+    pub fn parse_matchstatement(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_match: &ast::nodes::MatchStatement,
+    ) -> Statement {
+        let ast = self.context.get_ast(astkey);
+
+        let expr = self.parse_expression(astkey, &ast_match.expr);
+
+        let arms = ast_match
+            .arms
+            .iter()
+            .map(|arm| {
+                let arm = ast::as_node!(ast, MatchArm, arm);
+
+                let scope = self.state.create_scope();
+                self.state.push_scope(&scope);
+                let pattern = self.parse_pattern(astkey, &arm.pattern);
+                let guard = arm.guard.map(|g| self.parse_expression(astkey, &g));
+                let body =
+                    self.parse_statement_body(astkey, ast::as_node!(ast, StatementBody, &arm.body));
+                self.state.pop_scope();
+
+                asg::statements::MatchArm {
+                    pattern,
+                    guard,
+                    body: asg::statements::Branch { scope, body },
+                    location: arm.location.clone(),
+                }
+            })
+            .collect();
+
+        let elsebranch = ast_match.elsebranch.as_ref().map(|x| {
+            let scope = self.state.create_scope();
+            self.state.push_scope(&scope);
+            let body = self.parse_statement_body(astkey, ast::as_node!(ast, StatementBody, &x));
+            self.state.pop_scope();
+            asg::statements::Branch { scope, body }
+        });
+
+        let matchstmt = asg::statements::Match {
+            expr,
+            arms,
+            elsebranch,
+            location: ast_match.location.clone(),
+        };
+
+        asg::Statement::Match(matchstmt)
+    }
+
+    pub fn parse_whilestatement(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_while: &ast::nodes::WhileStatement,
+    ) -> Statement {
+        let ast = self.context.get_ast(astkey);
+
+        let condition = self.parse_expression(astkey, &ast_while.condition);
+
+        let scope = self.state.create_scope();
+        self.state.push_scope(&scope);
+        let body =
+            self.parse_statement_body(astkey, ast::as_node!(ast, StatementBody, &ast_while.body));
+        self.state.pop_scope();
+
+        let whilestmt = asg::statements::While {
+            condition,
+            body: asg::statements::Branch { scope, body },
+        };
+
+        asg::Statement::While(whilestmt)
+    }
+
+    pub fn parse_forstatement(
+        &mut self,
+        astkey: ast::AstKey,
+        ast_for: &ast::nodes::ForStatement,
+    ) -> Statement {
+        let ast = self.context.get_ast(astkey);
+        let symbol_name: String = ast.get_symbol(&ast_for.symbol).unwrap().into();
+
+        let iterable = self.parse_expression(astkey, &ast_for.iterable);
+
+        let scope = self.state.create_scope();
+        self.state.push_scope(&scope);
+        let symbol_decl = asg::symboltable::SymbolDeclaration::new(symbol_name.clone(), None);
+        self.state
+            .get_current_scope()
+            .symboltable
+            .declarations
+            .add(symbol_decl);
+        let body =
+            self.parse_statement_body(astkey, ast::as_node!(ast, StatementBody, &ast_for.body));
+        self.state.pop_scope();
+
+        let forstmt = asg::statements::For {
+            symbol: symbol_name,
+            iterable,
+            body: asg::statements::Branch { scope, body },
+        };
+
+        asg::Statement::For(forstmt)
+    }
+
     pub fn parse_returnstatement(
         &mut self,
         astkey: ast::AstKey,
         ast_return: &ast::nodes::ReturnStatement,
     ) -> Statement {
-        let expr = ast_return.expr.map(|e| self.parse_expression(astkey, &e));
+        let exprs = ast_return
+            .exprs
+            .iter()
+            .map(|e| self.parse_expression(astkey, e))
+            .collect();
 
-        let returnstmt = asg::statements::Return { expr };
+        let returnstmt = asg::statements::Return { exprs };
 
         asg::Statement::Return(returnstmt)
     }
@@ -179,7 +350,11 @@ impl<'a> Grapher<'a> {
         let lhs = self.parse_expression(astkey, &ast_assign.lhs);
         let rhs = self.parse_expression(astkey, &ast_assign.rhs);
 
-        let assignstmt = asg::statements::Assign { lhs, rhs };
+        let assignstmt = asg::statements::Assign {
+            lhs,
+            rhs,
+            location: ast_assign.location.clone(),
+        };
 
         asg::Statement::Assign(assignstmt)
     }
@@ -7,10 +7,17 @@ static ARIT_BINOPS: phf::Map<&'static str, fn(u32, u32) -> u32> = phf_map! {
     "-" => |a, b| a - b,
     "*" => |a, b| a * b,
     "/" => |a, b| a / b,
+    "%" => |a, b| a % b,
+    "&" => |a, b| a & b,
+    "|" => |a, b| a | b,
+    "^" => |a, b| a ^ b,
+    "<<" => |a, b| a << b,
+    ">>" => |a, b| a >> b,
 };
 
 static COMP_BINOPS: phf::Map<&'static str, fn(u32, u32) -> bool> = phf_map! {
     "==" => |a, b| a == b,
+    "!=" => |a, b| a != b,
     ">" => |a, b| a > b,
     ">=" => |a, b| a >= b,
     "<" => |a, b| a < b,
@@ -40,3 +47,124 @@ fn test_int_comp_binop_expressions() {
         assert_expression_as_str(exp.as_str(), expected.as_str());
     }
 }
+
+#[test]
+fn test_nondecimal_integer_literals() {
+    assert_expression_as_str("0xFF", "255");
+    assert_expression_as_str("0b1010 + 0o10", "18");
+    assert_expression_as_str("1_000 * 2", "2000");
+}
+
+#[test]
+fn test_float_literal_expressions() {
+    assert_expression_as_str("1.5", "1.5");
+    assert_expression_as_str("1.5 + 2.25", "3.75");
+    assert_expression_as_str("2.5e1 / 2.0", "12.5");
+    assert_expression_as_str("0.5 < 1.0", "true");
+}
+
+#[test]
+fn test_typed_float_declaration() {
+    let source = "\
+        def x : #primitives.f32 = 0.25\n\
+        def a = x * 2.0";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "0.5")
+    });
+}
+
+#[test]
+fn test_negation_expressions() {
+    assert_expression_as_str("-5", "-5");
+    assert_expression_as_str("- -5", "5");
+    assert_expression_as_str("-2 * 3", "-6");
+    assert_expression_as_str("10 + -4", "6");
+    assert_expression_as_str("-1.5", "-1.5");
+}
+
+#[test]
+fn test_bitwise_not_expressions() {
+    assert_expression_as_str("~0", "4294967295");
+    assert_expression_as_str("~-1", "0");
+    assert_expression_as_str("~~5", "5");
+}
+
+#[test]
+fn test_typed_negated_literals() {
+    let source = "\
+        def a : #primitives.s8 = -128\n\
+        def b : #primitives.s16 = -300\n\
+        def e : #primitives.s16 = -b\n\
+        def c : #primitives.u8 = ~0\n\
+        def d : #primitives.f32 = -0.5";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "-128");
+        assert_eq!(result.read_symbol_as_str(None, "e"), "300");
+        assert_eq!(result.read_symbol_as_str(None, "c"), "255");
+        assert_eq!(result.read_symbol_as_str(None, "d"), "-0.5");
+    });
+}
+
+#[test]
+fn test_bitwise_operator_precedence() {
+    assert_expression_as_str("6 | 3 ^ 5 & 4", "7");
+    assert_expression_as_str("1 + 2 << 3", "24");
+    assert_expression_as_str("7 % 4 * 2", "6");
+    assert_expression_as_str("3 & 1 != 0", "true");
+}
+
+#[test]
+fn test_float_modulo() {
+    assert_expression_as_str("7.5 % 2.0", "1.5");
+}
+
+#[test]
+fn test_shift_mixed_widths() {
+    // Shifts keep the type of the shifted value, whatever the type of the amount
+    let source = "\
+        def a : #primitives.u8 = 200\n\
+        def n : #primitives.u64 = 3\n\
+        def m : #primitives.u8 = 2\n\
+        def b : #primitives.u8 = a << n\n\
+        def c : #primitives.u8 = a >> n\n\
+        def x : #primitives.u64 = 1\n\
+        def d : #primitives.u64 = x << m\n\
+        def e : #primitives.u8 = a << 1";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "64");
+        assert_eq!(result.read_symbol_as_str(None, "c"), "25");
+        assert_eq!(result.read_symbol_as_str(None, "d"), "4");
+        assert_eq!(result.read_symbol_as_str(None, "e"), "144");
+    });
+}
+
+#[test]
+fn test_shift_signed_values() {
+    // Right shifts of signed values are arithmetic
+    let source = "\
+        def a : #primitives.s16 = -300\n\
+        def b : #primitives.s16 = a >> 2\n\
+        def c : #primitives.s16 = a << 1";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "-75");
+        assert_eq!(result.read_symbol_as_str(None, "c"), "-600");
+    });
+}
+
+#[test]
+#[should_panic]
+fn test_shift_amount_out_of_range() {
+    let source = "\
+        def a : #primitives.u8 = 1\n\
+        def b = a << 8";
+    test_interpreters(source, &|_| {});
+}
+
+#[test]
+#[should_panic]
+fn test_shift_amount_signed() {
+    let source = "\
+        def a : #primitives.s8 = 1\n\
+        def b = 1 << a";
+    test_interpreters(source, &|_| {});
+}
@@ -0,0 +1,98 @@
+use super::utils::*;
+
+static ARRAY_FIXTURE: &str = "\
+    var a : #primitives.u32[4] = [5, 6, 7, 8]\n\
+    var i : #primitives.u32 = 2\n\
+    ";
+
+#[test]
+fn test_array_index() {
+    assert_expression_as_str_with_fixture(ARRAY_FIXTURE, "a[0]", "5");
+    assert_expression_as_str_with_fixture(ARRAY_FIXTURE, "a[3]", "8");
+    assert_expression_as_str_with_fixture(ARRAY_FIXTURE, "a[i]", "7");
+    assert_expression_as_str_with_fixture(ARRAY_FIXTURE, "a[i + 1] * 2", "16");
+}
+
+#[test]
+fn test_array_element_assignment() {
+    let source = "\
+        var a : #primitives.u32[4] = [5, 6, 7, 8]\n\
+        a[1] = 60\n\
+        a[2] = a[1] + a[3]\n\
+        var b = a[1]\n\
+        var c = a[2]";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "60");
+        assert_eq!(result.read_symbol_as_str(None, "c"), "68");
+    });
+}
+
+#[test]
+fn test_array_copy() {
+    let source = "\
+        var a : #primitives.u32[2] = [1, 2]\n\
+        var b = a\n\
+        b[0] = 10\n\
+        var c = a[0]\n\
+        var d = b[0]";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "c"), "1");
+        assert_eq!(result.read_symbol_as_str(None, "d"), "10");
+    });
+}
+
+#[test]
+fn test_array_default_value() {
+    let source = "\
+        var a : #primitives.s16[3]\n\
+        var b = a[2]";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "0");
+    });
+}
+
+#[test]
+fn test_array_typed_elements() {
+    let source = "\
+        var a : #primitives.u8[2] = [250, 3]\n\
+        var b = a[0] + a[1]";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "253");
+    });
+}
+
+#[test]
+fn test_array_nested() {
+    let source = "\
+        var a : #primitives.u32[2][3] = [[1, 2], [3, 4], [5, 6]]\n\
+        a[1][0] = 30\n\
+        var b = a[1][0]\n\
+        var c = a[2][1]";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "30");
+        assert_eq!(result.read_symbol_as_str(None, "c"), "6");
+    });
+}
+
+#[test]
+fn test_array_sum_loop() {
+    let source = "\
+        var a : #primitives.u32[4] = [5, 6, 7, 8]\n\
+        var sum : #primitives.u32 = 0\n\
+        for i in 0..4 do\n\
+            \tsum = sum + a[i]\n\
+        end";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "sum"), "26");
+    });
+}
+
+#[test]
+#[should_panic(expected = "out of bounds for array of length 4 at strsource:3:10")]
+fn test_array_index_out_of_bounds() {
+    let source = "\
+        var a : #primitives.u32[4] = [5, 6, 7, 8]\n\
+        var i : #primitives.u32 = 4\n\
+        var b = a[i]";
+    test_interpreters(source, &|_| {});
+}
@@ -14,7 +14,50 @@ fn test_var_assign() {
     });
 }
 
+#[test]
+fn test_const_assign() {
+    let source = "\
+        var a : #primitives.u32 = 5\n\
+        const b = a * 2";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "10");
+    });
+}
+
+#[test]
+fn test_inferred_declaration() {
+    let source = "\
+        a := 5\n\
+        b : #primitives.u32 = 7\n\
+        a = a + b";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "12");
+    });
+}
+
 #[test]
 fn test_int_literal_expression() {
     assert_expression_as_str("5", "5");
 }
+
+#[test]
+fn test_char_literal_expression() {
+    assert_expression_as_str("'a'", "a");
+    assert_expression_as_str("'\\n'", "\n");
+    assert_expression_as_str("'\\''", "'");
+    assert_expression_as_str("'\"'", "\"");
+    assert_expression_as_str("'å'", "å");
+}
+
+#[test]
+fn test_char_comparison() {
+    assert_expression_as_str("'a' < 'b'", "true");
+    assert_expression_as_str("'a' == 'b'", "false");
+}
+
+#[test]
+fn test_char_var() {
+    test_interpreters("var a : #primitives.char = 'x'", &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "x");
+    });
+}
@@ -0,0 +1,130 @@
+use super::utils::*;
+
+static DYNAMIC_ARRAY_FIXTURE: &str = "\
+    var a : #primitives.u32[..] = [5, 6, 7]\n\
+    var i : #primitives.u32 = 2\n\
+    ";
+
+#[test]
+fn test_dynamic_array_index() {
+    assert_expression_as_str_with_fixture(DYNAMIC_ARRAY_FIXTURE, "a[0]", "5");
+    assert_expression_as_str_with_fixture(DYNAMIC_ARRAY_FIXTURE, "a[i]", "7");
+    assert_expression_as_str_with_fixture(DYNAMIC_ARRAY_FIXTURE, "#array.len(a)", "3");
+}
+
+#[test]
+fn test_dynamic_array_push_pop() {
+    let source = "\
+        var a : #primitives.u8[..] = [1, 2]\n\
+        #array.push(a, 3)\n\
+        #array.push(a, 4)\n\
+        var b = #array.pop(a)\n\
+        var c = #array.len(a)\n\
+        var d = a[2]";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "4");
+        assert_eq!(result.read_symbol_as_str(None, "c"), "3");
+        assert_eq!(result.read_symbol_as_str(None, "d"), "3");
+    });
+}
+
+#[test]
+fn test_dynamic_array_clear() {
+    let source = "\
+        var a : #primitives.u32[..] = [1, 2, 3]\n\
+        #array.clear(a)\n\
+        var b = #array.len(a)\n\
+        #array.push(a, 10)\n\
+        var c = a[0]";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "0");
+        assert_eq!(result.read_symbol_as_str(None, "c"), "10");
+    });
+}
+
+#[test]
+fn test_dynamic_array_literal() {
+    let source = "\
+        var a = [true, ..]\n\
+        #array.push(a, false)\n\
+        var b = a[1]\n\
+        var c = #array.len(a)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "false");
+        assert_eq!(result.read_symbol_as_str(None, "c"), "2");
+    });
+}
+
+#[test]
+fn test_dynamic_array_inferred_element() {
+    let source = "\
+        var a : [..] = ['x', 'y']\n\
+        #array.push(a, 'z')\n\
+        var b = a[2]";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "z");
+    });
+}
+
+#[test]
+fn test_dynamic_array_shared() {
+    let source = "\
+        var a : #primitives.u32[..] = [1, 2]\n\
+        var b = a\n\
+        b[0] = 10\n\
+        #array.push(b, 3)\n\
+        var c = a[0]\n\
+        var d = #array.len(a)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "c"), "10");
+        assert_eq!(result.read_symbol_as_str(None, "d"), "3");
+    });
+}
+
+#[test]
+fn test_dynamic_array_of_arrays() {
+    let source = "\
+        var a : #primitives.u32[2][..] = [[1, 2]]\n\
+        #array.push(a, [3, 4])\n\
+        a[1][0] = 30\n\
+        var b = a[1][0]\n\
+        var c = a[0][1]";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "30");
+        assert_eq!(result.read_symbol_as_str(None, "c"), "2");
+    });
+}
+
+#[test]
+fn test_dynamic_array_sum_loop() {
+    let source = "\
+        var a : #primitives.u64[..] = [5, 6]\n\
+        #array.push(a, 7)\n\
+        var sum : #primitives.u64 = 0\n\
+        for i in 0..#array.len(a) do\n\
+            \tsum = sum + a[i]\n\
+        end";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "sum"), "18");
+    });
+}
+
+#[test]
+#[should_panic(expected = "out of bounds for array of length 3 at strsource:3:10")]
+fn test_dynamic_array_index_out_of_bounds() {
+    let source = "\
+        var a : #primitives.u32[..] = [5, 6, 7]\n\
+        var i : #primitives.u32 = 3\n\
+        var b = a[i]";
+    test_interpreters(source, &|_| {});
+}
+
+#[test]
+#[should_panic(expected = "Cannot pop from an empty array")]
+fn test_dynamic_array_pop_empty() {
+    let source = "\
+        var a : #primitives.u32[..] = [5]\n\
+        #array.pop(a)\n\
+        #array.pop(a)";
+    test_interpreters(source, &|_| {});
+}
@@ -0,0 +1,124 @@
+use super::utils::*;
+
+static ENUM_FIXTURE: &str = "\
+    def Shape =\n\
+        \tenum begin\n\
+            \t\tcase none\n\
+            \t\tcase circle(radius : #primitives.u32)\n\
+            \t\tcase rect(w : #primitives.u32, h : #primitives.u32)\n\
+        \tend\n\
+    def area = func(s : Shape) -> #primitives.u32 do\n\
+        \treturn\n\
+            \t\tmatch s\n\
+            \t\tcase none => 0\n\
+            \t\tcase circle(r) => 3 * r * r\n\
+            \t\tcase rect(w, h) => w * h\n\
+    end\n\
+    ";
+
+#[test]
+fn test_enum_variant_without_payload() {
+    assert_expression_as_str_with_fixture(ENUM_FIXTURE, "area(Shape.none)", "0");
+}
+
+#[test]
+fn test_enum_variant_with_payload() {
+    assert_expression_as_str_with_fixture(ENUM_FIXTURE, "area(Shape.rect(3, 4))", "12");
+}
+
+#[test]
+fn test_enum_default_is_first_variant() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(ENUM_FIXTURE, "var s : Shape").as_str(),
+        "area(s)",
+        "0",
+    );
+}
+
+#[test]
+fn test_enum_assign_variant() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            ENUM_FIXTURE,
+            "\
+                var s : Shape = Shape.none\n\
+                s = Shape.circle(2)\n\
+            ",
+        )
+        .as_str(),
+        "area(s)",
+        "12",
+    );
+}
+
+#[test]
+fn test_match_statement_bindings() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            ENUM_FIXTURE,
+            "\
+                var a : #primitives.u32 = 0\n\
+                match Shape.rect(5, 7)\n\
+                case circle(r) then\n\
+                    \ta = r\n\
+                case rect(_, h) then\n\
+                    \ta = h\n\
+                else\n\
+                    \ta = 1\n\
+                end\n\
+            ",
+        )
+        .as_str(),
+        "a",
+        "7",
+    );
+}
+
+#[test]
+fn test_match_statement_else() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            ENUM_FIXTURE,
+            "\
+                var a : #primitives.u32 = 0\n\
+                match Shape.none\n\
+                case circle(r) then\n\
+                    \ta = r\n\
+                else\n\
+                    \ta = 9\n\
+                end\n\
+            ",
+        )
+        .as_str(),
+        "a",
+        "9",
+    );
+}
+
+#[test]
+fn test_generic_enum() {
+    assert_expression_as_str_with_fixture(
+        "\
+            def Option =\n\
+                \tenum begin\n\
+                    \t\tcase some(value : $T)\n\
+                    \t\tcase none\n\
+                \tend\n\
+            var o : Option(#primitives.u32) = Option(#primitives.u32).some(6)\n\
+        ",
+        "match o case some(v) => v + 1 case none => 0",
+        "7",
+    );
+}
+
+#[test]
+#[should_panic(expected = "Enum has no variant square")]
+fn test_wrong_enum_variant() {
+    assert_expression_as_str_with_fixture(ENUM_FIXTURE, "area(Shape.square)", "0");
+}
+
+#[test]
+#[should_panic(expected = "Mismatching number of fields for enum variant")]
+fn test_wrong_enum_variant_field_count() {
+    assert_expression_as_str_with_fixture(ENUM_FIXTURE, "area(Shape.rect(3))", "0");
+}
@@ -0,0 +1,261 @@
+use super::utils::*;
+
+#[test]
+fn test_default_arguments() {
+    let source = "\
+        def scaled = func(x : #primitives.u32, scale : #primitives.u32 = 2) -> #primitives.u32 do\n\
+            return x * scale\n\
+        end\n\
+        var a = scaled(5)\n\
+        var b = scaled(5, 3)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "10");
+        assert_eq!(result.read_symbol_as_str(None, "b"), "15");
+    });
+}
+
+#[test]
+fn test_named_arguments() {
+    let source = "\
+        def sub = func(a : #primitives.s32, b : #primitives.s32 = 1, c : #primitives.s32 = 0) -> #primitives.s32 do\n\
+            return a - b - c\n\
+        end\n\
+        var x = sub(10, c = 3)\n\
+        var y = sub(b = 4, a = 20)\n\
+        var z = sub(1, 2, c = 3)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "x"), "6");
+        assert_eq!(result.read_symbol_as_str(None, "y"), "16");
+        assert_eq!(result.read_symbol_as_str(None, "z"), "-4");
+    });
+}
+
+#[test]
+fn test_default_arguments_from_module() {
+    let source = "\
+        module defaults begin\n\
+            \tdef base = 16\n\
+            \tdef digits = func(n : #primitives.u32, b : #primitives.u32 = base) -> #primitives.u32 do\n\
+                \t\treturn n / b\n\
+            \tend\n\
+        end\n\
+        def base = 10\n\
+        var a = defaults.digits(256)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "16");
+    });
+}
+
+#[test]
+#[should_panic(expected = "Unknown parameter")]
+fn test_unknown_named_argument() {
+    let source = "\
+        def f = func(a : #primitives.u32) do\n\
+        end\n\
+        f(b = 1)";
+    test_interpreters(source, &|_| {});
+}
+
+#[test]
+#[should_panic(expected = "is given more than once")]
+fn test_named_argument_given_twice() {
+    let source = "\
+        def f = func(a : #primitives.u32) do\n\
+        end\n\
+        f(1, a = 2)";
+    test_interpreters(source, &|_| {});
+}
+
+#[test]
+#[should_panic(expected = "Missing argument for parameter")]
+fn test_missing_argument() {
+    let source = "\
+        def f = func(a : #primitives.u32, b : #primitives.u32 = 1) do\n\
+        end\n\
+        f(b = 2)";
+    test_interpreters(source, &|_| {});
+}
+
+#[test]
+fn test_capture_by_value() {
+    let source = "\
+        var offset : #primitives.u32 = 10\n\
+        def add = func(x : #primitives.u32) -> #primitives.u32 do\n\
+            \treturn x + offset\n\
+        end\n\
+        offset = 20\n\
+        var a = add(5)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "15");
+    });
+}
+
+#[test]
+fn test_capture_by_reference() {
+    let source = "\
+        var count : #primitives.u32 = 0\n\
+        var step : #primitives.u32 = 1\n\
+        def increment = func[&count, step]() do\n\
+            \tcount = count + step\n\
+        end\n\
+        step = 5\n\
+        increment()\n\
+        increment()";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "count"), "2");
+    });
+}
+
+#[test]
+fn test_returned_closure() {
+    let source = "\
+        def make_adder = func(n : #primitives.u32) do\n\
+            \tdef adder = func(x : #primitives.u32) -> #primitives.u32 do\n\
+                \t\treturn x + n\n\
+            \tend\n\
+            \treturn adder\n\
+        end\n\
+        var add3 = make_adder(3)\n\
+        var add7 = make_adder(7)\n\
+        var a = add3(1)\n\
+        var b = add7(1)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "4");
+        assert_eq!(result.read_symbol_as_str(None, "b"), "8");
+    });
+}
+
+#[test]
+fn test_recursive_local_function() {
+    let source = "\
+        def factorial = func(n : #primitives.u32) -> #primitives.u32 do\n\
+            \tvar result : #primitives.u32 = 1\n\
+            \tif n > 1 then\n\
+                \t\tresult = n * factorial(n - 1)\n\
+            \tend\n\
+            \treturn result\n\
+        end\n\
+        var a = factorial(5)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "120");
+    });
+}
+
+#[test]
+#[should_panic(expected = "outlives the variable")]
+fn test_wrong_returned_reference_capture() {
+    let source = "\
+        def make_counter = func() do\n\
+            \tvar count : #primitives.u32 = 0\n\
+            \tdef counter = func[&count]() do\n\
+                \t\tcount = count + 1\n\
+            \tend\n\
+            \treturn counter\n\
+        end\n\
+        def counter = make_counter()";
+    test_interpreters(source, &|_| {});
+}
+
+#[test]
+fn test_function_as_argument() {
+    let source = "\
+        def apply = func(v : #primitives.u32, f : func(a : #primitives.u32) -> #primitives.u32) -> #primitives.u32 do\n\
+            \treturn f(v)\n\
+        end\n\
+        def double = func(x : #primitives.u32) -> #primitives.u32 do\n\
+            \treturn x * 2\n\
+        end\n\
+        var a = apply(5, double)\n\
+        var b = apply(5, func(x : #primitives.u32) -> #primitives.u32 do\n\
+            \treturn x + 1\n\
+        end)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "10");
+        assert_eq!(result.read_symbol_as_str(None, "b"), "6");
+    });
+}
+
+#[test]
+fn test_calling_returned_function() {
+    let source = "\
+        def nested = func(b : #primitives.u32) do\n\
+            \treturn func(c : #primitives.u32) -> #primitives.u32 do\n\
+                \t\treturn c + b\n\
+            \tend\n\
+        end\n\
+        var a = nested(3)(4)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "7");
+    });
+}
+
+#[test]
+fn test_early_return_in_recursion() {
+    let source = "\
+        def fib = func(n : #primitives.u32) -> #primitives.u32 do\n\
+            \tif n < 2 then\n\
+                \t\treturn n\n\
+            \tend\n\
+            \treturn fib(n - 1) + fib(n - 2)\n\
+        end\n\
+        var a = fib(10)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "55");
+    });
+}
+
+#[test]
+fn test_generic_function() {
+    let source = "\
+        def add = func(a : $T, b : $T) -> $T do\n\
+            \treturn a + b\n\
+        end\n\
+        var x : #primitives.u8 = 200\n\
+        var a = add(x, 50)\n\
+        var b = add(1.5, 2.25)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "250");
+        assert_eq!(result.read_symbol_as_str(None, "b"), "3.75");
+    });
+}
+
+#[test]
+fn test_generic_function_with_several_type_parameters() {
+    let source = "\
+        def swap = func(a : $A, b : $B) -> ($B, $A) do\n\
+            \tvar first : $B = b\n\
+            \treturn first, a\n\
+        end\n\
+        def a, b = swap(true, 7)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "7");
+        assert_eq!(result.read_symbol_as_str(None, "b"), "true");
+    });
+}
+
+#[test]
+fn test_recursive_generic_function() {
+    let source = "\
+        def sum = func(n : $T) -> $T do\n\
+            \tif n == 0 then\n\
+                \t\treturn n\n\
+            \tend\n\
+            \treturn n + sum(n - 1)\n\
+        end\n\
+        var n : #primitives.u64 = 10\n\
+        var a = sum(n)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "55");
+    });
+}
+
+#[test]
+#[should_panic(expected = "is bound to both")]
+fn test_wrong_generic_argument_types() {
+    let source = "\
+        def add = func(a : $T, b : $T) -> $T do\n\
+            \treturn a + b\n\
+        end\n\
+        def a = add(1, 2.5)";
+    test_interpreters(source, &|_| {});
+}
@@ -0,0 +1,53 @@
+use super::utils::*;
+
+use phf::phf_map;
+
+static LOGIC_BINOPS: phf::Map<&'static str, fn(bool, bool) -> bool> = phf_map! {
+    "and" => |a, b| a && b,
+    "or" => |a, b| a || b,
+};
+
+#[test]
+fn test_logic_binop_expressions() {
+    for op in LOGIC_BINOPS.keys() {
+        for a in [false, true] {
+            for b in [false, true] {
+                let exp = format!("{} {} {}", a, op, b);
+                let x: fn(bool, bool) -> bool = LOGIC_BINOPS[op];
+                let expected = format!("{}", x(a, b));
+                assert_expression_as_str(exp.as_str(), expected.as_str());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_logic_not_expressions() {
+    assert_expression_as_str("not true", "false");
+    assert_expression_as_str("not false", "true");
+    assert_expression_as_str("not not true", "true");
+}
+
+#[test]
+fn test_logic_mixed_expressions() {
+    assert_expression_as_str("not 1 == 2", "true");
+    assert_expression_as_str("1 < 2 and 3 < 4", "true");
+    assert_expression_as_str("true or false and false", "true");
+    assert_expression_as_str("not true or true", "true");
+}
+
+#[test]
+fn test_logic_short_circuit() {
+    // Right hand sides would fail with division by zero if evaluated
+    assert_expression_as_str("false and 1 / 0 == 1", "false");
+    assert_expression_as_str("true or 1 / 0 == 1", "true");
+}
+
+#[test]
+fn test_logic_with_vars() {
+    assert_expression_as_str_with_fixture(
+        "var a : #primitives.bool = true\nvar b : #primitives.bool = false",
+        "a and not b",
+        "true",
+    );
+}
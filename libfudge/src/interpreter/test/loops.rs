@@ -0,0 +1,282 @@
+use super::utils::*;
+
+static COUNTER_FIXTURE: &str = "\
+    var i : #primitives.u32 = 0\n\
+    var sum : #primitives.u32 = 0\n\
+    ";
+
+#[test]
+fn test_while_counter() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            COUNTER_FIXTURE,
+            "\
+                while i < 10 do\n\
+                    \tsum = sum + i\n\
+                    \ti = i + 1\n\
+                end\
+            ",
+        )
+        .as_str(),
+        "sum",
+        "45",
+    );
+}
+
+#[test]
+fn test_while_false_condition() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            COUNTER_FIXTURE,
+            "\
+                while false do\n\
+                    \ti = i + 1\n\
+                end\
+            ",
+        )
+        .as_str(),
+        "i",
+        "0",
+    );
+}
+
+#[test]
+fn test_while_break() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            COUNTER_FIXTURE,
+            "\
+                while true do\n\
+                    \tif i == 7 then\n\
+                        \t\tbreak\n\
+                    \tend\n\
+                    \ti = i + 1\n\
+                end\
+            ",
+        )
+        .as_str(),
+        "i",
+        "7",
+    );
+}
+
+#[test]
+fn test_while_continue() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            COUNTER_FIXTURE,
+            "\
+                while i < 10 do\n\
+                    \ti = i + 1\n\
+                    \tif i % 2 == 0 then\n\
+                        \t\tcontinue\n\
+                    \tend\n\
+                    \tsum = sum + i\n\
+                end\
+            ",
+        )
+        .as_str(),
+        "sum",
+        "25",
+    );
+}
+
+#[test]
+fn test_while_nested_break() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            COUNTER_FIXTURE,
+            "\
+                while i < 4 do\n\
+                    \tvar j : #primitives.u32 = 0\n\
+                    \twhile true do\n\
+                        \t\tif j == i then\n\
+                            \t\t\tbreak\n\
+                        \t\tend\n\
+                        \t\tsum = sum + 1\n\
+                        \t\tj = j + 1\n\
+                    \tend\n\
+                    \ti = i + 1\n\
+                end\
+            ",
+        )
+        .as_str(),
+        "sum",
+        "6",
+    );
+}
+
+#[test]
+fn test_for_range() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            COUNTER_FIXTURE,
+            "\
+                for n in 0..10 do\n\
+                    \tsum = sum + n\n\
+                end\
+            ",
+        )
+        .as_str(),
+        "sum",
+        "45",
+    );
+}
+
+#[test]
+fn test_for_range_empty() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            COUNTER_FIXTURE,
+            "\
+                for n in 5..5 do\n\
+                    \ti = i + 1\n\
+                end\n\
+                for n in 5..2 do\n\
+                    \ti = i + 1\n\
+                end\
+            ",
+        )
+        .as_str(),
+        "i",
+        "0",
+    );
+}
+
+#[test]
+fn test_for_range_with_variable_bounds() {
+    assert_expression_as_str_with_fixture(
+        "\
+            var a : #primitives.s16 = -3\n\
+            var b : #primitives.s16 = 3\n\
+            var sum : #primitives.s16 = 0\n\
+            for n in a..b + 1 do\n\
+                \tsum = sum + n * n\n\
+            end\
+        ",
+        "sum",
+        "28",
+    );
+}
+
+#[test]
+fn test_for_range_bounds_evaluated_once() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            COUNTER_FIXTURE,
+            "\
+                var n : #primitives.u32 = 4\n\
+                for k in 0..n do\n\
+                    \tn = n + 1\n\
+                    \tk = 100\n\
+                    \ti = i + 1\n\
+                end\
+            ",
+        )
+        .as_str(),
+        "i",
+        "4",
+    );
+}
+
+#[test]
+fn test_for_range_break_and_continue() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            COUNTER_FIXTURE,
+            "\
+                for n in 0..100 do\n\
+                    \tif n == 10 then\n\
+                        \t\tbreak\n\
+                    \tend\n\
+                    \tif n % 2 == 1 then\n\
+                        \t\tcontinue\n\
+                    \tend\n\
+                    \tsum = sum + n\n\
+                end\
+            ",
+        )
+        .as_str(),
+        "sum",
+        "20",
+    );
+}
+
+#[test]
+fn test_for_range_nested() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            COUNTER_FIXTURE,
+            "\
+                for a in 0..4 do\n\
+                    \tfor b in a..4 do\n\
+                        \t\tsum = sum + 1\n\
+                    \tend\n\
+                end\
+            ",
+        )
+        .as_str(),
+        "sum",
+        "10",
+    );
+}
+
+#[test]
+fn test_for_array() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            COUNTER_FIXTURE,
+            "\
+                var a : #primitives.u32[4] = [3, 5, 7, 9]\n\
+                for n in a do\n\
+                    \tsum = sum + n\n\
+                    \tn = 0\n\
+                end\
+            ",
+        )
+        .as_str(),
+        "sum + a[0]",
+        "27",
+    );
+}
+
+#[test]
+fn test_for_dynamic_array_and_slice() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            COUNTER_FIXTURE,
+            "\
+                var a : #primitives.u32[..] = [1, 2, 3]\n\
+                #array.push(a, 4)\n\
+                for n in a do\n\
+                    \tsum = sum + n\n\
+                end\n\
+                for n in a[1..3] do\n\
+                    \ti = i * 10 + n\n\
+                end\
+            ",
+        )
+        .as_str(),
+        "sum * 100 + i",
+        "1023",
+    );
+}
+
+#[test]
+fn test_for_dynamic_array_checks_length_every_iteration() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            COUNTER_FIXTURE,
+            "\
+                var a : #primitives.u32[..] = [1, 2, 3, 4]\n\
+                for n in a do\n\
+                    \tsum = sum + #array.pop(a)\n\
+                    \ti = i + 1\n\
+                end\
+            ",
+        )
+        .as_str(),
+        "i * 100 + sum",
+        "207",
+    );
+}
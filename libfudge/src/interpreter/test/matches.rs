@@ -0,0 +1,109 @@
+use super::utils::*;
+
+static MATCH_FIXTURE: &str = "\
+    def Point =\n\
+        \tstruct begin\n\
+            \t\tvar x : #primitives.u32\n\
+            \t\tvar y : #primitives.u32\n\
+        \tend\n\
+    def classify = func(n : #primitives.s32) -> #primitives.u32 do\n\
+        \treturn\n\
+            \t\tmatch n\n\
+            \t\tcase 0 => 0\n\
+            \t\tcase -5..0 => 1\n\
+            \t\tcase 1..10 if n != 7 => 2\n\
+            \t\tcase 7 => 7\n\
+            \t\telse 9\n\
+    end\n\
+    var p : Point\n\
+    ";
+
+#[test]
+fn test_match_literal_pattern() {
+    assert_expression_as_str_with_fixture(MATCH_FIXTURE, "classify(0)", "0");
+}
+
+#[test]
+fn test_match_negative_range_pattern() {
+    assert_expression_as_str_with_fixture(MATCH_FIXTURE, "classify(-3)", "1");
+}
+
+#[test]
+fn test_match_range_pattern_excludes_end() {
+    assert_expression_as_str_with_fixture(MATCH_FIXTURE, "classify(10)", "9");
+}
+
+#[test]
+fn test_match_guard() {
+    assert_expression_as_str_with_fixture(MATCH_FIXTURE, "classify(4) * 10 + classify(7)", "27");
+}
+
+#[test]
+fn test_match_bool_and_char_literals() {
+    assert_expression_as_str_with_fixture(
+        "\
+            var b : #primitives.bool = false\n\
+            var c : #primitives.char = 'q'\n\
+        ",
+        "(match b case true => 1 case false => 2) + (match c case 'a'..'r' => 10 else 20)",
+        "12",
+    );
+}
+
+#[test]
+fn test_match_struct_pattern() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            MATCH_FIXTURE,
+            "\
+                var a : #primitives.u32 = 0\n\
+                p.x = 3\n\
+                p.y = 4\n\
+                match p\n\
+                case { x = 0, y = v } then\n\
+                    \ta = v\n\
+                case { x = 3, y = v } then\n\
+                    \ta = v * 10\n\
+                case _ then\n\
+                    \ta = 1\n\
+                end\n\
+            ",
+        )
+        .as_str(),
+        "a",
+        "40",
+    );
+}
+
+#[test]
+fn test_match_struct_pattern_guard_sees_bindings() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            MATCH_FIXTURE,
+            "\
+                p.x = 2\n\
+                p.y = 5\n\
+            ",
+        )
+        .as_str(),
+        "match p case { x = a, y = b } if a > b => a - b case { y = b } => b",
+        "5",
+    );
+}
+
+#[test]
+fn test_match_tuple_pattern() {
+    assert_expression_as_str_with_fixture(
+        "\
+            var t : (#primitives.u32, #primitives.bool) = (5, true)\n\
+        ",
+        "match t case (_, false) => 0 case (n, true) => n",
+        "5",
+    );
+}
+
+#[test]
+#[should_panic(expected = "Struct has no field z")]
+fn test_wrong_match_struct_pattern_field() {
+    assert_expression_as_str_with_fixture(MATCH_FIXTURE, "match p case { z = _ } => 1", "1");
+}
@@ -1,7 +1,19 @@
 pub mod arithmetics;
+pub mod arrays;
 pub mod basic;
+pub mod dynamicarrays;
+pub mod enums;
+pub mod functions;
+pub mod logic;
+pub mod loops;
+pub mod matches;
 pub mod modules;
+pub mod optionals;
+pub mod references;
+pub mod results;
+pub mod slices;
 pub mod structs;
+pub mod tuples;
 
 pub mod utils;
 pub use utils::*;
@@ -0,0 +1,55 @@
+use super::utils::*;
+
+#[test]
+fn test_optional_null_default() {
+    let source = "\
+        var x : ?#primitives.u32\n\
+        var a = x == null";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "true");
+    });
+}
+
+#[test]
+fn test_optional_narrowing() {
+    let source = "\
+        var x : ?#primitives.u32 = null\n\
+        x = 5\n\
+        var y : #primitives.u32 = 0\n\
+        if x != null then\n\
+            y = x + 1\n\
+        end\n\
+        x = null\n\
+        var a = y\n\
+        var b = x == null";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "6");
+        assert_eq!(result.read_symbol_as_str(None, "b"), "true");
+    });
+}
+
+#[test]
+fn test_optional_parameter() {
+    let source = "\
+        def orzero = func(a : ?#primitives.u32) -> #primitives.u32 do\n\
+            if a != null then\n\
+                return a\n\
+            end\n\
+            return 0\n\
+        end\n\
+        var a = orzero(null)\n\
+        var b = orzero(4)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "0");
+        assert_eq!(result.read_symbol_as_str(None, "b"), "4");
+    });
+}
+
+#[test]
+#[should_panic(expected = "Cannot dereference null")]
+fn test_dereference_null() {
+    let source = "\
+        var r : ?&#primitives.u32 = null\n\
+        def a = *r";
+    test_interpreters(source, &|_| {});
+}
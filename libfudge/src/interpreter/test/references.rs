@@ -0,0 +1,57 @@
+use super::utils::*;
+
+#[test]
+fn test_reference_write_through() {
+    let source = "\
+        var x : #primitives.u32 = 5\n\
+        var r : &#primitives.u32 = &x\n\
+        *r = *r + 2\n\
+        var a = x\n\
+        var b = *r";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "7");
+        assert_eq!(result.read_symbol_as_str(None, "b"), "7");
+    });
+}
+
+#[test]
+fn test_reference_parameter() {
+    let source = "\
+        def increment = func(a : &#primitives.u32, by : #primitives.u32) do\n\
+            *a = *a + by\n\
+        end\n\
+        var x : #primitives.u32 = 5\n\
+        increment(&x, 10)\n\
+        var a = x";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "15");
+    });
+}
+
+#[test]
+fn test_reference_rebinding() {
+    let source = "\
+        var x : #primitives.u32 = 1\n\
+        var y : #primitives.u32 = 2\n\
+        var r = &x\n\
+        r = &y\n\
+        *r = 20\n\
+        var a = x\n\
+        var b = y";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "1");
+        assert_eq!(result.read_symbol_as_str(None, "b"), "20");
+    });
+}
+
+#[test]
+#[should_panic(expected = "outlives the variable")]
+fn test_returned_local_reference() {
+    let source = "\
+        def f = func() do\n\
+            var local : #primitives.u32 = 5\n\
+            return &local\n\
+        end\n\
+        def r = f()";
+    test_interpreters(source, &|_| {});
+}
@@ -0,0 +1,74 @@
+use super::utils::*;
+
+static RESULT_FIXTURE: &str = "\
+    def Parsed = #result(#primitives.u32, #primitives.u32)\n\
+    def half = func(n : #primitives.u32) -> Parsed do\n\
+        \tif n % 2 == 1 then\n\
+            \t\treturn Parsed.error(1)\n\
+        \tend\n\
+        \treturn Parsed.ok(n / 2)\n\
+    end\n\
+    def quarter = func(n : #primitives.u32) -> Parsed do\n\
+        \treturn Parsed.ok(try half(try half(n)))\n\
+    end\n\
+    def unwrap = func(r : Parsed) -> #primitives.u32 do\n\
+        \treturn\n\
+            \t\tmatch r\n\
+            \t\tcase ok(v) => v\n\
+            \t\tcase error(e) => 100 + e\n\
+    end\n\
+    ";
+
+#[test]
+fn test_result_variants() {
+    assert_expression_as_str_with_fixture(RESULT_FIXTURE, "unwrap(half(8))", "4");
+    assert_expression_as_str_with_fixture(RESULT_FIXTURE, "unwrap(half(7))", "101");
+}
+
+#[test]
+fn test_try_ok() {
+    assert_expression_as_str_with_fixture(RESULT_FIXTURE, "unwrap(quarter(20))", "5");
+}
+
+#[test]
+fn test_try_propagates_error() {
+    assert_expression_as_str_with_fixture(RESULT_FIXTURE, "unwrap(quarter(6))", "101");
+}
+
+#[test]
+fn test_try_skips_rest_of_function() {
+    let source = format!(
+        "{}\n{}",
+        RESULT_FIXTURE,
+        "\
+        var calls : #primitives.u32 = 0\n\
+        def count = func(n : #primitives.u32, c : &#primitives.u32) -> Parsed do\n\
+            \tvar x = try half(n)\n\
+            \t*c = *c + 1\n\
+            \treturn Parsed.ok(x)\n\
+        end\n\
+        var a = unwrap(count(4, &calls))\n\
+        var b = unwrap(count(5, &calls))\n\
+        var c = calls"
+    );
+    test_interpreters(&source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "a"), "2");
+        assert_eq!(result.read_symbol_as_str(None, "b"), "101");
+        assert_eq!(result.read_symbol_as_str(None, "c"), "1");
+    });
+}
+
+#[test]
+#[should_panic(expected = "Try can only be used in functions returning a result")]
+fn test_try_outside_result_function() {
+    let source = format!(
+        "{}\n{}",
+        RESULT_FIXTURE,
+        "\
+        def f = func() -> #primitives.u32 do\n\
+            \treturn try half(3)\n\
+        end\n\
+        def a = f()"
+    );
+    test_interpreters(&source, &|_| {});
+}
@@ -0,0 +1,112 @@
+use super::utils::*;
+
+static SLICE_FIXTURE: &str = "\
+    var a = [10, 20, 30, 40, 50]\n\
+    var s = a[1..4]\n\
+    ";
+
+#[test]
+fn test_slice_index() {
+    assert_expression_as_str_with_fixture(SLICE_FIXTURE, "s[0]", "20");
+    assert_expression_as_str_with_fixture(SLICE_FIXTURE, "s[2]", "40");
+    assert_expression_as_str_with_fixture(SLICE_FIXTURE, "#array.len(s)", "3");
+    assert_expression_as_str_with_fixture(SLICE_FIXTURE, "#array.len(a[2..2])", "0");
+}
+
+#[test]
+fn test_slice_is_view() {
+    let source = "\
+        var a = [1, 2, 3, 4]\n\
+        var s = a[1..3]\n\
+        s[0] = 20\n\
+        a[2] = 30\n\
+        var b = a[1]\n\
+        var c = s[1]";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "20");
+        assert_eq!(result.read_symbol_as_str(None, "c"), "30");
+    });
+}
+
+#[test]
+fn test_slice_of_slice() {
+    let source = "\
+        var a = [1, 2, 3, 4, 5]\n\
+        var s = a[1..5]\n\
+        var t = s[1..3]\n\
+        t[1] = 40\n\
+        var b = t[0]\n\
+        var c = a[3]\n\
+        var d = #array.len(t)";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "3");
+        assert_eq!(result.read_symbol_as_str(None, "c"), "40");
+        assert_eq!(result.read_symbol_as_str(None, "d"), "2");
+    });
+}
+
+#[test]
+fn test_slice_of_dynamic_array() {
+    let source = "\
+        var a : #primitives.u32[..] = [1, 2, 3]\n\
+        #array.push(a, 4)\n\
+        var s : #primitives.u32[&] = a[2..4]\n\
+        s[1] = 40\n\
+        var b = a[3]\n\
+        var c = s[0]";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "40");
+        assert_eq!(result.read_symbol_as_str(None, "c"), "3");
+    });
+}
+
+#[test]
+fn test_slice_as_function_argument() {
+    let source = "\
+        def sum = func(s : #primitives.u32[&]) -> #primitives.u32 do\n\
+            var total : #primitives.u32 = 0\n\
+            for i in 0..#array.len(s) do\n\
+                total = total + s[i]\n\
+            end\n\
+            return total\n\
+        end\n\
+        def clear_first = func(s : #primitives.u32[&]) do\n\
+            s[0] = 0\n\
+        end\n\
+        var a : #primitives.u32[4] = [1, 2, 3, 4]\n\
+        var b = sum(a[1..4])\n\
+        clear_first(a[2..3])\n\
+        var c = a[2]";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "b"), "9");
+        assert_eq!(result.read_symbol_as_str(None, "c"), "0");
+    });
+}
+
+#[test]
+#[should_panic(expected = "Slice 2..6 out of bounds for length 5 at strsource:2:10")]
+fn test_slice_out_of_bounds() {
+    let source = "\
+        var a = [10, 20, 30, 40, 50]\n\
+        var s = a[2..6]";
+    test_interpreters(source, &|_| {});
+}
+
+#[test]
+#[should_panic(expected = "Slice 3..2 out of bounds for length 5 at strsource:2:10")]
+fn test_slice_reversed_bounds() {
+    let source = "\
+        var a = [10, 20, 30, 40, 50]\n\
+        var s = a[3..2]";
+    test_interpreters(source, &|_| {});
+}
+
+#[test]
+#[should_panic(expected = "Index 2 out of bounds for slice of length 2 at strsource:3:10")]
+fn test_slice_index_out_of_bounds() {
+    let source = "\
+        var a = [10, 20, 30, 40, 50]\n\
+        var s = a[1..3]\n\
+        var b = s[2]";
+    test_interpreters(source, &|_| {});
+}
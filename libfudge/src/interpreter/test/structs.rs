@@ -15,10 +15,6 @@ static STRUCT_FIXTURE: &str = "\
     var b : B\n\
     ";
 
-fn append_to_fixture(fixture: &str, appendix: &str) -> String {
-    format!("{}\n{}", fixture, appendix)
-}
-
 #[test]
 fn test_struct_field_default_simple() {
     assert_expression_as_str_with_fixture(STRUCT_FIXTURE, "a.a", "0");
@@ -102,3 +98,175 @@ fn test_struct_field_nested_assign_multiple() {
         "16",
     );
 }
+
+static GENERIC_STRUCT_FIXTURE: &str = "\
+    def Pair =\n\
+        \tstruct begin\n\
+            \t\tvar first : $T\n\
+            \t\tvar second : $T\n\
+        \tend\n\
+    def Entry =\n\
+        \tstruct begin\n\
+            \t\tvar key : $K\n\
+            \t\tvar value : Pair($V)\n\
+        \tend\n\
+    var a : Pair(#primitives.u32)\n\
+    var b : Entry(#primitives.u8, #primitives.f64)\n\
+    ";
+
+#[test]
+fn test_generic_struct_field_default() {
+    assert_expression_as_str_with_fixture(GENERIC_STRUCT_FIXTURE, "a.first + a.second", "0");
+}
+
+#[test]
+fn test_generic_struct_field_assign() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            GENERIC_STRUCT_FIXTURE,
+            "\
+                a.first = 3
+                a.second = 5
+            ",
+        )
+        .as_str(),
+        "a.first + a.second",
+        "8",
+    );
+}
+
+#[test]
+fn test_generic_struct_nested_instance() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            GENERIC_STRUCT_FIXTURE,
+            "\
+                b.key = 7
+                b.value.second = 2.5
+            ",
+        )
+        .as_str(),
+        "b.value.second * 2.0",
+        "5",
+    );
+}
+
+#[test]
+fn test_generic_struct_as_generic_function_parameter() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            GENERIC_STRUCT_FIXTURE,
+            "\
+                def sum = func(p : Pair($T)) -> $T do\n\
+                    \treturn p.first + p.second\n\
+                end\n\
+                a.first = 4
+                a.second = 9
+            ",
+        )
+        .as_str(),
+        "sum(a)",
+        "13",
+    );
+}
+
+#[test]
+#[should_panic(expected = "Expected 1 type arguments, got 2")]
+fn test_wrong_generic_struct_type_argument_count() {
+    assert_expression_as_str_with_fixture(
+        GENERIC_STRUCT_FIXTURE,
+        "Pair(#primitives.u32, #primitives.u32)",
+        "",
+    );
+}
+
+#[test]
+#[should_panic(expected = "Generic struct requires type arguments")]
+fn test_wrong_generic_struct_without_type_arguments() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(GENERIC_STRUCT_FIXTURE, "var c : Pair").as_str(),
+        "c.first",
+        "",
+    );
+}
+
+#[test]
+fn test_struct_construction_all_fields() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(STRUCT_FIXTURE, "var c = A { a = 3, b = 5, c = 8 }").as_str(),
+        "c.a + c.b + c.c",
+        "16",
+    );
+}
+
+#[test]
+fn test_struct_construction_omitted_fields_default() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(STRUCT_FIXTURE, "var c = A { b = 5 }").as_str(),
+        "c.a + c.b + c.c",
+        "5",
+    );
+}
+
+#[test]
+fn test_struct_construction_nested() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(STRUCT_FIXTURE, "var c = B { a = A { c = 7 } }").as_str(),
+        "c.a.c",
+        "7",
+    );
+}
+
+#[test]
+fn test_struct_construction_wraps_optional_fields() {
+    assert_expression_as_str_with_fixture(
+        "\
+            def O =\n\
+                \tstruct begin\n\
+                    \t\tvar a : ?#primitives.u32\n\
+                    \t\tvar b : ?#primitives.u32\n\
+                \tend\n\
+            var c = O { a = 7 }\n\
+        ",
+        "c.a != null and c.b == null",
+        "true",
+    );
+}
+
+#[test]
+fn test_struct_construction_in_module() {
+    test_interpreters(
+        "\
+            module std begin\n\
+                \tdef mystruct =\n\
+                    \t\tstruct begin\n\
+                        \t\t\tvar value : #primitives.u32\n\
+                    \t\tend\n\
+            end\n\
+            var a = std.mystruct { value = 3 }\n\
+            var b = a.value
+        ",
+        &|result| {
+            assert_eq!(result.read_symbol_as_str(None, "b"), "3");
+        },
+    );
+}
+
+#[test]
+fn test_generic_struct_construction() {
+    assert_expression_as_str_with_fixture(
+        append_to_fixture(
+            GENERIC_STRUCT_FIXTURE,
+            "var c = Pair(#primitives.u32) { first = 4, second = 9 }",
+        )
+        .as_str(),
+        "c.first + c.second",
+        "13",
+    );
+}
+
+#[test]
+#[should_panic(expected = "Struct has no field d")]
+fn test_wrong_struct_construction_unknown_field() {
+    assert_expression_as_str_with_fixture(STRUCT_FIXTURE, "A { d = 3 }", "");
+}
@@ -0,0 +1,77 @@
+use super::utils::*;
+
+#[test]
+fn test_multiple_return_values() {
+    let source = "\
+        def divmod = func(a : #primitives.u32, b : #primitives.u32) -> (#primitives.u32, #primitives.u32) do\n\
+            return a / b, a % b\n\
+        end\n\
+        var q, r = divmod(17, 5)\n\
+        var c = q\n\
+        var d = r";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "c"), "3");
+        assert_eq!(result.read_symbol_as_str(None, "d"), "2");
+    });
+}
+
+#[test]
+fn test_return_values_take_output_types() {
+    let source = "\
+        def small = func() -> (#primitives.u8, #primitives.s16) do\n\
+            return 1, -2\n\
+        end\n\
+        var a, b = small()\n\
+        var x : #primitives.u8 = a\n\
+        var y : #primitives.s16 = b\n\
+        var c = x\n\
+        var d = y";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "c"), "1");
+        assert_eq!(result.read_symbol_as_str(None, "d"), "-2");
+    });
+}
+
+#[test]
+fn test_ignored_return_values() {
+    let source = "\
+        def three = func() -> (#primitives.u32, #primitives.u32, #primitives.u32) do\n\
+            return 1, 2, 3\n\
+        end\n\
+        var _, b, _ = three()\n\
+        var c = b";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "c"), "2");
+    });
+}
+
+#[test]
+fn test_tuple_values() {
+    let source = "\
+        def pair = func() -> (#primitives.u32, #primitives.bool) do\n\
+            return (4, true)\n\
+        end\n\
+        var t = pair()\n\
+        var u : (#primitives.u8, #primitives.bool) = (5, false)\n\
+        u = (6, true)\n\
+        var a, b = t\n\
+        var c, d = u\n\
+        var e = a\n\
+        var f = b\n\
+        var g = c\n\
+        var h = d";
+    test_interpreters(source, &|result| {
+        assert_eq!(result.read_symbol_as_str(None, "e"), "4");
+        assert_eq!(result.read_symbol_as_str(None, "f"), "true");
+        assert_eq!(result.read_symbol_as_str(None, "g"), "6");
+        assert_eq!(result.read_symbol_as_str(None, "h"), "true");
+    });
+}
+
+#[test]
+#[should_panic(expected = "Mismatching number of symbols and tuple elements in declaration")]
+fn test_destructuring_count_mismatch() {
+    let source = "\
+        var a, b, c = (1, 2)";
+    test_interpreters(source, &|_| {});
+}
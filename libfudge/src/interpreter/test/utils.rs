@@ -42,7 +42,7 @@ pub fn assert_expression_as_str_with_fixture(fixture: &str, exp: &str, expected:
     let source = format!(
         "\
             {}\n\
-            def __res = {}\
+            var __res = {}\
         ",
         fixture, exp
     );
@@ -55,3 +55,7 @@ pub fn assert_expression_as_str_with_fixture(fixture: &str, exp: &str, expected:
 pub fn assert_expression_as_str(exp: &str, expected: &str) {
     assert_expression_as_str_with_fixture("", exp, expected)
 }
+
+pub fn append_to_fixture(fixture: &str, appendix: &str) -> String {
+    format!("{}\n{}", fixture, appendix)
+}
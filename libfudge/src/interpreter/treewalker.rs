@@ -3,12 +3,15 @@ use crate::ast::*;
 use crate::typesystem::*;
 
 use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::fmt;
 use std::mem;
 
 use dyn_fmt::AsStrFormatExt;
 
 use crate::shared::BinaryOperationType;
+use crate::shared::UnaryOperationType;
+use crate::source::SourceLocation;
 use crate::utils::StringKey;
 
 pub struct Context<'a> {
@@ -93,6 +96,24 @@ impl VariableEnvironment {
     fn has_symbol(&self, s: &ast::SymbolRef) -> bool {
         self.symbol_storage_lookup.contains_key(s)
     }
+
+    fn checkpoint(&self) -> VariableCheckpoint {
+        VariableCheckpoint {
+            symbol_storage_lookup: self.symbol_storage_lookup.clone(),
+            storage_len: self.storage.len(),
+        }
+    }
+
+    // Drops everything added since the checkpoint, i.e. when leaving a block scope
+    fn restore(&mut self, checkpoint: VariableCheckpoint) {
+        self.symbol_storage_lookup = checkpoint.symbol_storage_lookup;
+        self.storage.truncate(checkpoint.storage_len);
+    }
+}
+
+struct VariableCheckpoint {
+    symbol_storage_lookup: HashMap<ast::SymbolRef, usize>,
+    storage_len: usize,
 }
 
 pub struct Module {
@@ -139,6 +160,16 @@ pub struct State {
     pub strings: Vec<String>,
     pub stackframes: Vec<StackFrame>,
     pub current_module: Option<StringKey>,
+    pub loop_control: Option<LoopControl>,
+    // Storage for dynamic arrays, nothing is freed until the program ends
+    pub heap: Vec<ArrayInstance>,
+}
+
+// Set by break and continue statements, until the enclosing loop handles it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LoopControl {
+    Break,
+    Continue,
 }
 
 pub struct TreeWalker<'a> {
@@ -151,11 +182,21 @@ pub struct StackFrame {
     index: usize,
     variables: VariableEnvironment,
     returnvalue: Option<Value>,
+    // Set by return statements, the rest of the function body is skipped
+    returned: bool,
+    // Declared types of the returned values, so literals can be returned as them
+    outputparams: Vec<TypeId>,
+    // Frame the called function was defined in, functions defined there are visible
+    definingframe: Option<usize>,
+    // Types bound to the type parameters of a generic function by the arguments
+    typearguments: HashMap<String, TypeId>,
 }
 
 pub struct Function {
     module: StringKey,
     signature: FunctionSignature,
+    // Default argument expressions, per input parameter
+    defaults: Vec<Option<AstRef>>,
     body: AstRef,
 }
 
@@ -163,6 +204,9 @@ pub struct Function {
 pub struct FunctionRef {
     index: u64,
     module: StringKey,
+    // Captured locals, captures by reference refer into the defining frame
+    captures: Vec<(ast::SymbolRef, Value)>,
+    definingframe: Option<usize>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -171,11 +215,51 @@ pub struct StructInstance {
     pub fields: HashMap<ast::SymbolRef, Value>,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumInstance {
+    pub definition: EnumDefinition,
+    // Index of the variant, fields are stored in the order they are declared in
+    pub variant: usize,
+    pub fields: Vec<Value>,
+}
+
+// Variant with payload fields, called with the fields to create an instance
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariantConstructor {
+    pub definition: EnumDefinition,
+    pub variant: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArrayInstance {
+    // Kept separately, so the type of empty arrays is known
+    pub element: TypeId,
+    pub elements: Vec<Value>,
+}
+
+// The array a slice views, either referenced or on the heap
+#[derive(Debug, Clone, PartialEq)]
+pub enum SliceSource {
+    Array(SimpleValueRef),
+    Heap(usize),
+}
+
+// Borrowed view into a range of elements, the elements are not copied
+#[derive(Debug, Clone, PartialEq)]
+pub struct SliceInstance {
+    pub element: TypeId,
+    pub source: SliceSource,
+    pub start: usize,
+    pub length: usize,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Utf8StaticString(u64);
 #[derive(Debug, Clone, PartialEq)]
 pub struct Bool(bool);
 #[derive(Debug, Clone, PartialEq)]
+pub struct Char(char);
+#[derive(Debug, Clone, PartialEq)]
 pub struct U8(u8);
 #[derive(Debug, Clone, PartialEq)]
 pub struct U16(u16);
@@ -200,6 +284,7 @@ pub struct F64(f64);
 pub enum PrimitiveValue {
     Utf8StaticString(Utf8StaticString),
     Bool(Bool),
+    Char(Char),
     U8(U8),
     U16(U16),
     U32(U32),
@@ -217,7 +302,12 @@ pub struct Add;
 pub struct Mul;
 pub struct Sub;
 pub struct Div;
+pub struct Mod;
+pub struct BitAnd;
+pub struct BitOr;
+pub struct BitXor;
 pub struct Equals;
+pub struct NotEquals;
 pub struct LessThan;
 pub struct LessThanOrEq;
 pub struct GeaterThan;
@@ -232,7 +322,12 @@ fn perform_binop<
         + BinOp<Sub>
         + BinOp<Mul>
         + BinOp<Div>
+        + BinOp<Mod>
+        + BinOp<BitAnd>
+        + BinOp<BitOr>
+        + BinOp<BitXor>
         + BinOp<Equals>
+        + BinOp<NotEquals>
         + BinOp<LessThan>
         + BinOp<LessThanOrEq>
         + BinOp<GeaterThan>
@@ -247,11 +342,22 @@ fn perform_binop<
         BinaryOperationType::Sub => BinOp::<Sub>::perform(lhs, rhs),
         BinaryOperationType::Mul => BinOp::<Mul>::perform(lhs, rhs),
         BinaryOperationType::Div => BinOp::<Div>::perform(lhs, rhs),
+        BinaryOperationType::Mod => BinOp::<Mod>::perform(lhs, rhs),
+        BinaryOperationType::BitAnd => BinOp::<BitAnd>::perform(lhs, rhs),
+        BinaryOperationType::BitOr => BinOp::<BitOr>::perform(lhs, rhs),
+        BinaryOperationType::BitXor => BinOp::<BitXor>::perform(lhs, rhs),
         BinaryOperationType::Equals => BinOp::<Equals>::perform(lhs, rhs),
+        BinaryOperationType::NotEquals => BinOp::<NotEquals>::perform(lhs, rhs),
         BinaryOperationType::LessThan => BinOp::<LessThan>::perform(lhs, rhs),
         BinaryOperationType::LessThanOrEq => BinOp::<LessThanOrEq>::perform(lhs, rhs),
         BinaryOperationType::GreaterThan => BinOp::<GeaterThan>::perform(lhs, rhs),
         BinaryOperationType::GreaterThanOrEq => BinOp::<GreaterThanOrEq>::perform(lhs, rhs),
+        BinaryOperationType::And | BinaryOperationType::Or => {
+            panic!("Logical operations are short-circuited, not performed on values")
+        }
+        BinaryOperationType::ShiftLeft | BinaryOperationType::ShiftRight => {
+            panic!("Shift operations take an amount of any unsigned type, see perform_shift")
+        }
     };
 }
 
@@ -289,32 +395,125 @@ macro_rules! primitive_binop_unsupported {
 }
 
 primitive_binop_impl!(BinOp<Add>, +, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
-primitive_binop_unsupported!(BinOp<Add>, Utf8StaticString,);
+primitive_binop_unsupported!(BinOp<Add>, Utf8StaticString, Char,);
 
 primitive_binop_impl!(BinOp<Sub>, -, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
-primitive_binop_unsupported!(BinOp<Sub>, Utf8StaticString,);
+primitive_binop_unsupported!(BinOp<Sub>, Utf8StaticString, Char,);
 
 primitive_binop_impl!(BinOp<Mul>, *, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
-primitive_binop_unsupported!(BinOp<Mul>, Utf8StaticString,);
+primitive_binop_unsupported!(BinOp<Mul>, Utf8StaticString, Char,);
 
 primitive_binop_impl!(BinOp<Div>, /, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
-primitive_binop_unsupported!(BinOp<Div>, Utf8StaticString,);
+primitive_binop_unsupported!(BinOp<Div>, Utf8StaticString, Char,);
+
+primitive_binop_impl!(BinOp<Mod>, %, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
+primitive_binop_unsupported!(BinOp<Mod>, Utf8StaticString, Char,);
+
+primitive_binop_impl!(BinOp<BitAnd>, &, U8, U16, U32, U64, S8, S16, S32, S64,);
+primitive_binop_unsupported!(BinOp<BitAnd>, Utf8StaticString, Char, F32, F64,);
+
+primitive_binop_impl!(BinOp<BitOr>, |, U8, U16, U32, U64, S8, S16, S32, S64,);
+primitive_binop_unsupported!(BinOp<BitOr>, Utf8StaticString, Char, F32, F64,);
+
+primitive_binop_impl!(BinOp<BitXor>, ^, U8, U16, U32, U64, S8, S16, S32, S64,);
+primitive_binop_unsupported!(BinOp<BitXor>, Utf8StaticString, Char, F32, F64,);
 
-primitive_comparison_impl!(BinOp<Equals>, ==, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
+primitive_comparison_impl!(BinOp<Equals>, ==, Char, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
 primitive_binop_unsupported!(BinOp<Equals>, Utf8StaticString,);
 
-primitive_comparison_impl!(BinOp<LessThan>, <, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
+primitive_comparison_impl!(BinOp<NotEquals>, !=, Char, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
+primitive_binop_unsupported!(BinOp<NotEquals>, Utf8StaticString,);
+
+primitive_comparison_impl!(BinOp<LessThan>, <, Char, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
 primitive_binop_unsupported!(BinOp<LessThan>, Utf8StaticString,);
 
-primitive_comparison_impl!(BinOp<LessThanOrEq>, <=, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
+primitive_comparison_impl!(BinOp<LessThanOrEq>, <=, Char, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
 primitive_binop_unsupported!(BinOp<LessThanOrEq>, Utf8StaticString,);
 
-primitive_comparison_impl!(BinOp<GeaterThan>, >, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
+primitive_comparison_impl!(BinOp<GeaterThan>, >, Char, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
 primitive_binop_unsupported!(BinOp<GeaterThan>, Utf8StaticString,);
 
-primitive_comparison_impl!(BinOp<GreaterThanOrEq>, >=, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
+primitive_comparison_impl!(BinOp<GreaterThanOrEq>, >=, Char, U8, U16, U32, U64, S8, S16, S32, S64, F32, F64,);
 primitive_binop_unsupported!(BinOp<GreaterThanOrEq>, Utf8StaticString,);
 
+// Shifts keep the type of the shifted value, the amount can be any unsigned integer type,
+//  but must be less than the bit width of the shifted value
+trait Shift {
+    fn perform_shift(&self, op: &BinaryOperationType, amount: u64) -> Value;
+}
+
+macro_rules! primitive_shift_impl {
+    ($($t:tt: $bits:ty,)*) => ($(
+        impl Shift for $t {
+            fn perform_shift(&self, op: &BinaryOperationType, amount: u64) -> Value {
+                let value = self.0 as $bits;
+                let shifted = u32::try_from(amount).ok().and_then(|amount| match op {
+                    BinaryOperationType::ShiftLeft => value.checked_shl(amount),
+                    // Right shifts of signed values are arithmetic
+                    BinaryOperationType::ShiftRight => value.checked_shr(amount),
+                    _ => panic!("{:?} is not a shift operation", op),
+                });
+
+                match shifted {
+                    Some(v) => Value::Primitive(PrimitiveValue::$t($t(v as _))),
+                    None => panic!(
+                        "Shift amount {} out of range for {}",
+                        amount,
+                        stringify!($bits)
+                    ),
+                }
+            }
+        }
+    )*)
+}
+
+primitive_shift_impl!(U8: u8, U16: u16, U32: u32, U64: u64, S8: i8, S16: i16, S32: i32, S64: i64,);
+
+pub struct Neg;
+pub struct BitNot;
+
+trait UnOp<Op> {
+    fn perform(&self) -> Value;
+}
+
+fn perform_unop<T: UnOp<Neg> + UnOp<BitNot>>(op: &UnaryOperationType, operand: &T) -> Value {
+    return match op {
+        UnaryOperationType::Neg => UnOp::<Neg>::perform(operand),
+        UnaryOperationType::BitNot => UnOp::<BitNot>::perform(operand),
+        UnaryOperationType::Not => {
+            panic!("Logical operations are performed on bools, not on values")
+        }
+    };
+}
+
+macro_rules! primitive_unop_impl {
+    ($optrait:ty, $op:tt, $($t:tt,)*) => ($(
+        impl $optrait for $t {
+            #[inline]
+            fn perform(&self) -> Value {
+                Value::Primitive(PrimitiveValue::$t($t($op self.0)))
+            }
+        }
+    )*)
+}
+
+macro_rules! primitive_unop_unsupported {
+    ($optrait:ty, $($t:ty,)*) => ($(
+        impl $optrait for $t {
+            #[inline]
+            fn perform(&self) -> Value {
+                panic!("Unary operation {}, not supported for {}", stringify!($optrait), stringify!($t))
+            }
+        }
+    )*)
+}
+
+primitive_unop_impl!(UnOp<Neg>, -, S8, S16, S32, S64, F32, F64,);
+primitive_unop_unsupported!(UnOp<Neg>, U8, U16, U32, U64,);
+
+primitive_unop_impl!(UnOp<BitNot>, !, U8, U16, U32, U64, S8, S16, S32, S64,);
+primitive_unop_unsupported!(UnOp<BitNot>, F32, F64,);
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Null,
@@ -323,8 +522,18 @@ pub enum Value {
     BuiltInFunction(BuiltInFunction),
     Function(FunctionRef),
     StructInstance(StructInstance),
+    EnumInstance(EnumInstance),
+    VariantConstructor(VariantConstructor),
+    Array(ArrayInstance),
+    // Index into the heap, copies refer to the same elements
+    DynamicArray(usize),
+    Slice(SliceInstance),
+    // Elements are stored by value, so they are copied along with the tuple
+    Tuple(Vec<Value>),
     Module(StringKey),
     ValueRef(ValueRef),
+    // Explicit reference, unlike value refs it is not dereferenced implicitly
+    Reference(ValueRef),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -372,10 +581,24 @@ pub struct SubscriptedValueRef {
     field: ast::SymbolRef,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedValueRef {
+    vref: SimpleValueRef,
+    index: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeapElementRef {
+    array: usize,
+    index: usize,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ValueRef {
     SimpleValueRef(SimpleValueRef),
     SubscriptedValueRef(SubscriptedValueRef),
+    IndexedValueRef(IndexedValueRef),
+    HeapElementRef(HeapElementRef),
 }
 
 pub struct ValueRefDisplay<'a> {
@@ -425,6 +648,17 @@ impl<'a> fmt::Debug for ValueRefDisplay<'a> {
                     r.field
                 )
             }
+            ValueRef::IndexedValueRef(r) => {
+                write!(
+                    f,
+                    "IndexedValueRef({}[{}])",
+                    get_simple_valueref_debug_name(&r.vref, self.state),
+                    r.index
+                )
+            }
+            ValueRef::HeapElementRef(r) => {
+                write!(f, "HeapElementRef({}[{}])", r.array, r.index)
+            }
         }
     }
 }
@@ -462,6 +696,7 @@ impl Value {
                     TypeId::Primitive(PrimitiveType::StaticStringUtf8)
                 }
                 PrimitiveValue::Bool(_) => TypeId::Primitive(PrimitiveType::Bool),
+                PrimitiveValue::Char(_) => TypeId::Primitive(PrimitiveType::Char),
                 PrimitiveValue::U8(_) => TypeId::Primitive(PrimitiveType::U8),
                 PrimitiveValue::U16(_) => TypeId::Primitive(PrimitiveType::U16),
                 PrimitiveValue::U32(_) => TypeId::Primitive(PrimitiveType::U32),
@@ -480,7 +715,27 @@ impl Value {
                     .clone(),
             ),
             Value::StructInstance(instance) => TypeId::Struct(instance.definition.clone()),
+            Value::EnumInstance(instance) => TypeId::Enum(instance.definition.clone()),
+            Value::VariantConstructor(constructor) => TypeId::Function(FunctionSignature {
+                inputparams: constructor.definition.variants[constructor.variant]
+                    .fields
+                    .clone(),
+                outputparams: vec![TypeId::Enum(constructor.definition.clone())],
+            }),
+            Value::Array(array) => {
+                TypeId::new_array(array.element.clone(), array.elements.len() as u64)
+            }
+            Value::DynamicArray(handle) => {
+                TypeId::new_dynamic_array(state.heap[*handle].element.clone())
+            }
+            Value::Slice(slice) => TypeId::new_slice(slice.element.clone()),
+            Value::Tuple(elements) => {
+                TypeId::Tuple(elements.iter().map(|e| e.get_type(state)).collect())
+            }
             Value::Module(_) => TypeId::Module,
+            Value::Reference(vref) => {
+                TypeId::new_reference(state.full_deref_valueref(vref).get_type(state))
+            }
             _ => panic!("Value type cannot be found: {:?}", &self),
         }
     }
@@ -490,6 +745,7 @@ impl Value {
             Value::Primitive(p) => match p {
                 PrimitiveValue::Utf8StaticString(v) => state.strings[v.0 as usize].clone(),
                 PrimitiveValue::Bool(v) => v.0.to_string(),
+                PrimitiveValue::Char(v) => v.0.to_string(),
                 PrimitiveValue::U8(v) => v.0.to_string(),
                 PrimitiveValue::U16(v) => v.0.to_string(),
                 PrimitiveValue::U32(v) => v.0.to_string(),
@@ -505,6 +761,20 @@ impl Value {
         }
     }
 
+    // Optionals are null or a plain value, so a value used as an optional has the optional
+    //  type. Null only has a type of its own if the optional type is not generic.
+    fn get_type_as(&self, expected: &TypeId, state: &State) -> TypeId {
+        let typeid = self.get_type(state);
+        match (expected, &typeid) {
+            (TypeId::Optional(_), TypeId::Null) if expected.is_bound(&HashMap::new()) => {
+                expected.clone()
+            }
+            (TypeId::Optional(_), TypeId::Null | TypeId::Optional(_)) => typeid,
+            (TypeId::Optional(_), _) => TypeId::new_optional(typeid),
+            _ => typeid,
+        }
+    }
+
     fn match_type(&self, o: &Value, state: &State) -> bool {
         return self.is_type(&o.get_type(state), state);
     }
@@ -559,7 +829,36 @@ fn create_stackframe_ref_from_value(frame: &mut StackFrame, value: Value) -> Sim
     })
 }
 
-fn create_default_value(typeid: &TypeId) -> Value {
+// Frame of the stack variable a value ref points into, globals and heap elements have none
+fn get_valueref_frame(vref: &ValueRef) -> Option<usize> {
+    let vref = match vref {
+        ValueRef::SimpleValueRef(vref) => vref,
+        ValueRef::SubscriptedValueRef(r) => &r.vref,
+        ValueRef::IndexedValueRef(r) => &r.vref,
+        ValueRef::HeapElementRef(_) => return None,
+    };
+    match vref {
+        SimpleValueRef::NamedStackValueRef(r) => Some(r.frame),
+        SimpleValueRef::IndexedStackValueRef(r) => Some(r.frame),
+        _ => None,
+    }
+}
+
+fn get_slice_element_ref(slice: &SliceInstance, index: usize) -> ValueRef {
+    let index = slice.start + index;
+    match &slice.source {
+        SliceSource::Array(vref) => ValueRef::IndexedValueRef(IndexedValueRef {
+            vref: vref.clone(),
+            index,
+        }),
+        SliceSource::Heap(array) => ValueRef::HeapElementRef(HeapElementRef {
+            array: *array,
+            index,
+        }),
+    }
+}
+
+fn create_default_value(typeid: &TypeId, state: &mut State) -> Value {
     match typeid {
         TypeId::Primitive(p) => match p {
             PrimitiveType::StaticStringUtf8 => {
@@ -567,6 +866,7 @@ fn create_default_value(typeid: &TypeId) -> Value {
                 Value::Primitive(PrimitiveValue::Utf8StaticString(Utf8StaticString(0)))
             }
             PrimitiveType::Bool => Value::Primitive(PrimitiveValue::Bool(Bool(false))),
+            PrimitiveType::Char => Value::Primitive(PrimitiveValue::Char(Char('\0'))),
             PrimitiveType::U8 => Value::Primitive(PrimitiveValue::U8(U8(0))),
             PrimitiveType::U16 => Value::Primitive(PrimitiveValue::U16(U16(0))),
             PrimitiveType::U32 => Value::Primitive(PrimitiveValue::U32(U32(0))),
@@ -579,10 +879,16 @@ fn create_default_value(typeid: &TypeId) -> Value {
             PrimitiveType::F64 => Value::Primitive(PrimitiveValue::F64(F64(0.0))),
         },
         TypeId::Struct(definition) => {
+            assert!(
+                !definition.is_generic(),
+                "Generic struct requires type arguments: {}",
+                typeid.to_string()
+            );
+
             let mut fields = HashMap::new();
 
             for field in &definition.fields {
-                fields.insert(field.0.clone(), create_default_value(&field.1));
+                fields.insert(field.0.clone(), create_default_value(&field.1, state));
             }
 
             Value::StructInstance(StructInstance {
@@ -590,19 +896,270 @@ fn create_default_value(typeid: &TypeId) -> Value {
                 fields: fields,
             })
         }
+        // The first variant, with default values for its fields
+        TypeId::Enum(definition) => {
+            assert!(
+                !definition.is_generic(),
+                "Generic enum requires type arguments: {}",
+                typeid.to_string()
+            );
+
+            let variant = match definition.variants.first() {
+                Some(variant) => variant,
+                None => panic!("Enum without variants has no default value"),
+            };
+
+            Value::EnumInstance(EnumInstance {
+                definition: definition.clone(),
+                variant: 0,
+                fields: variant
+                    .fields
+                    .iter()
+                    .map(|(_, t)| create_default_value(t, state))
+                    .collect(),
+            })
+        }
+        TypeId::Array { element, length } => Value::Array(ArrayInstance {
+            element: element.as_ref().clone(),
+            // Not cloned, so that dynamic array elements do not share storage
+            elements: (0..*length)
+                .map(|_| create_default_value(element, state))
+                .collect(),
+        }),
+        TypeId::DynamicArray { element } => state.allocate_array(ArrayInstance {
+            element: element.as_ref().clone(),
+            elements: Vec::new(),
+        }),
+        TypeId::Tuple(elements) => Value::Tuple(
+            elements
+                .iter()
+                .map(|e| create_default_value(e, state))
+                .collect(),
+        ),
+        TypeId::Optional(_) => create_null_value(),
         _ => panic!("No default value for typeid {:?}", typeid),
     }
 }
 
+// Converts an integer literal to a value of the given type, if it is a numeric type
+fn create_integer_value(typeid: &TypeId, value: u64) -> Option<Value> {
+    macro_rules! checked {
+        ($t:tt, $inner:ty) => {
+            PrimitiveValue::$t($t(<$inner>::try_from(value).unwrap_or_else(|_| {
+                panic!(
+                    "Integer literal {} does not fit in {}",
+                    value,
+                    stringify!($inner)
+                )
+            })))
+        };
+    }
+
+    let v = match typeid {
+        TypeId::Primitive(p) => match p {
+            PrimitiveType::U8 => checked!(U8, u8),
+            PrimitiveType::U16 => checked!(U16, u16),
+            PrimitiveType::U32 => checked!(U32, u32),
+            PrimitiveType::U64 => checked!(U64, u64),
+            PrimitiveType::S8 => {
+                let v = i8::try_from(value)
+                    .unwrap_or_else(|_| panic!("Integer literal {} does not fit in i8", value));
+                PrimitiveValue::S8(S8(v as i16))
+            }
+            PrimitiveType::S16 => checked!(S16, i16),
+            PrimitiveType::S32 => checked!(S32, i32),
+            PrimitiveType::S64 => checked!(S64, i64),
+            PrimitiveType::F32 => PrimitiveValue::F32(F32(value as f32)),
+            PrimitiveType::F64 => PrimitiveValue::F64(F64(value as f64)),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    return Some(Value::Primitive(v));
+}
+
+// Converts a negated integer literal to a value of the given type, if it is a signed numeric type
+fn create_negated_integer_value(typeid: &TypeId, value: u64) -> Option<Value> {
+    // Negate before narrowing, so the full signed range is available, i.e. -128 for s8
+    let negated = -(value as i128);
+
+    macro_rules! checked {
+        ($t:tt, $inner:ty) => {
+            PrimitiveValue::$t($t(<$inner>::try_from(negated).unwrap_or_else(|_| {
+                panic!(
+                    "Integer literal {} does not fit in {}",
+                    negated,
+                    stringify!($inner)
+                )
+            })))
+        };
+    }
+
+    let v = match typeid {
+        TypeId::Primitive(p) => match p {
+            PrimitiveType::U8 | PrimitiveType::U16 | PrimitiveType::U32 | PrimitiveType::U64 => {
+                panic!("Negative integer literal {} cannot be unsigned", negated)
+            }
+            PrimitiveType::S8 => {
+                let v = i8::try_from(negated)
+                    .unwrap_or_else(|_| panic!("Integer literal {} does not fit in i8", negated));
+                PrimitiveValue::S8(S8(v as i16))
+            }
+            PrimitiveType::S16 => checked!(S16, i16),
+            PrimitiveType::S32 => checked!(S32, i32),
+            PrimitiveType::S64 => checked!(S64, i64),
+            PrimitiveType::F32 => PrimitiveValue::F32(F32(negated as f32)),
+            PrimitiveType::F64 => PrimitiveValue::F64(F64(negated as f64)),
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    return Some(Value::Primitive(v));
+}
+
+// Converts a float literal to a value of the given type, if it is a float type
+fn create_float_value(typeid: &TypeId, value: f64) -> Option<Value> {
+    let v = match typeid {
+        TypeId::Primitive(PrimitiveType::F32) => PrimitiveValue::F32(F32(value as f32)),
+        TypeId::Primitive(PrimitiveType::F64) => PrimitiveValue::F64(F64(value)),
+        _ => return None,
+    };
+
+    return Some(Value::Primitive(v));
+}
+
+// Arrays can be indexed by any unsigned integer type
+fn get_index_value(value: &Value) -> Option<u64> {
+    match value {
+        Value::Primitive(PrimitiveValue::U8(n)) => Some(n.0 as u64),
+        Value::Primitive(PrimitiveValue::U16(n)) => Some(n.0 as u64),
+        Value::Primitive(PrimitiveValue::U32(n)) => Some(n.0 as u64),
+        Value::Primitive(PrimitiveValue::U64(n)) => Some(n.0),
+        _ => None,
+    }
+}
+
+fn is_numeric_literal(node: &ast::Node) -> bool {
+    match node {
+        ast::Node::IntegerLiteral(_) | ast::Node::FloatLiteral(_) => true,
+        _ => false,
+    }
+}
+
+// Negated numeric literals, i.e. "-5", are typed the same way as plain literals
+fn is_negated_numeric_literal(context: &Context, astref: &AstRef) -> bool {
+    match context.get_node(astref) {
+        ast::Node::UnaryOperation(n) if n.optype == UnaryOperationType::Neg => {
+            is_numeric_literal(context.get_node(&from_astref(astref, &n.expr)))
+        }
+        _ => false,
+    }
+}
+
+// All symbols referenced in the subtree, including the capture lists of nested function literals
+fn collect_referenced_symbols(ast: &Ast, noderef: &NodeRef) -> Vec<ast::SymbolRef> {
+    fn collect(ast: &Ast, noderef: &NodeRef, symbols: &mut Vec<ast::SymbolRef>) {
+        let node = ast.get_node(noderef);
+        match node {
+            ast::Node::SymbolReference(n) => symbols.push(n.symbol.clone()),
+            ast::Node::FunctionLiteral(n) => {
+                symbols.extend(n.captures.iter().map(|c| c.0.clone()));
+            }
+            _ => {}
+        }
+
+        visit_children(node, |childref| {
+            collect(ast, childref, symbols);
+            true
+        });
+    }
+
+    let mut symbols = Vec::new();
+    collect(ast, noderef, &mut symbols);
+    symbols
+}
+
 impl<'a> TreeWalker<'a> {
     fn evaluate_integerliteral(&mut self, intlit: &ast::nodes::IntegerLiteral) -> Value {
-        return Value::Primitive(PrimitiveValue::U32(U32(intlit.value as u32)));
+        let default_type = if intlit.signed {
+            PrimitiveType::S32
+        } else {
+            PrimitiveType::U32
+        };
+        return create_integer_value(&TypeId::Primitive(default_type), intlit.value).unwrap();
+    }
+
+    fn evaluate_floatliteral(&mut self, floatlit: &ast::nodes::FloatLiteral) -> Value {
+        return Value::Primitive(PrimitiveValue::F64(F64(floatlit.value)));
+    }
+
+    // Numeric literals do not carry a type of their own, so if the expected type
+    //  is known, evaluate them as that type rather than their default type
+    fn evaluate_expression_as_type(&mut self, astref: &AstRef, typeid: &TypeId) -> Value {
+        // Values of optionals are plain values of the type
+        if let TypeId::Optional(target) = typeid {
+            return self.evaluate_expression_as_type(astref, target);
+        }
+
+        let value = match self.context.get_node(astref) {
+            ast::Node::IntegerLiteral(n) => create_integer_value(typeid, n.value),
+            ast::Node::FloatLiteral(n) => create_float_value(typeid, n.value),
+            ast::Node::UnaryOperation(n) if is_negated_numeric_literal(self.context, astref) => {
+                match self.context.get_node(&from_astref(astref, &n.expr)) {
+                    ast::Node::IntegerLiteral(n) => create_negated_integer_value(typeid, n.value),
+                    ast::Node::FloatLiteral(n) => create_float_value(typeid, -n.value),
+                    _ => None,
+                }
+            }
+            ast::Node::UnaryOperation(n) if n.optype == UnaryOperationType::BitNot => {
+                let operand =
+                    self.evaluate_expression_as_type(&from_astref(astref, &n.expr), typeid);
+                Some(self.perform_unaryoperation(&n.optype, &operand))
+            }
+            ast::Node::ArrayLiteral(n) => match typeid {
+                TypeId::Array { element, length: _ } if !n.dynamic => {
+                    Some(self.evaluate_arrayliteral_as_type(astref, n, element))
+                }
+                // Static literals are also accepted as initial contents of dynamic arrays
+                TypeId::DynamicArray { element } => {
+                    match self.evaluate_arrayliteral_as_type(astref, n, element) {
+                        Value::Array(array) => Some(self.state.allocate_array(array)),
+                        _ => unreachable!(),
+                    }
+                }
+                _ => None,
+            },
+            ast::Node::TupleLiteral(n) => match typeid {
+                TypeId::Tuple(elements) if elements.len() == n.elements.len() => {
+                    Some(Value::Tuple(
+                        n.elements
+                            .iter()
+                            .zip(elements)
+                            .map(|(e, t)| {
+                                self.evaluate_expression_as_type(&from_astref(astref, e), t)
+                                    .clone_or_move_inner(&self.state)
+                            })
+                            .collect(),
+                    ))
+                }
+                _ => None,
+            },
+            _ => None,
+        };
+
+        return value.unwrap_or_else(|| self.evaluate_expression(astref));
     }
 
     fn evaluate_booleanliteral(&mut self, intlit: &ast::nodes::BooleanLiteral) -> Value {
         return Value::Primitive(PrimitiveValue::Bool(Bool(intlit.value)));
     }
 
+    fn evaluate_characterliteral(&mut self, charlit: &ast::nodes::CharacterLiteral) -> Value {
+        return Value::Primitive(PrimitiveValue::Char(Char(charlit.value)));
+    }
+
     fn evaluate_stringliteral(&mut self, strlit: &ast::nodes::StringLiteral) -> Value {
         let id = self.state.strings.len() as u64;
         self.state.strings.push(strlit.text.clone());
@@ -613,11 +1170,22 @@ impl<'a> TreeWalker<'a> {
         match &builtin.object {
             ast::BuiltInObject::Function(o) => return create_builtin_function(o),
             ast::BuiltInObject::PrimitiveType(o) => return create_primitive_type(o),
+            ast::BuiltInObject::ResultType => {
+                return Value::Type(TypeId::Enum(EnumDefinition::new_generic_result()))
+            }
         };
     }
 
     fn evaluate_subscript(&mut self, astref: &AstRef, subscript: &ast::nodes::SubScript) -> Value {
         let exprvalue = self.evaluate_expression(&from_astref(&astref, &subscript.expr));
+        if self.state.has_returned() {
+            return create_null_value();
+        }
+
+        // Subscripting an enum type names one of its variants
+        if let Value::Type(TypeId::Enum(definition)) = exprvalue.get_inner_ref(&self.state) {
+            return self.evaluate_variant(astref, definition.clone(), &subscript.field);
+        }
 
         return match &exprvalue {
             Value::Null => panic!("Null value in subscript {:?}!", subscript),
@@ -645,7 +1213,9 @@ impl<'a> TreeWalker<'a> {
                             field: subscript.field.clone(),
                         }))
                     }
-                    ValueRef::SubscriptedValueRef(_) => {
+                    ValueRef::SubscriptedValueRef(_)
+                    | ValueRef::IndexedValueRef(_)
+                    | ValueRef::HeapElementRef(_) => {
                         // To support chained subscripts, store the inner subscripted ref
                         //  on the stack so we can reference it as a simple value ref
                         Value::ValueRef(ValueRef::SubscriptedValueRef(SubscriptedValueRef {
@@ -662,72 +1232,531 @@ impl<'a> TreeWalker<'a> {
         };
     }
 
-    fn evaluate_ifexpression(
+    // Variants without fields are values of the enum, the others construct one when called
+    fn evaluate_variant(
         &mut self,
         astref: &AstRef,
-        ifexpr: &ast::nodes::IfExpression,
+        definition: EnumDefinition,
+        symbol: &ast::SymbolRef,
     ) -> Value {
-        for branch in &ifexpr.branches {
-            let condition = branch.0;
-            let expr = branch.1;
+        assert!(
+            !definition.is_generic(),
+            "Generic enum requires type arguments: {}",
+            TypeId::Enum(definition).to_string()
+        );
 
-            let condvalue = self.evaluate_expression(&from_astref(&astref, &condition));
-            let boolvalue = match condvalue {
-                Value::Primitive(PrimitiveValue::Bool(n)) => Some(n.0),
-                _ => None,
-            };
+        let (variant, payload) = match definition.get_variant(symbol) {
+            Some((index, variant)) => (index, !variant.fields.is_empty()),
+            None => panic!(
+                "Enum has no variant {}",
+                self.context.get_ast(astref).get_symbol(symbol).unwrap()
+            ),
+        };
 
-            assert!(
-                boolvalue.is_some(),
-                "if conditional expression was not a bool value",
-            );
+        if payload {
+            Value::VariantConstructor(VariantConstructor {
+                definition,
+                variant,
+            })
+        } else {
+            Value::EnumInstance(EnumInstance {
+                definition,
+                variant,
+                fields: Vec::new(),
+            })
+        }
+    }
 
-            if boolvalue.unwrap() {
-                return self.evaluate_expression(&from_astref(&astref, &expr));
-            }
+    fn evaluate_arrayliteral(
+        &mut self,
+        astref: &AstRef,
+        aliteral: &ast::nodes::ArrayLiteral,
+    ) -> Value {
+        let mut elements = Vec::new();
+        for e in &aliteral.elements {
+            let value = self.evaluate_expression(&from_astref(astref, e));
+            elements.push(value.clone_or_move_inner(&self.state));
+        }
+        if self.state.has_returned() {
+            return create_null_value();
         }
 
-        if ifexpr.elsebranch.is_some() {
-            return self.evaluate_expression(&from_astref(&astref, &ifexpr.elsebranch.unwrap()));
+        // Without an expected type, the first element decides the element type
+        let element = match elements.first() {
+            Some(first) => first.get_type(&self.state),
+            None => panic!("Cannot infer the element type of an empty array literal"),
         };
 
-        return create_null_value();
-    }
+        for e in &elements {
+            assert_eq!(
+                e.get_type(&self.state),
+                element,
+                "Mismatching types for array elements"
+            );
+        }
 
-    fn evaluate_ifstatement(&mut self, astref: &AstRef, ifstmt: &ast::nodes::IfStatement) {
-        for branch in &ifstmt.branches {
-            let condition = branch.0;
-            let body = branch.1;
+        let array = ArrayInstance { element, elements };
 
-            let condvalue = self.evaluate_expression(&from_astref(&astref, &condition));
-            let boolvalue = match condvalue {
-                Value::Primitive(PrimitiveValue::Bool(n)) => Some(n.0),
-                _ => None,
-            };
+        if aliteral.dynamic {
+            return self.state.allocate_array(array);
+        }
 
-            assert!(
-                boolvalue.is_some(),
-                "if conditional expression was not a bool value",
-            );
+        return Value::Array(array);
+    }
 
-            if boolvalue.unwrap() {
-                let node = self.context.get_node(&from_astref(astref, &body));
-                match node {
-                    ast::Node::StatementBody(n) => {
-                        return self.evaluate_statementbody(astref, &n);
-                    }
-                    _ => {
-                        panic!("Expected statement body");
-                    }
-                }
+    fn evaluate_arrayliteral_as_type(
+        &mut self,
+        astref: &AstRef,
+        aliteral: &ast::nodes::ArrayLiteral,
+        element: &TypeId,
+    ) -> Value {
+        let mut elements = Vec::new();
+        for e in &aliteral.elements {
+            let value = self.evaluate_expression_as_type(&from_astref(astref, e), element);
+            if self.state.has_returned() {
+                break;
             }
+            assert_eq!(
+                &value.get_type(&self.state),
+                element,
+                "Mismatching types for array elements"
+            );
+            elements.push(value.clone_or_move_inner(&self.state));
         }
 
-        if ifstmt.elsebranch.is_some() {
-            match self
-                .context
-                .get_node(&from_astref(astref, &ifstmt.elsebranch.unwrap()))
-            {
+        return Value::Array(ArrayInstance {
+            element: element.clone(),
+            elements,
+        });
+    }
+
+    fn evaluate_indexexpression(
+        &mut self,
+        astref: &AstRef,
+        indexexpr: &ast::nodes::IndexExpression,
+    ) -> Value {
+        let exprvalue = self.evaluate_expression(&from_astref(&astref, &indexexpr.expr));
+
+        // Indexing with a range creates a slice
+        if let ast::Node::RangeExpression(range) = self
+            .context
+            .get_node(&from_astref(&astref, &indexexpr.index))
+        {
+            return self.evaluate_slice(astref, exprvalue, range, &indexexpr.location);
+        }
+
+        let indexvalue = self.evaluate_expression(&from_astref(&astref, &indexexpr.index));
+        if self.state.has_returned() {
+            return create_null_value();
+        }
+
+        let index = get_index_value(indexvalue.get_inner_ref(&self.state)).unwrap_or_else(|| {
+            panic!(
+                "Index must be an unsigned integer, got {:?} at {}",
+                indexvalue, indexexpr.location
+            )
+        });
+
+        let (length, heaparray) = match exprvalue.get_inner_ref(&self.state) {
+            // Indexing a type gives an array type, i.e. "u8[4]"
+            Value::Type(t) => return Value::Type(TypeId::new_array(t.clone(), index)),
+            Value::Array(array) => (array.elements.len() as u64, None),
+            Value::DynamicArray(handle) => (
+                self.state.heap[*handle].elements.len() as u64,
+                Some(*handle),
+            ),
+            Value::Slice(slice) => {
+                if index >= slice.length as u64 {
+                    panic!(
+                        "Index {} out of bounds for slice of length {} at {}",
+                        index, slice.length, indexexpr.location
+                    );
+                }
+
+                return Value::ValueRef(get_slice_element_ref(slice, index as usize));
+            }
+            n => panic!("Cannot index value {:?} at {}", n, indexexpr.location),
+        };
+
+        if index >= length {
+            panic!(
+                "Index {} out of bounds for array of length {} at {}",
+                index, length, indexexpr.location
+            );
+        }
+
+        let index = index as usize;
+
+        // Heap elements can always be referenced directly
+        if let Some(array) = heaparray {
+            return Value::ValueRef(ValueRef::HeapElementRef(HeapElementRef { array, index }));
+        }
+
+        return match &exprvalue {
+            Value::Array(array) => {
+                // Not a ref, thus an r-value, like an array literal
+                array.elements[index].clone()
+            }
+            Value::ValueRef(ValueRef::SimpleValueRef(vref)) => {
+                Value::ValueRef(ValueRef::IndexedValueRef(IndexedValueRef {
+                    vref: vref.clone(),
+                    index,
+                }))
+            }
+            Value::ValueRef(_) => {
+                // Store the inner ref on the stack so it can be referenced as a simple value ref,
+                //  same as for chained subscripts
+                Value::ValueRef(ValueRef::IndexedValueRef(IndexedValueRef {
+                    vref: create_stackframe_ref_from_value(
+                        self.state.stackframes.last_mut().unwrap(),
+                        exprvalue,
+                    ),
+                    index,
+                }))
+            }
+            _ => unreachable!(),
+        };
+    }
+
+    fn evaluate_slice(
+        &mut self,
+        astref: &AstRef,
+        exprvalue: Value,
+        range: &ast::nodes::RangeExpression,
+        location: &SourceLocation,
+    ) -> Value {
+        let startvalue = self.evaluate_expression(&from_astref(&astref, &range.start));
+        let endvalue = self.evaluate_expression(&from_astref(&astref, &range.end));
+        if self.state.has_returned() {
+            return create_null_value();
+        }
+
+        let get_bound = |value: &Value| {
+            get_index_value(value.get_inner_ref(&self.state)).unwrap_or_else(|| {
+                panic!(
+                    "Slice bounds must be unsigned integers, got {:?} at {}",
+                    value, location
+                )
+            }) as usize
+        };
+        let start = get_bound(&startvalue);
+        let end = get_bound(&endvalue);
+
+        let (element, length, source, offset) = match exprvalue.get_inner_ref(&self.state) {
+            Value::Array(array) => (array.element.clone(), array.elements.len(), None, 0),
+            Value::DynamicArray(handle) => {
+                let array = &self.state.heap[*handle];
+                (
+                    array.element.clone(),
+                    array.elements.len(),
+                    Some(SliceSource::Heap(*handle)),
+                    0,
+                )
+            }
+            // Slices of slices view the same array
+            Value::Slice(slice) => (
+                slice.element.clone(),
+                slice.length,
+                Some(slice.source.clone()),
+                slice.start,
+            ),
+            n => panic!("Cannot slice value {:?} at {}", n, location),
+        };
+
+        let source = match (source, &exprvalue) {
+            (Some(source), _) => source,
+            (None, Value::ValueRef(ValueRef::SimpleValueRef(vref))) => {
+                SliceSource::Array(vref.clone())
+            }
+            // R-values and other refs are kept on the stack, same as for indexing
+            (None, _) => SliceSource::Array(create_stackframe_ref_from_value(
+                self.state.stackframes.last_mut().unwrap(),
+                exprvalue,
+            )),
+        };
+
+        if start > end || end > length {
+            panic!(
+                "Slice {}..{} out of bounds for length {} at {}",
+                start, end, length, location
+            );
+        }
+
+        return Value::Slice(SliceInstance {
+            element,
+            source,
+            start: offset + start,
+            length: end - start,
+        });
+    }
+
+    // A tuple of types is a tuple type, anything else is a tuple value
+    fn evaluate_tupleliteral(
+        &mut self,
+        astref: &AstRef,
+        tliteral: &ast::nodes::TupleLiteral,
+    ) -> Value {
+        let elements: Vec<Value> = tliteral
+            .elements
+            .iter()
+            .map(|e| {
+                self.evaluate_expression(&from_astref(astref, e))
+                    .clone_or_move_inner(&self.state)
+            })
+            .collect();
+
+        if elements.iter().all(|e| matches!(e, Value::Type(_))) {
+            return Value::Type(TypeId::Tuple(
+                elements
+                    .into_iter()
+                    .map(|e| match e {
+                        Value::Type(t) => t,
+                        _ => unreachable!(),
+                    })
+                    .collect(),
+            ));
+        }
+
+        return Value::Tuple(elements);
+    }
+
+    fn evaluate_slicetype(&mut self, astref: &AstRef, typeexpr: &ast::nodes::SliceType) -> Value {
+        let elementvalue = self.evaluate_expression(&from_astref(&astref, &typeexpr.expr));
+
+        return match elementvalue.get_inner_ref(&self.state) {
+            Value::Type(t) => Value::Type(TypeId::new_slice(t.clone())),
+            n => panic!("Slice element is not a type: {:?}", n),
+        };
+    }
+
+    fn evaluate_optionaltype(
+        &mut self,
+        astref: &AstRef,
+        typeexpr: &ast::nodes::OptionalType,
+    ) -> Value {
+        let targetvalue = self.evaluate_expression(&from_astref(&astref, &typeexpr.expr));
+
+        return match targetvalue.get_inner_ref(&self.state) {
+            Value::Type(t) => Value::Type(TypeId::new_optional(t.clone())),
+            n => panic!("Optional target is not a type: {:?}", n),
+        };
+    }
+
+    // Referencing a type gives the reference type, other references point at the storage
+    //  of the value
+    fn evaluate_referenceexpression(
+        &mut self,
+        astref: &AstRef,
+        reference: &ast::nodes::ReferenceExpression,
+    ) -> Value {
+        let value = self.evaluate_expression(&from_astref(&astref, &reference.expr));
+        if self.state.has_returned() {
+            return create_null_value();
+        }
+
+        if let Value::Type(t) = value.get_inner_ref(&self.state) {
+            return Value::Type(TypeId::new_reference(t.clone()));
+        }
+
+        return match value {
+            Value::ValueRef(vref) => Value::Reference(self.state.find_ref_to_leaf_value(&vref)),
+            n => panic!("Only variables can be referenced, got {:?}", n),
+        };
+    }
+
+    // Dereferenced values can be assigned to, like variables
+    fn evaluate_dereferenceexpression(
+        &mut self,
+        astref: &AstRef,
+        dereference: &ast::nodes::DereferenceExpression,
+    ) -> Value {
+        let value = self.evaluate_expression(&from_astref(&astref, &dereference.expr));
+        if self.state.has_returned() {
+            return create_null_value();
+        }
+
+        return match value.get_inner_ref(&self.state) {
+            Value::Reference(vref) => Value::ValueRef(vref.clone()),
+            Value::Null => panic!("Cannot dereference null"),
+            n => panic!("Cannot dereference non-reference value {:?}", n),
+        };
+    }
+
+    fn evaluate_tryexpression(
+        &mut self,
+        astref: &AstRef,
+        tryexpr: &ast::nodes::TryExpression,
+    ) -> Value {
+        let value = self
+            .evaluate_expression(&from_astref(&astref, &tryexpr.expr))
+            .clone_or_move_inner(&self.state);
+        if self.state.has_returned() {
+            return create_null_value();
+        }
+
+        let mut instance = match value {
+            Value::EnumInstance(n) if n.definition.get_result_types().is_some() => n,
+            n => panic!("Only results can be tried, got {:?}", n),
+        };
+        let (okindex, _) = instance
+            .definition
+            .get_variant(&StringKey::from_str("ok"))
+            .unwrap();
+        if instance.variant == okindex {
+            return instance.fields.remove(0);
+        }
+
+        // The error is returned from the function as its own result type
+        let frame = self.state.stackframes.last_mut().unwrap();
+        let output = match frame.outputparams.as_slice() {
+            [TypeId::Enum(n)] if n.get_result_types().is_some() => n.clone(),
+            _ => panic!("Try can only be used in functions returning a result"),
+        };
+        let (errorindex, _) = output.get_variant(&StringKey::from_str("error")).unwrap();
+        frame.returnvalue = Some(Value::EnumInstance(EnumInstance {
+            definition: output,
+            variant: errorindex,
+            fields: instance.fields,
+        }));
+        frame.returned = true;
+
+        return create_null_value();
+    }
+
+    fn evaluate_functiontype(
+        &mut self,
+        astref: &AstRef,
+        typeexpr: &ast::nodes::FunctionType,
+    ) -> Value {
+        let mut signature = FunctionSignature::new_simple();
+
+        for inparam in &typeexpr.inputparams {
+            let ast = self.context.get_ast(&astref);
+            let n = as_node!(ast, InputParameter, inparam);
+            let symbol = n.symbol.clone();
+
+            let typeval = self.evaluate_expression(&from_astref(&astref, &n.typeexpr));
+            match typeval.get_inner_ref(&self.state) {
+                Value::Type(t) => signature.inputparams.push((symbol, t.clone())),
+                n => panic!("Function parameter is not a type: {:?}", n),
+            }
+        }
+
+        for outparam in &typeexpr.outputparams {
+            let ast = self.context.get_ast(&astref);
+            let n = as_node!(ast, OutputParameter, outparam);
+
+            let typeval = self.evaluate_expression(&from_astref(&astref, &n.typeexpr));
+            match typeval.get_inner_ref(&self.state) {
+                Value::Type(t) => signature.outputparams.push(t.clone()),
+                n => panic!("Function output parameter is not a type: {:?}", n),
+            }
+        }
+
+        return Value::Type(TypeId::Function(signature));
+    }
+
+    // Within a call of a generic function the bound type, otherwise the parameter itself
+    fn evaluate_typeparameter(
+        &mut self,
+        astref: &AstRef,
+        typeparam: &ast::nodes::TypeParameter,
+    ) -> Value {
+        let ast = self.context.get_ast(&astref);
+        let symbol = ast.get_symbol(&typeparam.symbol).unwrap();
+
+        let frame = self.state.stackframes.last().unwrap();
+        return match frame.typearguments.get(symbol) {
+            Some(t) => Value::Type(t.clone()),
+            None => Value::Type(TypeId::TypeParameter(symbol.clone())),
+        };
+    }
+
+    fn evaluate_dynamicarraytype(
+        &mut self,
+        astref: &AstRef,
+        typeexpr: &ast::nodes::DynamicArrayType,
+    ) -> Value {
+        let elementvalue = self.evaluate_expression(&from_astref(&astref, &typeexpr.expr));
+
+        return match elementvalue.get_inner_ref(&self.state) {
+            Value::Type(t) => Value::Type(TypeId::new_dynamic_array(t.clone())),
+            n => panic!("Dynamic array element is not a type: {:?}", n),
+        };
+    }
+
+    fn evaluate_ifexpression(
+        &mut self,
+        astref: &AstRef,
+        ifexpr: &ast::nodes::IfExpression,
+    ) -> Value {
+        for branch in &ifexpr.branches {
+            let condition = branch.0;
+            let expr = branch.1;
+
+            let condvalue = self.evaluate_expression(&from_astref(&astref, &condition));
+            if self.state.has_returned() {
+                return create_null_value();
+            }
+            let boolvalue = match condvalue.get_inner_ref(&self.state) {
+                Value::Primitive(PrimitiveValue::Bool(n)) => Some(n.0),
+                _ => None,
+            };
+
+            assert!(
+                boolvalue.is_some(),
+                "if conditional expression was not a bool value",
+            );
+
+            if boolvalue.unwrap() {
+                return self.evaluate_expression(&from_astref(&astref, &expr));
+            }
+        }
+
+        if ifexpr.elsebranch.is_some() {
+            return self.evaluate_expression(&from_astref(&astref, &ifexpr.elsebranch.unwrap()));
+        };
+
+        return create_null_value();
+    }
+
+    fn evaluate_ifstatement(&mut self, astref: &AstRef, ifstmt: &ast::nodes::IfStatement) {
+        for branch in &ifstmt.branches {
+            let condition = branch.0;
+            let body = branch.1;
+
+            let condvalue = self.evaluate_expression(&from_astref(&astref, &condition));
+            if self.state.has_returned() {
+                return;
+            }
+            let boolvalue = match condvalue.get_inner_ref(&self.state) {
+                Value::Primitive(PrimitiveValue::Bool(n)) => Some(n.0),
+                _ => None,
+            };
+
+            assert!(
+                boolvalue.is_some(),
+                "if conditional expression was not a bool value",
+            );
+
+            if boolvalue.unwrap() {
+                let node = self.context.get_node(&from_astref(astref, &body));
+                match node {
+                    ast::Node::StatementBody(n) => {
+                        return self.evaluate_statementbody(astref, &n);
+                    }
+                    _ => {
+                        panic!("Expected statement body");
+                    }
+                }
+            }
+        }
+
+        if ifstmt.elsebranch.is_some() {
+            match self
+                .context
+                .get_node(&from_astref(astref, &ifstmt.elsebranch.unwrap()))
+            {
                 ast::Node::StatementBody(n) => {
                     self.evaluate_statementbody(astref, &n);
                 }
@@ -738,11 +1767,190 @@ impl<'a> TreeWalker<'a> {
         }
     }
 
+    fn evaluate_whilestatement(&mut self, astref: &AstRef, whilestmt: &ast::nodes::WhileStatement) {
+        let ast = self.context.get_ast(astref);
+        let body = as_node!(ast, StatementBody, &whilestmt.body);
+
+        loop {
+            let condvalue = self.evaluate_expression(&from_astref(&astref, &whilestmt.condition));
+            if self.state.has_returned() {
+                break;
+            }
+            let condition = match condvalue.get_inner_ref(&self.state) {
+                Value::Primitive(PrimitiveValue::Bool(n)) => n.0,
+                _ => panic!("while conditional expression was not a bool value"),
+            };
+
+            if !condition {
+                break;
+            }
+
+            self.evaluate_blockscoped_statementbody(astref, body);
+
+            match self.state.loop_control.take() {
+                Some(LoopControl::Break) => break,
+                Some(LoopControl::Continue) | None => (),
+            }
+            if self.state.has_returned() {
+                break;
+            }
+        }
+    }
+
+    fn evaluate_forstatement(&mut self, astref: &AstRef, forstmt: &ast::nodes::ForStatement) {
+        let ast = self.context.get_ast(astref);
+        let body = as_node!(ast, StatementBody, &forstmt.body);
+        let range = match ast.get_node(&forstmt.iterable) {
+            ast::Node::RangeExpression(n) => n,
+            _ => return self.evaluate_forstatement_over_array(astref, forstmt, body),
+        };
+
+        // Both bounds are evaluated once, before entering the loop
+        let (start, end) = self.evaluate_operands(
+            &from_astref(astref, &range.start),
+            &from_astref(astref, &range.end),
+        );
+        if self.state.has_returned() {
+            return;
+        }
+        let mut counter = start.clone_or_move_inner(&self.state);
+        let end = end.clone_or_move_inner(&self.state);
+
+        let one = match &counter {
+            Value::Primitive(
+                PrimitiveValue::U8(_)
+                | PrimitiveValue::U16(_)
+                | PrimitiveValue::U32(_)
+                | PrimitiveValue::U64(_)
+                | PrimitiveValue::S8(_)
+                | PrimitiveValue::S16(_)
+                | PrimitiveValue::S32(_)
+                | PrimitiveValue::S64(_),
+            ) => create_integer_value(&counter.get_type(&self.state), 1).unwrap(),
+            _ => panic!("Range bounds must be integers, got {:?}", counter),
+        };
+
+        loop {
+            let condition =
+                self.perform_binaryoperation(&BinaryOperationType::LessThan, &counter, &end);
+            if condition != Value::Primitive(PrimitiveValue::Bool(Bool(true))) {
+                break;
+            }
+
+            // The loop symbol only lives in the body, and holds a copy of the counter
+            //  so assigning to it does not affect the iteration
+            let checkpoint = self.state.get_current_variables().checkpoint();
+            self.state
+                .get_current_variables_mut()
+                .add_with_symbol(forstmt.symbol.clone(), counter.clone());
+            self.evaluate_statementbody(astref, body);
+            self.state.get_current_variables_mut().restore(checkpoint);
+
+            if self.state.loop_control.take() == Some(LoopControl::Break)
+                || self.state.has_returned()
+            {
+                break;
+            }
+
+            counter = self.perform_binaryoperation(&BinaryOperationType::Add, &counter, &one);
+        }
+    }
+
+    // The array is evaluated once, its length is checked before every iteration so
+    //  the loop stops early if a dynamic array shrinks
+    fn evaluate_forstatement_over_array(
+        &mut self,
+        astref: &AstRef,
+        forstmt: &ast::nodes::ForStatement,
+        body: &ast::nodes::StatementBody,
+    ) {
+        let array = self.evaluate_expression(&from_astref(astref, &forstmt.iterable));
+        if self.state.has_returned() {
+            return;
+        }
+
+        let mut index = 0;
+        loop {
+            let element = match array.get_inner_ref(&self.state) {
+                Value::Array(array) => array.elements.get(index).cloned(),
+                Value::DynamicArray(handle) => {
+                    self.state.heap[*handle].elements.get(index).cloned()
+                }
+                Value::Slice(slice) => (index < slice.length).then(|| {
+                    Value::ValueRef(get_slice_element_ref(slice, index))
+                        .clone_or_move_inner(&self.state)
+                }),
+                n => panic!("Cannot iterate over {:?}", n),
+            };
+            let element = match element {
+                Some(element) => element,
+                None => break,
+            };
+
+            let checkpoint = self.state.get_current_variables().checkpoint();
+            self.state
+                .get_current_variables_mut()
+                .add_with_symbol(forstmt.symbol.clone(), element);
+            self.evaluate_statementbody(astref, body);
+            self.state.get_current_variables_mut().restore(checkpoint);
+
+            if self.state.loop_control.take() == Some(LoopControl::Break)
+                || self.state.has_returned()
+            {
+                break;
+            }
+
+            index += 1;
+        }
+    }
+
     fn evaluate_returnstatement(&mut self, astref: &AstRef, retstmt: &ast::nodes::ReturnStatement) {
-        self.state.stackframes.last_mut().unwrap().returnvalue = match retstmt.expr {
-            Some(expr) => Some(self.evaluate_expression(&from_astref(&astref, &expr))),
-            _ => None,
+        let outputparams = self.state.stackframes.last().unwrap().outputparams.clone();
+
+        // Copied out, since refs to the frame are invalid once it is popped
+        let mut evaluate_returned = |expr: &ast::NodeRef, typeid: Option<&TypeId>| {
+            let exprref = from_astref(&astref, expr);
+            match typeid {
+                Some(typeid) => self.evaluate_expression_as_type(&exprref, typeid),
+                None => self.evaluate_expression(&exprref),
+            }
+            .clone_or_move_inner(&self.state)
         };
+
+        // Several returned values are gathered into a tuple, like a single returned tuple expression
+        let returnvalue = match retstmt.exprs.as_slice() {
+            [] => None,
+            [expr] => {
+                let expected = match outputparams.len() {
+                    0 => None,
+                    1 => Some(outputparams[0].clone()),
+                    _ => Some(TypeId::Tuple(outputparams)),
+                };
+                Some(evaluate_returned(expr, expected.as_ref()))
+            }
+            exprs => {
+                assert_eq!(
+                    exprs.len(),
+                    outputparams.len(),
+                    "Mismatching number of returned values"
+                );
+                Some(Value::Tuple(
+                    exprs
+                        .iter()
+                        .zip(&outputparams)
+                        .map(|(e, t)| evaluate_returned(e, Some(t)))
+                        .collect(),
+                ))
+            }
+        };
+        // A try in the returned expressions has already returned its error
+        if self.state.has_returned() {
+            return;
+        }
+
+        let frame = self.state.stackframes.last_mut().unwrap();
+        frame.returnvalue = returnvalue;
+        frame.returned = true;
     }
 
     fn evaluate_assignstatement(
@@ -751,13 +1959,21 @@ impl<'a> TreeWalker<'a> {
         assignstmt: &ast::nodes::AssignStatement,
     ) {
         let mut lhs = self.evaluate_expression(&from_astref(&astref, &assignstmt.lhs));
-        let rhs = self.evaluate_expression(&from_astref(&astref, &assignstmt.rhs));
+        let lhstype = lhs.get_type(&self.state);
+        let rhs =
+            self.evaluate_expression_as_type(&from_astref(&astref, &assignstmt.rhs), &lhstype);
+        if self.state.has_returned() {
+            return;
+        }
 
-        assert_eq!(
-            lhs.get_type(&self.state),
-            rhs.get_type(&self.state),
-            "Mismatching types for assignment",
-        );
+        // Optionals hold either null or a plain value, values do not know their optional type
+        if !matches!(lhstype, TypeId::Null) && !matches!(rhs.get_type(&self.state), TypeId::Null) {
+            assert_eq!(
+                lhs.get_type(&self.state),
+                rhs.get_type(&self.state),
+                "Mismatching types for assignment",
+            );
+        }
 
         let rvalue = rhs.clone_or_move_inner(&self.state);
         let lvalue = lhs.get_inner_ref_mut(&mut self.state);
@@ -765,47 +1981,348 @@ impl<'a> TreeWalker<'a> {
         *lvalue = rvalue;
     }
 
-    fn evaluate_binaryoperation(
+    fn evaluate_expression_as_bool(&mut self, astref: &AstRef) -> bool {
+        let value = self.evaluate_expression(astref);
+        if self.state.has_returned() {
+            return false;
+        }
+        return match value.get_inner_ref(&self.state) {
+            Value::Primitive(PrimitiveValue::Bool(n)) => n.0,
+            _ => panic!("Expected bool value, got {:?}", value),
+        };
+    }
+
+    fn evaluate_logicaloperation(
         &mut self,
         astref: &AstRef,
         binop: &ast::nodes::BinaryOperation,
     ) -> Value {
+        let lhs = self.evaluate_expression_as_bool(&from_astref(&astref, &binop.lhs));
+
+        // Only evaluate rhs if lhs does not already decide the result
+        let result = match binop.optype {
+            BinaryOperationType::And => {
+                lhs && self.evaluate_expression_as_bool(&from_astref(&astref, &binop.rhs))
+            }
+            BinaryOperationType::Or => {
+                lhs || self.evaluate_expression_as_bool(&from_astref(&astref, &binop.rhs))
+            }
+            _ => panic!("{:?} is not a logical operation", binop.optype),
+        };
+
+        return Value::Primitive(PrimitiveValue::Bool(Bool(result)));
+    }
+
+    fn evaluate_unaryoperation(
+        &mut self,
+        astref: &AstRef,
+        unop: &ast::nodes::UnaryOperation,
+    ) -> Value {
+        let exprref = from_astref(&astref, &unop.expr);
+
+        if unop.optype == UnaryOperationType::Not {
+            return Value::Primitive(PrimitiveValue::Bool(Bool(
+                !self.evaluate_expression_as_bool(&exprref),
+            )));
+        }
+
+        // Without an expected type, negated integer literals default to s32
+        if is_negated_numeric_literal(self.context, astref) {
+            if let ast::Node::IntegerLiteral(n) = self.context.get_node(&exprref) {
+                return create_negated_integer_value(
+                    &TypeId::Primitive(PrimitiveType::S32),
+                    n.value,
+                )
+                .unwrap();
+            }
+        }
+
+        let value = self.evaluate_expression(&exprref);
+        return self.perform_unaryoperation(&unop.optype, &value);
+    }
+
+    fn perform_unaryoperation(&self, optype: &UnaryOperationType, value: &Value) -> Value {
+        if self.state.has_returned() {
+            return create_null_value();
+        }
+        return match value.get_inner_ref(&self.state) {
+            Value::Primitive(v) => match v {
+                PrimitiveValue::U8(v) => perform_unop(optype, v),
+                PrimitiveValue::U16(v) => perform_unop(optype, v),
+                PrimitiveValue::U32(v) => perform_unop(optype, v),
+                PrimitiveValue::U64(v) => perform_unop(optype, v),
+                PrimitiveValue::S8(v) => perform_unop(optype, v),
+                PrimitiveValue::S16(v) => perform_unop(optype, v),
+                PrimitiveValue::S32(v) => perform_unop(optype, v),
+                PrimitiveValue::S64(v) => perform_unop(optype, v),
+                PrimitiveValue::F32(v) => perform_unop(optype, v),
+                PrimitiveValue::F64(v) => perform_unop(optype, v),
+                _ => panic!("Unary operation {:?} not supported for {:?}", optype, value),
+            },
+            _ => panic!("Unary operation {:?} not supported for {:?}", optype, value),
+        };
+    }
+
+    fn evaluate_shiftoperation(
+        &mut self,
+        astref: &AstRef,
+        binop: &ast::nodes::BinaryOperation,
+    ) -> Value {
+        // Operands are not unified, the shifted value decides the type of the result
         let lhsval = self.evaluate_expression(&from_astref(&astref, &binop.lhs));
         let rhsval = self.evaluate_expression(&from_astref(&astref, &binop.rhs));
+        if self.state.has_returned() {
+            return create_null_value();
+        }
+
+        let amount = match rhsval.get_inner_ref(&self.state) {
+            Value::Primitive(PrimitiveValue::U8(n)) => n.0 as u64,
+            Value::Primitive(PrimitiveValue::U16(n)) => n.0 as u64,
+            Value::Primitive(PrimitiveValue::U32(n)) => n.0 as u64,
+            Value::Primitive(PrimitiveValue::U64(n)) => n.0,
+            n => panic!("Shift amount must be an unsigned integer, got {:?}", n),
+        };
+
+        return match lhsval.get_inner_ref(&self.state) {
+            Value::Primitive(v) => match v {
+                PrimitiveValue::U8(v) => v.perform_shift(&binop.optype, amount),
+                PrimitiveValue::U16(v) => v.perform_shift(&binop.optype, amount),
+                PrimitiveValue::U32(v) => v.perform_shift(&binop.optype, amount),
+                PrimitiveValue::U64(v) => v.perform_shift(&binop.optype, amount),
+                PrimitiveValue::S8(v) => v.perform_shift(&binop.optype, amount),
+                PrimitiveValue::S16(v) => v.perform_shift(&binop.optype, amount),
+                PrimitiveValue::S32(v) => v.perform_shift(&binop.optype, amount),
+                PrimitiveValue::S64(v) => v.perform_shift(&binop.optype, amount),
+                v => panic!(
+                    "Binary operation {:?} not supported for {:?}",
+                    binop.optype, v
+                ),
+            },
+            v => panic!(
+                "Binary operation {:?} not supported for {:?}",
+                binop.optype, v
+            ),
+        };
+    }
+
+    fn evaluate_binaryoperation(
+        &mut self,
+        astref: &AstRef,
+        binop: &ast::nodes::BinaryOperation,
+    ) -> Value {
+        match binop.optype {
+            BinaryOperationType::And | BinaryOperationType::Or => {
+                return self.evaluate_logicaloperation(astref, binop)
+            }
+            BinaryOperationType::ShiftLeft | BinaryOperationType::ShiftRight => {
+                return self.evaluate_shiftoperation(astref, binop)
+            }
+            _ => (),
+        }
+
+        let lhsref = from_astref(&astref, &binop.lhs);
+        let rhsref = from_astref(&astref, &binop.rhs);
+        let (lhsval, rhsval) = self.evaluate_operands(&lhsref, &rhsref);
+
+        return self.perform_binaryoperation(&binop.optype, &lhsval, &rhsval);
+    }
 
+    // Evaluates both operands of an operation that requires them to be of the same type
+    fn evaluate_operands(&mut self, lhsref: &AstRef, rhsref: &AstRef) -> (Value, Value) {
+        // Let a literal operand take the type of the other side,
+        //  negated literals are signed, so they decide over plain literals
+        let typedness = |r: &AstRef| {
+            if is_numeric_literal(self.context.get_node(r)) {
+                0
+            } else if is_negated_numeric_literal(self.context, r) {
+                1
+            } else {
+                2
+            }
+        };
+        let (lhsval, rhsval) = if typedness(rhsref) > typedness(lhsref) {
+            let rhsval = self.evaluate_expression(rhsref);
+            let rhstype = rhsval.get_type(&self.state);
+            (self.evaluate_expression_as_type(lhsref, &rhstype), rhsval)
+        } else {
+            let lhsval = self.evaluate_expression(lhsref);
+            let lhstype = lhsval.get_type(&self.state);
+            (lhsval, self.evaluate_expression_as_type(rhsref, &lhstype))
+        };
+
+        // Optionals are compared to null
+        let nullable = |v: &Value| matches!(v.get_inner_ref(&self.state), Value::Null);
         assert!(
-            lhsval.match_type(&rhsval, &self.state),
+            nullable(&lhsval) || nullable(&rhsval) || lhsval.match_type(&rhsval, &self.state),
             "Mismatching types! {:?} vs {:?}",
             lhsval,
             rhsval
         );
 
+        return (lhsval, rhsval);
+    }
+
+    fn perform_binaryoperation(
+        &self,
+        optype: &BinaryOperationType,
+        lhsval: &Value,
+        rhsval: &Value,
+    ) -> Value {
+        if self.state.has_returned() {
+            return create_null_value();
+        }
         return match (
             lhsval.get_inner_ref(&self.state),
             rhsval.get_inner_ref(&self.state),
         ) {
             (Value::Primitive(l), Value::Primitive(r)) => match (l, r) {
-                (PrimitiveValue::U32(l2), PrimitiveValue::U32(r2)) => {
-                    perform_binop(&binop.optype, l2, r2)
-                }
-                (PrimitiveValue::U32(_), _) => unreachable!(),
-
-                // TODO: Remove
-                _ => unreachable!(),
+                (PrimitiveValue::Char(l), PrimitiveValue::Char(r)) => perform_binop(optype, l, r),
+                (PrimitiveValue::U8(l), PrimitiveValue::U8(r)) => perform_binop(optype, l, r),
+                (PrimitiveValue::U16(l), PrimitiveValue::U16(r)) => perform_binop(optype, l, r),
+                (PrimitiveValue::U32(l), PrimitiveValue::U32(r)) => perform_binop(optype, l, r),
+                (PrimitiveValue::U64(l), PrimitiveValue::U64(r)) => perform_binop(optype, l, r),
+                (PrimitiveValue::S8(l), PrimitiveValue::S8(r)) => perform_binop(optype, l, r),
+                (PrimitiveValue::S16(l), PrimitiveValue::S16(r)) => perform_binop(optype, l, r),
+                (PrimitiveValue::S32(l), PrimitiveValue::S32(r)) => perform_binop(optype, l, r),
+                (PrimitiveValue::S64(l), PrimitiveValue::S64(r)) => perform_binop(optype, l, r),
+                (PrimitiveValue::F32(l), PrimitiveValue::F32(r)) => perform_binop(optype, l, r),
+                (PrimitiveValue::F64(l), PrimitiveValue::F64(r)) => perform_binop(optype, l, r),
+                _ => panic!(
+                    "Binary operation {:?} not supported for {:?}",
+                    optype, lhsval
+                ),
             },
+            // Null is only equal to null
+            (l @ Value::Null, r) | (l, r @ Value::Null)
+                if matches!(
+                    optype,
+                    BinaryOperationType::Equals | BinaryOperationType::NotEquals
+                ) =>
+            {
+                let equal = matches!((l, r), (Value::Null, Value::Null));
+                Value::Primitive(PrimitiveValue::Bool(Bool(
+                    equal == (*optype == BinaryOperationType::Equals),
+                )))
+            }
             _ => panic!(
                 "Binary operation {:?} not supported for {:?}",
-                binop.optype, lhsval
+                optype, lhsval
             ),
         };
     }
 
+    // Places named arguments at their parameters and fills in default arguments
+    fn bind_call_arguments(
+        &mut self,
+        callable: &Value,
+        args: Vec<Value>,
+        namedargs: Vec<(SymbolRef, AstRef)>,
+    ) -> Vec<Value> {
+        let (module, inputparams, defaults) = match callable.get_inner_ref(&self.state) {
+            Value::Function(fref) => {
+                let function = &self.state.get_module(&fref.module).functions[fref.index as usize];
+                (
+                    function.module.clone(),
+                    function.signature.inputparams.clone(),
+                    function.defaults.clone(),
+                )
+            }
+            _ => {
+                assert!(
+                    namedargs.is_empty(),
+                    "Built-in functions do not take named arguments"
+                );
+                return args;
+            }
+        };
+
+        assert!(
+            args.len() <= inputparams.len(),
+            "Function takes {} arguments, got {}",
+            inputparams.len(),
+            args.len()
+        );
+
+        let mut bound: Vec<Option<Value>> = args.into_iter().map(Some).collect();
+        bound.resize_with(inputparams.len(), || None);
+
+        for (symbol, exprref) in namedargs {
+            let index = inputparams
+                .iter()
+                .position(|p| p.0 == symbol)
+                .unwrap_or_else(|| panic!("Unknown parameter {} in call", symbol));
+            assert!(
+                bound[index].is_none(),
+                "Parameter {} is given more than once",
+                symbol
+            );
+            bound[index] = Some(self.evaluate_expression_as_type(&exprref, &inputparams[index].1));
+        }
+
+        // Defaults are evaluated in an empty frame, in the module of the function
+        let old_module = self.state.current_module.replace(module);
+        self.state.stackframes.push(StackFrame {
+            index: self.state.stackframes.len(),
+            variables: VariableEnvironment::new(),
+            returnvalue: None,
+            returned: false,
+            outputparams: Vec::new(),
+            definingframe: None,
+            typearguments: HashMap::new(),
+        });
+        let args = bound
+            .into_iter()
+            .enumerate()
+            .map(|(i, arg)| match arg {
+                Some(arg) => arg,
+                None => {
+                    let exprref = defaults[i].unwrap_or_else(|| {
+                        panic!("Missing argument for parameter {}", inputparams[i].0)
+                    });
+                    self.evaluate_expression_as_type(&exprref, &inputparams[i].1)
+                }
+            })
+            .collect();
+        self.state.stackframes.pop();
+        self.state.current_module = old_module;
+
+        args
+    }
+
     fn evaluate_calloperation(
         &mut self,
         astref: &AstRef,
         callop: &ast::nodes::CallOperation,
     ) -> Value {
         let callable = self.evaluate_expression(&from_astref(&astref, &callop.expr));
+        if self.state.has_returned() {
+            return create_null_value();
+        }
+
+        // Calling a generic struct or enum instantiates it with the type arguments
+        match callable.get_inner_ref(&self.state) {
+            Value::Type(TypeId::Struct(definition)) => {
+                let definition = definition.clone();
+                let arguments = self.evaluate_type_arguments(astref, callop);
+                let instance = definition
+                    .instantiate(&arguments)
+                    .unwrap_or_else(|e| panic!("{}", e));
+                return Value::Type(TypeId::Struct(instance));
+            }
+            Value::Type(TypeId::Enum(definition)) => {
+                let definition = definition.clone();
+                let arguments = self.evaluate_type_arguments(astref, callop);
+                let instance = definition
+                    .instantiate(&arguments)
+                    .unwrap_or_else(|e| panic!("{}", e));
+                return Value::Type(TypeId::Enum(instance));
+            }
+            Value::VariantConstructor(constructor) => {
+                let constructor = constructor.clone();
+                return self.evaluate_variant_construction(astref, callop, constructor);
+            }
+            _ => (),
+        }
 
         /*println!(
             "Calling: {:?}...",
@@ -819,8 +2336,63 @@ impl<'a> TreeWalker<'a> {
         let ast = self.context.get_ast(&astref);
         let arglist = as_node!(ast, ArgumentList, &callop.arglist);
         let mut args = Vec::new();
+        let mut namedargs = Vec::new();
+        let paramtypes = match callable.get_inner_ref(&self.state) {
+            Value::Function(fref) => self.state.get_module(&fref.module).functions
+                [fref.index as usize]
+                .signature
+                .inputparams
+                .iter()
+                .map(|p| p.1.clone())
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        // Numeric literals passed for unbound type parameters take the types bound by
+        //  the other arguments, so they are evaluated last
+        let mut typearguments = HashMap::new();
+        let mut literals = Vec::new();
+
         for arg in &arglist.args {
-            let val = self.evaluate_expression(&from_astref(&astref, &arg));
+            if self.state.has_returned() {
+                return create_null_value();
+            }
+
+            // Named arguments are bound after all positional arguments
+            if let ast::Node::NamedArgument(n) = ast.get_node(arg) {
+                namedargs.push((n.symbol.clone(), from_astref(&astref, &n.expr)));
+                continue;
+            }
+
+            let argref = from_astref(&astref, &arg);
+            let paramtype: Option<&TypeId> = paramtypes.get(args.len());
+            if let Some(t) = paramtype.filter(|t| !t.is_bound(&typearguments)) {
+                if is_numeric_literal(ast.get_node(arg))
+                    || is_negated_numeric_literal(self.context, &argref)
+                {
+                    literals.push((args.len(), argref));
+                    args.push(create_null_value());
+                    continue;
+                }
+
+                // Mismatches are reported when the frame is built
+                let val = self.evaluate_expression(&argref);
+                let _ = t.bind_type_parameters(&val.get_type(&self.state), &mut typearguments);
+                args.push(val);
+                continue;
+            }
+
+            let val = if let Some(t) = paramtype {
+                self.evaluate_expression_as_type(&argref, &t.substitute(&typearguments))
+            } else if let (Value::BuiltInFunction(BuiltInFunction::ArrayPush), 1) =
+                (callable.get_inner_ref(&self.state), args.len())
+            {
+                // Pushed values take the element type of the array
+                let element = self.state.get_dynamic_array_mut(&args[0]).element.clone();
+                self.evaluate_expression_as_type(&argref, &element)
+            } else {
+                self.evaluate_expression(&argref)
+            };
 
             /*println!(
                 "Call argument {}: {:?}",
@@ -828,9 +2400,21 @@ impl<'a> TreeWalker<'a> {
                 ValueDisplay { v: &val, tw: self }
             );*/
 
-            args.push(val);
+            args.push(val);
+        }
+        if self.state.has_returned() {
+            return create_null_value();
+        }
+
+        for (i, argref) in literals {
+            let t = paramtypes[i].substitute(&typearguments);
+            args[i] = self.evaluate_expression_as_type(&argref, &t);
+            let _ = paramtypes[i]
+                .bind_type_parameters(&args[i].get_type(&self.state), &mut typearguments);
         }
 
+        let args = self.bind_call_arguments(&callable, args, namedargs);
+
         let actual = callable.get_inner_ref(&self.state);
         let returnvalue = match actual {
             Value::Function(fref) => {
@@ -839,20 +2423,40 @@ impl<'a> TreeWalker<'a> {
                 let inputparams = &function.signature.inputparams;
                 assert!(args.len() == inputparams.len());
 
-                // Check signature and build frames
+                // Check signature, binding the type parameters of generic functions
+                let mut typearguments = HashMap::new();
+                for (arg, param) in args.iter().zip(inputparams) {
+                    if let Err(e) = param.1.bind_type_parameters(
+                        &arg.get_type_as(&param.1, &self.state),
+                        &mut typearguments,
+                    ) {
+                        panic!("Callable argument type mismatch! {}", e);
+                    }
+                }
+
+                // Build frames
                 let mut frame = StackFrame {
                     index: self.state.stackframes.len(),
                     variables: VariableEnvironment::new(),
                     returnvalue: None,
+                    returned: false,
+                    outputparams: function
+                        .signature
+                        .outputparams
+                        .iter()
+                        .map(|t| t.substitute(&typearguments))
+                        .collect(),
+                    definingframe: fref.definingframe,
+                    typearguments,
                 };
-                for (i, arg) in args.into_iter().enumerate() {
-                    assert!(
-                        arg.get_type(&self.state) == inputparams[i].1,
-                        "Callable argument type mismatch! Arg: {:?}, Param: {:?}",
-                        arg.get_type(&self.state),
-                        inputparams[i].1
-                    );
 
+                // Captures go first, so parameters shadow them
+                for (symbol, value) in &fref.captures {
+                    frame
+                        .variables
+                        .add_with_symbol(symbol.clone(), value.clone());
+                }
+                for (i, arg) in args.into_iter().enumerate() {
                     // Copy inner value to not automatically reference other stacks
                     let arg = arg.clone_or_move_inner(&self.state);
 
@@ -879,11 +2483,32 @@ impl<'a> TreeWalker<'a> {
 
                 self.state.stackframes.push(frame);
                 self.evaluate_statementbody(&fnastref, node);
-                let result = if let Some(v) = self.state.stackframes.pop().unwrap().returnvalue {
-                    Some(v.clone_or_move_inner(&self.state))
-                } else {
-                    None
-                };
+                let frame = self.state.stackframes.pop().unwrap();
+                let result = frame.returnvalue;
+
+                // Returned references cannot outlive the frame they refer to
+                if let Some(Value::Reference(vref)) = &result {
+                    assert!(
+                        !matches!(get_valueref_frame(vref), Some(f) if f >= frame.index),
+                        "Returned reference outlives the variable it refers to"
+                    );
+                }
+
+                // Captured references cannot outlive the frame they refer to
+                if let Some(Value::Function(fref)) = &result {
+                    for (symbol, value) in &fref.captures {
+                        if let Value::ValueRef(ValueRef::SimpleValueRef(
+                            SimpleValueRef::IndexedStackValueRef(r),
+                        )) = value
+                        {
+                            assert!(
+                                r.frame < frame.index,
+                                "Captured reference to {} outlives the variable",
+                                symbol
+                            );
+                        }
+                    }
+                }
 
                 self.state.current_module = old_module;
 
@@ -902,18 +2527,13 @@ impl<'a> TreeWalker<'a> {
                             TypeId::Primitive(PrimitiveType::StaticStringUtf8)
                         );
 
-                        // TODO: Parse format string, for now, assume all args are u32s
-                        let mut format_types = Vec::new();
-                        for _i in 0..args.len() {
-                            format_types.push(TypeId::Primitive(PrimitiveType::U32));
-                        }
-
-                        // Check signature and build frames
+                        // TODO: Parse format string, for now, accept any primitive
                         let mut strargs = Vec::new();
-                        for (i, arg) in args[1..].iter().enumerate() {
+                        for arg in &args[1..] {
                             assert!(
-                                arg.get_type(&self.state) == format_types[i],
-                                "Type mismatch!"
+                                matches!(arg.get_type(&self.state), TypeId::Primitive(_)),
+                                "Type mismatch! Only primitive format arguments are supported, got {:?}",
+                                arg.get_type(&self.state)
                             );
                             strargs.push(arg.to_string(&self.state));
                         }
@@ -922,10 +2542,52 @@ impl<'a> TreeWalker<'a> {
 
                         // Print
                         println!("{}", fmt.format(&strargs));
+
+                        None
                     }
-                };
+                    BuiltInFunction::ArrayLen => {
+                        assert!(args.len() == 1);
+                        let length = match args[0].get_inner_ref(&self.state) {
+                            Value::Array(array) => array.elements.len(),
+                            Value::DynamicArray(handle) => self.state.heap[*handle].elements.len(),
+                            Value::Slice(slice) => slice.length,
+                            n => panic!("Expected an array, got {:?}", n),
+                        };
+                        Some(Value::Primitive(PrimitiveValue::U64(U64(length as u64))))
+                    }
+                    BuiltInFunction::ArrayPush => {
+                        assert!(args.len() == 2);
+                        let value = args[1].clone().clone_or_move_inner(&self.state);
+                        let valuetype = value.get_type(&self.state);
+                        let array = self.state.get_dynamic_array_mut(&args[0]);
+                        assert_eq!(
+                            valuetype, array.element,
+                            "Pushed value does not match the array element type"
+                        );
+                        array.elements.push(value);
+
+                        None
+                    }
+                    BuiltInFunction::ArrayPop => {
+                        assert!(args.len() == 1);
+                        let array = self.state.get_dynamic_array_mut(&args[0]);
+                        Some(
+                            array
+                                .elements
+                                .pop()
+                                .unwrap_or_else(|| panic!("Cannot pop from an empty array")),
+                        )
+                    }
+                    BuiltInFunction::ArrayClear => {
+                        assert!(args.len() == 1);
+                        self.state.get_dynamic_array_mut(&args[0]).elements.clear();
 
-                None
+                        None
+                    }
+                    BuiltInFunction::ArrayNew | BuiltInFunction::Allocate => {
+                        panic!("{} cannot be called directly", n.to_str())
+                    }
+                }
             }
             _ => panic!("Expression was not a function: {:?}", actual),
         };
@@ -937,12 +2599,75 @@ impl<'a> TreeWalker<'a> {
         }
     }
 
+    fn evaluate_type_arguments(
+        &mut self,
+        astref: &AstRef,
+        callop: &ast::nodes::CallOperation,
+    ) -> Vec<TypeId> {
+        let ast = self.context.get_ast(&astref);
+        let arglist = as_node!(ast, ArgumentList, &callop.arglist);
+
+        let mut arguments = Vec::new();
+        for arg in &arglist.args {
+            let typeval = self.evaluate_expression(&from_astref(&astref, &arg));
+            match typeval.get_inner_ref(&self.state) {
+                Value::Type(n) => arguments.push(n.clone()),
+                _ => panic!(
+                    "Expected Type expression for type argument, got {:?}",
+                    typeval
+                ),
+            }
+        }
+
+        return arguments;
+    }
+
+    // Fields are passed positionally, in the order they are declared in
+    fn evaluate_variant_construction(
+        &mut self,
+        astref: &AstRef,
+        callop: &ast::nodes::CallOperation,
+        constructor: VariantConstructor,
+    ) -> Value {
+        let ast = self.context.get_ast(&astref);
+        let arglist = as_node!(ast, ArgumentList, &callop.arglist);
+        let fieldtypes = &constructor.definition.variants[constructor.variant].fields;
+
+        assert_eq!(
+            arglist.args.len(),
+            fieldtypes.len(),
+            "Mismatching number of fields for enum variant"
+        );
+
+        let mut fields = Vec::new();
+        for (arg, (_, fieldtype)) in arglist.args.iter().zip(fieldtypes) {
+            let value = self
+                .evaluate_expression_as_type(&from_astref(&astref, &arg), fieldtype)
+                .clone_or_move_inner(&self.state);
+            if self.state.has_returned() {
+                return create_null_value();
+            }
+            assert_eq!(
+                value.get_type(&self.state),
+                *fieldtype,
+                "Mismatching type for enum variant field"
+            );
+            fields.push(value);
+        }
+
+        return Value::EnumInstance(EnumInstance {
+            definition: constructor.definition,
+            variant: constructor.variant,
+            fields,
+        });
+    }
+
     fn evaluate_structliteral(
         &mut self,
         astref: &AstRef,
         sliteral: &ast::nodes::StructLiteral,
     ) -> Value {
-        let mut definition = StructDefinition { fields: Vec::new() };
+        let mut fields = Vec::new();
 
         for field in &sliteral.fields {
             let ast = self.context.get_ast(&astref);
@@ -957,10 +2682,273 @@ impl<'a> TreeWalker<'a> {
                 ),
             };
 
-            definition.fields.push((n.symbol.clone(), typeid.clone()));
+            fields.push((n.symbol.clone(), typeid.clone()));
+        }
+
+        // Fields referring to type parameters make the struct generic
+        return Value::Type(TypeId::Struct(StructDefinition::new(fields)));
+    }
+
+    // Omitted fields take their default values
+    fn evaluate_structconstruction(
+        &mut self,
+        astref: &AstRef,
+        construction: &ast::nodes::StructConstruction,
+    ) -> Value {
+        let typeval = self.evaluate_expression(&from_astref(&astref, &construction.expr));
+        let typeid = match typeval.get_inner_ref(&self.state) {
+            Value::Type(n @ TypeId::Struct(_)) => n.clone(),
+            n => panic!("Cannot construct non-struct type {:?}", n),
+        };
+
+        let mut instance = match create_default_value(&typeid, &mut self.state) {
+            Value::StructInstance(instance) => instance,
+            _ => unreachable!(),
+        };
+        for (symbol, expr) in &construction.fields {
+            let fieldtype = match instance.definition.get_field(symbol) {
+                Some(fieldtype) => fieldtype.clone(),
+                None => panic!("Struct has no field {}", symbol),
+            };
+            let value = self
+                .evaluate_expression_as_type(&from_astref(&astref, expr), &fieldtype)
+                .clone_or_move_inner(&self.state);
+            if self.state.has_returned() {
+                return create_null_value();
+            }
+            assert_eq!(
+                value.get_type_as(&fieldtype, &self.state),
+                fieldtype,
+                "Mismatching type for struct field"
+            );
+            instance.fields.insert(symbol.clone(), value);
+        }
+
+        return Value::StructInstance(instance);
+    }
+
+    fn evaluate_enumliteral(
+        &mut self,
+        astref: &AstRef,
+        eliteral: &ast::nodes::EnumLiteral,
+    ) -> Value {
+        let mut variants = Vec::new();
+
+        for variant in &eliteral.variants {
+            let ast = self.context.get_ast(&astref);
+            let n = as_node!(ast, EnumVariant, variant);
+
+            let mut fields = Vec::new();
+            for field in &n.fields {
+                let ast = self.context.get_ast(&astref);
+                let field = as_node!(ast, StructField, field);
+
+                let typeval = self.evaluate_expression(&from_astref(&astref, &field.typeexpr));
+                match typeval.get_inner_ref(&self.state) {
+                    Value::Type(t) => fields.push((field.symbol.clone(), t.clone())),
+                    _ => panic!(
+                        "Expected Type expression for variant field, got {:?}",
+                        typeval
+                    ),
+                }
+            }
+
+            variants.push(EnumVariant {
+                name: n.symbol.clone(),
+                fields,
+            });
+        }
+
+        // Fields referring to type parameters make the enum generic
+        return Value::Type(TypeId::Enum(EnumDefinition::new(variants)));
+    }
+
+    // Finds the arm matching the value of the expression, along with the values
+    //  bound by its pattern. Guarded arms are only taken if the guard holds.
+    fn find_match_arm(
+        &mut self,
+        astref: &AstRef,
+        expr: &NodeRef,
+        arms: &[NodeRef],
+    ) -> Option<(NodeRef, Vec<(ast::SymbolRef, Value)>)> {
+        let value = self
+            .evaluate_expression(&from_astref(astref, expr))
+            .clone_or_move_inner(&self.state);
+        if self.state.has_returned() {
+            return None;
+        }
+
+        let ast = self.context.get_ast(astref);
+        for arm in arms {
+            let arm = as_node!(ast, MatchArm, arm);
+            let mut bindings = Vec::new();
+            if !self.match_pattern(astref, &arm.pattern, &value, &mut bindings) {
+                continue;
+            }
+
+            if let Some(guard) = &arm.guard {
+                // The guard sees the symbols bound by the pattern
+                let checkpoint = self.state.get_current_variables().checkpoint();
+                for (symbol, value) in &bindings {
+                    self.state
+                        .get_current_variables_mut()
+                        .add_with_symbol(symbol.clone(), value.clone());
+                }
+                let holds = self
+                    .evaluate_expression(&from_astref(astref, guard))
+                    .clone_or_move_inner(&self.state);
+                self.state.get_current_variables_mut().restore(checkpoint);
+
+                if holds != Value::Primitive(PrimitiveValue::Bool(Bool(true))) {
+                    continue;
+                }
+            }
+
+            return Some((arm.body, bindings));
+        }
+
+        None
+    }
+
+    fn match_pattern(
+        &mut self,
+        astref: &AstRef,
+        pattern: &NodeRef,
+        value: &Value,
+        bindings: &mut Vec<(ast::SymbolRef, Value)>,
+    ) -> bool {
+        let ast = self.context.get_ast(astref);
+        match (ast.get_node(pattern), value) {
+            (ast::Node::VariantPattern(pattern), Value::EnumInstance(instance)) => {
+                let (index, variant) = match instance.definition.get_variant(&pattern.symbol) {
+                    Some(n) => n,
+                    None => panic!(
+                        "Enum has no variant {}",
+                        ast.get_symbol(&pattern.symbol).unwrap()
+                    ),
+                };
+
+                assert!(
+                    pattern.bindings.is_empty() || pattern.bindings.len() == variant.fields.len(),
+                    "Mismatching number of bindings for variant {}",
+                    ast.get_symbol(&pattern.symbol).unwrap()
+                );
+
+                if index != instance.variant {
+                    return false;
+                }
+
+                for (symbol, value) in pattern.bindings.iter().zip(&instance.fields) {
+                    if let Some(symbol) = symbol {
+                        bindings.push((symbol.clone(), value.clone()));
+                    }
+                }
+                true
+            }
+            (ast::Node::VariantPattern(_), n) => panic!("Cannot match variant of {:?}", n),
+            (ast::Node::WildcardPattern(_), _) => true,
+            (ast::Node::BindingPattern(pattern), _) => {
+                bindings.push((pattern.symbol.clone(), value.clone()));
+                true
+            }
+            (ast::Node::LiteralPattern(pattern), _) => {
+                *value == self.evaluate_pattern_value(astref, &pattern.value, value)
+            }
+            (ast::Node::RangePattern(pattern), _) => {
+                let start = self.evaluate_pattern_value(astref, &pattern.start, value);
+                let end = self.evaluate_pattern_value(astref, &pattern.end, value);
+                let holds = |op, bound: &Value| {
+                    self.perform_binaryoperation(op, value, bound)
+                        == Value::Primitive(PrimitiveValue::Bool(Bool(true)))
+                };
+                holds(&BinaryOperationType::GreaterThanOrEq, &start)
+                    && holds(&BinaryOperationType::LessThan, &end)
+            }
+            (ast::Node::StructPattern(pattern), Value::StructInstance(instance)) => {
+                pattern.fields.iter().all(|(symbol, fieldpattern)| {
+                    let field = match instance.fields.get(symbol) {
+                        Some(n) => n,
+                        None => panic!("Struct has no field {}", ast.get_symbol(symbol).unwrap()),
+                    };
+                    self.match_pattern(astref, fieldpattern, field, bindings)
+                })
+            }
+            (ast::Node::StructPattern(_), n) => panic!("Cannot match struct pattern on {:?}", n),
+            (ast::Node::TuplePattern(pattern), Value::Tuple(elements)) => {
+                assert!(
+                    pattern.elements.len() == elements.len(),
+                    "Tuple pattern has {} elements, matched tuple has {}",
+                    pattern.elements.len(),
+                    elements.len()
+                );
+                pattern
+                    .elements
+                    .iter()
+                    .zip(elements)
+                    .all(|(elementpattern, element)| {
+                        self.match_pattern(astref, elementpattern, element, bindings)
+                    })
+            }
+            (ast::Node::TuplePattern(_), n) => panic!("Cannot match tuple pattern on {:?}", n),
+            (n, _) => panic!("Not a pattern! Node: {:?}", ast::NodeInfo::name(n)),
+        }
+    }
+
+    // Evaluates an expression of a pattern, literals take the type of the matched value
+    fn evaluate_pattern_value(&mut self, astref: &AstRef, expr: &NodeRef, value: &Value) -> Value {
+        let typeid = value.get_type(&self.state);
+        self.evaluate_expression_as_type(&from_astref(astref, expr), &typeid)
+            .clone_or_move_inner(&self.state)
+    }
+
+    fn evaluate_matchexpression(
+        &mut self,
+        astref: &AstRef,
+        matchexpr: &ast::nodes::MatchExpression,
+    ) -> Value {
+        let (expr, bindings) = match self.find_match_arm(astref, &matchexpr.expr, &matchexpr.arms) {
+            Some(n) => n,
+            None if self.state.has_returned() => return create_null_value(),
+            None => match matchexpr.elsebranch {
+                Some(n) => (n, Vec::new()),
+                None => panic!("No match arm for value"),
+            },
+        };
+
+        // Bound symbols only live in the arm, so the result cannot refer to them
+        let checkpoint = self.state.get_current_variables().checkpoint();
+        for (symbol, value) in bindings {
+            self.state
+                .get_current_variables_mut()
+                .add_with_symbol(symbol, value);
         }
+        let value = self
+            .evaluate_expression(&from_astref(astref, &expr))
+            .clone_or_move_inner(&self.state);
+        self.state.get_current_variables_mut().restore(checkpoint);
+
+        return value;
+    }
 
-        return Value::Type(TypeId::Struct(definition));
+    fn evaluate_matchstatement(&mut self, astref: &AstRef, matchstmt: &ast::nodes::MatchStatement) {
+        let (body, bindings) = match self.find_match_arm(astref, &matchstmt.expr, &matchstmt.arms) {
+            Some(n) => n,
+            None if self.state.has_returned() => return,
+            None => match matchstmt.elsebranch {
+                Some(n) => (n, Vec::new()),
+                None => panic!("No match arm for value"),
+            },
+        };
+
+        let checkpoint = self.state.get_current_variables().checkpoint();
+        for (symbol, value) in bindings {
+            self.state
+                .get_current_variables_mut()
+                .add_with_symbol(symbol, value);
+        }
+        let ast = self.context.get_ast(astref);
+        self.evaluate_statementbody(astref, as_node!(ast, StatementBody, &body));
+        self.state.get_current_variables_mut().restore(checkpoint);
     }
 
     fn evaluate_functionliteral(
@@ -972,6 +2960,7 @@ impl<'a> TreeWalker<'a> {
             inputparams: Vec::new(),
             outputparams: Vec::new(),
         };
+        let mut defaults = Vec::new();
 
         for inparam in &fnliteral.inputparams {
             let ast = self.context.get_ast(&astref);
@@ -991,6 +2980,7 @@ impl<'a> TreeWalker<'a> {
             signature
                 .inputparams
                 .push((n.symbol.clone(), typeid.clone()));
+            defaults.push(n.defaultexpr.map(|e| from_astref(&astref, &e)));
         }
 
         for outparam in &fnliteral.outputparams {
@@ -998,7 +2988,7 @@ impl<'a> TreeWalker<'a> {
             let n = as_node!(ast, OutputParameter, outparam);
 
             let typeval: Value = self.evaluate_expression(&from_astref(&astref, &n.typeexpr));
-            let typeid = match typeval {
+            let typeid = match typeval.get_inner_ref(&self.state) {
                 Value::Type(n) => n,
                 _ => panic!(
                     "Expected Type expression for output parameter, got {:?}",
@@ -1006,13 +2996,15 @@ impl<'a> TreeWalker<'a> {
                 ),
             };
 
-            signature.outputparams.push(typeid);
+            signature.outputparams.push(typeid.clone());
         }
 
         let module = self.state.current_module.as_ref().unwrap().clone();
         let funcref = FunctionRef {
             index: self.state.get_current_module().functions.len() as u64,
             module: module.clone(),
+            captures: self.capture_variables(astref, fnliteral),
+            definingframe: self.state.stackframes.last().map(|f| f.index),
         };
         self.state
             .get_current_module_mut()
@@ -1020,12 +3012,73 @@ impl<'a> TreeWalker<'a> {
             .push(Function {
                 module: module,
                 signature: signature.clone(),
+                defaults,
                 body: from_astref(&astref, &fnliteral.body),
             });
 
         return Value::Function(funcref);
     }
 
+    // Explicitly captured symbols are captured as listed, other locals of the current frame
+    //  used by the literal are captured by value
+    fn capture_variables(
+        &self,
+        astref: &AstRef,
+        fnliteral: &ast::nodes::FunctionLiteral,
+    ) -> Vec<(ast::SymbolRef, Value)> {
+        let ast = self.context.get_ast(astref);
+        let mut captures: Vec<(ast::SymbolRef, ast::CaptureType)> = fnliteral.captures.clone();
+        for symbol in collect_referenced_symbols(ast, &fnliteral.body) {
+            if !captures.iter().any(|c| c.0 == symbol) {
+                captures.push((symbol, ast::CaptureType::Value));
+            }
+        }
+
+        let frame = match self.state.stackframes.last() {
+            Some(frame) => frame,
+            None => {
+                assert!(
+                    fnliteral.captures.is_empty(),
+                    "Function literals outside of functions cannot capture variables"
+                );
+                return Vec::new();
+            }
+        };
+
+        let mut values = Vec::new();
+        for (symbol, capturetype) in captures {
+            let index = match frame.variables.symbol_storage_lookup.get(&symbol) {
+                Some(index) => *index,
+                None => {
+                    // Implicit captures only apply to locals, anything else is looked up on use
+                    assert!(
+                        !fnliteral.captures.iter().any(|c| c.0 == symbol),
+                        "Cannot capture {}, it is not a local variable",
+                        symbol
+                    );
+                    continue;
+                }
+            };
+
+            let value = match capturetype {
+                ast::CaptureType::Value => frame
+                    .variables
+                    .get(index)
+                    .clone()
+                    .clone_or_move_inner(&self.state),
+                ast::CaptureType::Reference => Value::ValueRef(ValueRef::SimpleValueRef(
+                    SimpleValueRef::IndexedStackValueRef(IndexedStackValueRef {
+                        frame: frame.index,
+                        index,
+                    }),
+                )),
+            };
+            values.push((symbol, value));
+        }
+
+        values
+    }
+
     fn evaluate_module(&mut self, astref: &AstRef, module_node: &ast::nodes::Module) {
         let ast = self.context.get_ast(astref);
 
@@ -1065,9 +3118,25 @@ impl<'a> TreeWalker<'a> {
     fn evaluate_statementbody(&mut self, astref: &AstRef, body: &ast::nodes::StatementBody) {
         for s in &body.statements {
             self.evaluate_statement(&from_astref(&astref, s));
+
+            // Skip the rest of the body on break, continue or return
+            if self.state.loop_control.is_some() || self.state.has_returned() {
+                break;
+            }
         }
     }
 
+    // Symbols declared in the body go out of scope after it
+    fn evaluate_blockscoped_statementbody(
+        &mut self,
+        astref: &AstRef,
+        body: &ast::nodes::StatementBody,
+    ) {
+        let checkpoint = self.state.get_current_variables().checkpoint();
+        self.evaluate_statementbody(astref, body);
+        self.state.get_current_variables_mut().restore(checkpoint);
+    }
+
     fn evaluate_symbolreference(
         &mut self,
         astref: &AstRef,
@@ -1093,26 +3162,43 @@ impl<'a> TreeWalker<'a> {
         astref: &AstRef,
         symdecl: &ast::nodes::SymbolDeclaration,
     ) {
-        let typeval = match &symdecl.typeexpr {
-            Some(n) => Some(self.evaluate_expression(&from_astref(&astref, n))),
-            _ => None,
+        // A "[..]" annotation only makes the array dynamic, the element type comes from the init value
+        let dynamic_only = match &symdecl.typeexpr {
+            Some(n) => matches!(
+                self.context.get_node(&from_astref(&astref, n)),
+                ast::Node::ArrayLiteral(n) if n.dynamic && n.elements.is_empty()
+            ),
+            _ => false,
         };
 
-        let initval = if let Some(initexpr) = &symdecl.initexpr {
-            Some(self.evaluate_expression(&from_astref(astref, initexpr)))
-        } else {
-            None
+        let typeval = match &symdecl.typeexpr {
+            Some(n) if !dynamic_only => Some(self.evaluate_expression(&from_astref(&astref, n))),
+            _ => None,
         };
 
         let typevaltype = if let Some(typeval) = &typeval {
             match typeval.get_inner_ref(&self.state) {
-                Value::Type(n) => Some(n),
+                Value::Type(n) => Some(n.clone()),
                 _ => panic!("Type expression is not a type!"),
             }
         } else {
             None
         };
 
+        let initval = if let Some(initexpr) = &symdecl.initexpr {
+            let initref = from_astref(astref, initexpr);
+            Some(if let Some(typevaltype) = &typevaltype {
+                self.evaluate_expression_as_type(&initref, typevaltype)
+            } else {
+                self.evaluate_expression(&initref)
+            })
+        } else {
+            None
+        };
+        if self.state.has_returned() {
+            return;
+        }
+
         let actual_initval = initval.unwrap_or_else(|| {
             assert!(
                 typeval.is_some(),
@@ -1122,13 +3208,13 @@ impl<'a> TreeWalker<'a> {
                     .get_symbol(&symdecl.symbol)
                     .unwrap()
             );
-            create_default_value(&typevaltype.as_ref().unwrap())
+            create_default_value(&typevaltype.as_ref().unwrap(), &mut self.state)
         });
 
         if let Some(typevaltype) = typevaltype {
-            let inittype = actual_initval.get_type(&self.state);
+            let inittype = actual_initval.get_type_as(&typevaltype, &self.state);
             assert_eq!(
-                *typevaltype,
+                typevaltype,
                 inittype,
                 "Mismatching types for symbol declaration {}",
                 self.context
@@ -1140,13 +3226,21 @@ impl<'a> TreeWalker<'a> {
 
         let actual_initval = actual_initval.clone_or_move_inner(&self.state);
 
-        // TODO: This is not correct for modules inside functions
-        let symenv = if !self.state.stackframes.is_empty() {
-            &mut self.state.stackframes.last_mut().unwrap().variables
+        let actual_initval = if dynamic_only {
+            match actual_initval {
+                Value::Array(array) => self.state.allocate_array(array),
+                Value::DynamicArray(_) => actual_initval,
+                n => panic!(
+                    "Expected an array for dynamic array declaration, got {:?}",
+                    n
+                ),
+            }
         } else {
-            &mut self.state.get_current_module_mut().globals
+            actual_initval
         };
 
+        let symenv = self.state.get_current_variables_mut();
+
         assert!(
             !symenv.has_symbol(&symdecl.symbol),
             "Symbol {} is already defined!",
@@ -1158,19 +3252,81 @@ impl<'a> TreeWalker<'a> {
         symenv.add_with_symbol(symdecl.symbol.clone(), actual_initval);
     }
 
+    fn evaluate_destructuringdeclaration(
+        &mut self,
+        astref: &AstRef,
+        destructdecl: &ast::nodes::DestructuringDeclaration,
+    ) {
+        let initval = self
+            .evaluate_expression(&from_astref(astref, &destructdecl.initexpr))
+            .clone_or_move_inner(&self.state);
+        if self.state.has_returned() {
+            return;
+        }
+
+        let elements = match initval {
+            Value::Tuple(elements) => elements,
+            n => panic!("Only tuples can be destructured, got {:?}", n),
+        };
+
+        assert_eq!(
+            elements.len(),
+            destructdecl.symbols.len(),
+            "Mismatching number of symbols and tuple elements in declaration"
+        );
+
+        for (symbol, value) in destructdecl.symbols.iter().zip(elements) {
+            // Ignored elements are still evaluated, just never bound
+            if let Some(symbol) = symbol {
+                let symenv = self.state.get_current_variables_mut();
+
+                assert!(
+                    !symenv.has_symbol(symbol),
+                    "Symbol {} is already defined!",
+                    self.context.get_ast(astref).get_symbol(symbol).unwrap()
+                );
+                symenv.add_with_symbol(symbol.clone(), value);
+            }
+        }
+    }
+
     fn evaluate_expression(&mut self, astref: &AstRef) -> Value {
+        // Once a try has returned an error the rest of the function is skipped, so
+        //  expressions evaluate to null and operations on them are not performed
+        if self.state.has_returned() {
+            return create_null_value();
+        }
+
         match self.context.get_node(astref) {
             ast::Node::BuiltInObjectReference(n) => self.evaluate_builtinref(n),
             ast::Node::IntegerLiteral(n) => self.evaluate_integerliteral(n),
+            ast::Node::CharacterLiteral(n) => self.evaluate_characterliteral(n),
+            ast::Node::FloatLiteral(n) => self.evaluate_floatliteral(n),
             ast::Node::BooleanLiteral(n) => self.evaluate_booleanliteral(n),
+            ast::Node::NullLiteral(_) => create_null_value(),
             ast::Node::StringLiteral(n) => self.evaluate_stringliteral(n),
             ast::Node::StructLiteral(n) => self.evaluate_structliteral(astref, n),
+            ast::Node::EnumLiteral(n) => self.evaluate_enumliteral(astref, n),
+            ast::Node::StructConstruction(n) => self.evaluate_structconstruction(astref, n),
+            ast::Node::ArrayLiteral(n) => self.evaluate_arrayliteral(astref, n),
+            ast::Node::TupleLiteral(n) => self.evaluate_tupleliteral(astref, n),
             ast::Node::FunctionLiteral(n) => self.evaluate_functionliteral(astref, n),
             ast::Node::SymbolReference(n) => self.evaluate_symbolreference(astref, n),
             ast::Node::CallOperation(n) => self.evaluate_calloperation(astref, n),
             ast::Node::BinaryOperation(n) => self.evaluate_binaryoperation(astref, n),
+            ast::Node::UnaryOperation(n) => self.evaluate_unaryoperation(astref, n),
             ast::Node::IfExpression(n) => self.evaluate_ifexpression(astref, n),
+            ast::Node::MatchExpression(n) => self.evaluate_matchexpression(astref, n),
             ast::Node::SubScript(n) => self.evaluate_subscript(astref, n),
+            ast::Node::IndexExpression(n) => self.evaluate_indexexpression(astref, n),
+            ast::Node::DynamicArrayType(n) => self.evaluate_dynamicarraytype(astref, n),
+            ast::Node::SliceType(n) => self.evaluate_slicetype(astref, n),
+            ast::Node::OptionalType(n) => self.evaluate_optionaltype(astref, n),
+            ast::Node::ReferenceExpression(n) => self.evaluate_referenceexpression(astref, n),
+            ast::Node::DereferenceExpression(n) => self.evaluate_dereferenceexpression(astref, n),
+            ast::Node::TryExpression(n) => self.evaluate_tryexpression(astref, n),
+            ast::Node::FunctionType(n) => self.evaluate_functiontype(astref, n),
+            ast::Node::TypeParameter(n) => self.evaluate_typeparameter(astref, n),
             n => {
                 panic!("Not an expression! Node: {:?}", ast::NodeInfo::name(n));
             }
@@ -1185,7 +3341,17 @@ impl<'a> TreeWalker<'a> {
             ast::Node::Module(n) => self.evaluate_module(astref, n),
             ast::Node::StatementBody(n) => self.evaluate_statementbody(astref, n),
             ast::Node::SymbolDeclaration(n) => self.evaluate_symboldeclaration(astref, n),
+            ast::Node::DestructuringDeclaration(n) => {
+                self.evaluate_destructuringdeclaration(astref, n)
+            }
             ast::Node::IfStatement(n) => self.evaluate_ifstatement(astref, n),
+            ast::Node::MatchStatement(n) => self.evaluate_matchstatement(astref, n),
+            ast::Node::WhileStatement(n) => self.evaluate_whilestatement(astref, n),
+            ast::Node::ForStatement(n) => self.evaluate_forstatement(astref, n),
+            ast::Node::BreakStatement(_) => self.state.loop_control = Some(LoopControl::Break),
+            ast::Node::ContinueStatement(_) => {
+                self.state.loop_control = Some(LoopControl::Continue)
+            }
             ast::Node::ReturnStatement(n) => self.evaluate_returnstatement(astref, n),
             ast::Node::AssignStatement(n) => self.evaluate_assignstatement(astref, n),
             _ => {
@@ -1196,6 +3362,25 @@ impl<'a> TreeWalker<'a> {
 }
 
 impl State {
+    fn has_returned(&self) -> bool {
+        self.stackframes.last().map_or(false, |f| f.returned)
+    }
+
+    fn allocate_array(&mut self, array: ArrayInstance) -> Value {
+        self.heap.push(array);
+        Value::DynamicArray(self.heap.len() - 1)
+    }
+
+    fn get_dynamic_array_mut(&mut self, value: &Value) -> &mut ArrayInstance {
+        match value.get_inner_ref(self) {
+            Value::DynamicArray(handle) => {
+                let handle = *handle;
+                &mut self.heap[handle]
+            }
+            n => panic!("Expected a dynamic array, got {:?}", n),
+        }
+    }
+
     fn get_module(&self, key: &StringKey) -> &Module {
         return self.all_modules.get(key).unwrap();
     }
@@ -1212,13 +3397,31 @@ impl State {
         return self.get_module_mut(&self.current_module.as_ref().unwrap().clone());
     }
 
+    // TODO: This is not correct for modules inside functions
+    fn get_current_variables(&self) -> &VariableEnvironment {
+        if !self.stackframes.is_empty() {
+            &self.stackframes.last().unwrap().variables
+        } else {
+            &self.get_current_module().globals
+        }
+    }
+
+    fn get_current_variables_mut(&mut self) -> &mut VariableEnvironment {
+        if !self.stackframes.is_empty() {
+            &mut self.stackframes.last_mut().unwrap().variables
+        } else {
+            &mut self.get_current_module_mut().globals
+        }
+    }
+
+    // Refs can be passed into calls through slices, so the frame is not always the last one
     fn get_indexed_stack_value(&self, sref: &IndexedStackValueRef) -> &Value {
-        self.stackframes.last().unwrap().variables.get(sref.index)
+        self.stackframes[sref.frame].variables.get(sref.index)
     }
 
     fn get_indexed_stack_value_mut(&mut self, sref: &IndexedStackValueRef) -> &mut Value {
         self.stackframes
-            .last_mut()
+            .get_mut(sref.frame)
             .unwrap()
             .variables
             .get_mut(sref.index)
@@ -1304,10 +3507,24 @@ impl State {
         }
     }
 
+    fn resolve_indexed_valueref(&self, ivref: &IndexedValueRef) -> &Value {
+        let v = match self.resolve_simple_valueref(&ivref.vref) {
+            Value::ValueRef(vref) => self.full_deref_valueref(vref),
+            n => n,
+        };
+
+        match v {
+            Value::Array(array) => &array.elements[ivref.index],
+            n => panic!("IndexedValueRef not supported on value {:?}", n),
+        }
+    }
+
     fn resolve_valueref(&self, vref: &ValueRef) -> &Value {
         match vref {
             ValueRef::SimpleValueRef(r) => self.resolve_simple_valueref(r),
             ValueRef::SubscriptedValueRef(r) => self.resolve_subscripted_valueref(r),
+            ValueRef::IndexedValueRef(r) => self.resolve_indexed_valueref(r),
+            ValueRef::HeapElementRef(r) => &self.heap[r.array].elements[r.index],
         }
     }
 
@@ -1342,10 +3559,22 @@ impl State {
         };
     }
 
+    fn resolve_indexed_valueref_mut(&mut self, ivref: IndexedValueRef) -> &mut Value {
+        // Find final ref non-mutably
+        let leaf_ref = self.find_ref_to_leaf_value(&ValueRef::SimpleValueRef(ivref.vref));
+
+        return match self.resolve_valueref_mut(leaf_ref) {
+            Value::Array(array) => &mut array.elements[ivref.index],
+            n => panic!("IndexedValueRef not supported on value {:?}", n),
+        };
+    }
+
     fn resolve_valueref_mut(&mut self, vref: ValueRef) -> &mut Value {
         match vref {
             ValueRef::SimpleValueRef(r) => self.resolve_simple_valueref_mut(r),
             ValueRef::SubscriptedValueRef(r) => self.resolve_subscripted_valueref_mut(r),
+            ValueRef::IndexedValueRef(r) => self.resolve_indexed_valueref_mut(r),
+            ValueRef::HeapElementRef(r) => &mut self.heap[r.array].elements[r.index],
         }
     }
 
@@ -1437,6 +3666,20 @@ impl State {
                     }),
                 ));
             }
+
+            // Functions of enclosing frames are not captured, so they can refer to themselves
+            let mut definingframe = frame.definingframe;
+            while let Some(frame) = definingframe.and_then(|i| self.stackframes.get(i)) {
+                if let Some(Value::Function(_)) = frame.variables.get_from_symbol(&symbol) {
+                    return Some(ValueRef::SimpleValueRef(
+                        SimpleValueRef::NamedStackValueRef(NamedStackValueRef {
+                            frame: frame.index,
+                            symbol: symbol.clone(),
+                        }),
+                    ));
+                }
+                definingframe = frame.definingframe;
+            }
         }
 
         self.lookup_symbol_from_module(&self.current_module.as_ref().unwrap().clone(), symbol)
@@ -1452,6 +3695,8 @@ impl<'a> TreeWalker<'a> {
                 strings: Vec::new(),
                 stackframes: Vec::new(),
                 current_module: None,
+                loop_control: None,
+                heap: Vec::new(),
             },
             context: context,
         }
@@ -1493,6 +3738,10 @@ impl<'a> TreeWalker<'a> {
             index: 0,
             variables: VariableEnvironment::new(),
             returnvalue: None,
+            returned: false,
+            outputparams: Vec::new(),
+            definingframe: None,
+            typearguments: HashMap::new(),
         });
 
         self.evaluate_statement(&main.unwrap());
@@ -38,15 +38,13 @@ impl BasicBlock {
     }
 
     pub fn add_declaration_on_next_instruction(&mut self, variable: VariableKey) {
-        debug_assert!(!self.variable_declarations.contains_key(&variable));
-
+        // Variables can be assigned several times, the first assignment in the block declares it
         let next_instruction_index = self.instructions.len();
-        self.variable_declarations.insert(
-            variable,
-            VariableDeclarationInfo {
+        self.variable_declarations
+            .entry(variable)
+            .or_insert(VariableDeclarationInfo {
                 declare_point: next_instruction_index,
-            },
-        );
+            });
     }
 
     pub fn add_usage_on_next_instruction(&mut self, variable: VariableKey) {
@@ -82,23 +80,105 @@ impl<'a> BasicBlockEditor<'a> {
 
     pub fn call_static(
         &mut self,
-        variable: VariableKey,
+        variables: Vec<VariableKey>,
         function: FunctionKey,
         args: Vec<VariableKey>,
     ) {
-        self.block.add_declaration_on_next_instruction(variable);
+        for variable in &variables {
+            self.block.add_declaration_on_next_instruction(*variable);
+        }
         for arg in &args {
             self.block.add_usage_on_next_instruction(*arg);
         }
 
         self.block
             .push_instruction(Instruction::CallStatic(instructions::CallStatic {
-                variable,
+                variables,
                 function,
                 args,
             }));
     }
 
+    pub fn call_closure(
+        &mut self,
+        variables: Vec<VariableKey>,
+        closure: VariableKey,
+        args: Vec<VariableKey>,
+    ) {
+        for variable in &variables {
+            self.block.add_declaration_on_next_instruction(*variable);
+        }
+        self.block.add_usage_on_next_instruction(closure);
+        for arg in &args {
+            self.block.add_usage_on_next_instruction(*arg);
+        }
+
+        self.block
+            .push_instruction(Instruction::CallClosure(instructions::CallClosure {
+                variables,
+                closure,
+                args,
+            }));
+    }
+
+    pub fn make_closure(
+        &mut self,
+        variable: VariableKey,
+        function: FunctionKey,
+        captures: Vec<VariableKey>,
+    ) {
+        self.block.add_declaration_on_next_instruction(variable);
+        for capture in &captures {
+            self.block.add_usage_on_next_instruction(*capture);
+        }
+
+        self.block
+            .push_instruction(Instruction::MakeClosure(instructions::MakeClosure {
+                variable,
+                function,
+                captures,
+            }));
+    }
+
+    pub fn load_capture(&mut self, variable: VariableKey, closure: VariableKey, index: usize) {
+        self.block.add_declaration_on_next_instruction(variable);
+        self.block.add_usage_on_next_instruction(closure);
+
+        self.block
+            .push_instruction(Instruction::LoadCapture(instructions::LoadCapture {
+                variable,
+                closure,
+                index,
+            }));
+    }
+
+    pub fn address_of(&mut self, variable: VariableKey, target: VariableKey) {
+        self.block.add_declaration_on_next_instruction(variable);
+        self.block.add_usage_on_next_instruction(target);
+
+        self.block
+            .push_instruction(Instruction::AddressOf(instructions::AddressOf {
+                variable,
+                target,
+            }));
+    }
+
+    pub fn load(&mut self, variable: VariableKey, address: VariableKey) {
+        self.block.add_declaration_on_next_instruction(variable);
+        self.block.add_usage_on_next_instruction(address);
+
+        self.block
+            .push_instruction(Instruction::Load(instructions::Load { variable, address }));
+    }
+
+    pub fn store(&mut self, address: VariableKey, value: VariableKey) {
+        self.block.add_usage_on_next_instruction(address);
+        self.block.add_usage_on_next_instruction(value);
+
+        self.block
+            .push_instruction(Instruction::Store(instructions::Store { address, value }));
+    }
+
     pub fn call_builtin(
         &mut self,
         variable: VariableKey,
@@ -118,6 +198,150 @@ impl<'a> BasicBlockEditor<'a> {
             }))
     }
 
+    pub fn unary_op(
+        &mut self,
+        variable: VariableKey,
+        op: crate::shared::UnaryOperationType,
+        operand: VariableKey,
+    ) {
+        self.block.add_declaration_on_next_instruction(variable);
+        self.block.add_usage_on_next_instruction(operand);
+
+        self.block
+            .push_instruction(Instruction::UnaryOp(instructions::UnaryOp {
+                variable,
+                op,
+                operand,
+            }));
+    }
+
+    pub fn binary_op(
+        &mut self,
+        variable: VariableKey,
+        op: crate::shared::BinaryOperationType,
+        lhs: VariableKey,
+        rhs: VariableKey,
+    ) {
+        self.block.add_declaration_on_next_instruction(variable);
+        self.block.add_usage_on_next_instruction(lhs);
+        self.block.add_usage_on_next_instruction(rhs);
+
+        self.block
+            .push_instruction(Instruction::BinOp(instructions::BinOp {
+                variable,
+                op,
+                lhs,
+                rhs,
+            }));
+    }
+
+    pub fn load_element(&mut self, variable: VariableKey, array: VariableKey, index: VariableKey) {
+        self.block.add_declaration_on_next_instruction(variable);
+        self.block.add_usage_on_next_instruction(array);
+        self.block.add_usage_on_next_instruction(index);
+
+        self.block
+            .push_instruction(Instruction::LoadElement(instructions::LoadElement {
+                variable,
+                array,
+                index,
+            }));
+    }
+
+    pub fn store_element(&mut self, array: VariableKey, index: VariableKey, value: VariableKey) {
+        // Storing an element is a partial assignment, the array has to live before it
+        self.block.add_declaration_on_next_instruction(array);
+        self.block.add_usage_on_next_instruction(array);
+        self.block.add_usage_on_next_instruction(index);
+        self.block.add_usage_on_next_instruction(value);
+
+        self.block
+            .push_instruction(Instruction::StoreElement(instructions::StoreElement {
+                array,
+                index,
+                value,
+            }));
+    }
+
+    pub fn load_field(&mut self, variable: VariableKey, target: VariableKey, offset: u64) {
+        self.block.add_declaration_on_next_instruction(variable);
+        self.block.add_usage_on_next_instruction(target);
+
+        self.block
+            .push_instruction(Instruction::LoadField(instructions::LoadField {
+                variable,
+                target,
+                offset,
+            }));
+    }
+
+    pub fn store_field(&mut self, target: VariableKey, offset: u64, value: VariableKey) {
+        // Like elements, storing a field is a partial assignment of the target
+        self.block.add_declaration_on_next_instruction(target);
+        self.block.add_usage_on_next_instruction(target);
+        self.block.add_usage_on_next_instruction(value);
+
+        self.block
+            .push_instruction(Instruction::StoreField(instructions::StoreField {
+                target,
+                offset,
+                value,
+            }));
+    }
+
+    pub fn slice(
+        &mut self,
+        variable: VariableKey,
+        array: VariableKey,
+        start: VariableKey,
+        end: VariableKey,
+    ) {
+        self.block.add_declaration_on_next_instruction(variable);
+        self.block.add_usage_on_next_instruction(array);
+        self.block.add_usage_on_next_instruction(start);
+        self.block.add_usage_on_next_instruction(end);
+
+        self.block
+            .push_instruction(Instruction::Slice(instructions::Slice {
+                variable,
+                array,
+                start,
+                end,
+            }));
+    }
+
+    pub fn slice_length(&mut self, variable: VariableKey, slice: VariableKey) {
+        self.block.add_declaration_on_next_instruction(variable);
+        self.block.add_usage_on_next_instruction(slice);
+
+        self.block
+            .push_instruction(Instruction::SliceLength(instructions::SliceLength {
+                variable,
+                slice,
+            }));
+    }
+
+    pub fn jump(&mut self, target: BasicBlockKey) {
+        self.block
+            .push_instruction(Instruction::Jump(instructions::Jump { target }));
+    }
+
+    pub fn branch(
+        &mut self,
+        condition: VariableKey,
+        on_true: BasicBlockKey,
+        on_false: BasicBlockKey,
+    ) {
+        self.block.add_usage_on_next_instruction(condition);
+
+        self.block
+            .push_instruction(Instruction::Branch(instructions::Branch {
+                condition,
+                on_true,
+                on_false,
+            }));
+    }
+
     pub fn do_return(&mut self, values: Vec<VariableKey>) {
         for value in &values {
             self.block.add_usage_on_next_instruction(*value);
@@ -127,6 +351,13 @@ impl<'a> BasicBlockEditor<'a> {
             .push_instruction(Instruction::Return(instructions::Return { values }));
     }
 
+    pub fn panic(&mut self, message: VariableKey) {
+        self.block.add_usage_on_next_instruction(message);
+
+        self.block
+            .push_instruction(Instruction::Panic(instructions::Panic { message }));
+    }
+
     pub fn halt(&mut self) {
         self.block.push_instruction(Instruction::Halt);
     }
@@ -12,12 +12,18 @@ pub struct Function {
     pub basicblockstore: BasicBlockStore,
     pub variablestore: VariableStore,
     pub entry: BasicBlockKey,
+    // Variables holding the arguments, in the order they are passed
+    pub params: Vec<VariableKey>,
+    // Types of the values stored in the closure of the function, in order
+    pub captures: Vec<TypeId>,
 }
 
 pub struct FunctionBuilder {
     name: String,
     basicblockstore: BasicBlockStore,
     pub variablestore: VariableStore, // TODO: pub
+    params: Vec<VariableKey>,
+    captures: Vec<TypeId>,
 }
 
 impl FunctionBuilder {
@@ -26,9 +32,24 @@ impl FunctionBuilder {
             name,
             basicblockstore: BasicBlockStore::new(),
             variablestore: VariableStore::new(),
+            params: Vec::new(),
+            captures: Vec::new(),
         }
     }
 
+    // Parameters are assigned on entry, before the first instruction of the entry block
+    pub fn add_param(&mut self, entry: &BasicBlockKey, variable: VariableKey) {
+        self.basicblockstore
+            .get_mut(entry)
+            .add_declaration_on_next_instruction(variable);
+        self.params.push(variable);
+    }
+
+    pub fn add_capture(&mut self, typeid: TypeId) -> usize {
+        self.captures.push(typeid);
+        self.captures.len() - 1
+    }
+
     pub fn create_block(&mut self) -> BasicBlockKey {
         self.basicblockstore.add(BasicBlock::new())
     }
@@ -43,26 +64,20 @@ impl FunctionBuilder {
         self.variablestore.get(&variable)
     }
 
-    pub fn find_last_variable_for_symbol(
-        &self,
-        block: &BasicBlockKey,
-        symbol: &StringKey,
-    ) -> Option<VariableKey> {
-        // TODO: Search through linked blocks
-        let block = self.basicblockstore.get(block);
-        for instr in &block.instructions {
-            match instr {
-                Instruction::Assign(n) => match self.variablestore.get(&n.variable) {
-                    Variable::Named {
-                        symbol: vsym,
-                        typeid: _,
-                    } => {
-                        if *vsym == *symbol {
-                            return Some(n.variable.clone());
-                        }
+    pub fn find_last_variable_for_symbol(&self, symbol: &StringKey) -> Option<VariableKey> {
+        // Named variables are not versioned per block, all assignments to a symbol
+        //  write to the same variable, so the last declared one is the one in scope
+        // TODO: Shadowing in nested scopes will need scope information here
+        for variablekey in self.variablestore.keys().rev() {
+            match self.variablestore.get(&variablekey) {
+                Variable::Named {
+                    symbol: vsym,
+                    typeid: _,
+                } => {
+                    if *vsym == *symbol {
+                        return Some(variablekey);
                     }
-                    _ => {}
-                },
+                }
                 _ => {}
             }
         }
@@ -70,6 +85,27 @@ impl FunctionBuilder {
         None
     }
 
+    pub fn jump(&mut self, from: &BasicBlockKey, to: BasicBlockKey) {
+        self.edit_block(from).jump(to);
+        self.basicblockstore.get_mut(&to).add_incoming_block(*from);
+    }
+
+    pub fn branch(
+        &mut self,
+        from: &BasicBlockKey,
+        condition: VariableKey,
+        on_true: BasicBlockKey,
+        on_false: BasicBlockKey,
+    ) {
+        self.edit_block(from).branch(condition, on_true, on_false);
+        self.basicblockstore
+            .get_mut(&on_true)
+            .add_incoming_block(*from);
+        self.basicblockstore
+            .get_mut(&on_false)
+            .add_incoming_block(*from);
+    }
+
     pub fn add_named_variable(&mut self, symbol: StringKey, typeid: TypeId) -> VariableKey {
         self.variablestore.add(Variable::Named { symbol, typeid })
     }
@@ -147,6 +183,8 @@ impl FunctionBuilder {
             basicblockstore: self.basicblockstore,
             variablestore: self.variablestore,
             entry,
+            params: self.params,
+            captures: self.captures,
         }
     }
 }
@@ -3,6 +3,7 @@ use super::*;
 use crate::utils::objectstore::*;
 use crate::utils::*;
 
+use crate::shared::{BinaryOperationType, UnaryOperationType};
 use crate::typesystem::*;
 
 pub type BasicBlockStore = IndexedObjectStore<BasicBlock>;
@@ -22,7 +23,24 @@ pub enum Instruction {
     Assign(instructions::Assign),
     CallBuiltIn(instructions::CallBuiltIn),
     CallStatic(instructions::CallStatic),
+    CallClosure(instructions::CallClosure),
+    MakeClosure(instructions::MakeClosure),
+    LoadCapture(instructions::LoadCapture),
+    AddressOf(instructions::AddressOf),
+    Load(instructions::Load),
+    Store(instructions::Store),
+    UnaryOp(instructions::UnaryOp),
+    BinOp(instructions::BinOp),
+    LoadElement(instructions::LoadElement),
+    StoreElement(instructions::StoreElement),
+    LoadField(instructions::LoadField),
+    StoreField(instructions::StoreField),
+    Slice(instructions::Slice),
+    SliceLength(instructions::SliceLength),
+    Jump(instructions::Jump),
+    Branch(instructions::Branch),
     Return(instructions::Return),
+    Panic(instructions::Panic),
     Halt,
     Noop, // TODO: This is needed to keep static indexes
 }
@@ -43,17 +61,139 @@ pub mod instructions {
         pub args: Vec<VariableKey>,
     }
 
+    // One variable per returned value, which are passed in the call registers
     #[derive(Debug)]
     pub struct CallStatic {
-        pub variable: VariableKey,
+        pub variables: Vec<VariableKey>,
         pub function: FunctionKey,
         pub args: Vec<VariableKey>,
     }
 
+    // The closure is passed as the first argument, followed by the regular arguments
+    #[derive(Debug)]
+    pub struct CallClosure {
+        pub variables: Vec<VariableKey>,
+        pub closure: VariableKey,
+        pub args: Vec<VariableKey>,
+    }
+
+    // Allocates a closure holding the function and the captured values, in order
+    #[derive(Debug)]
+    pub struct MakeClosure {
+        pub variable: VariableKey,
+        pub function: FunctionKey,
+        pub captures: Vec<VariableKey>,
+    }
+
+    #[derive(Debug)]
+    pub struct LoadCapture {
+        pub variable: VariableKey,
+        pub closure: VariableKey,
+        pub index: usize,
+    }
+
+    // The target has to stay in memory for as long as the address is used
+    #[derive(Debug)]
+    pub struct AddressOf {
+        pub variable: VariableKey,
+        pub target: VariableKey,
+    }
+
+    #[derive(Debug)]
+    pub struct Load {
+        pub variable: VariableKey,
+        pub address: VariableKey,
+    }
+
+    #[derive(Debug)]
+    pub struct Store {
+        pub address: VariableKey,
+        pub value: VariableKey,
+    }
+
+    #[derive(Debug)]
+    pub struct UnaryOp {
+        pub variable: VariableKey,
+        pub op: UnaryOperationType,
+        pub operand: VariableKey,
+    }
+
+    #[derive(Debug)]
+    pub struct BinOp {
+        pub variable: VariableKey,
+        pub op: BinaryOperationType,
+        pub lhs: VariableKey,
+        pub rhs: VariableKey,
+    }
+
+    // Index is not bounds checked, that is done by separate instructions
+    #[derive(Debug)]
+    pub struct LoadElement {
+        pub variable: VariableKey,
+        pub array: VariableKey,
+        pub index: VariableKey,
+    }
+
+    #[derive(Debug)]
+    pub struct StoreElement {
+        pub array: VariableKey,
+        pub index: VariableKey,
+        pub value: VariableKey,
+    }
+
+    // Fields are addressed by their byte offset into the target, like the tag and the
+    //  payload of enums
+    #[derive(Debug)]
+    pub struct LoadField {
+        pub variable: VariableKey,
+        pub target: VariableKey,
+        pub offset: u64,
+    }
+
+    #[derive(Debug)]
+    pub struct StoreField {
+        pub target: VariableKey,
+        pub offset: u64,
+        pub value: VariableKey,
+    }
+
+    // View of the elements start..end, the bounds are checked by separate instructions
+    #[derive(Debug)]
+    pub struct Slice {
+        pub variable: VariableKey,
+        pub array: VariableKey,
+        pub start: VariableKey,
+        pub end: VariableKey,
+    }
+
+    #[derive(Debug)]
+    pub struct SliceLength {
+        pub variable: VariableKey,
+        pub slice: VariableKey,
+    }
+
+    #[derive(Debug)]
+    pub struct Jump {
+        pub target: BasicBlockKey,
+    }
+
+    #[derive(Debug)]
+    pub struct Branch {
+        pub condition: VariableKey,
+        pub on_true: BasicBlockKey,
+        pub on_false: BasicBlockKey,
+    }
+
     #[derive(Debug)]
     pub struct Return {
         pub values: Vec<VariableKey>,
     }
+
+    // Aborts the program with a string message
+    #[derive(Debug)]
+    pub struct Panic {
+        pub message: VariableKey,
+    }
 }
 
 #[derive(Debug, Clone)]
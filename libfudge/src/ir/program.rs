@@ -45,7 +45,7 @@ pub fn print_program(program: &Program) {
         fn data_to_string(data: &Vec<u8>, caplength: usize) -> String {
             let mut ret = String::new();
 
-            for b in &data[0..caplength] {
+            for b in &data[0..caplength.min(data.len())] {
                 ret.push(if (*b as char).is_ascii_graphic() {
                     *b as char
                 } else if (*b as char) == ' ' {
@@ -155,17 +155,155 @@ pub fn print_program(program: &Program) {
                                 call_args_to_string(function, &n.args)
                             )
                         }
+                        Instruction::CallStatic(n) if n.variables.is_empty() => {
+                            format!(
+                                "f{}({})",
+                                n.function,
+                                call_args_to_string(function, &n.args)
+                            )
+                        }
                         Instruction::CallStatic(n) => {
                             format!(
-                                "v{} = f{}({})",
-                                resolve_rhs_variablekey(function, n.variable),
+                                "{} = f{}({})",
+                                call_args_to_string(function, &n.variables),
                                 n.function,
                                 call_args_to_string(function, &n.args)
                             )
                         }
+                        Instruction::CallClosure(n) if n.variables.is_empty() => {
+                            format!(
+                                "v{}({})",
+                                resolve_rhs_variablekey(function, n.closure),
+                                call_args_to_string(function, &n.args)
+                            )
+                        }
+                        Instruction::CallClosure(n) => {
+                            format!(
+                                "{} = v{}({})",
+                                call_args_to_string(function, &n.variables),
+                                resolve_rhs_variablekey(function, n.closure),
+                                call_args_to_string(function, &n.args)
+                            )
+                        }
+                        Instruction::MakeClosure(n) => {
+                            format!(
+                                "v{} = closure f{}[{}]",
+                                resolve_rhs_variablekey(function, n.variable),
+                                n.function,
+                                call_args_to_string(function, &n.captures)
+                            )
+                        }
+                        Instruction::LoadCapture(n) => {
+                            format!(
+                                "v{} = v{}.capture{}",
+                                resolve_rhs_variablekey(function, n.variable),
+                                resolve_rhs_variablekey(function, n.closure),
+                                n.index
+                            )
+                        }
+                        Instruction::AddressOf(n) => {
+                            format!(
+                                "v{} = &v{}",
+                                resolve_rhs_variablekey(function, n.variable),
+                                resolve_rhs_variablekey(function, n.target)
+                            )
+                        }
+                        Instruction::Load(n) => {
+                            format!(
+                                "v{} = *v{}",
+                                resolve_rhs_variablekey(function, n.variable),
+                                resolve_rhs_variablekey(function, n.address)
+                            )
+                        }
+                        Instruction::Store(n) => {
+                            format!(
+                                "*v{} = v{}",
+                                resolve_rhs_variablekey(function, n.address),
+                                resolve_rhs_variablekey(function, n.value)
+                            )
+                        }
+                        Instruction::UnaryOp(n) => {
+                            format!(
+                                "v{} = {:?} v{}",
+                                resolve_rhs_variablekey(function, n.variable),
+                                n.op,
+                                resolve_rhs_variablekey(function, n.operand)
+                            )
+                        }
+                        Instruction::BinOp(n) => {
+                            format!(
+                                "v{} = v{} {:?} v{}",
+                                resolve_rhs_variablekey(function, n.variable),
+                                resolve_rhs_variablekey(function, n.lhs),
+                                n.op,
+                                resolve_rhs_variablekey(function, n.rhs)
+                            )
+                        }
+                        Instruction::LoadElement(n) => {
+                            format!(
+                                "v{} = v{}[v{}]",
+                                resolve_rhs_variablekey(function, n.variable),
+                                resolve_rhs_variablekey(function, n.array),
+                                resolve_rhs_variablekey(function, n.index)
+                            )
+                        }
+                        Instruction::StoreElement(n) => {
+                            format!(
+                                "v{}[v{}] = v{}",
+                                resolve_rhs_variablekey(function, n.array),
+                                resolve_rhs_variablekey(function, n.index),
+                                resolve_rhs_variablekey(function, n.value)
+                            )
+                        }
+                        Instruction::LoadField(n) => {
+                            format!(
+                                "v{} = v{}[+{}]",
+                                resolve_rhs_variablekey(function, n.variable),
+                                resolve_rhs_variablekey(function, n.target),
+                                n.offset
+                            )
+                        }
+                        Instruction::StoreField(n) => {
+                            format!(
+                                "v{}[+{}] = v{}",
+                                resolve_rhs_variablekey(function, n.target),
+                                n.offset,
+                                resolve_rhs_variablekey(function, n.value)
+                            )
+                        }
+                        Instruction::Slice(n) => {
+                            format!(
+                                "v{} = v{}[v{}..v{}]",
+                                resolve_rhs_variablekey(function, n.variable),
+                                resolve_rhs_variablekey(function, n.array),
+                                resolve_rhs_variablekey(function, n.start),
+                                resolve_rhs_variablekey(function, n.end)
+                            )
+                        }
+                        Instruction::SliceLength(n) => {
+                            format!(
+                                "v{} = len v{}",
+                                resolve_rhs_variablekey(function, n.variable),
+                                resolve_rhs_variablekey(function, n.slice)
+                            )
+                        }
+                        Instruction::Jump(n) => {
+                            format!("jump b{}", n.target)
+                        }
+                        Instruction::Branch(n) => {
+                            format!(
+                                "branch v{} ? b{} : b{}",
+                                resolve_rhs_variablekey(function, n.condition),
+                                n.on_true,
+                                n.on_false
+                            )
+                        }
                         Instruction::Return(n) => {
                             format!("return {}", call_args_to_string(function, &n.values))
                         }
+                        Instruction::Panic(n) => {
+                            format!("panic v{}", resolve_rhs_variablekey(function, n.message))
+                        }
                         Instruction::Halt => {
                             format!("halt")
                         }
@@ -196,7 +334,40 @@ pub fn print_program(program: &Program) {
                     Instruction::CallBuiltIn(n) => {
                         print_variable_target_intr(instr, &n.variable, function)
                     }
-                    Instruction::CallStatic(n) => {
+                    Instruction::UnaryOp(n) => {
+                        print_variable_target_intr(instr, &n.variable, function)
+                    }
+                    Instruction::BinOp(n) => {
+                        print_variable_target_intr(instr, &n.variable, function)
+                    }
+                    Instruction::LoadElement(n) => {
+                        print_variable_target_intr(instr, &n.variable, function)
+                    }
+                    Instruction::StoreElement(n) => {
+                        print_variable_target_intr(instr, &n.value, function)
+                    }
+                    Instruction::LoadField(n) => {
+                        print_variable_target_intr(instr, &n.variable, function)
+                    }
+                    Instruction::StoreField(n) => {
+                        print_variable_target_intr(instr, &n.value, function)
+                    }
+                    Instruction::Slice(n) => {
+                        print_variable_target_intr(instr, &n.variable, function)
+                    }
+                    Instruction::SliceLength(n) => {
+                        print_variable_target_intr(instr, &n.variable, function)
+                    }
+                    Instruction::MakeClosure(n) => {
+                        print_variable_target_intr(instr, &n.variable, function)
+                    }
+                    Instruction::LoadCapture(n) => {
+                        print_variable_target_intr(instr, &n.variable, function)
+                    }
+                    Instruction::AddressOf(n) => {
+                        print_variable_target_intr(instr, &n.variable, function)
+                    }
+                    Instruction::Load(n) => {
                         print_variable_target_intr(instr, &n.variable, function)
                     }
                     Instruction::Noop => (), // Just skip noops
@@ -4,246 +4,2910 @@ use crate::ir::*;
 
 use std::collections::HashMap;
 
+use crate::shared::BinaryOperationType;
 use crate::typesystem::*;
 use crate::utils::objectstore::ObjectStore;
 use crate::utils::StringKey;
 
+struct LoopInfo {
+    pub continue_block: BasicBlockKey,
+    pub break_block: BasicBlockKey,
+}
+
+struct FunctionInfo {
+    pub functionref: asg::FunctionRef,
+    // Function literals get their closure as the first parameter
+    pub closure: Option<VariableKey>,
+    // Variables holding the captured values or addresses, and whether they are addresses
+    pub captures: HashMap<asg::ResolvedSymbolReference, (VariableKey, bool)>,
+    // Types bound to the type parameters of the generic function being instantiated, also
+    //  used by the function literals inside it
+    pub typearguments: HashMap<String, TypeId>,
+}
+
 struct CodeGenContext {
     pub function_map: HashMap<asg::FunctionRef, FunctionKey>,
+    // Enclosing loops of the statement being generated, innermost last
+    pub loop_stack: Vec<LoopInfo>,
+    // Functions being generated, function literals are generated inside the enclosing function
+    pub function_stack: Vec<FunctionInfo>,
+    // Instances of generic functions, by the types bound to their type parameters
+    pub instance_map: HashMap<(asg::FunctionRef, Vec<TypeId>), FunctionKey>,
 }
 
 impl CodeGenContext {
     pub fn new() -> Self {
         Self {
             function_map: HashMap::new(),
+            loop_stack: Vec::new(),
+            function_stack: Vec::new(),
+            instance_map: HashMap::new(),
+        }
+    }
+
+    // Types in generic functions refer to their type parameters, replace them with the
+    //  types of the instance being generated
+    pub fn substitute_type(&self, typeid: &TypeId) -> TypeId {
+        match self.function_stack.last() {
+            Some(info) => typeid.substitute(&info.typearguments),
+            None => typeid.clone(),
+        }
+    }
+}
+
+fn get_declaration_type(
+    context: &CodeGenContext,
+    asg: &asg::Asg,
+    symref: &asg::ResolvedSymbolReference,
+) -> TypeId {
+    context.substitute_type(
+        asg.get_scope(&symref.scope)
+            .declarationtypes
+            .get(&symref.symbol)
+            .unwrap(),
+    )
+}
+
+fn get_expression_type(
+    context: &CodeGenContext,
+    scope: &asg::scope::Scope,
+    expressionkey: &asg::ExpressionKey,
+) -> TypeId {
+    context.substitute_type(scope.expressiontypes.get(expressionkey).unwrap())
+}
+
+// Reads a symbol, which is either a variable of this function, captured from an enclosing
+//  function or a function bound by a def
+fn generate_symbol_reference(
+    context: &CodeGenContext,
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &BasicBlockKey,
+    asg: &asg::Asg,
+    symref: &asg::ResolvedSymbolReference,
+) -> Expression {
+    let info = context.function_stack.last();
+    if let Some((variable, byreference)) = info.and_then(|i| i.captures.get(symref)) {
+        if !byreference {
+            return Expression::Variable(*variable);
+        }
+
+        let value =
+            functionbuilder.add_unnamed_variable(get_declaration_type(context, asg, symref));
+        functionbuilder
+            .edit_block(current_block)
+            .load(value, *variable);
+        return Expression::Variable(value);
+    }
+
+    // Recursive calls go through the closure that was called
+    let defined = asg.get_defined_function(symref);
+    if let Some(info) = info.filter(|i| defined == Some(i.functionref)) {
+        if let Some(closure) = info.closure {
+            return Expression::Variable(closure);
+        }
+    }
+
+    // Here, we search through blocks to find last assigned value
+    if let Some(variable) = functionbuilder.find_last_variable_for_symbol(&symref.symbol) {
+        return Expression::Variable(variable);
+    }
+
+    // Functions without captures are not captured, but can be referred to directly
+    if let Some(defined) = defined {
+        assert!(
+            !asg.get_function(&defined).is_generic(),
+            "Generic functions must be called directly"
+        );
+        let function = *context
+            .function_map
+            .get(&defined)
+            .expect(format!("Function {:?} is used before it is defined", symref.symbol).as_str());
+        let closure =
+            functionbuilder.add_unnamed_variable(get_declaration_type(context, asg, symref));
+        functionbuilder
+            .edit_block(current_block)
+            .make_closure(closure, function, Vec::new());
+        return Expression::Variable(closure);
+    }
+
+    panic!(
+        "Cannot find assigned variable for symbol {:?}",
+        symref.symbol
+    )
+}
+
+// Address of a variable captured by reference, taking it keeps the variable in memory
+fn generate_symbol_address(
+    context: &CodeGenContext,
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &BasicBlockKey,
+    asg: &asg::Asg,
+    symref: &asg::ResolvedSymbolReference,
+) -> VariableKey {
+    let target = match context
+        .function_stack
+        .last()
+        .and_then(|i| i.captures.get(symref))
+    {
+        Some((address, true)) => return *address,
+        Some((variable, false)) => *variable,
+        None => functionbuilder
+            .find_last_variable_for_symbol(&symref.symbol)
+            .expect(
+                format!(
+                    "Cannot find assigned variable for symbol {:?}",
+                    symref.symbol
+                )
+                .as_str(),
+            ),
+    };
+
+    let typeid = TypeId::new_reference(get_declaration_type(context, asg, symref));
+    let address = functionbuilder.add_unnamed_variable(typeid);
+    functionbuilder
+        .edit_block(current_block)
+        .address_of(address, target);
+    address
+}
+
+// Address held for a symbol expression captured by reference in the current function
+fn find_captured_address(
+    context: &CodeGenContext,
+    scope: &asg::scope::Scope,
+    expressionkey: &asg::ExpressionKey,
+) -> Option<VariableKey> {
+    let symref = match &scope.expressions.get(expressionkey).object {
+        asg::ExpressionObject::SymbolReference(n) => {
+            match scope.symboltable.references.get(&n.symbolref) {
+                asg::SymbolReference::ResolvedReference(n) => n,
+                asg::SymbolReference::UnresolvedReference(_) => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    match context.function_stack.last()?.captures.get(symref) {
+        Some((address, true)) => Some(*address),
+        _ => None,
+    }
+}
+
+// Generates a function, function literals take their closure as the first parameter and
+//  load the captured values from it on entry. Instances of generic functions are given
+//  the types bound to the type parameters, other functions inherit them.
+fn generate_function(
+    context: &mut CodeGenContext,
+    programbuilder: &mut ProgramBuilder,
+    asg: &asg::Asg,
+    functionref: &asg::FunctionRef,
+    closuretype: Option<TypeId>,
+    typearguments: Option<HashMap<String, TypeId>>,
+) -> FunctionKey {
+    let module = asg.get_module(&functionref.module);
+    let function = asg.get_function(functionref);
+
+    let instance = typearguments.is_some();
+    let name = match &typearguments {
+        Some(typearguments) => format!(
+            "{}.{}<{}>",
+            module.name,
+            function.name,
+            function
+                .typeparams
+                .iter()
+                .map(|t| typearguments[t].to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        None => format!("{}.{}", module.name, function.name),
+    };
+    let mut functionbuilder = FunctionBuilder::new(name);
+    let entry = functionbuilder.create_block();
+
+    // Push early, so parameter types are substituted for the instance
+    let typearguments = typearguments.unwrap_or_else(|| {
+        context
+            .function_stack
+            .last()
+            .map(|info| info.typearguments.clone())
+            .unwrap_or_default()
+    });
+    context.function_stack.push(FunctionInfo {
+        functionref: *functionref,
+        closure: None,
+        captures: HashMap::new(),
+        typearguments,
+    });
+
+    let closure = closuretype.map(|typeid| {
+        let closure = functionbuilder.add_unnamed_variable(typeid);
+        functionbuilder.add_param(&entry, closure);
+        closure
+    });
+
+    for param in &function.inparams {
+        let typeid = get_declaration_type(context, asg, &param.symref);
+        let variable = functionbuilder.add_named_variable(param.symref.symbol.clone(), typeid);
+        functionbuilder.add_param(&entry, variable);
+    }
+
+    let mut captures = HashMap::new();
+    if let Some(closure) = closure {
+        for capture in &function.captures {
+            let decltype = get_declaration_type(context, asg, &capture.symref);
+            let typeid = if capture.byreference {
+                TypeId::new_reference(decltype)
+            } else {
+                decltype
+            };
+
+            let variable = functionbuilder.add_unnamed_variable(typeid.clone());
+            let index = functionbuilder.add_capture(typeid);
+            functionbuilder
+                .edit_block(&entry)
+                .load_capture(variable, closure, index);
+            captures.insert(capture.symref.clone(), (variable, capture.byreference));
+        }
+    }
+
+    let info = context.function_stack.last_mut().unwrap();
+    info.closure = closure;
+    info.captures = captures;
+    let loop_stack = std::mem::take(&mut context.loop_stack);
+
+    let mut current_block = entry;
+    if let Some(body) = &function.body {
+        let scoperef = &asg::ScopeRef {
+            module: functionref.module,
+            scope: body.scope_nonowned,
+        };
+        generate_statement_body(
+            context,
+            programbuilder,
+            &mut functionbuilder,
+            asg,
+            &mut current_block,
+            &scoperef,
+            &body,
+        );
+    }
+
+    context.loop_stack = loop_stack;
+    context.function_stack.pop();
+
+    // Maybe add this in graph generation instead?
+    functionbuilder
+        .edit_block(&current_block)
+        .do_return(Vec::new());
+
+    // TODO: Ideally, this is done while building, so that modifications
+    //  can be done after this
+    // Update variable lifetimes
+    functionbuilder.update_variable_usage();
+
+    let functionkey = programbuilder.add_function(functionbuilder.finish(entry));
+
+    // TODO: This function_map relies on functions being generated before they can be referenced.
+    //  Add an indirect function map, or patch up references afterwards.
+    //  Possibly reserve functionkey in programbuilder before adding/editing the function.
+    if !instance {
+        context.function_map.insert(*functionref, functionkey);
+    }
+    functionkey
+}
+
+// Finds the function a def binds, when the callable refers to it directly
+fn find_called_function(
+    asg: &asg::Asg,
+    scoperef: &asg::ScopeRef,
+    callable: &asg::ExpressionKey,
+) -> Option<asg::FunctionRef> {
+    let scope = asg.get_scope(scoperef);
+    match &scope.expressions.get(callable).object {
+        asg::ExpressionObject::SymbolReference(n) => {
+            match scope.symboltable.references.get(&n.symbolref) {
+                asg::SymbolReference::ResolvedReference(n) => asg.get_defined_function(n),
+                asg::SymbolReference::UnresolvedReference(_) => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+// Closure of the instance of a generic function for the types bound at a call, every
+//  instance is generated once
+fn generate_instance_closure(
+    context: &mut CodeGenContext,
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &BasicBlockKey,
+    asg: &asg::Asg,
+    scoperef: &asg::ScopeRef,
+    functionref: &asg::FunctionRef,
+    callexpr: &asg::ExpressionKey,
+    callable: &asg::ExpressionKey,
+) -> (VariableKey, TypeId) {
+    let function = asg.get_function(functionref);
+    assert!(
+        function.captures.is_empty(),
+        "Generic functions cannot capture variables"
+    );
+
+    // Type arguments inside generic functions may refer to their own type parameters
+    let scope = asg.get_scope(scoperef);
+    let typeargs: Vec<TypeId> = scope.typearguments[callexpr]
+        .iter()
+        .map(|typeid| context.substitute_type(typeid))
+        .collect();
+    let typearguments: HashMap<String, TypeId> = function
+        .typeparams
+        .iter()
+        .cloned()
+        .zip(typeargs.iter().cloned())
+        .collect();
+
+    // The signature only refers to the type parameters of the called function
+    let closuretype = scope
+        .expressiontypes
+        .get(callable)
+        .unwrap()
+        .substitute(&typearguments);
+
+    // Recursive calls go through the closure that was called
+    if let Some(info) = context.function_stack.last() {
+        if info.functionref == *functionref && info.typearguments == typearguments {
+            return (info.closure.unwrap(), closuretype);
+        }
+    }
+
+    let key = (*functionref, typeargs);
+    let instance = match context.instance_map.get(&key) {
+        Some(instance) => *instance,
+        None => {
+            let instance = generate_function(
+                context,
+                programbuilder,
+                asg,
+                functionref,
+                Some(closuretype.clone()),
+                Some(typearguments.clone()),
+            );
+            context.instance_map.insert(key, instance);
+            instance
+        }
+    };
+
+    let closure = functionbuilder.add_unnamed_variable(closuretype.clone());
+    functionbuilder
+        .edit_block(current_block)
+        .make_closure(closure, instance, Vec::new());
+    (closure, closuretype)
+}
+
+// Calls a closure, returning one variable per returned value
+fn generate_closure_call(
+    context: &mut CodeGenContext,
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &mut BasicBlockKey,
+    asg: &asg::Asg,
+    scoperef: &asg::ScopeRef,
+    callexpr: &asg::ExpressionKey,
+    call: &asg::expressions::Call,
+) -> Vec<VariableKey> {
+    // Generic functions are called through the closure of an instance
+    let calledfunction = find_called_function(asg, scoperef, &call.callable);
+    let (closure, closuretype) = match calledfunction {
+        Some(functionref) if asg.get_function(&functionref).is_generic() => {
+            generate_instance_closure(
+                context,
+                programbuilder,
+                functionbuilder,
+                current_block,
+                asg,
+                scoperef,
+                &functionref,
+                callexpr,
+                &call.callable,
+            )
+        }
+        _ => {
+            let closure = generate_expression(
+                context,
+                programbuilder,
+                functionbuilder,
+                current_block,
+                asg,
+                scoperef,
+                &call.callable,
+            );
+            (
+                store_in_variable(functionbuilder, current_block, closure),
+                get_expression_type(context, asg.get_scope(scoperef), &call.callable),
+            )
+        }
+    };
+
+    // Named and default arguments need the function a def binds, other callables
+    //  only take positional arguments
+    let argexprs: Vec<(asg::ScopeRef, asg::ExpressionKey)> =
+        match find_called_function(asg, scoperef, &call.callable) {
+            Some(functionref) => {
+                let function = asg.get_function(&functionref);
+                let functionscope = asg::ScopeRef::new(functionref.module, function.scope);
+                function
+                    .resolve_call_arguments(asg, call)
+                    .unwrap_or_else(|e| panic!("{}", e.message))
+                    .into_iter()
+                    .map(|arg| match arg {
+                        asg::CallArgument::Supplied(expr) => (*scoperef, expr),
+                        // Default arguments belong to the scope of the called function
+                        asg::CallArgument::Default(expr) => (functionscope, expr),
+                    })
+                    .collect()
+            }
+            None => call.args.iter().map(|arg| (*scoperef, *arg)).collect(),
+        };
+
+    let mut args = Vec::new();
+    for (argscope, arg) in &argexprs {
+        let expr = generate_expression(
+            context,
+            programbuilder,
+            functionbuilder,
+            current_block,
+            asg,
+            argscope,
+            arg,
+        );
+        args.push(store_in_variable(functionbuilder, current_block, expr));
+    }
+
+    let returntypes = match closuretype {
+        TypeId::Function(signature) => signature.outputparams.clone(),
+        n => panic!("Type {:?} not supported as callable", n),
+    };
+    let variables: Vec<_> = returntypes
+        .into_iter()
+        .map(|typeid| functionbuilder.add_unnamed_variable(typeid))
+        .collect();
+
+    functionbuilder
+        .edit_block(current_block)
+        .call_closure(variables.clone(), closure, args);
+    variables
+}
+
+// Makes sure an expression lives in a variable, so it can be used as an operand
+fn store_in_variable(
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &BasicBlockKey,
+    expression: Expression,
+) -> VariableKey {
+    match expression {
+        Expression::Variable(n) => n,
+        Expression::Constant(_) => {
+            let etype = expression.get_type(&functionbuilder.variablestore);
+            let variable = functionbuilder.add_unnamed_variable(etype);
+            functionbuilder
+                .edit_block(current_block)
+                .assign(variable, expression);
+            variable
+        }
+    }
+}
+
+// Tuples have no variable of their own, each element is a named variable of its own
+fn tuple_element_symbol(symbol: &str, index: usize) -> asg::SymbolKey {
+    asg::SymbolKey::from_str(format!("{}.{}", symbol, index).as_str())
+}
+
+// Offset of the element in a tuple packed into a single variable, elements are stored
+//  back to back
+fn tuple_element_offset(elements: &[TypeId], index: usize) -> u64 {
+    elements[..index].iter().map(|e| e.size()).sum()
+}
+
+// Generates the elements of a tuple expression, one variable per element
+fn generate_tuple(
+    context: &mut CodeGenContext,
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &mut BasicBlockKey,
+    asg: &asg::Asg,
+    scoperef: &asg::ScopeRef,
+    expressionkey: &asg::ExpressionKey,
+) -> Vec<VariableKey> {
+    let scope = asg.get_scope(scoperef);
+    match &scope.expressions.get(expressionkey).object {
+        asg::ExpressionObject::Literal(asg::expressions::Literal::TupleLiteral(n)) => n
+            .elements
+            .iter()
+            .map(|e| {
+                let element = generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    e,
+                );
+                store_in_variable(functionbuilder, current_block, element)
+            })
+            .collect(),
+        asg::ExpressionObject::SymbolReference(n) => {
+            let sref = match scope.symboltable.references.get(&n.symbolref) {
+                asg::SymbolReference::ResolvedReference(n) => n,
+                asg::SymbolReference::UnresolvedReference(n) => {
+                    panic!("Unresolved reference! {:?}", n)
+                }
+            };
+            let symbol = &asg
+                .get_scope(&sref.scope)
+                .symboltable
+                .declarations
+                .get(&sref.symbol)
+                .symbol;
+            let length = match &get_expression_type(context, scope, expressionkey) {
+                TypeId::Tuple(elements) => elements.len(),
+                n => panic!("Expected a tuple, got {:?}", n),
+            };
+
+            (0..length)
+                .map(|i| {
+                    functionbuilder
+                        .find_last_variable_for_symbol(&tuple_element_symbol(symbol, i))
+                        .expect(format!("Cannot find assigned tuple {}", symbol).as_str())
+                })
+                .collect()
+        }
+        asg::ExpressionObject::Call(n) => generate_closure_call(
+            context,
+            programbuilder,
+            functionbuilder,
+            current_block,
+            asg,
+            scoperef,
+            expressionkey,
+            n,
+        ),
+        n => panic!("Unsupported tuple expression {:?}", n),
+    }
+}
+
+// Branches to a panic if the condition does not hold
+fn generate_runtime_check(
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &mut BasicBlockKey,
+    condition: VariableKey,
+    message: String,
+) {
+    let ok_block = functionbuilder.create_block();
+    let error_block = functionbuilder.create_block();
+    functionbuilder.branch(current_block, condition, ok_block, error_block);
+
+    *current_block = error_block;
+    let message = create_constant_staticstringutf8(message.as_str());
+    let message = Expression::Constant(Value::Primitive {
+        ptype: PrimitiveType::StaticStringUtf8,
+        data: programbuilder.add_constantdata(message) as u64,
+    });
+    let message = store_in_variable(functionbuilder, current_block, message);
+    functionbuilder.edit_block(current_block).panic(message);
+    functionbuilder.jump(current_block, ok_block);
+
+    *current_block = ok_block;
+}
+
+// Branches to a panic if the index is not below the length
+fn generate_bounds_check(
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &mut BasicBlockKey,
+    index: VariableKey,
+    length: VariableKey,
+    message: String,
+) {
+    // Compared with the length as lhs, since it is never of a smaller type than the index
+    let in_bounds = functionbuilder.add_unnamed_variable(TypeId::Primitive(PrimitiveType::Bool));
+    functionbuilder.edit_block(current_block).binary_op(
+        in_bounds,
+        BinaryOperationType::GreaterThan,
+        length,
+        index,
+    );
+
+    generate_runtime_check(
+        programbuilder,
+        functionbuilder,
+        current_block,
+        in_bounds,
+        message,
+    );
+}
+
+// Length of a static array, dynamic array or slice, as a u64
+fn generate_array_length(
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &BasicBlockKey,
+    array: VariableKey,
+) -> VariableKey {
+    let arraytype = functionbuilder
+        .variablestore
+        .get(&array)
+        .get_type(&functionbuilder.variablestore)
+        .clone();
+
+    let length = functionbuilder.add_unnamed_variable(TypeId::Primitive(PrimitiveType::U64));
+    match arraytype {
+        TypeId::Array {
+            element: _,
+            length: n,
+        } => {
+            functionbuilder.edit_block(current_block).assign(
+                length,
+                Expression::Constant(Value::Primitive {
+                    ptype: PrimitiveType::U64,
+                    data: n,
+                }),
+            );
+        }
+        TypeId::DynamicArray { .. } => {
+            functionbuilder.edit_block(current_block).call_builtin(
+                length,
+                BuiltInFunction::ArrayLen,
+                vec![array],
+            );
+        }
+        TypeId::Slice { .. } => {
+            functionbuilder
+                .edit_block(current_block)
+                .slice_length(length, array);
+        }
+        n => panic!("Cannot get the length of type {:?}", n),
+    }
+    length
+}
+
+// Evaluates and bounds checks the index of an index expression
+fn generate_index(
+    context: &mut CodeGenContext,
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &mut BasicBlockKey,
+    asg: &asg::Asg,
+    scoperef: &asg::ScopeRef,
+    array: VariableKey,
+    index: &asg::expressions::Index,
+) -> VariableKey {
+    let indexexpr = generate_expression(
+        context,
+        programbuilder,
+        functionbuilder,
+        current_block,
+        asg,
+        scoperef,
+        &index.index,
+    );
+    let indexvar = store_in_variable(functionbuilder, current_block, indexexpr);
+
+    let ptype = match functionbuilder
+        .variablestore
+        .get(&indexvar)
+        .get_type(&functionbuilder.variablestore)
+    {
+        TypeId::Primitive(n) => *n,
+        n => panic!("Unsupported index type: {:?}", n),
+    };
+
+    let scope = asg.get_scope(scoperef);
+    match &get_expression_type(context, scope, &index.expr) {
+        TypeId::Array { element: _, length } => {
+            // Indices of smaller types cannot go out of bounds of long arrays
+            if ptype.size() < 8 && *length >= 1 << (ptype.size() * 8) {
+                return indexvar;
+            }
+
+            let lengthvar = store_in_variable(
+                functionbuilder,
+                current_block,
+                Expression::Constant(Value::Primitive {
+                    ptype,
+                    data: *length,
+                }),
+            );
+            generate_bounds_check(
+                programbuilder,
+                functionbuilder,
+                current_block,
+                indexvar,
+                lengthvar,
+                format!(
+                    "Index out of bounds for array of length {} at {}",
+                    length, index.location
+                ),
+            );
+        }
+        arraytype @ (TypeId::DynamicArray { .. } | TypeId::Slice { .. }) => {
+            let kind = match arraytype {
+                TypeId::Slice { .. } => "slice",
+                _ => "dynamic array",
+            };
+            let lengthvar = generate_array_length(functionbuilder, current_block, array);
+            generate_bounds_check(
+                programbuilder,
+                functionbuilder,
+                current_block,
+                indexvar,
+                lengthvar,
+                format!("Index out of bounds for {} at {}", kind, index.location),
+            );
+        }
+        n => panic!("Cannot index type {:?}", n),
+    }
+
+    indexvar
+}
+
+// Appends a value to a dynamic array, the built-in only makes room for it
+fn generate_array_push(
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &BasicBlockKey,
+    array: VariableKey,
+    value: VariableKey,
+) {
+    let index = functionbuilder.add_unnamed_variable(TypeId::Primitive(PrimitiveType::U64));
+    functionbuilder.edit_block(current_block).call_builtin(
+        index,
+        BuiltInFunction::ArrayPush,
+        vec![array],
+    );
+    functionbuilder
+        .edit_block(current_block)
+        .store_element(array, index, value);
+}
+
+// Array built-ins only manage the array storage, elements are moved in and out
+//  with regular element instructions
+fn generate_array_builtin_call(
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &BasicBlockKey,
+    builtin: BuiltInFunction,
+    args: &[Expression],
+    returntype: &TypeId,
+) -> VariableKey {
+    let array = store_in_variable(functionbuilder, current_block, args[0].clone());
+
+    match builtin {
+        BuiltInFunction::ArrayPush => {
+            let value = store_in_variable(functionbuilder, current_block, args[1].clone());
+            generate_array_push(functionbuilder, current_block, array, value);
+            functionbuilder.add_unnamed_variable(TypeId::Null)
+        }
+        BuiltInFunction::ArrayPop => {
+            let index = functionbuilder.add_unnamed_variable(TypeId::Primitive(PrimitiveType::U64));
+            functionbuilder.edit_block(current_block).call_builtin(
+                index,
+                BuiltInFunction::ArrayPop,
+                vec![array],
+            );
+
+            let element = functionbuilder.add_unnamed_variable(returntype.clone());
+            functionbuilder
+                .edit_block(current_block)
+                .load_element(element, array, index);
+            element
+        }
+        BuiltInFunction::ArrayLen => generate_array_length(functionbuilder, current_block, array),
+        BuiltInFunction::ArrayClear => {
+            let returnvalue = functionbuilder.add_unnamed_variable(returntype.clone());
+            functionbuilder.edit_block(current_block).call_builtin(
+                returnvalue,
+                builtin,
+                vec![array],
+            );
+            returnvalue
         }
+        n => panic!("{} is not an array built-in", n.to_str()),
     }
 }
 
-fn generate_expression(
-    context: &mut CodeGenContext,
-    programbuilder: &mut ProgramBuilder,
-    functionbuilder: &mut FunctionBuilder,
-    current_block: &BasicBlockKey,
-    asg: &asg::Asg,
-    scoperef: &asg::ScopeRef,
-    expressionkey: &asg::ExpressionKey,
-) -> Expression {
-    let scope = asg.get_scope(scoperef);
-    let etype = scope.expressiontypes.get(expressionkey).unwrap();
-    match &scope.expressions.get(expressionkey).object {
-        asg::ExpressionObject::Literal(n) => match n {
-            asg::expressions::Literal::StringLiteral(n) => {
-                let data = create_constant_staticstringutf8(&*n.string);
+// Stores a value into an element of an array. Nested elements are loaded to temporaries,
+//  so these are written back from the innermost element and out.
+fn generate_element_store(
+    context: &mut CodeGenContext,
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &mut BasicBlockKey,
+    asg: &asg::Asg,
+    scoperef: &asg::ScopeRef,
+    expressionkey: &asg::ExpressionKey,
+    value: VariableKey,
+) {
+    let scope = asg.get_scope(scoperef);
+
+    // Collect the index expressions, from the outermost array and in
+    let mut chain = Vec::new();
+    let mut base = *expressionkey;
+    while let asg::ExpressionObject::Index(n) = &scope.expressions.get(&base).object {
+        chain.push((base, n));
+        base = n.expr;
+    }
+    chain.reverse();
+
+    let basearray = generate_expression(
+        context,
+        programbuilder,
+        functionbuilder,
+        current_block,
+        asg,
+        scoperef,
+        &base,
+    );
+    let mut arrays = vec![store_in_variable(functionbuilder, current_block, basearray)];
+    let mut indices = Vec::new();
+    for (i, (exprkey, index)) in chain.iter().enumerate() {
+        let indexvar = generate_index(
+            context,
+            programbuilder,
+            functionbuilder,
+            current_block,
+            asg,
+            scoperef,
+            *arrays.last().unwrap(),
+            index,
+        );
+        indices.push(indexvar);
+
+        if i + 1 < chain.len() {
+            let elementtype = &get_expression_type(context, scope, exprkey);
+            let element = functionbuilder.add_unnamed_variable(elementtype.clone());
+            functionbuilder.edit_block(current_block).load_element(
+                element,
+                *arrays.last().unwrap(),
+                indexvar,
+            );
+            arrays.push(element);
+        }
+    }
+
+    let mut value = value;
+    for (array, index) in arrays.iter().zip(indices.iter()).rev() {
+        functionbuilder
+            .edit_block(current_block)
+            .store_element(*array, *index, value);
+        value = *array;
+    }
+}
+
+// Enum values are built in place, the tag of the variant followed by its fields
+fn generate_enum_value(
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &BasicBlockKey,
+    definition: &EnumDefinition,
+    variant: &str,
+    fields: &[VariableKey],
+) -> VariableKey {
+    let (index, _) = definition
+        .get_variant(&asg::SymbolKey::from_str(variant))
+        .unwrap();
+
+    let value = functionbuilder.add_unnamed_variable(TypeId::Enum(definition.clone()));
+    let tag = store_in_variable(
+        functionbuilder,
+        current_block,
+        Expression::Constant(Value::Primitive {
+            ptype: definition.tag_type(),
+            data: index as u64,
+        }),
+    );
+    functionbuilder
+        .edit_block(current_block)
+        .store_field(value, 0, tag);
+    for (i, field) in fields.iter().enumerate() {
+        functionbuilder.edit_block(current_block).store_field(
+            value,
+            definition.field_offset(index, i),
+            *field,
+        );
+    }
+    value
+}
+
+// Optional values are built in place like enums, a tag that is zero for null followed by
+//  the value
+fn generate_optional_value(
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &BasicBlockKey,
+    optionaltype: &TypeId,
+    value: Option<VariableKey>,
+) -> VariableKey {
+    let optional = functionbuilder.add_unnamed_variable(optionaltype.clone());
+    let tag = store_in_variable(
+        functionbuilder,
+        current_block,
+        Expression::Constant(Value::Primitive {
+            ptype: PrimitiveType::U8,
+            data: value.is_some() as u64,
+        }),
+    );
+    functionbuilder
+        .edit_block(current_block)
+        .store_field(optional, 0, tag);
+    if let Some(value) = value {
+        functionbuilder.edit_block(current_block).store_field(
+            optional,
+            TypeId::OPTIONAL_VALUE_OFFSET,
+            value,
+        );
+    }
+    optional
+}
+
+// Default values are all zeroes for primitives, null for optionals, and built from the
+//  defaults of the fields for structs and the first variant of enums
+fn generate_default_value(
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &BasicBlockKey,
+    typeid: &TypeId,
+) -> VariableKey {
+    match typeid {
+        TypeId::Primitive(ptype) => store_in_variable(
+            functionbuilder,
+            current_block,
+            Expression::Constant(Value::Primitive {
+                ptype: *ptype,
+                data: 0,
+            }),
+        ),
+        TypeId::Optional(_) => {
+            generate_optional_value(functionbuilder, current_block, typeid, None)
+        }
+        TypeId::Struct(definition) => {
+            let value = functionbuilder.add_unnamed_variable(typeid.clone());
+            for (symbol, fieldtype) in &definition.fields {
+                let field = generate_default_value(functionbuilder, current_block, fieldtype);
+                functionbuilder.edit_block(current_block).store_field(
+                    value,
+                    definition.field_offset(symbol).unwrap(),
+                    field,
+                );
+            }
+            value
+        }
+        TypeId::Enum(definition) => {
+            let variant = &definition.variants[0];
+            let fields: Vec<_> = variant
+                .fields
+                .iter()
+                .map(|(_, fieldtype)| {
+                    generate_default_value(functionbuilder, current_block, fieldtype)
+                })
+                .collect();
+            generate_enum_value(
+                functionbuilder,
+                current_block,
+                definition,
+                &variant.name.to_string(),
+                &fields,
+            )
+        }
+        n => todo!("Default values of type {} are not supported", n.to_string()),
+    }
+}
+
+fn generate_optional_tag(
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &BasicBlockKey,
+    optional: VariableKey,
+) -> VariableKey {
+    let tag = functionbuilder.add_unnamed_variable(TypeId::Primitive(PrimitiveType::U8));
+    functionbuilder
+        .edit_block(current_block)
+        .load_field(tag, optional, 0);
+    tag
+}
+
+// Symbol expression of an optional that is read as its value, since it was narrowed by
+//  comparing it to null. Gives the symbol along with its optional type.
+fn find_narrowed_optional<'a>(
+    context: &CodeGenContext,
+    asg: &asg::Asg,
+    scope: &'a asg::scope::Scope,
+    expressionkey: &asg::ExpressionKey,
+) -> Option<(&'a asg::ResolvedSymbolReference, TypeId)> {
+    let symref = match &scope.expressions.get(expressionkey).object {
+        asg::ExpressionObject::SymbolReference(n) => {
+            match scope.symboltable.references.get(&n.symbolref) {
+                asg::SymbolReference::ResolvedReference(n) => n,
+                asg::SymbolReference::UnresolvedReference(_) => return None,
+            }
+        }
+        _ => return None,
+    };
+
+    let declared = asg
+        .get_scope(&symref.scope)
+        .declarationtypes
+        .get(&symref.symbol)?;
+    let declared = context.substitute_type(declared);
+    let narrowed = matches!(declared, TypeId::Optional(_))
+        && !matches!(
+            get_expression_type(context, scope, expressionkey),
+            TypeId::Optional(_)
+        );
+    narrowed.then(|| (symref, declared))
+}
+
+fn is_null_literal(scope: &asg::scope::Scope, expressionkey: &asg::ExpressionKey) -> bool {
+    matches!(
+        scope.expressions.get(expressionkey).object,
+        asg::ExpressionObject::Literal(asg::expressions::Literal::NullLiteral(_))
+    )
+}
+
+// Compares the matched value and continues in a new block if the comparison holds,
+//  otherwise branches to the block of the next arm
+fn generate_pattern_comparison(
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &mut BasicBlockKey,
+    op: BinaryOperationType,
+    value: VariableKey,
+    operand: VariableKey,
+    next_block: BasicBlockKey,
+) {
+    let condition = functionbuilder.add_unnamed_variable(TypeId::Primitive(PrimitiveType::Bool));
+    functionbuilder
+        .edit_block(current_block)
+        .binary_op(condition, op, value, operand);
+
+    let matched_block = functionbuilder.create_block();
+    functionbuilder.branch(current_block, condition, matched_block, next_block);
+    *current_block = matched_block;
+}
+
+// Tests the value against the pattern, binding the symbols of the pattern in the block
+//  it continues in when the value matches
+#[allow(clippy::too_many_arguments)]
+fn generate_pattern_test(
+    context: &mut CodeGenContext,
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &mut BasicBlockKey,
+    asg: &asg::Asg,
+    armscoperef: &asg::ScopeRef,
+    value: VariableKey,
+    valuetype: &TypeId,
+    pattern: &asg::misc::Pattern,
+    next_block: BasicBlockKey,
+) {
+    let armscope = asg.get_scope(armscoperef);
+    match pattern {
+        asg::misc::Pattern::Wildcard => (),
+        asg::misc::Pattern::Binding(symbol) => {
+            let symbolkey = asg::SymbolKey::from_str(symbol);
+            let decltype =
+                context.substitute_type(armscope.declarationtypes.get(&symbolkey).unwrap());
+            let binding = functionbuilder.add_named_variable(symbolkey, decltype);
+            functionbuilder
+                .edit_block(current_block)
+                .assign(binding, Expression::Variable(value));
+        }
+        asg::misc::Pattern::Literal(n) => {
+            let literal = generate_expression(
+                context,
+                programbuilder,
+                functionbuilder,
+                current_block,
+                asg,
+                armscoperef,
+                n,
+            );
+            let literal = store_in_variable(functionbuilder, current_block, literal);
+            generate_pattern_comparison(
+                functionbuilder,
+                current_block,
+                BinaryOperationType::Equals,
+                value,
+                literal,
+                next_block,
+            );
+        }
+        asg::misc::Pattern::Range { start, end } => {
+            for (bound, op) in [
+                (start, BinaryOperationType::GreaterThanOrEq),
+                (end, BinaryOperationType::LessThan),
+            ] {
+                let bound = generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    armscoperef,
+                    bound,
+                );
+                let bound = store_in_variable(functionbuilder, current_block, bound);
+                generate_pattern_comparison(
+                    functionbuilder,
+                    current_block,
+                    op,
+                    value,
+                    bound,
+                    next_block,
+                );
+            }
+        }
+        asg::misc::Pattern::Variant(pattern) => {
+            let definition = match valuetype {
+                TypeId::Enum(definition) => definition,
+                n => panic!("Cannot match variant on non-enum type {:?}", n),
+            };
+            let (index, _) = definition
+                .get_variant(&asg::SymbolKey::from_str(&pattern.name))
+                .unwrap();
+
+            let tag =
+                functionbuilder.add_unnamed_variable(TypeId::Primitive(definition.tag_type()));
+            functionbuilder
+                .edit_block(current_block)
+                .load_field(tag, value, 0);
+            let variant = store_in_variable(
+                functionbuilder,
+                current_block,
+                Expression::Constant(Value::Primitive {
+                    ptype: definition.tag_type(),
+                    data: index as u64,
+                }),
+            );
+            generate_pattern_comparison(
+                functionbuilder,
+                current_block,
+                BinaryOperationType::Equals,
+                tag,
+                variant,
+                next_block,
+            );
+
+            for (i, binding) in pattern.bindings.iter().enumerate() {
+                if let Some(binding) = binding {
+                    let symbolkey = asg::SymbolKey::from_str(binding);
+                    let decltype =
+                        context.substitute_type(armscope.declarationtypes.get(&symbolkey).unwrap());
+                    let field = functionbuilder.add_named_variable(symbolkey, decltype);
+                    functionbuilder.edit_block(current_block).load_field(
+                        field,
+                        value,
+                        definition.field_offset(index, i),
+                    );
+                }
+            }
+        }
+        // Fields and elements are tested in order, the first mismatch skips the rest
+        asg::misc::Pattern::Struct(fields) => {
+            let definition = match valuetype {
+                TypeId::Struct(definition) => definition,
+                n => panic!("Cannot match struct pattern on type {:?}", n),
+            };
+            for (field, fieldpattern) in fields {
+                let symbolkey = asg::SymbolKey::from_str(field);
+                let fieldtype = definition.get_field(&symbolkey).unwrap();
+                let fieldvalue = functionbuilder.add_unnamed_variable(fieldtype.clone());
+                functionbuilder.edit_block(current_block).load_field(
+                    fieldvalue,
+                    value,
+                    definition.field_offset(&symbolkey).unwrap(),
+                );
+                generate_pattern_test(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    armscoperef,
+                    fieldvalue,
+                    fieldtype,
+                    fieldpattern,
+                    next_block,
+                );
+            }
+        }
+        asg::misc::Pattern::Tuple(elementpatterns) => {
+            let elements = match valuetype {
+                TypeId::Tuple(elements) => elements,
+                n => panic!("Cannot match tuple pattern on type {:?}", n),
+            };
+            for (i, (elementpattern, elementtype)) in
+                elementpatterns.iter().zip(elements).enumerate()
+            {
+                let element = functionbuilder.add_unnamed_variable(elementtype.clone());
+                functionbuilder.edit_block(current_block).load_field(
+                    element,
+                    value,
+                    tuple_element_offset(elements, i),
+                );
+                generate_pattern_test(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    armscoperef,
+                    element,
+                    elementtype,
+                    elementpattern,
+                    next_block,
+                );
+            }
+        }
+    }
+}
+
+// Continues in a new block for the arm if the value matches the pattern and the guard
+//  holds. Returns the block to test the next arm in.
+#[allow(clippy::too_many_arguments)]
+fn generate_match_arm_test(
+    context: &mut CodeGenContext,
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &mut BasicBlockKey,
+    asg: &asg::Asg,
+    armscoperef: &asg::ScopeRef,
+    value: VariableKey,
+    valuetype: &TypeId,
+    pattern: &asg::misc::Pattern,
+    guard: &Option<asg::ExpressionKey>,
+) -> BasicBlockKey {
+    let next_block = functionbuilder.create_block();
+    generate_pattern_test(
+        context,
+        programbuilder,
+        functionbuilder,
+        current_block,
+        asg,
+        armscoperef,
+        value,
+        valuetype,
+        pattern,
+        next_block,
+    );
+
+    if let Some(guard) = guard {
+        let condition = generate_expression(
+            context,
+            programbuilder,
+            functionbuilder,
+            current_block,
+            asg,
+            armscoperef,
+            guard,
+        );
+        let condition = store_in_variable(functionbuilder, current_block, condition);
+
+        let guarded_block = functionbuilder.create_block();
+        functionbuilder.branch(current_block, condition, guarded_block, next_block);
+        *current_block = guarded_block;
+    }
+
+    next_block
+}
+
+// Loads the matched value, along with its type. Tuples are packed into a single variable,
+//  so patterns can test their elements like fields.
+fn generate_match_value(
+    context: &mut CodeGenContext,
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &mut BasicBlockKey,
+    asg: &asg::Asg,
+    scoperef: &asg::ScopeRef,
+    expr: &asg::ExpressionKey,
+) -> (VariableKey, TypeId) {
+    let valuetype = get_expression_type(context, asg.get_scope(scoperef), expr);
+
+    if let TypeId::Tuple(elements) = &valuetype {
+        let values = generate_tuple(
+            context,
+            programbuilder,
+            functionbuilder,
+            current_block,
+            asg,
+            scoperef,
+            expr,
+        );
+        let tuple = functionbuilder.add_unnamed_variable(valuetype.clone());
+        for (i, element) in values.into_iter().enumerate() {
+            functionbuilder.edit_block(current_block).store_field(
+                tuple,
+                tuple_element_offset(elements, i),
+                element,
+            );
+        }
+        return (tuple, valuetype);
+    }
+
+    let value = generate_expression(
+        context,
+        programbuilder,
+        functionbuilder,
+        current_block,
+        asg,
+        scoperef,
+        expr,
+    );
+    let value = store_in_variable(functionbuilder, current_block, value);
+
+    (value, valuetype)
+}
+
+// Generates an expression, plain values used as optionals are wrapped in one
+fn generate_expression(
+    context: &mut CodeGenContext,
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &mut BasicBlockKey,
+    asg: &asg::Asg,
+    scoperef: &asg::ScopeRef,
+    expressionkey: &asg::ExpressionKey,
+) -> Expression {
+    let expression = generate_plain_expression(
+        context,
+        programbuilder,
+        functionbuilder,
+        current_block,
+        asg,
+        scoperef,
+        expressionkey,
+    );
+
+    match asg.get_scope(scoperef).optionalvalues.get(expressionkey) {
+        Some(optionaltype) => {
+            let optionaltype = context.substitute_type(optionaltype);
+            let value = store_in_variable(functionbuilder, current_block, expression);
+            Expression::Variable(generate_optional_value(
+                functionbuilder,
+                current_block,
+                &optionaltype,
+                Some(value),
+            ))
+        }
+        None => expression,
+    }
+}
+
+fn generate_plain_expression(
+    context: &mut CodeGenContext,
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    current_block: &mut BasicBlockKey,
+    asg: &asg::Asg,
+    scoperef: &asg::ScopeRef,
+    expressionkey: &asg::ExpressionKey,
+) -> Expression {
+    let scope = asg.get_scope(scoperef);
+    let etype = &get_expression_type(context, scope, expressionkey);
+    match &scope.expressions.get(expressionkey).object {
+        asg::ExpressionObject::Literal(n) => match n {
+            asg::expressions::Literal::StringLiteral(n) => {
+                let data = create_constant_staticstringutf8(&*n.string);
+
+                let constdata = programbuilder.add_constantdata(data);
+
+                // TODO: This is awkward, a Value should be able to hold its
+                //  own value.
+                // If it did, we would not need to refcount constdata.
+                // On the other hand, staticstringutf8 is a reftype, we are not passing the bytes around
+                Expression::Constant(Value::Primitive {
+                    ptype: PrimitiveType::StaticStringUtf8,
+                    data: constdata as u64,
+                })
+            }
+            asg::expressions::Literal::BoolLiteral(n) => Expression::Constant(Value::Primitive {
+                ptype: PrimitiveType::Bool,
+                data: n.value as u64,
+            }),
+            asg::expressions::Literal::IntegerLiteral(n) => {
+                let ptype = match etype {
+                    TypeId::Primitive(n) => n,
+                    _ => panic!("Unsupported integer literal type: {:?}", etype),
+                };
+                let value = Value::Primitive {
+                    ptype: *ptype,
+                    data: n.data,
+                };
+                Expression::Constant(value)
+            }
+            asg::expressions::Literal::FloatLiteral(n) => {
+                // Floats are stored as their bit pattern
+                let (ptype, data) = match etype {
+                    TypeId::Primitive(PrimitiveType::F32) => {
+                        (PrimitiveType::F32, (n.value as f32).to_bits() as u64)
+                    }
+                    TypeId::Primitive(PrimitiveType::F64) => {
+                        (PrimitiveType::F64, n.value.to_bits())
+                    }
+                    _ => panic!("Unsupported float literal type: {:?}", etype),
+                };
+                Expression::Constant(Value::Primitive { ptype, data })
+            }
+            asg::expressions::Literal::CharLiteral(n) => Expression::Constant(Value::Primitive {
+                ptype: PrimitiveType::Char,
+                data: n.value as u64,
+            }),
+            asg::expressions::Literal::NullLiteral(_) => Expression::Variable(
+                generate_optional_value(functionbuilder, current_block, etype, None),
+            ),
+            asg::expressions::Literal::StructLiteral(_) => todo!(),
+            asg::expressions::Literal::EnumLiteral(_) => {
+                panic!("Enum types only exist at compile time")
+            }
+            asg::expressions::Literal::TupleLiteral(_) => {
+                panic!("Tuples are generated per element, not as a single expression")
+            }
+            asg::expressions::Literal::ArrayLiteral(n) => {
+                let array = functionbuilder.add_unnamed_variable(etype.clone());
+
+                // Dynamic arrays are allocated empty and then pushed to
+                if let TypeId::DynamicArray { element } = etype {
+                    let elementsize = store_in_variable(
+                        functionbuilder,
+                        current_block,
+                        Expression::Constant(Value::Primitive {
+                            ptype: PrimitiveType::U64,
+                            data: element.size(),
+                        }),
+                    );
+                    functionbuilder.edit_block(current_block).call_builtin(
+                        array,
+                        BuiltInFunction::ArrayNew,
+                        vec![elementsize],
+                    );
+
+                    for e in &n.elements {
+                        let element = generate_expression(
+                            context,
+                            programbuilder,
+                            functionbuilder,
+                            current_block,
+                            asg,
+                            scoperef,
+                            e,
+                        );
+                        let element = store_in_variable(functionbuilder, current_block, element);
+                        generate_array_push(functionbuilder, current_block, array, element);
+                    }
+
+                    return Expression::Variable(array);
+                }
+
+                for (i, e) in n.elements.iter().enumerate() {
+                    let element = generate_expression(
+                        context,
+                        programbuilder,
+                        functionbuilder,
+                        current_block,
+                        asg,
+                        scoperef,
+                        e,
+                    );
+                    let element = store_in_variable(functionbuilder, current_block, element);
+                    let index = store_in_variable(
+                        functionbuilder,
+                        current_block,
+                        Expression::Constant(Value::Primitive {
+                            ptype: PrimitiveType::U64,
+                            data: i as u64,
+                        }),
+                    );
+                    functionbuilder
+                        .edit_block(current_block)
+                        .store_element(array, index, element);
+                }
+                Expression::Variable(array)
+            }
+            asg::expressions::Literal::FunctionLiteral(n) => {
+                let functionref = asg::FunctionRef {
+                    module: scoperef.module,
+                    function: n.functionkey,
+                };
+                assert!(
+                    !asg.get_function(&functionref).is_generic(),
+                    "Generic functions must be called directly"
+                );
+                let function = generate_function(
+                    context,
+                    programbuilder,
+                    asg,
+                    &functionref,
+                    Some(etype.clone()),
+                    None,
+                );
+
+                // Captured values are copied when the closure is created, references point
+                //  to the variable itself
+                let mut captures = Vec::new();
+                for capture in &asg.get_function(&functionref).captures {
+                    let value = if capture.byreference {
+                        generate_symbol_address(
+                            context,
+                            functionbuilder,
+                            current_block,
+                            asg,
+                            &capture.symref,
+                        )
+                    } else {
+                        let value = generate_symbol_reference(
+                            context,
+                            functionbuilder,
+                            current_block,
+                            asg,
+                            &capture.symref,
+                        );
+                        store_in_variable(functionbuilder, current_block, value)
+                    };
+                    captures.push(value);
+                }
+
+                let closure = functionbuilder.add_unnamed_variable(etype.clone());
+                functionbuilder
+                    .edit_block(current_block)
+                    .make_closure(closure, function, captures);
+                Expression::Variable(closure)
+            }
+            asg::expressions::Literal::ModuleLiteral(_) => todo!(),
+        },
+        asg::ExpressionObject::BuiltInFunction(n) => {
+            let value = Value::BuiltInFunction {
+                builtin: n.function,
+            };
+            Expression::Constant(value)
+        }
+        asg::ExpressionObject::PrimitiveType(_) => todo!(),
+        asg::ExpressionObject::ResultType(_) => todo!(),
+        asg::ExpressionObject::DynamicArrayType(_) => todo!(),
+        asg::ExpressionObject::SliceType(_) => todo!(),
+        asg::ExpressionObject::OptionalType(_) => todo!(),
+        asg::ExpressionObject::FunctionType(_) => todo!(),
+        asg::ExpressionObject::TypeParameter(_) => todo!(),
+        asg::ExpressionObject::SymbolReference(n) => {
+            let sref = scope.symboltable.references.get(&n.symbolref);
+            match sref {
+                asg::SymbolReference::ResolvedReference(n) => {
+                    let value =
+                        generate_symbol_reference(context, functionbuilder, current_block, asg, n);
+                    if find_narrowed_optional(context, asg, scope, expressionkey).is_none() {
+                        return value;
+                    }
+
+                    // Narrowed optionals are read as their value
+                    let optional = store_in_variable(functionbuilder, current_block, value);
+                    let unwrapped = functionbuilder.add_unnamed_variable(etype.clone());
+                    functionbuilder.edit_block(current_block).load_field(
+                        unwrapped,
+                        optional,
+                        TypeId::OPTIONAL_VALUE_OFFSET,
+                    );
+                    Expression::Variable(unwrapped)
+                }
+                asg::SymbolReference::UnresolvedReference(n) => {
+                    panic!("Unresolved reference! {:?}", n)
+                }
+            }
+        }
+        asg::ExpressionObject::If(_) => todo!(),
+        asg::ExpressionObject::Try(n) => {
+            let result = generate_expression(
+                context,
+                programbuilder,
+                functionbuilder,
+                current_block,
+                asg,
+                scoperef,
+                &n.expr,
+            );
+            let result = store_in_variable(functionbuilder, current_block, result);
+            let definition = match get_expression_type(context, scope, &n.expr) {
+                TypeId::Enum(definition) => definition,
+                t => panic!("Only results can be tried, not {}", t.to_string()),
+            };
+            let (okindex, _) = definition
+                .get_variant(&asg::SymbolKey::from_str("ok"))
+                .unwrap();
+            let (errorindex, errorvariant) = definition
+                .get_variant(&asg::SymbolKey::from_str("error"))
+                .unwrap();
+
+            let tag =
+                functionbuilder.add_unnamed_variable(TypeId::Primitive(definition.tag_type()));
+            functionbuilder
+                .edit_block(current_block)
+                .load_field(tag, result, 0);
+            let errortag = store_in_variable(
+                functionbuilder,
+                current_block,
+                Expression::Constant(Value::Primitive {
+                    ptype: definition.tag_type(),
+                    data: errorindex as u64,
+                }),
+            );
+            let is_error =
+                functionbuilder.add_unnamed_variable(TypeId::Primitive(PrimitiveType::Bool));
+            functionbuilder.edit_block(current_block).binary_op(
+                is_error,
+                BinaryOperationType::Equals,
+                tag,
+                errortag,
+            );
+
+            let error_block = functionbuilder.create_block();
+            let ok_block = functionbuilder.create_block();
+            functionbuilder.branch(current_block, is_error, error_block, ok_block);
+
+            // Errors are returned from the function as the result it outputs
+            *current_block = error_block;
+            let error = functionbuilder.add_unnamed_variable(errorvariant.fields[0].1.clone());
+            functionbuilder.edit_block(current_block).load_field(
+                error,
+                result,
+                definition.field_offset(errorindex, 0),
+            );
+            let output = match context.substitute_type(&scope.propagatederrors[expressionkey]) {
+                TypeId::Enum(output) => output,
+                t => panic!("Errors cannot be propagated as {}", t.to_string()),
+            };
+            let returned =
+                generate_enum_value(functionbuilder, current_block, &output, "error", &[error]);
+            functionbuilder
+                .edit_block(current_block)
+                .do_return(vec![returned]);
+
+            *current_block = ok_block;
+            let value = functionbuilder.add_unnamed_variable(etype.clone());
+            functionbuilder.edit_block(current_block).load_field(
+                value,
+                result,
+                definition.field_offset(okindex, 0),
+            );
+            Expression::Variable(value)
+        }
+        asg::ExpressionObject::Match(n) => {
+            let (value, valuetype) = generate_match_value(
+                context,
+                programbuilder,
+                functionbuilder,
+                current_block,
+                asg,
+                scoperef,
+                &n.expr,
+            );
+
+            // Every arm assigns the result before jumping to the end. The end block is
+            //  created last, so the result is assigned in blocks before it.
+            let result = functionbuilder.add_unnamed_variable(etype.clone());
+            let mut arm_blocks = Vec::new();
+            for arm in &n.arms {
+                let armscoperef = asg::ScopeRef::new(scoperef.module, arm.scope);
+                let next_block = generate_match_arm_test(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    &armscoperef,
+                    value,
+                    &valuetype,
+                    &arm.pattern,
+                    &arm.guard,
+                );
+
+                let armvalue = generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    &armscoperef,
+                    &arm.expr,
+                );
+                functionbuilder
+                    .edit_block(current_block)
+                    .assign(result, armvalue);
+                arm_blocks.push(*current_block);
+
+                *current_block = next_block;
+            }
+
+            // Without an else, the arms are exhaustive and this is never reached
+            if let Some(elsebranch) = &n.elsebranch {
+                let elsevalue = generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    elsebranch,
+                );
+                functionbuilder
+                    .edit_block(current_block)
+                    .assign(result, elsevalue);
+            }
+            arm_blocks.push(*current_block);
+
+            let end_block = functionbuilder.create_block();
+            for arm_block in &arm_blocks {
+                functionbuilder.jump(arm_block, end_block);
+            }
+
+            *current_block = end_block;
+            Expression::Variable(result)
+        }
+        asg::ExpressionObject::Call(n) => {
+            // Calling a variant with fields constructs a value of its enum
+            if let asg::ExpressionObject::Subscript(subscript) =
+                &scope.expressions.get(&n.callable).object
+            {
+                if let (TypeId::Type, TypeId::Enum(definition)) =
+                    (&get_expression_type(context, scope, &subscript.expr), etype)
+                {
+                    let mut fields = Vec::new();
+                    for arg in &n.args {
+                        let field = generate_expression(
+                            context,
+                            programbuilder,
+                            functionbuilder,
+                            current_block,
+                            asg,
+                            scoperef,
+                            arg,
+                        );
+                        fields.push(store_in_variable(functionbuilder, current_block, field));
+                    }
+                    return Expression::Variable(generate_enum_value(
+                        functionbuilder,
+                        current_block,
+                        definition,
+                        &subscript.symbol,
+                        &fields,
+                    ));
+                }
+            }
+
+            // User functions are always called through their closure
+            if let TypeId::Function(_) = &get_expression_type(context, scope, &n.callable) {
+                let variables = generate_closure_call(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    expressionkey,
+                    n,
+                );
+                return match variables.as_slice() {
+                    [] => Expression::Variable(functionbuilder.add_unnamed_variable(TypeId::Null)),
+                    [variable] => Expression::Variable(*variable),
+                    _ => panic!("Calls returning several values are generated per element"),
+                };
+            }
+
+            // Generate callable
+            let _callable = generate_expression(
+                context,
+                programbuilder,
+                functionbuilder,
+                current_block,
+                asg,
+                scoperef,
+                &n.callable,
+            );
+
+            // Generate arguments
+            let mut args = Vec::new();
+            for arg in &n.args {
+                let expr = generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    arg,
+                );
+                args.push(expr);
+            }
+
+            let callabletype = &get_expression_type(context, scope, &n.callable);
+            let returnvar = match callabletype {
+                TypeId::BuiltInFunction(BuiltInFunction::PrintFormat) => {
+                    assert!(args.len() >= 1);
+                    let dynarglen_const = Expression::Constant(Value::Primitive {
+                        ptype: PrimitiveType::U64,
+                        data: args.len() as u64 - 1, // First arg is string
+                    });
+
+                    fn copy_expr_and_push(
+                        functionbuilder: &mut FunctionBuilder,
+                        current_block: &BasicBlockKey,
+                        arg_vars: &mut Vec<VariableKey>,
+                        expression: Expression,
+                    ) {
+                        // Store the expression in a variable
+                        let etype = expression.get_type(&functionbuilder.variablestore);
+                        let var = functionbuilder.add_unnamed_variable(etype);
+                        functionbuilder
+                            .edit_block(current_block)
+                            .assign(var, expression);
+
+                        arg_vars.push(var);
+                    }
+
+                    // Inject dynamic value count after first arg
+                    // TODO: Avoid clones
+                    let mut arg_vars = Vec::new();
+                    copy_expr_and_push(
+                        functionbuilder,
+                        current_block,
+                        &mut arg_vars,
+                        args[0].clone(),
+                    );
+                    copy_expr_and_push(
+                        functionbuilder,
+                        current_block,
+                        &mut arg_vars,
+                        dynarglen_const,
+                    );
+
+                    // Create dynamic value wrappers
+                    for arg in &args[1..] {
+                        let typeid = arg.get_type(&functionbuilder.variablestore);
+
+                        // Store value, so we can wrap it
+                        let variable = functionbuilder.add_unnamed_variable(typeid.clone());
+                        functionbuilder
+                            .edit_block(current_block)
+                            .assign(variable, arg.clone());
+
+                        // Wrap the variable
+                        let dynvalexpr =
+                            Expression::Constant(Value::TypedValue { typeid, variable });
+
+                        copy_expr_and_push(
+                            functionbuilder,
+                            current_block,
+                            &mut arg_vars,
+                            dynvalexpr,
+                        );
+                    }
+
+                    let returnvalue = functionbuilder.add_unnamed_variable(TypeId::Null);
+                    functionbuilder.edit_block(current_block).call_builtin(
+                        returnvalue,
+                        BuiltInFunction::PrintFormat,
+                        arg_vars,
+                    );
+                    returnvalue
+                }
+                TypeId::BuiltInFunction(n) => {
+                    generate_array_builtin_call(functionbuilder, current_block, *n, &args, etype)
+                }
+                _ => panic!("Type {:?} not supported as callable", callabletype),
+            };
+
+            Expression::Variable(returnvar)
+        }
+        asg::ExpressionObject::BinOp(n) => match n.op {
+            BinaryOperationType::And | BinaryOperationType::Or => {
+                // Short-circuit by only evaluating rhs in its own block, if needed
+                let result = functionbuilder.add_unnamed_variable(etype.clone());
+
+                let lhs = generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    &n.lhs,
+                );
+                functionbuilder
+                    .edit_block(current_block)
+                    .assign(result, lhs);
+
+                let rhs_block = functionbuilder.create_block();
+                let end_block = functionbuilder.create_block();
+
+                if n.op == BinaryOperationType::And {
+                    functionbuilder.branch(current_block, result, rhs_block, end_block);
+                } else {
+                    functionbuilder.branch(current_block, result, end_block, rhs_block);
+                }
+
+                *current_block = rhs_block;
+                let rhs = generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    &n.rhs,
+                );
+                functionbuilder
+                    .edit_block(current_block)
+                    .assign(result, rhs);
+                functionbuilder.jump(current_block, end_block);
+
+                *current_block = end_block;
+                Expression::Variable(result)
+            }
+            _ => {
+                let lhs = generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    &n.lhs,
+                );
+                let lhs = store_in_variable(functionbuilder, current_block, lhs);
+                let rhs = generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    &n.rhs,
+                );
+                let rhs = store_in_variable(functionbuilder, current_block, rhs);
+
+                // Optionals are compared to null by their tags
+                let (lhs, rhs) = match get_expression_type(context, scope, &n.lhs) {
+                    TypeId::Optional(_) => {
+                        assert!(
+                            is_null_literal(scope, &n.lhs) || is_null_literal(scope, &n.rhs),
+                            "Optionals can only be compared to null"
+                        );
+                        (
+                            generate_optional_tag(functionbuilder, current_block, lhs),
+                            generate_optional_tag(functionbuilder, current_block, rhs),
+                        )
+                    }
+                    _ => (lhs, rhs),
+                };
+
+                let result = functionbuilder.add_unnamed_variable(etype.clone());
+                functionbuilder
+                    .edit_block(current_block)
+                    .binary_op(result, n.op, lhs, rhs);
+
+                Expression::Variable(result)
+            }
+        },
+        asg::ExpressionObject::UnaryOp(n) => {
+            let operand = generate_expression(
+                context,
+                programbuilder,
+                functionbuilder,
+                current_block,
+                asg,
+                scoperef,
+                &n.expr,
+            );
+            let operand = store_in_variable(functionbuilder, current_block, operand);
+
+            let result = functionbuilder.add_unnamed_variable(etype.clone());
+            functionbuilder
+                .edit_block(current_block)
+                .unary_op(result, n.op, operand);
+
+            Expression::Variable(result)
+        }
+        asg::ExpressionObject::Range(_) => {
+            panic!("Range expressions can only be used as the iterable of a for loop")
+        }
+        asg::ExpressionObject::Subscript(n) => {
+            match (&get_expression_type(context, scope, &n.expr), etype) {
+                // Variants without fields are values of their enum
+                (TypeId::Type, TypeId::Enum(definition)) => Expression::Variable(
+                    generate_enum_value(functionbuilder, current_block, definition, &n.symbol, &[]),
+                ),
+                (TypeId::Struct(definition), _) => {
+                    let instance = generate_expression(
+                        context,
+                        programbuilder,
+                        functionbuilder,
+                        current_block,
+                        asg,
+                        scoperef,
+                        &n.expr,
+                    );
+                    let instance = store_in_variable(functionbuilder, current_block, instance);
+
+                    let field = functionbuilder.add_unnamed_variable(etype.clone());
+                    functionbuilder.edit_block(current_block).load_field(
+                        field,
+                        instance,
+                        definition
+                            .field_offset(&asg::SymbolKey::from_str(&n.symbol))
+                            .unwrap(),
+                    );
+                    Expression::Variable(field)
+                }
+                _ => todo!(),
+            }
+        }
+        // Omitted fields are stored first, the initialized ones follow in source order
+        asg::ExpressionObject::StructConstruction(n) => {
+            let definition = match etype {
+                TypeId::Struct(definition) => definition,
+                n => panic!("Cannot construct non-struct type {:?}", n),
+            };
+
+            let instance = functionbuilder.add_unnamed_variable(etype.clone());
+            for (symbol, fieldtype) in &definition.fields {
+                let initialized = n
+                    .fields
+                    .iter()
+                    .any(|(field, _)| asg::SymbolKey::from_str(field) == *symbol);
+                if !initialized {
+                    let field = generate_default_value(functionbuilder, current_block, fieldtype);
+                    functionbuilder.edit_block(current_block).store_field(
+                        instance,
+                        definition.field_offset(symbol).unwrap(),
+                        field,
+                    );
+                }
+            }
+            for (field, expr) in &n.fields {
+                let value = generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    expr,
+                );
+                let value = store_in_variable(functionbuilder, current_block, value);
+                functionbuilder.edit_block(current_block).store_field(
+                    instance,
+                    definition
+                        .field_offset(&asg::SymbolKey::from_str(field))
+                        .unwrap(),
+                    value,
+                );
+            }
+
+            Expression::Variable(instance)
+        }
+        asg::ExpressionObject::Reference(n) => {
+            let symref = match &scope.expressions.get(&n.expr).object {
+                asg::ExpressionObject::SymbolReference(n) => {
+                    match scope.symboltable.references.get(&n.symbolref) {
+                        asg::SymbolReference::ResolvedReference(n) => n,
+                        asg::SymbolReference::UnresolvedReference(n) => {
+                            panic!("Unresolved reference! {:?}", n)
+                        }
+                    }
+                }
+                _ => panic!("Only variables can be referenced"),
+            };
+            Expression::Variable(generate_symbol_address(
+                context,
+                functionbuilder,
+                current_block,
+                asg,
+                symref,
+            ))
+        }
+        asg::ExpressionObject::Dereference(n) => {
+            let address = generate_expression(
+                context,
+                programbuilder,
+                functionbuilder,
+                current_block,
+                asg,
+                scoperef,
+                &n.expr,
+            );
+            let address = store_in_variable(functionbuilder, current_block, address);
+
+            let value = functionbuilder.add_unnamed_variable(etype.clone());
+            functionbuilder
+                .edit_block(current_block)
+                .load(value, address);
+            Expression::Variable(value)
+        }
+        asg::ExpressionObject::Index(n) => {
+            let array = generate_expression(
+                context,
+                programbuilder,
+                functionbuilder,
+                current_block,
+                asg,
+                scoperef,
+                &n.expr,
+            );
+            let array = store_in_variable(functionbuilder, current_block, array);
+            let index = generate_index(
+                context,
+                programbuilder,
+                functionbuilder,
+                current_block,
+                asg,
+                scoperef,
+                array,
+                n,
+            );
+
+            let result = functionbuilder.add_unnamed_variable(etype.clone());
+            functionbuilder
+                .edit_block(current_block)
+                .load_element(result, array, index);
+
+            Expression::Variable(result)
+        }
+        asg::ExpressionObject::Slice(n) => {
+            let array = generate_expression(
+                context,
+                programbuilder,
+                functionbuilder,
+                current_block,
+                asg,
+                scoperef,
+                &n.expr,
+            );
+            let array = store_in_variable(functionbuilder, current_block, array);
+            let start = generate_expression(
+                context,
+                programbuilder,
+                functionbuilder,
+                current_block,
+                asg,
+                scoperef,
+                &n.start,
+            );
+            let start = store_in_variable(functionbuilder, current_block, start);
+            let end = generate_expression(
+                context,
+                programbuilder,
+                functionbuilder,
+                current_block,
+                asg,
+                scoperef,
+                &n.end,
+            );
+            let end = store_in_variable(functionbuilder, current_block, end);
+
+            // Both start <= end and end <= length have to hold
+            let length = generate_array_length(functionbuilder, current_block, array);
+            let message = match &get_expression_type(context, scope, &n.expr) {
+                TypeId::Array { element: _, length } => format!(
+                    "Slice out of bounds for array of length {} at {}",
+                    length, n.location
+                ),
+                _ => format!("Slice out of bounds at {}", n.location),
+            };
+            for (upper, lower) in [(length, end), (end, start)] {
+                let in_bounds =
+                    functionbuilder.add_unnamed_variable(TypeId::Primitive(PrimitiveType::Bool));
+                functionbuilder.edit_block(current_block).binary_op(
+                    in_bounds,
+                    BinaryOperationType::GreaterThanOrEq,
+                    upper,
+                    lower,
+                );
+                generate_runtime_check(
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    in_bounds,
+                    message.clone(),
+                );
+            }
+
+            let result = functionbuilder.add_unnamed_variable(etype.clone());
+            functionbuilder
+                .edit_block(current_block)
+                .slice(result, array, start, end);
+
+            Expression::Variable(result)
+        }
+    }
+}
+
+// Iterates over the elements of an array, dynamic array or slice. The array is evaluated
+//  once, its length is checked before every iteration.
+fn generate_array_loop(
+    context: &mut CodeGenContext,
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    asg: &asg::Asg,
+    current_block: &mut BasicBlockKey,
+    scoperef: &asg::ScopeRef,
+    forstmt: &asg::statements::For,
+) {
+    let scope = asg.get_scope(scoperef);
+    let elementtype = match get_expression_type(context, scope, &forstmt.iterable) {
+        TypeId::Array { element, .. }
+        | TypeId::DynamicArray { element }
+        | TypeId::Slice { element } => *element,
+        n => panic!("Cannot iterate over type {:?}", n),
+    };
+
+    let array = generate_expression(
+        context,
+        programbuilder,
+        functionbuilder,
+        current_block,
+        asg,
+        scoperef,
+        &forstmt.iterable,
+    );
+    let array = store_in_variable(functionbuilder, current_block, array);
+    let index = store_in_variable(
+        functionbuilder,
+        current_block,
+        Expression::Constant(Value::Primitive {
+            ptype: PrimitiveType::U64,
+            data: 0,
+        }),
+    );
+
+    let condition_block = functionbuilder.create_block();
+    functionbuilder.jump(current_block, condition_block);
+    *current_block = condition_block;
+
+    let length = generate_array_length(functionbuilder, current_block, array);
+    let condition = functionbuilder.add_unnamed_variable(TypeId::Primitive(PrimitiveType::Bool));
+    functionbuilder.edit_block(current_block).binary_op(
+        condition,
+        BinaryOperationType::LessThan,
+        index,
+        length,
+    );
+
+    let body_block = functionbuilder.create_block();
+    let step_block = functionbuilder.create_block();
+    let end_block = functionbuilder.create_block();
+    functionbuilder.branch(current_block, condition, body_block, end_block);
+
+    *current_block = body_block;
+    let symbol =
+        functionbuilder.add_named_variable(asg::SymbolKey::from_str(&*forstmt.symbol), elementtype);
+    functionbuilder
+        .edit_block(current_block)
+        .load_element(symbol, array, index);
+
+    context.loop_stack.push(LoopInfo {
+        continue_block: step_block,
+        break_block: end_block,
+    });
+    generate_branch(
+        context,
+        programbuilder,
+        functionbuilder,
+        asg,
+        current_block,
+        scoperef,
+        &forstmt.body,
+    );
+    context.loop_stack.pop();
+    functionbuilder.jump(current_block, step_block);
+
+    *current_block = step_block;
+    let one = store_in_variable(
+        functionbuilder,
+        current_block,
+        Expression::Constant(Value::Primitive {
+            ptype: PrimitiveType::U64,
+            data: 1,
+        }),
+    );
+    functionbuilder.edit_block(current_block).binary_op(
+        index,
+        BinaryOperationType::Add,
+        index,
+        one,
+    );
+    functionbuilder.jump(current_block, condition_block);
+
+    *current_block = end_block;
+}
+
+fn generate_branch(
+    context: &mut CodeGenContext,
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    asg: &asg::Asg,
+    current_block: &mut BasicBlockKey,
+    scoperef: &asg::ScopeRef,
+    branch: &asg::statements::Branch,
+) {
+    if let Some(body) = &branch.body {
+        generate_statement_body(
+            context,
+            programbuilder,
+            functionbuilder,
+            asg,
+            current_block,
+            &asg::ScopeRef::new(scoperef.module, branch.scope),
+            body,
+        );
+    }
+}
+
+fn generate_statement_body(
+    context: &mut CodeGenContext,
+    programbuilder: &mut ProgramBuilder,
+    functionbuilder: &mut FunctionBuilder,
+    asg: &asg::Asg,
+    current_block: &mut BasicBlockKey,
+    scoperef: &asg::ScopeRef,
+    body: &asg::StatementBody,
+) {
+    for stmnt in &body.statements {
+        match stmnt {
+            asg::Statement::If(n) => {
+                let end_block = functionbuilder.create_block();
+
+                for (condition, branch) in &n.branches {
+                    let condition = generate_expression(
+                        context,
+                        programbuilder,
+                        functionbuilder,
+                        current_block,
+                        asg,
+                        scoperef,
+                        condition,
+                    );
+                    let condition = store_in_variable(functionbuilder, current_block, condition);
+
+                    let branch_block = functionbuilder.create_block();
+                    let next_block = functionbuilder.create_block();
+                    functionbuilder.branch(current_block, condition, branch_block, next_block);
+
+                    *current_block = branch_block;
+                    generate_branch(
+                        context,
+                        programbuilder,
+                        functionbuilder,
+                        asg,
+                        current_block,
+                        scoperef,
+                        branch,
+                    );
+                    functionbuilder.jump(current_block, end_block);
+
+                    *current_block = next_block;
+                }
+
+                if let Some(elsebranch) = &n.elsebranch {
+                    generate_branch(
+                        context,
+                        programbuilder,
+                        functionbuilder,
+                        asg,
+                        current_block,
+                        scoperef,
+                        elsebranch,
+                    );
+                }
+                functionbuilder.jump(current_block, end_block);
+
+                *current_block = end_block;
+            }
+            asg::Statement::Match(n) => {
+                let (value, valuetype) = generate_match_value(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    &n.expr,
+                );
+
+                let end_block = functionbuilder.create_block();
+                for arm in &n.arms {
+                    let next_block = generate_match_arm_test(
+                        context,
+                        programbuilder,
+                        functionbuilder,
+                        current_block,
+                        asg,
+                        &asg::ScopeRef::new(scoperef.module, arm.body.scope),
+                        value,
+                        &valuetype,
+                        &arm.pattern,
+                        &arm.guard,
+                    );
+
+                    generate_branch(
+                        context,
+                        programbuilder,
+                        functionbuilder,
+                        asg,
+                        current_block,
+                        scoperef,
+                        &arm.body,
+                    );
+                    functionbuilder.jump(current_block, end_block);
+
+                    *current_block = next_block;
+                }
+
+                if let Some(elsebranch) = &n.elsebranch {
+                    generate_branch(
+                        context,
+                        programbuilder,
+                        functionbuilder,
+                        asg,
+                        current_block,
+                        scoperef,
+                        elsebranch,
+                    );
+                }
+                functionbuilder.jump(current_block, end_block);
+
+                *current_block = end_block;
+            }
+            asg::Statement::While(n) => {
+                // Condition gets its own block, so the loop body can jump back to it
+                let condition_block = functionbuilder.create_block();
+                functionbuilder.jump(current_block, condition_block);
+                *current_block = condition_block;
+
+                let condition = generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    &n.condition,
+                );
+                let condition = store_in_variable(functionbuilder, current_block, condition);
+
+                let body_block = functionbuilder.create_block();
+                let end_block = functionbuilder.create_block();
+                functionbuilder.branch(current_block, condition, body_block, end_block);
+
+                *current_block = body_block;
+                context.loop_stack.push(LoopInfo {
+                    continue_block: condition_block,
+                    break_block: end_block,
+                });
+                generate_branch(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    asg,
+                    current_block,
+                    scoperef,
+                    &n.body,
+                );
+                context.loop_stack.pop();
+                functionbuilder.jump(current_block, condition_block);
+
+                *current_block = end_block;
+            }
+            asg::Statement::For(n)
+                if !matches!(
+                    asg.get_scope(scoperef).expressions.get(&n.iterable).object,
+                    asg::ExpressionObject::Range(_)
+                ) =>
+            {
+                generate_array_loop(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    asg,
+                    current_block,
+                    scoperef,
+                    n,
+                );
+            }
+            asg::Statement::For(n) => {
+                let scope = asg.get_scope(scoperef);
+                let range = match &scope.expressions.get(&n.iterable).object {
+                    asg::ExpressionObject::Range(range) => range,
+                    _ => unreachable!(),
+                };
+                let elementtype = &get_expression_type(context, scope, &n.iterable);
+                let ptype = match elementtype {
+                    TypeId::Primitive(n) => *n,
+                    _ => panic!("Unsupported range type: {:?}", elementtype),
+                };
+
+                // Both bounds are evaluated once, before entering the loop
+                let counter = functionbuilder.add_unnamed_variable(elementtype.clone());
+                let start = generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    &range.start,
+                );
+                functionbuilder
+                    .edit_block(current_block)
+                    .assign(counter, start);
+
+                let end = functionbuilder.add_unnamed_variable(elementtype.clone());
+                let endvalue = generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    &range.end,
+                );
+                functionbuilder
+                    .edit_block(current_block)
+                    .assign(end, endvalue);
+
+                let condition_block = functionbuilder.create_block();
+                functionbuilder.jump(current_block, condition_block);
+                *current_block = condition_block;
 
-                let constdata = programbuilder.add_constantdata(data);
+                let condition =
+                    functionbuilder.add_unnamed_variable(TypeId::Primitive(PrimitiveType::Bool));
+                functionbuilder.edit_block(current_block).binary_op(
+                    condition,
+                    BinaryOperationType::LessThan,
+                    counter,
+                    end,
+                );
 
-                // TODO: This is awkward, a Value should be able to hold its
-                //  own value.
-                // If it did, we would not need to refcount constdata.
-                // On the other hand, staticstringutf8 is a reftype, we are not passing the bytes around
-                Expression::Constant(Value::Primitive {
-                    ptype: PrimitiveType::StaticStringUtf8,
-                    data: constdata as u64,
-                })
+                let body_block = functionbuilder.create_block();
+                let step_block = functionbuilder.create_block();
+                let end_block = functionbuilder.create_block();
+                functionbuilder.branch(current_block, condition, body_block, end_block);
+
+                // The loop symbol gets a copy of the counter, so assigning to it in the body
+                //  does not affect the iteration
+                *current_block = body_block;
+                let symbol = functionbuilder
+                    .add_named_variable(asg::SymbolKey::from_str(&*n.symbol), elementtype.clone());
+                functionbuilder
+                    .edit_block(current_block)
+                    .assign(symbol, Expression::Variable(counter));
+
+                context.loop_stack.push(LoopInfo {
+                    continue_block: step_block,
+                    break_block: end_block,
+                });
+                generate_branch(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    asg,
+                    current_block,
+                    scoperef,
+                    &n.body,
+                );
+                context.loop_stack.pop();
+                functionbuilder.jump(current_block, step_block);
+
+                *current_block = step_block;
+                let one = store_in_variable(
+                    functionbuilder,
+                    current_block,
+                    Expression::Constant(Value::Primitive { ptype, data: 1 }),
+                );
+                functionbuilder.edit_block(current_block).binary_op(
+                    counter,
+                    BinaryOperationType::Add,
+                    counter,
+                    one,
+                );
+                functionbuilder.jump(current_block, condition_block);
+
+                *current_block = end_block;
             }
-            asg::expressions::Literal::BoolLiteral(_) => todo!(),
-            asg::expressions::Literal::IntegerLiteral(n) => {
-                let ptype = match etype {
-                    TypeId::Primitive(n) => n,
-                    _ => panic!("Unsupported integer literal type: {:?}", etype),
+            asg::Statement::Break | asg::Statement::Continue => {
+                // Outside of a loop, the parser has already reported the statement, so there
+                //  is nothing to lower
+                let loopinfo = match context.loop_stack.last() {
+                    Some(loopinfo) => loopinfo,
+                    None => continue,
                 };
-                let value = Value::Primitive {
-                    ptype: *ptype,
-                    data: n.data,
+                let target = match stmnt {
+                    asg::Statement::Break => loopinfo.break_block,
+                    _ => loopinfo.continue_block,
                 };
-                Expression::Constant(value)
-            }
-            asg::expressions::Literal::StructLiteral(_) => todo!(),
-            asg::expressions::Literal::FunctionLiteral(_) => todo!(),
-            asg::expressions::Literal::ModuleLiteral(_) => todo!(),
-        },
-        asg::ExpressionObject::BuiltInFunction(n) => {
-            let value = Value::BuiltInFunction {
-                builtin: n.function,
-            };
-            Expression::Constant(value)
-        }
-        asg::ExpressionObject::PrimitiveType(_) => todo!(),
-        asg::ExpressionObject::SymbolReference(n) => {
-            let sref = scope.symboltable.references.get(&n.symbolref);
-            match sref {
-                asg::SymbolReference::ResolvedReference(n) => {
-                    // TODO: Not sure how to deal with difference between closed variables and globals
-                    assert!(
-                        n.scope == *scoperef,
-                        "Only local scope references supported for now!"
-                    );
+                functionbuilder.jump(current_block, target);
 
-                    // Here, we search through blocks to find last assigned value
-                    let variable = functionbuilder
-                        .find_last_variable_for_symbol(current_block, &n.symbol)
-                        .expect(
-                            format!("Cannot find assigned variable for symbol {:?}", n.symbol)
-                                .as_str(),
+                // Anything following in the same body is unreachable
+                *current_block = functionbuilder.create_block();
+            }
+            asg::Statement::Return(n) => {
+                // Returned tuples are spread, so every returned value is passed on its own
+                let scope = asg.get_scope(scoperef);
+                let mut values = Vec::new();
+                for expr in &n.exprs {
+                    if let TypeId::Tuple(_) = &get_expression_type(context, scope, expr) {
+                        values.extend(generate_tuple(
+                            context,
+                            programbuilder,
+                            functionbuilder,
+                            current_block,
+                            asg,
+                            scoperef,
+                            expr,
+                        ));
+                    } else {
+                        let value = generate_expression(
+                            context,
+                            programbuilder,
+                            functionbuilder,
+                            current_block,
+                            asg,
+                            scoperef,
+                            expr,
                         );
-                    Expression::Variable(variable)
-                }
-                asg::SymbolReference::UnresolvedReference(n) => {
-                    panic!("Unresolved reference! {:?}", n)
+                        values.push(store_in_variable(functionbuilder, current_block, value));
+                    }
                 }
-            }
-        }
-        asg::ExpressionObject::If(_) => todo!(),
-        asg::ExpressionObject::Call(n) => {
-            // Generate callable
-            let _callable = generate_expression(
-                context,
-                programbuilder,
-                functionbuilder,
-                current_block,
-                asg,
-                scoperef,
-                &n.callable,
-            );
+                functionbuilder.edit_block(current_block).do_return(values);
 
-            // Generate arguments
-            let mut args = Vec::new();
-            for arg in &n.args {
-                let expr = generate_expression(
+                // Anything following in the same body is unreachable
+                *current_block = functionbuilder.create_block();
+            }
+            asg::Statement::Destructure(n) => {
+                let elements = generate_tuple(
                     context,
                     programbuilder,
                     functionbuilder,
                     current_block,
                     asg,
                     scoperef,
-                    arg,
+                    &n.expr,
                 );
-                args.push(expr);
+
+                let scope = asg.get_scope(&scoperef);
+                for (symbol, element) in n.symbols.iter().zip(elements) {
+                    if let Some(symbol) = symbol {
+                        let symbolkey = asg::SymbolKey::from_str(symbol.as_str());
+                        let decltype = &context
+                            .substitute_type(scope.declarationtypes.get(&symbolkey).unwrap());
+                        let assignee =
+                            functionbuilder.add_named_variable(symbolkey, decltype.clone());
+                        functionbuilder
+                            .edit_block(current_block)
+                            .assign(assignee, Expression::Variable(element));
+                    }
+                }
             }
+            asg::Statement::Initialize(n) => {
+                let symbolkey = asg::SymbolKey::from_str(&*n.symbol);
+                let scope = asg.get_scope(&scoperef);
 
-            let callabletype = scope.expressiontypes.get(&n.callable).unwrap();
-            let returnvar = match callabletype {
-                TypeId::BuiltInFunction(n) => {
-                    assert!(args.len() >= 1);
-                    let dynarglen_const = Expression::Constant(Value::Primitive {
-                        ptype: PrimitiveType::U64,
-                        data: args.len() as u64 - 1, // First arg is string
-                    });
+                // Generic functions are generated per instance, where they are called
+                if let asg::ExpressionObject::Literal(asg::expressions::Literal::FunctionLiteral(
+                    literal,
+                )) = &scope.expressions.get(&n.expr).object
+                {
+                    let functionref = asg::FunctionRef {
+                        module: scoperef.module,
+                        function: literal.functionkey,
+                    };
+                    if asg.get_function(&functionref).is_generic() {
+                        continue;
+                    }
+                }
 
-                    fn copy_expr_and_push(
-                        functionbuilder: &mut FunctionBuilder,
-                        current_block: &BasicBlockKey,
-                        arg_vars: &mut Vec<VariableKey>,
-                        expression: Expression,
-                    ) {
-                        // Store the expression in a variable
-                        let etype = expression.get_type(&functionbuilder.variablestore);
-                        let var = functionbuilder.add_unnamed_variable(etype);
+                let decltype =
+                    &context.substitute_type(scope.declarationtypes.get(&symbolkey).unwrap());
+
+                // Types only exist at compile time, there is nothing to initialize
+                if *decltype == TypeId::Type {
+                    continue;
+                }
+
+                if let TypeId::Tuple(elementtypes) = decltype {
+                    let elements = generate_tuple(
+                        context,
+                        programbuilder,
+                        functionbuilder,
+                        current_block,
+                        asg,
+                        scoperef,
+                        &n.expr,
+                    );
+                    for (i, (element, elementtype)) in
+                        elements.into_iter().zip(elementtypes).enumerate()
+                    {
+                        let assignee = functionbuilder.add_named_variable(
+                            tuple_element_symbol(&n.symbol, i),
+                            elementtype.clone(),
+                        );
                         functionbuilder
                             .edit_block(current_block)
-                            .assign(var, expression);
-
-                        arg_vars.push(var);
+                            .assign(assignee, Expression::Variable(element));
                     }
+                    continue;
+                }
 
-                    // Inject dynamic value count after first arg
-                    // TODO: Avoid clones
-                    let mut arg_vars = Vec::new();
-                    copy_expr_and_push(
+                let sourceexpr = generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    &n.expr,
+                );
+
+                let assignee = functionbuilder.add_named_variable(symbolkey, decltype.clone());
+
+                let mut block = functionbuilder.edit_block(current_block);
+                block.assign(assignee, sourceexpr);
+            }
+            asg::Statement::Assign(n) => {
+                let scope = asg.get_scope(scoperef);
+
+                // Variables captured by reference are written through their address
+                if let Some(address) = find_captured_address(context, scope, &n.lhs) {
+                    let sourceexpr = generate_expression(
+                        context,
+                        programbuilder,
                         functionbuilder,
                         current_block,
-                        &mut arg_vars,
-                        args[0].clone(),
+                        asg,
+                        scoperef,
+                        &n.rhs,
                     );
-                    copy_expr_and_push(
+                    let value = store_in_variable(functionbuilder, current_block, sourceexpr);
+                    functionbuilder
+                        .edit_block(current_block)
+                        .store(address, value);
+                    continue;
+                }
+                // Dereferenced values are written through the address
+                if let asg::ExpressionObject::Dereference(target) =
+                    &scope.expressions.get(&n.lhs).object
+                {
+                    let address = generate_expression(
+                        context,
+                        programbuilder,
                         functionbuilder,
                         current_block,
-                        &mut arg_vars,
-                        dynarglen_const,
+                        asg,
+                        scoperef,
+                        &target.expr,
+                    );
+                    let address = store_in_variable(functionbuilder, current_block, address);
+                    let sourceexpr = generate_expression(
+                        context,
+                        programbuilder,
+                        functionbuilder,
+                        current_block,
+                        asg,
+                        scoperef,
+                        &n.rhs,
+                    );
+                    let value = store_in_variable(functionbuilder, current_block, sourceexpr);
+                    functionbuilder
+                        .edit_block(current_block)
+                        .store(address, value);
+                    continue;
+                }
+                if let asg::ExpressionObject::Index(_) = &scope.expressions.get(&n.lhs).object {
+                    let sourceexpr = generate_expression(
+                        context,
+                        programbuilder,
+                        functionbuilder,
+                        current_block,
+                        asg,
+                        scoperef,
+                        &n.rhs,
                     );
+                    let value = store_in_variable(functionbuilder, current_block, sourceexpr);
 
-                    // Create dynamic value wrappers
-                    for arg in &args[1..] {
-                        let typeid = arg.get_type(&functionbuilder.variablestore);
+                    generate_element_store(
+                        context,
+                        programbuilder,
+                        functionbuilder,
+                        current_block,
+                        asg,
+                        scoperef,
+                        &n.lhs,
+                        value,
+                    );
+                    continue;
+                }
 
-                        // Store value, so we can wrap it
-                        let variable = functionbuilder.add_unnamed_variable(typeid.clone());
+                // Tuples are assigned element by element
+                if let TypeId::Tuple(_) = &get_expression_type(context, scope, &n.lhs) {
+                    let variables = generate_tuple(
+                        context,
+                        programbuilder,
+                        functionbuilder,
+                        current_block,
+                        asg,
+                        scoperef,
+                        &n.lhs,
+                    );
+                    let elements = generate_tuple(
+                        context,
+                        programbuilder,
+                        functionbuilder,
+                        current_block,
+                        asg,
+                        scoperef,
+                        &n.rhs,
+                    );
+                    for (variable, element) in variables.into_iter().zip(elements) {
                         functionbuilder
                             .edit_block(current_block)
-                            .assign(variable, arg.clone());
-
-                        // Wrap the variable
-                        let dynvalexpr =
-                            Expression::Constant(Value::TypedValue { typeid, variable });
-
-                        copy_expr_and_push(
-                            functionbuilder,
-                            current_block,
-                            &mut arg_vars,
-                            dynvalexpr,
-                        );
+                            .assign(variable, Expression::Variable(element));
                     }
-
-                    let returnvalue = functionbuilder.add_unnamed_variable(TypeId::Null);
-                    functionbuilder.edit_block(current_block).call_builtin(
-                        returnvalue,
-                        *n,
-                        arg_vars,
-                    );
-                    returnvalue
+                    continue;
                 }
-                TypeId::Function(_) => panic!("User callables not yet supported!"),
-                _ => panic!("Type {:?} not supported as callable", callabletype),
-            };
-
-            Expression::Variable(returnvar)
-        }
-        asg::ExpressionObject::BinOp(_) => todo!(),
-        asg::ExpressionObject::Subscript(_) => todo!(),
-    }
-}
 
-fn generate_statement_body(
-    context: &mut CodeGenContext,
-    programbuilder: &mut ProgramBuilder,
-    functionbuilder: &mut FunctionBuilder,
-    asg: &asg::Asg,
-    scoperef: &asg::ScopeRef,
-    body: &asg::StatementBody,
-) -> BasicBlockKey {
-    let blockkey = functionbuilder.create_block();
-    for stmnt in &body.statements {
-        match stmnt {
-            asg::Statement::If(_) => todo!(),
-            asg::Statement::Return(_) => todo!(),
-            asg::Statement::Initialize(n) => {
-                let symbolkey = asg::SymbolKey::from_str(&*n.symbol);
-                let scope = asg.get_scope(&scoperef);
-                let decltype = scope.declarationtypes.get(&symbolkey).unwrap();
+                let variable = match generate_expression(
+                    context,
+                    programbuilder,
+                    functionbuilder,
+                    current_block,
+                    asg,
+                    scoperef,
+                    &n.lhs,
+                ) {
+                    Expression::Variable(n) => n,
+                    _ => panic!("Can only assign to variables"),
+                };
 
                 let sourceexpr = generate_expression(
                     context,
                     programbuilder,
                     functionbuilder,
-                    &blockkey,
+                    current_block,
                     asg,
                     scoperef,
-                    &n.expr,
+                    &n.rhs,
                 );
 
-                let assignee = functionbuilder.add_named_variable(symbolkey, decltype.clone());
-
-                let mut block = functionbuilder.edit_block(&blockkey);
-                block.assign(assignee, sourceexpr);
+                functionbuilder
+                    .edit_block(current_block)
+                    .assign(variable, sourceexpr);
             }
-            asg::Statement::Assign(n) => todo!(),
             asg::Statement::ExpressionWrapper(n) => {
                 generate_expression(
                     context,
                     programbuilder,
                     functionbuilder,
-                    &blockkey,
+                    current_block,
                     asg,
                     scoperef,
                     &n.expr,
@@ -251,7 +2915,6 @@ fn generate_statement_body(
             }
         }
     }
-    blockkey
 }
 
 pub fn generate_program(asg: &asg::Asg) -> ir::Program {
@@ -275,90 +2938,58 @@ pub fn generate_program(asg: &asg::Asg) -> ir::Program {
                 let name = format!("__{}__init", module.name);
                 let mut functionbuilder = FunctionBuilder::new(name);
 
-                let entry = generate_statement_body(
+                let entry = functionbuilder.create_block();
+                let mut current_block = entry;
+                generate_statement_body(
                     &mut context,
                     &mut programbuilder,
                     &mut functionbuilder,
                     asg,
+                    &mut current_block,
                     &scoperef,
                     &body,
                 );
+                functionbuilder
+                    .edit_block(&current_block)
+                    .do_return(Vec::new());
 
                 let function = functionbuilder.finish(entry);
                 module_inits.push(programbuilder.add_function(function));
             }
-
-            // Generate all functions in module
-            for functionkey in module.functionstore.keys() {
-                let function = module.functionstore.get(&functionkey);
-
-                if let Some(body) = &function.body {
-                    let scoperef = &asg::ScopeRef {
-                        module: modulekey,
-                        scope: body.scope_nonowned,
-                    };
-
-                    let name = format!("{}.{}", module.name, function.name);
-                    let mut functionbuilder = FunctionBuilder::new(name);
-
-                    let entry = generate_statement_body(
-                        &mut context,
-                        &mut programbuilder,
-                        &mut functionbuilder,
-                        asg,
-                        &scoperef,
-                        &body,
-                    );
-
-                    // TODO: This wont work, we need the last block, not the entry
-                    // Maybe add this in graph generation instead?
-                    functionbuilder.edit_block(&entry).do_return(Vec::new());
-
-                    // TODO: Ideally, this is done while building, so that modifications
-                    //  can be done after this
-                    // Update variable lifetimes
-                    functionbuilder.update_variable_usage();
-
-                    let function = functionbuilder.finish(entry);
-
-                    // TODO: This function_map relies on functions being generated before they can be referenced.
-                    //  Add an indirect function map, or patch up references afterwards.
-                    //  Possibly reserve functionkey in programbuilder before adding/editing the function.
-                    context.function_map.insert(
-                        asg::FunctionRef {
-                            module: modulekey,
-                            function: functionkey,
-                        },
-                        programbuilder.add_function(function),
-                    );
-                }
-            }
         }
 
+        // Function literals are generated where they are defined, along with their closures
+        let mainfuncref = asg::FunctionRef {
+            module: asg.global_module,
+            function: asg.main,
+        };
+        generate_function(
+            &mut context,
+            &mut programbuilder,
+            asg,
+            &mainfuncref,
+            None,
+            None,
+        );
+
         // Add program global preamble
         let global_init_function = {
             let mut functionbuilder = ir::FunctionBuilder::new("_global_init".into());
 
             let entry = functionbuilder.create_block();
             {
-                // All init functions return null
-                let dummy_var = functionbuilder.add_unnamed_variable(TypeId::Null);
-
                 let mut block = functionbuilder.edit_block(&entry);
 
                 // Call all module inits
                 // TODO: Figure out order here
                 for function in module_inits {
-                    block.call_static(dummy_var, function, Vec::new());
+                    // Init functions return nothing
+                    block.call_static(Vec::new(), function, Vec::new());
                 }
 
                 // Finally call main
                 // TODO: Should main be called with cmd args?
-                let mainfuncref = asg::FunctionRef {
-                    module: asg.global_module,
-                    function: asg.main,
-                };
-                block.call_static(dummy_var, context.function_map[&mainfuncref], Vec::new());
+                block.call_static(Vec::new(), context.function_map[&mainfuncref], Vec::new());
 
                 // Don't forget to halt the program
                 block.halt();
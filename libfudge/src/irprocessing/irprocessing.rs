@@ -44,7 +44,65 @@ pub fn substitute_variable(
     }
 }
 
+pub fn count_variable_definitions(
+    irfunction: &ir::Function,
+) -> std::collections::HashMap<ir::VariableKey, usize> {
+    let mut definitions = std::collections::HashMap::new();
+    for param in &irfunction.params {
+        *definitions.entry(*param).or_insert(0) += 1;
+    }
+    for block in irfunction.basicblockstore.values() {
+        for instr in &block.instructions {
+            let variable = match instr {
+                ir::Instruction::Assign(n) => n.variable,
+                ir::Instruction::CallBuiltIn(n) => n.variable,
+                ir::Instruction::CallStatic(n) => {
+                    for variable in &n.variables {
+                        *definitions.entry(*variable).or_insert(0) += 1;
+                    }
+                    continue;
+                }
+                ir::Instruction::CallClosure(n) => {
+                    for variable in &n.variables {
+                        *definitions.entry(*variable).or_insert(0) += 1;
+                    }
+                    continue;
+                }
+                ir::Instruction::MakeClosure(n) => n.variable,
+                ir::Instruction::LoadCapture(n) => n.variable,
+                ir::Instruction::AddressOf(n) => {
+                    // The target can be modified through the address
+                    *definitions.entry(n.target).or_insert(0) += 1;
+                    n.variable
+                }
+                ir::Instruction::Load(n) => n.variable,
+                ir::Instruction::UnaryOp(n) => n.variable,
+                ir::Instruction::BinOp(n) => n.variable,
+                ir::Instruction::LoadElement(n) => n.variable,
+                // Stored elements modify the array, so it cannot be merged with a copy
+                ir::Instruction::StoreElement(n) => n.array,
+                ir::Instruction::LoadField(n) => n.variable,
+                // Same for stored fields
+                ir::Instruction::StoreField(n) => n.target,
+                ir::Instruction::Slice(n) => {
+                    // Elements can be stored through the slice as well
+                    *definitions.entry(n.array).or_insert(0) += 1;
+                    n.variable
+                }
+                ir::Instruction::SliceLength(n) => n.variable,
+                _ => continue,
+            };
+            *definitions.entry(variable).or_insert(0) += 1;
+        }
+    }
+    definitions
+}
+
 pub fn process_function(irfunction: &mut ir::Function) {
+    // Variables assigned in several places (i.e. across branches) cannot be merged
+    let definitions = count_variable_definitions(irfunction);
+    let is_single_definition = |v: &ir::VariableKey| definitions.get(v).copied().unwrap_or(0) <= 1;
+
     // Find variable substitutions, vx = vy
     let mut variable_substitutions = Vec::new();
     for block in irfunction.basicblockstore.values_mut() {
@@ -52,7 +110,9 @@ pub fn process_function(irfunction: &mut ir::Function) {
             match instr {
                 ir::Instruction::Assign(n) => {
                     // Remove trivial assigns
-                    if let Some(v) = is_trivial_assign(&n) {
+                    if let Some(v) = is_trivial_assign(&n)
+                        .filter(|v| is_single_definition(v) && is_single_definition(&n.variable))
+                    {
                         variable_substitutions.push((n.variable, v));
                         block.variable_declarations.remove(&n.variable);
                         *instr = ir::Instruction::Noop;
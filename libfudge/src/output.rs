@@ -10,13 +10,26 @@ pub fn print_errors<'a>(errors: &Vec<Error>, source: &'a Source) {
     let file_id = files.add(source.name(), source.to_str());
 
     for err in errors {
-        let diagnostic = Diagnostic::error()
+        let diagnostic = if is_warning(err.id) {
+            Diagnostic::warning()
+        } else {
+            Diagnostic::error()
+        };
+        let diagnostic = diagnostic
             .with_message(err.message.clone())
             .with_code(error_code(err.id))
-            .with_labels(vec![Label::primary(
-                file_id,
-                err.source_span.pos as usize..(err.source_span.pos as usize + err.source_span.len),
-            )]);
+            .with_labels(
+                std::iter::once(Label::primary(
+                    file_id,
+                    err.source_span.pos as usize
+                        ..(err.source_span.pos as usize + err.source_span.len),
+                ))
+                .chain(err.labels.iter().map(|(span, message)| {
+                    Label::secondary(file_id, span.pos as usize..(span.pos as usize + span.len))
+                        .with_message(message.clone())
+                }))
+                .collect(),
+            );
 
         // Output error
         let mut writer = BufferedStandardStream::stdout(ColorChoice::Always);
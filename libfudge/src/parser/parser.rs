@@ -9,6 +9,7 @@ use crate::source::*;
 use crate::typesystem::*;
 
 use crate::shared::BinaryOperationType;
+use crate::shared::UnaryOperationType;
 
 mod builtins;
 mod expressions;
@@ -42,6 +43,11 @@ struct Parser<'a> {
     current_line: LineInfo,
     need_normal_layout_check: bool,
     ismain: bool,
+    // Number of loops enclosing the statement being parsed, within the current function
+    loop_depth: u32,
+    // Function types in output parameters end before "do", which starts the body of the
+    //  function they are the output of
+    in_output_parameter: bool,
 }
 
 pub struct ParserResult {
@@ -92,6 +98,8 @@ impl<'a> Parser<'a> {
             },
             need_normal_layout_check: false,
             ismain: ismain,
+            loop_depth: 0,
+            in_output_parameter: false,
         }
     }
 
@@ -371,12 +379,20 @@ impl<'a> Parser<'a> {
             self.expect(TokenType::Colon)?;
 
             if let Some(n) = self.parse_expression()? {
+                // Optional default argument
+                let defaultexpr = if self.accept(TokenType::Equals) {
+                    Some(self.expect_expression()?)
+                } else {
+                    None
+                };
+
                 return Ok(Some(
                     self.ast.replace_node(
                         node,
                         ast::nodes::InputParameter {
                             symbol,
                             typeexpr: n,
+                            defaultexpr,
                         }
                         .into(),
                     ),
@@ -396,7 +412,11 @@ impl<'a> Parser<'a> {
     fn parse_output_parameter(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
         let node = self.ast.reserve_node();
 
-        if let Some(n) = self.parse_expression()? {
+        let in_output_parameter = std::mem::replace(&mut self.in_output_parameter, true);
+        let expr = self.parse_expression();
+        self.in_output_parameter = in_output_parameter;
+
+        if let Some(n) = expr? {
             return Ok(Some(self.ast.replace_node(
                 node,
                 ast::nodes::OutputParameter { typeexpr: n }.into(),
@@ -454,11 +474,97 @@ impl<'a> Parser<'a> {
         return Ok(None);
     }
 
+    fn parse_enum_literal(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        if self.accept(TokenType::Enum) {
+            self.expect_with_layout(TokenType::Begin, TokenLayoutType::BlockKeyword)?;
+
+            let node = self.ast.reserve_node();
+            let mut variants = Vec::new();
+
+            while let Some(v) = self.parse_enumvariant()? {
+                variants.push(v);
+            }
+
+            self.expect_with_layout(TokenType::End, TokenLayoutType::BlockEnd)?;
+
+            return Ok(Some(
+                self.ast
+                    .replace_node(node, ast::nodes::EnumLiteral { variants }.into()),
+            ));
+        }
+        return Ok(None);
+    }
+
+    fn parse_enumvariant(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        if self.accept(TokenType::Case) {
+            let node = self.ast.reserve_node();
+
+            self.expect(TokenType::Identifier)?;
+            let symbol = self.get_last_token_symbol();
+
+            // Optional payload, comma-separated fields with mandatory type specifiers
+            let mut fields = Vec::new();
+            if self.accept(TokenType::OpeningParenthesis) {
+                loop {
+                    let field = self.ast.reserve_node();
+
+                    self.expect(TokenType::Identifier)?;
+                    let symbol = self.get_last_token_symbol();
+
+                    self.expect(TokenType::Colon)?;
+                    let typeexpr = self.expect_expression()?;
+
+                    fields.push(self.ast.replace_node(
+                        field,
+                        ast::nodes::StructField { symbol, typeexpr }.into(),
+                    ));
+
+                    if !self.accept(TokenType::Comma) {
+                        break;
+                    }
+                }
+
+                self.expect(TokenType::ClosingParenthesis)?;
+            }
+
+            return Ok(Some(self.ast.replace_node(
+                node,
+                ast::nodes::EnumVariant { symbol, fields }.into(),
+            )));
+        }
+
+        return Ok(None);
+    }
+
     fn parse_function_literal_or_type(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
         if self.accept(TokenType::Func) {
+            let location = self
+                .tokens
+                .get_token_location(self.last_token.as_ref().unwrap());
             let node = self.ast.reserve_node();
             let mut inputparams = Vec::new();
             let mut outputparams = Vec::new();
+            let mut captures = Vec::new();
+
+            // Optional capture list
+            if self.accept(TokenType::OpeningSquareBracket) {
+                loop {
+                    let capturetype = if self.accept(TokenType::Ampersand) {
+                        ast::CaptureType::Reference
+                    } else {
+                        ast::CaptureType::Value
+                    };
+
+                    self.expect(TokenType::Identifier)?;
+                    captures.push((self.get_last_token_symbol(), capturetype));
+
+                    if !self.accept(TokenType::Comma) {
+                        break;
+                    }
+                }
+
+                self.expect(TokenType::ClosingSquareBracket)?;
+            }
 
             // Optional input parameters
             if self.accept(TokenType::OpeningParenthesis) {
@@ -482,7 +588,12 @@ impl<'a> Parser<'a> {
             }
 
             // Optional output paramters
+            let mut outputlocation = None;
             if self.accept(TokenType::Arrow) {
+                outputlocation = Some(
+                    self.tokens
+                        .get_token_location(self.last_token.as_ref().unwrap()),
+                );
                 if self.accept(TokenType::OpeningParenthesis) {
                     if let Some(n) = self.parse_output_parameter()? {
                         outputparams.push(n);
@@ -512,12 +623,21 @@ impl<'a> Parser<'a> {
                         "Expected output parameter declaration".into(),
                     ))?);
                 }
+
+                let end = &self.last_token.as_ref().unwrap().source_span;
+                outputlocation = outputlocation.map(|location| location.extended_to(end));
             }
 
             // If there is a body following, we are dealing with a function literal
             //  otherwise, a type literal
-            if self.accept_with_layout(TokenType::Do, TokenLayoutType::BlockKeyword) {
-                let body = self.parse_statementbody()?;
+            if !self.in_output_parameter
+                && self.accept_with_layout(TokenType::Do, TokenLayoutType::BlockKeyword)
+            {
+                // Loops do not extend into function bodies
+                let loop_depth = std::mem::replace(&mut self.loop_depth, 0);
+                let body = self.parse_statementbody();
+                self.loop_depth = loop_depth;
+                let body = body?;
 
                 self.expect_with_layout(TokenType::End, TokenLayoutType::BlockEnd)?;
 
@@ -525,14 +645,67 @@ impl<'a> Parser<'a> {
                     self.ast.replace_node(
                         node,
                         ast::nodes::FunctionLiteral {
+                            captures,
                             inputparams,
                             outputparams,
                             body,
+                            location,
+                            outputlocation,
                         }
                         .into(),
                     ),
                 ));
             }
+
+            // Only values are captured, so a type has nothing to capture
+            if !captures.is_empty() {
+                return Err(self.log_error(error::Error::at_span(
+                    errors::CapturesOnFunctionType,
+                    self.last_token.as_ref().unwrap().source_span,
+                    "Function types cannot have a capture list".into(),
+                ))?);
+            }
+
+            return Ok(Some(
+                self.ast.replace_node(
+                    node,
+                    ast::nodes::FunctionType {
+                        inputparams,
+                        outputparams,
+                    }
+                    .into(),
+                ),
+            ));
+        }
+
+        return Ok(None);
+    }
+
+    fn parse_argument(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        if let Some(n) = self.parse_expression()? {
+            // A plain symbol followed by "=" names the parameter the argument is for
+            if let ast::Node::SymbolReference(symref) = self.ast.get_node(&n) {
+                let symbol = symref.symbol.clone();
+                let location = self
+                    .tokens
+                    .get_token_location(self.last_token.as_ref().unwrap());
+                if self.accept(TokenType::Equals) {
+                    let expr = self.expect_expression()?;
+                    let location =
+                        location.extended_to(&self.last_token.as_ref().unwrap().source_span);
+                    return Ok(Some(self.ast.replace_node(
+                        n,
+                        ast::nodes::NamedArgument {
+                            symbol,
+                            expr,
+                            location,
+                        }
+                        .into(),
+                    )));
+                }
+            }
+
+            return Ok(Some(n));
         }
 
         return Ok(None);
@@ -541,13 +714,41 @@ impl<'a> Parser<'a> {
     fn parse_argumentlist(&mut self) -> Result<ast::NodeRef, error::ErrorId> {
         let node = self.ast.reserve_node();
         let mut args = Vec::new();
+        let mut names: Vec<ast::SymbolRef> = Vec::new();
 
-        // TODO: Add new node for argument expression
-        if let Some(n) = self.parse_expression()? {
+        if let Some(n) = self.parse_argument()? {
             args.push(n);
 
-            while self.accept(TokenType::Comma) {
-                if let Some(n) = self.parse_expression()? {
+            loop {
+                let span = self.last_token.as_ref().unwrap().source_span;
+                let named = match self.ast.get_node(args.last().unwrap()) {
+                    ast::Node::NamedArgument(n) => Some(n.symbol.clone()),
+                    _ => None,
+                };
+
+                if let Some(symbol) = named {
+                    if names.contains(&symbol) {
+                        let name = self.ast.get_symbol(&symbol).unwrap().clone();
+                        self.log_error(error::Error::at_span(
+                            errors::DuplicateNamedArgument,
+                            span,
+                            format!("Argument '{}' is given more than once", name).into(),
+                        ))?;
+                    }
+                    names.push(symbol);
+                } else if !names.is_empty() {
+                    self.log_error(error::Error::at_span(
+                        errors::PositionalArgumentAfterNamed,
+                        span,
+                        "Positional arguments cannot follow named arguments".into(),
+                    ))?;
+                }
+
+                if !self.accept(TokenType::Comma) {
+                    break;
+                }
+
+                if let Some(n) = self.parse_argument()? {
                     args.push(n);
                 } else {
                     return Err(self.log_error(error::Error::at_span(
@@ -564,26 +765,49 @@ impl<'a> Parser<'a> {
             .replace_node(node, ast::nodes::ArgumentList { args: args }.into()));
     }
 
-    fn accept_binaryoperator(&mut self) -> Option<BinaryOperationType> {
+    fn peek_binaryoperator(&self) -> Option<BinaryOperationType> {
         if let Some(tt) = &self.current_token {
-            let r = match tt.tokentype {
+            // Dereference and reference operators can start a new statement,
+            //  so they only continue an expression on the same line
+            let is_new_line = tt.source_span.pos == self.current_line.first_token_pos;
+            if is_new_line && matches!(tt.tokentype, TokenType::Star | TokenType::Ampersand) {
+                return None;
+            }
+
+            return match tt.tokentype {
                 TokenType::Plus => Some(BinaryOperationType::Add),
                 TokenType::Minus => Some(BinaryOperationType::Sub),
                 TokenType::Star => Some(BinaryOperationType::Mul),
                 TokenType::Slash => Some(BinaryOperationType::Div),
+                TokenType::Percent => Some(BinaryOperationType::Mod),
+                TokenType::Ampersand => Some(BinaryOperationType::BitAnd),
+                TokenType::Pipe => Some(BinaryOperationType::BitOr),
+                TokenType::Caret => Some(BinaryOperationType::BitXor),
+                TokenType::LeftShift => Some(BinaryOperationType::ShiftLeft),
+                TokenType::RightShift => Some(BinaryOperationType::ShiftRight),
                 TokenType::CompareEq => Some(BinaryOperationType::Equals),
+                TokenType::CompareNotEq => Some(BinaryOperationType::NotEquals),
                 TokenType::GreaterThan => Some(BinaryOperationType::GreaterThan),
                 TokenType::GreaterThanOrEq => Some(BinaryOperationType::GreaterThanOrEq),
                 TokenType::LessThan => Some(BinaryOperationType::LessThan),
                 TokenType::LessThanOrEq => Some(BinaryOperationType::LessThanOrEq),
+                TokenType::And => Some(BinaryOperationType::And),
+                TokenType::Or => Some(BinaryOperationType::Or),
                 _ => None,
             };
+        }
 
-            if r.is_some() {
-                self.advance();
-            }
+        return None;
+    }
 
-            return r;
+    fn accept_unaryoperator(&mut self) -> Option<UnaryOperationType> {
+        // Accept properly to start any pending blocks on the operator
+        if self.accept(TokenType::Not) {
+            return Some(UnaryOperationType::Not);
+        } else if self.accept(TokenType::Minus) {
+            return Some(UnaryOperationType::Neg);
+        } else if self.accept(TokenType::Tilde) {
+            return Some(UnaryOperationType::BitNot);
         }
 
         return None;
@@ -723,26 +947,300 @@ impl<'a> Parser<'a> {
         return Ok(None);
     }
 
+    fn parse_match_statement(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        if self.accept(TokenType::Match) {
+            let node = self.ast.reserve_node();
+            let location = self
+                .tokens
+                .get_token_location(self.last_token.as_ref().unwrap());
+
+            let expr = self.expect_expression()?;
+            let location = location.extended_to(&self.last_token.as_ref().unwrap().source_span);
+
+            let mut arms = Vec::new();
+            let mut elsebranch: Option<ast::NodeRef> = None;
+
+            while self.accept_with_layout(TokenType::Case, TokenLayoutType::BlockLinker) {
+                let arm = self.ast.reserve_node();
+                let armlocation = self
+                    .tokens
+                    .get_token_location(self.last_token.as_ref().unwrap());
+                let pattern = self.parse_pattern()?;
+                let guard = self.parse_match_guard()?;
+                let armlocation =
+                    armlocation.extended_to(&self.last_token.as_ref().unwrap().source_span);
+
+                self.expect_with_layout(TokenType::Then, TokenLayoutType::BlockKeyword)?;
+                let body = self.parse_statementbody()?;
+
+                arms.push(
+                    self.ast.replace_node(
+                        arm,
+                        ast::nodes::MatchArm {
+                            pattern,
+                            guard,
+                            body,
+                            location: armlocation,
+                        }
+                        .into(),
+                    ),
+                );
+            }
+
+            // Arms not matched by any case
+            if self.accept_with_layout(TokenType::Else, TokenLayoutType::BlockLinker) {
+                elsebranch = Some(self.parse_statementbody()?);
+            }
+
+            self.expect_with_layout(TokenType::End, TokenLayoutType::BlockEnd)?;
+
+            return Ok(Some(
+                self.ast.replace_node(
+                    node,
+                    ast::nodes::MatchStatement {
+                        expr,
+                        arms,
+                        elsebranch,
+                        location,
+                    }
+                    .into(),
+                ),
+            ));
+        }
+
+        return Ok(None);
+    }
+
+    // Patterns of match arms. At the top level a bare identifier names an enum variant,
+    //  nested in a struct or tuple pattern it binds the matched value instead
+    fn parse_pattern(&mut self) -> Result<ast::NodeRef, error::ErrorId> {
+        return self.parse_subpattern(true);
+    }
+
+    fn parse_subpattern(&mut self, toplevel: bool) -> Result<ast::NodeRef, error::ErrorId> {
+        if self.accept(TokenType::Identifier) {
+            let node = self.ast.reserve_node();
+            let symbol = self.get_last_token_symbol();
+
+            if self.ast.get_symbol(&symbol).unwrap() == "_" {
+                return Ok(self
+                    .ast
+                    .replace_node(node, ast::nodes::WildcardPattern {}.into()));
+            }
+
+            // Variant name with optional bindings for its payload fields, i.e. "circle(r)"
+            if self.accept(TokenType::OpeningParenthesis) {
+                let mut bindings = Vec::new();
+                while self.accept(TokenType::Identifier) {
+                    let s = self.get_last_token_symbol();
+                    bindings.push(Some(s).filter(|s| self.ast.get_symbol(s).unwrap() != "_"));
+
+                    if !self.accept(TokenType::Comma) {
+                        break;
+                    }
+                }
+
+                self.expect(TokenType::ClosingParenthesis)?;
+
+                return Ok(self
+                    .ast
+                    .replace_node(node, ast::nodes::VariantPattern { symbol, bindings }.into()));
+            }
+
+            if toplevel {
+                return Ok(self.ast.replace_node(
+                    node,
+                    ast::nodes::VariantPattern {
+                        symbol,
+                        bindings: Vec::new(),
+                    }
+                    .into(),
+                ));
+            }
+
+            return Ok(self
+                .ast
+                .replace_node(node, ast::nodes::BindingPattern { symbol }.into()));
+        } else if self.accept(TokenType::OpeningCurlyBrace) {
+            let node = self.ast.reserve_node();
+
+            let mut fields = Vec::new();
+            loop {
+                self.expect(TokenType::Identifier)?;
+                let symbol = self.get_last_token_symbol();
+                self.expect(TokenType::Equals)?;
+                fields.push((symbol, self.parse_subpattern(false)?));
+
+                if !self.accept(TokenType::Comma) {
+                    break;
+                }
+            }
+
+            self.expect(TokenType::ClosingCurlyBrace)?;
+
+            return Ok(self
+                .ast
+                .replace_node(node, ast::nodes::StructPattern { fields }.into()));
+        } else if self.accept(TokenType::OpeningParenthesis) {
+            let node = self.ast.reserve_node();
+
+            let mut elements = Vec::new();
+            loop {
+                elements.push(self.parse_subpattern(false)?);
+
+                if !self.accept(TokenType::Comma) {
+                    break;
+                }
+            }
+
+            self.expect(TokenType::ClosingParenthesis)?;
+
+            return Ok(self
+                .ast
+                .replace_node(node, ast::nodes::TuplePattern { elements }.into()));
+        }
+
+        // Anything else is a literal, or a range of literals which is reused as the pattern
+        let value = self.expect_expression()?;
+        if let ast::Node::RangeExpression(n) = self.ast.get_node(&value) {
+            let (start, end) = (n.start, n.end);
+            return Ok(self
+                .ast
+                .replace_node(value, ast::nodes::RangePattern { start, end }.into()));
+        }
+
+        return Ok(self
+            .ast
+            .add_node(ast::nodes::LiteralPattern { value }.into()));
+    }
+
+    // Optional condition following the pattern of a match arm
+    fn parse_match_guard(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        if self.accept(TokenType::If) {
+            return Ok(Some(self.expect_expression()?));
+        }
+
+        return Ok(None);
+    }
+
     fn parse_return_statement(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
         if self.accept(TokenType::Return) {
             let node = self.ast.reserve_node();
 
-            let expr = self.parse_expression()?;
+            // Multiple return values are comma-separated
+            let mut exprs = Vec::new();
+            if let Some(n) = self.parse_expression()? {
+                exprs.push(n);
+
+                while self.accept(TokenType::Comma) {
+                    exprs.push(self.expect_expression()?);
+                }
+            }
 
             // TODO: Parse end of statement
 
             return Ok(Some(
                 self.ast
-                    .replace_node(node, ast::nodes::ReturnStatement { expr }.into()),
+                    .replace_node(node, ast::nodes::ReturnStatement { exprs }.into()),
+            ));
+        }
+
+        return Ok(None);
+    }
+
+    fn parse_while_statement(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        if self.accept(TokenType::While) {
+            let node = self.ast.reserve_node();
+
+            let condition = self.expect_expression()?;
+
+            self.expect_with_layout(TokenType::Do, TokenLayoutType::BlockKeyword)?;
+
+            self.loop_depth += 1;
+            let body = self.parse_statementbody();
+            self.loop_depth -= 1;
+            let body = body?;
+
+            self.expect_with_layout(TokenType::End, TokenLayoutType::BlockEnd)?;
+
+            return Ok(Some(self.ast.replace_node(
+                node,
+                ast::nodes::WhileStatement { condition, body }.into(),
+            )));
+        }
+
+        return Ok(None);
+    }
+
+    fn parse_for_statement(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        if self.accept(TokenType::For) {
+            let node = self.ast.reserve_node();
+
+            self.expect(TokenType::Identifier)?;
+            let symbol = self.get_last_token_symbol();
+
+            self.expect(TokenType::In)?;
+
+            let iterable = self.expect_expression()?;
+
+            self.expect_with_layout(TokenType::Do, TokenLayoutType::BlockKeyword)?;
+
+            self.loop_depth += 1;
+            let body = self.parse_statementbody();
+            self.loop_depth -= 1;
+            let body = body?;
+
+            self.expect_with_layout(TokenType::End, TokenLayoutType::BlockEnd)?;
+
+            return Ok(Some(
+                self.ast.replace_node(
+                    node,
+                    ast::nodes::ForStatement {
+                        symbol,
+                        iterable,
+                        body,
+                    }
+                    .into(),
+                ),
             ));
         }
 
         return Ok(None);
     }
 
+    fn parse_loop_control_statement(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        let (node, error, name): (ast::Node, _, &str) = if self.accept(TokenType::Break) {
+            (
+                ast::nodes::BreakStatement {}.into(),
+                errors::BreakOutsideLoop,
+                "break",
+            )
+        } else if self.accept(TokenType::Continue) {
+            (
+                ast::nodes::ContinueStatement {}.into(),
+                errors::ContinueOutsideLoop,
+                "continue",
+            )
+        } else {
+            return Ok(None);
+        };
+
+        if self.loop_depth == 0 {
+            self.log_error(error::Error::at_span(
+                error,
+                self.last_token.as_ref().unwrap().source_span,
+                format!("'{}' can only be used inside a loop", name).into(),
+            ))?;
+        }
+
+        return Ok(Some(self.ast.add_node(node)));
+    }
+
     fn parse_symbol_declaration(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
         let decltype = if self.accept(TokenType::Def) {
             Some(ast::SymbolDeclarationType::Def)
+        } else if self.accept(TokenType::Const) {
+            Some(ast::SymbolDeclarationType::Const)
         } else if self.accept(TokenType::Var) {
             Some(ast::SymbolDeclarationType::Var)
         } else {
@@ -754,6 +1252,16 @@ impl<'a> Parser<'a> {
 
             self.expect(TokenType::Identifier)?;
             let symbol = self.get_last_token_symbol();
+            let location = self
+                .tokens
+                .get_token_location(self.last_token.as_ref().unwrap());
+
+            // A comma after the first symbol declares several symbols from a tuple
+            if self.accept(TokenType::Comma) {
+                return Ok(Some(self.parse_destructuring_declaration(
+                    node, symbol, decltype, location,
+                )?));
+            }
 
             // Optional type specifier
             let typexpr = if self.accept(TokenType::Colon) {
@@ -762,46 +1270,138 @@ impl<'a> Parser<'a> {
                 None
             };
 
-            // Only vars can be default-initialized
-            let initexpr = if decltype != ast::SymbolDeclarationType::Var {
-                // TODO: Explain why this is expected on error
-                self.expect(TokenType::Equals)?;
-                Some(self.expect_expression()?)
-            } else if self.accept(TokenType::Equals) {
-                Some(self.expect_expression()?)
-            } else {
-                None
-            };
-
-            return Ok(Some(
-                self.ast.replace_node(
-                    node,
-                    ast::nodes::SymbolDeclaration {
-                        symbol: symbol,
-                        decltype: decltype,
-                        typeexpr: typexpr,
-                        initexpr: initexpr,
-                    }
-                    .into(),
-                ),
-            ));
+            return Ok(Some(self.parse_symbol_declaration_initializer(
+                node, symbol, decltype, typexpr, location,
+            )?));
         }
 
         return Ok(None);
     }
 
+    fn parse_symbol_declaration_initializer(
+        &mut self,
+        node: ast::NodeRef,
+        symbol: ast::SymbolRef,
+        decltype: ast::SymbolDeclarationType,
+        typexpr: Option<ast::NodeRef>,
+        location: SourceLocation,
+    ) -> Result<ast::NodeRef, error::ErrorId> {
+        // Only vars can be default-initialized
+        let initexpr = if decltype != ast::SymbolDeclarationType::Var {
+            // TODO: Explain why this is expected on error
+            self.expect(TokenType::Equals)?;
+            Some(self.expect_expression()?)
+        } else if self.accept(TokenType::Equals) {
+            Some(self.expect_expression()?)
+        } else {
+            None
+        };
+
+        return Ok(self.ast.replace_node(
+            node,
+            ast::nodes::SymbolDeclaration {
+                symbol: symbol,
+                decltype: decltype,
+                typeexpr: typexpr,
+                initexpr: initexpr,
+                location: location,
+            }
+            .into(),
+        ));
+    }
+
+    // Short-hand declarations, "a := 5" or "a : T = 5", the symbol was already parsed as an expression
+    fn parse_inferred_declaration(
+        &mut self,
+        expr: ast::NodeRef,
+        location: &SourceLocation,
+    ) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        let symbol = match self.ast.get_node(&expr) {
+            ast::Node::SymbolReference(symref) => symref.symbol.clone(),
+            _ => return Ok(None),
+        };
+
+        if !self.accept(TokenType::Colon) {
+            return Ok(None);
+        }
+
+        // ":=" leaves out the type specifier
+        let typexpr = self.parse_expression()?;
+
+        return Ok(Some(self.parse_symbol_declaration_initializer(
+            expr,
+            symbol,
+            ast::SymbolDeclarationType::Inferred,
+            typexpr,
+            location.clone(),
+        )?));
+    }
+
+    fn parse_destructuring_declaration(
+        &mut self,
+        node: ast::NodeRef,
+        first: ast::SymbolRef,
+        decltype: ast::SymbolDeclarationType,
+        location: SourceLocation,
+    ) -> Result<ast::NodeRef, error::ErrorId> {
+        let mut symbols = vec![first];
+
+        loop {
+            self.expect(TokenType::Identifier)?;
+            symbols.push(self.get_last_token_symbol());
+
+            if !self.accept(TokenType::Comma) {
+                break;
+            }
+        }
+
+        // Underscores mark ignored elements
+        let is_ignored = |s: &ast::SymbolRef| self.ast.get_symbol(s).unwrap() == "_";
+        let symbols = symbols
+            .into_iter()
+            .map(|s| if is_ignored(&s) { None } else { Some(s) })
+            .collect();
+
+        // The tuple is required, there is nothing to default-initialize from
+        self.expect(TokenType::Equals)?;
+        let initexpr = self.expect_expression()?;
+
+        return Ok(self.ast.replace_node(
+            node,
+            ast::nodes::DestructuringDeclaration {
+                symbols,
+                decltype,
+                initexpr,
+                location,
+            }
+            .into(),
+        ));
+    }
+
     fn parse_expression_lead_in_statement(
         &mut self,
     ) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        let location = match &self.current_token {
+            Some(t) => self.tokens.get_token_location(t),
+            None => return Ok(None),
+        };
+
         if let Some(expr) = self.parse_expression()? {
-            if self.accept(TokenType::Equals) {
+            if let Some(n) = self.parse_inferred_declaration(expr, &location)? {
+                return Ok(Some(n));
+            } else if self.accept(TokenType::Equals) {
                 let rhs = self.expect_expression()?;
+
+                // The location spans the whole statement
+                let location = location.extended_to(&self.last_token.as_ref().unwrap().source_span);
+
                 // TODO: Wrong node order, expr is already added
                 return Ok(Some(
                     self.ast.add_node(
                         ast::nodes::AssignStatement {
                             lhs: expr,
                             rhs: rhs,
+                            location: location,
                         }
                         .into(),
                     ),
@@ -832,8 +1432,16 @@ impl<'a> Parser<'a> {
             return Ok(Some(n));
         } else if let Some(n) = self.parse_if_statement()? {
             return Ok(Some(n));
+        } else if let Some(n) = self.parse_match_statement()? {
+            return Ok(Some(n));
         } else if let Some(n) = self.parse_return_statement()? {
             return Ok(Some(n));
+        } else if let Some(n) = self.parse_while_statement()? {
+            return Ok(Some(n));
+        } else if let Some(n) = self.parse_for_statement()? {
+            return Ok(Some(n));
+        } else if let Some(n) = self.parse_loop_control_statement()? {
+            return Ok(Some(n));
         } else if let Some(n) = self.parse_expression_lead_in_statement()? {
             return Ok(Some(n));
         } else if let Some(n) = self.parse_module_declaration()? {
@@ -48,31 +48,31 @@ impl<'a> Parser<'a> {
                     .is_some()
                 {
                     symbolstrings.pop();
-                    let node = self.ast.reserve_node();
-
-                    let builtinfunc = self.ast.add_node(
+                    return self.parse_builtin_call(BuiltInFunction::PrintFormat);
+                }
+            } else if symbolstrings.as_slice() == ["result"] {
+                symbolstrings.pop();
+                return Ok(Some(
+                    self.ast.add_node(
                         ast::nodes::BuiltInObjectReference {
-                            object: ast::BuiltInObject::Function(BuiltInFunction::PrintFormat),
+                            object: ast::BuiltInObject::ResultType,
                         }
                         .into(),
-                    );
-
-                    self.expect(TokenType::OpeningParenthesis)?;
-
-                    let arglist = self.parse_argumentlist()?;
-
-                    self.expect(TokenType::ClosingParenthesis)?;
+                    ),
+                ));
+            } else if symbolstrings.last().filter(|s| **s == "array").is_some() {
+                symbolstrings.pop();
+                let function = match symbolstrings.last().map(|s| s.as_str()) {
+                    Some("len") => Some(BuiltInFunction::ArrayLen),
+                    Some("push") => Some(BuiltInFunction::ArrayPush),
+                    Some("pop") => Some(BuiltInFunction::ArrayPop),
+                    Some("clear") => Some(BuiltInFunction::ArrayClear),
+                    _ => None,
+                };
 
-                    return Ok(Some(
-                        self.ast.replace_node(
-                            node,
-                            ast::nodes::CallOperation {
-                                expr: builtinfunc,
-                                arglist: arglist,
-                            }
-                            .into(),
-                        ),
-                    ));
+                if let Some(function) = function {
+                    symbolstrings.pop();
+                    return self.parse_builtin_call(function);
                 }
             }
 
@@ -88,4 +88,41 @@ impl<'a> Parser<'a> {
 
         return Ok(None);
     }
+
+    // Built-in functions are only valid as direct calls
+    fn parse_builtin_call(
+        &mut self,
+        function: BuiltInFunction,
+    ) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        let node = self.ast.reserve_node();
+
+        let builtinfunc = self.ast.add_node(
+            ast::nodes::BuiltInObjectReference {
+                object: ast::BuiltInObject::Function(function),
+            }
+            .into(),
+        );
+
+        self.expect(TokenType::OpeningParenthesis)?;
+        let location = self
+            .tokens
+            .get_token_location(self.last_token.as_ref().unwrap());
+
+        let arglist = self.parse_argumentlist()?;
+
+        self.expect(TokenType::ClosingParenthesis)?;
+        let location = location.extended_to(&self.last_token.as_ref().unwrap().source_span);
+
+        return Ok(Some(
+            self.ast.replace_node(
+                node,
+                ast::nodes::CallOperation {
+                    expr: builtinfunc,
+                    arglist: arglist,
+                    location,
+                }
+                .into(),
+            ),
+        ));
+    }
 }
@@ -1,13 +1,22 @@
 use super::*;
 
 use crate::shared::BinaryOperationType;
+use crate::shared::UnaryOperationType;
 
 use snailquote;
 
 pub enum OpPrecedence {
+    Prefix,
     MulDiv,
     AddSub,
+    Shifts,
+    BitAnd,
+    BitXor,
+    BitOr,
     Comparisons,
+    LogicalNot,
+    LogicalAnd,
+    LogicalOr,
 }
 
 pub const fn binop_precedence(optype: &BinaryOperationType) -> u32 {
@@ -17,11 +26,29 @@ pub const fn binop_precedence(optype: &BinaryOperationType) -> u32 {
         Sub => OpPrecedence::AddSub as u32,
         Mul => OpPrecedence::MulDiv as u32,
         Div => OpPrecedence::MulDiv as u32,
+        Mod => OpPrecedence::MulDiv as u32,
+        ShiftLeft => OpPrecedence::Shifts as u32,
+        ShiftRight => OpPrecedence::Shifts as u32,
+        BitAnd => OpPrecedence::BitAnd as u32,
+        BitXor => OpPrecedence::BitXor as u32,
+        BitOr => OpPrecedence::BitOr as u32,
         Equals => OpPrecedence::Comparisons as u32,
+        NotEquals => OpPrecedence::Comparisons as u32,
         LessThan => OpPrecedence::Comparisons as u32,
         LessThanOrEq => OpPrecedence::Comparisons as u32,
         GreaterThan => OpPrecedence::Comparisons as u32,
         GreaterThanOrEq => OpPrecedence::Comparisons as u32,
+        And => OpPrecedence::LogicalAnd as u32,
+        Or => OpPrecedence::LogicalOr as u32,
+    }
+}
+
+pub const fn unaryop_precedence(optype: &UnaryOperationType) -> u32 {
+    use UnaryOperationType::*;
+    match optype {
+        Not => OpPrecedence::LogicalNot as u32,
+        Neg => OpPrecedence::Prefix as u32,
+        BitNot => OpPrecedence::Prefix as u32,
     }
 }
 
@@ -29,6 +56,102 @@ pub const fn has_higher_precedence(a: &BinaryOperationType, b: &BinaryOperationT
     binop_precedence(a) < binop_precedence(b)
 }
 
+pub enum NumericLiteral {
+    Integer(u64),
+    Float(f64),
+}
+
+// Digit separators are only allowed between two digits, "1_000" but not "_1", "1__0" or "1_.0"
+fn strip_digit_separators(digits: &str, radix: u32) -> Result<String, String> {
+    let bytes = digits.as_bytes();
+    for (i, c) in bytes.iter().enumerate() {
+        if *c == b'_' {
+            let is_digit = |c: u8| (c as char).is_digit(radix);
+            let after_digit = i > 0 && is_digit(bytes[i - 1]);
+            let before_digit = i + 1 < bytes.len() && is_digit(bytes[i + 1]);
+
+            if !after_digit || !before_digit {
+                return Err("Digit separators need to be placed between digits".into());
+            }
+        }
+    }
+
+    return Ok(digits.replace('_', ""));
+}
+
+fn parse_integer_literal(digits: &str, radix: u32, name: &str) -> Result<NumericLiteral, String> {
+    use std::num::IntErrorKind;
+
+    let digits = strip_digit_separators(digits, radix)?;
+
+    return match u64::from_str_radix(&digits, radix) {
+        Ok(value) => Ok(NumericLiteral::Integer(value)),
+        Err(e) => Err(match e.kind() {
+            IntErrorKind::Empty => format!("Missing digits in {} literal", name),
+            IntErrorKind::PosOverflow => format!("{} literal does not fit in 64 bits", name),
+            _ => format!("Invalid digit in {} literal", name),
+        }),
+    };
+}
+
+fn parse_float_literal(text: &str) -> Result<NumericLiteral, String> {
+    let text = strip_digit_separators(text, 10)?;
+
+    // Rust accepts "1." and "1.e5", we require digits on both sides of the point
+    if let Some(i) = text.find('.') {
+        if !text[i + 1..].starts_with(|c: char| c.is_ascii_digit()) {
+            return Err("Expected digits after decimal point".into());
+        }
+    }
+
+    return match text.parse::<f64>() {
+        Ok(value) if value.is_finite() => Ok(NumericLiteral::Float(value)),
+        Ok(_) => Err("Float literal is out of range".into()),
+        Err(_) => Err("Invalid float literal".into()),
+    };
+}
+
+// The scanner only guarantees that numeric literals start with a digit,
+//  so any validation of the actual number happens here
+pub fn parse_numeric_literal(text: &str) -> Result<NumericLiteral, String> {
+    if let Some(digits) = text.strip_prefix("0x") {
+        return parse_integer_literal(digits, 16, "Hexadecimal");
+    } else if let Some(digits) = text.strip_prefix("0b") {
+        return parse_integer_literal(digits, 2, "Binary");
+    } else if let Some(digits) = text.strip_prefix("0o") {
+        return parse_integer_literal(digits, 8, "Octal");
+    } else if text.contains(|c| c == '.' || c == 'e' || c == 'E') {
+        return parse_float_literal(text);
+    }
+
+    return parse_integer_literal(text, 10, "Decimal");
+}
+
+// Character literals use the same escapes as string literals, the scanner
+//  keeps the surrounding single quotes around
+pub fn parse_character_literal(text: &str) -> Result<char, String> {
+    let inner = text
+        .strip_prefix('\'')
+        .and_then(|t| t.strip_suffix('\''))
+        .ok_or_else(|| "Unterminated character literal".to_string())?;
+
+    // NOTE: Snailquote requires double quotes around the text, so a lone
+    //  double quote cannot be passed through it
+    let string = if inner == "\"" {
+        inner.to_string()
+    } else {
+        snailquote::unescape(&format!("\"{}\"", inner))
+            .map_err(|_| "Invalid escape sequence in character literal".to_string())?
+    };
+
+    let mut chars = string.chars();
+    return match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        (None, _) => Err("Empty character literal".into()),
+        _ => Err("Character literal can only contain a single character".into()),
+    };
+}
+
 impl<'a> Parser<'a> {
     pub fn parse_expression(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
         self.push_block();
@@ -37,14 +160,43 @@ impl<'a> Parser<'a> {
         return result;
     }
 
-    // Parses expressions composed with operators
+    // Parses expressions composed with operators, ranges bind looser than any operator
     fn parse_composite_expression(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        let start = match self.parse_bounded_composite_expression(u32::MAX)? {
+            Some(start) => start,
+            None => return Ok(None),
+        };
+
+        if !self.accept(TokenType::DotDot) {
+            return Ok(Some(start));
+        }
+
+        if let Some(end) = self.parse_bounded_composite_expression(u32::MAX)? {
+            return Ok(Some(
+                self.ast
+                    .add_node(ast::nodes::RangeExpression { start, end }.into()),
+            ));
+        } else {
+            return Err(self.log_error(error::Error::at_span(
+                errors::ExpectedExpression,
+                self.last_token.as_ref().unwrap().source_span,
+                "Expected end of range expression".into(),
+            ))?);
+        }
+    }
+
+    // Parses expressions composed with operators that bind tighter than the precedence bound,
+    //  any looser operator is left for the caller
+    fn parse_bounded_composite_expression(
+        &mut self,
+        bound: u32,
+    ) -> Result<Option<ast::NodeRef>, error::ErrorId> {
         // For more info, see Shunting Yard Algorithm
 
         let mut exprstack: Vec<ast::NodeRef> = Vec::new();
         let mut binopstack: Vec<BinaryOperationType> = Vec::new();
 
-        if let Some(expr) = self.parse_left_recursive_expression()? {
+        if let Some(expr) = self.parse_unary_expression()? {
             exprstack.push(expr);
         } else {
             return Ok(None);
@@ -73,7 +225,12 @@ impl<'a> Parser<'a> {
         }
 
         // Parse entire expression, separating expressions and operators
-        while let Some(optype) = self.accept_binaryoperator() {
+        while let Some(optype) = self
+            .peek_binaryoperator()
+            .filter(|op| binop_precedence(op) < bound)
+        {
+            self.advance();
+
             // Bind expressions as long as the new operator has lower or same priority
             // This ensures left-associativity since all available expressions are bound as soon as possible
             while !binopstack.is_empty()
@@ -84,7 +241,7 @@ impl<'a> Parser<'a> {
 
             binopstack.push(optype);
 
-            if let Some(expr) = self.parse_left_recursive_expression()? {
+            if let Some(expr) = self.parse_unary_expression()? {
                 exprstack.push(expr);
             } else {
                 return Err(self.log_error(error::Error::at_span(
@@ -105,6 +262,72 @@ impl<'a> Parser<'a> {
         return Ok(exprstack.pop());
     }
 
+    // Parses expressions with prefix operators
+    fn parse_unary_expression(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        if let Some(optype) = self.accept_unaryoperator() {
+            let node = self.ast.reserve_node();
+
+            // The operand is bound by the precedence of the operator,
+            //  so "not a == b" becomes "not (a == b)", but "not a and b" becomes "(not a) and b"
+            if let Some(expr) =
+                self.parse_bounded_composite_expression(unaryop_precedence(&optype))?
+            {
+                // Negated integer literals are marked as signed, so they can be typed as such
+                if optype == UnaryOperationType::Neg {
+                    if let ast::Node::IntegerLiteral(n) = self.ast.get_node_mut(&expr) {
+                        n.signed = true;
+                    }
+                }
+
+                return Ok(Some(self.ast.replace_node(
+                    node,
+                    ast::nodes::UnaryOperation { optype, expr }.into(),
+                )));
+            } else {
+                return Err(self.log_error(error::Error::at_span(
+                    errors::ExpectedExpression,
+                    self.last_token.as_ref().unwrap().source_span,
+                    "Expected operand expression".into(),
+                ))?);
+            }
+        }
+
+        // References, optional types and tries bind like the other prefix operators, so "&a[0]"
+        //  is the address of the element
+        let prefix = if self.accept(TokenType::Ampersand) {
+            TokenType::Ampersand
+        } else if self.accept(TokenType::Star) {
+            TokenType::Star
+        } else if self.accept(TokenType::Question) {
+            TokenType::Question
+        } else if self.accept(TokenType::Try) {
+            TokenType::Try
+        } else {
+            return self.parse_left_recursive_expression();
+        };
+
+        let location = self
+            .tokens
+            .get_token_location(self.last_token.as_ref().unwrap());
+        let node = self.ast.reserve_node();
+        if let Some(expr) = self.parse_bounded_composite_expression(OpPrecedence::Prefix as u32)? {
+            let location = location.extended_to(&self.last_token.as_ref().unwrap().source_span);
+            let prefixed = match prefix {
+                TokenType::Ampersand => ast::nodes::ReferenceExpression { expr, location }.into(),
+                TokenType::Star => ast::nodes::DereferenceExpression { expr, location }.into(),
+                TokenType::Question => ast::nodes::OptionalType { expr }.into(),
+                _ => ast::nodes::TryExpression { expr, location }.into(),
+            };
+            return Ok(Some(self.ast.replace_node(node, prefixed)));
+        } else {
+            return Err(self.log_error(error::Error::at_span(
+                errors::ExpectedExpression,
+                self.last_token.as_ref().unwrap().source_span,
+                "Expected operand expression".into(),
+            ))?);
+        }
+    }
+
     // Parses expressions leading with expressions
     fn parse_left_recursive_expression(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
         if let Some(expr) = self.parse_primary_expression()? {
@@ -142,9 +365,13 @@ impl<'a> Parser<'a> {
         // Calls
         if self.accept(TokenType::OpeningParenthesis) {
             let node = self.ast.reserve_node();
+            let location = self
+                .tokens
+                .get_token_location(self.last_token.as_ref().unwrap());
             let arglist = self.parse_argumentlist()?;
 
             self.expect(TokenType::ClosingParenthesis)?;
+            let location = location.extended_to(&self.last_token.as_ref().unwrap().source_span);
 
             return Ok(Some(
                 self.ast.replace_node(
@@ -152,6 +379,97 @@ impl<'a> Parser<'a> {
                     ast::nodes::CallOperation {
                         expr: *head,
                         arglist: arglist,
+                        location,
+                    }
+                    .into(),
+                ),
+            ));
+        }
+        // Indexing
+        else if self.accept(TokenType::OpeningSquareBracket) {
+            let node = self.ast.reserve_node();
+
+            // Dynamic array types, T[..]
+            if self.accept(TokenType::DotDot) {
+                self.expect(TokenType::ClosingSquareBracket)?;
+
+                return Ok(Some(self.ast.replace_node(
+                    node,
+                    ast::nodes::DynamicArrayType { expr: *head }.into(),
+                )));
+            }
+
+            // Slice types, T[&]
+            if self.accept(TokenType::Ampersand) {
+                self.expect(TokenType::ClosingSquareBracket)?;
+
+                return Ok(Some(self.ast.replace_node(
+                    node,
+                    ast::nodes::SliceType { expr: *head }.into(),
+                )));
+            }
+
+            let location = self
+                .tokens
+                .get_token_location(self.last_token.as_ref().unwrap());
+
+            let index = if let Some(n) = self.parse_expression()? {
+                n
+            } else {
+                return Err(self.log_error(error::Error::at_span(
+                    errors::ExpectedExpression,
+                    self.last_token.as_ref().unwrap().source_span,
+                    "Expected index expression".into(),
+                ))?);
+            };
+
+            self.expect(TokenType::ClosingSquareBracket)?;
+
+            return Ok(Some(
+                self.ast.replace_node(
+                    node,
+                    ast::nodes::IndexExpression {
+                        expr: *head,
+                        index,
+                        location,
+                    }
+                    .into(),
+                ),
+            ));
+        }
+        // Struct construction, T { field = expr, ... }
+        else if self.accept(TokenType::OpeningCurlyBrace) {
+            let node = self.ast.reserve_node();
+            let mut fields: Vec<(ast::SymbolRef, ast::NodeRef)> = Vec::new();
+
+            while self.accept(TokenType::Identifier) {
+                let span = self.last_token.as_ref().unwrap().source_span;
+                let symbol = self.get_last_token_symbol();
+                if fields.iter().any(|(s, _)| *s == symbol) {
+                    let name = self.ast.get_symbol(&symbol).unwrap().clone();
+                    self.log_error(error::Error::at_span(
+                        errors::DuplicateFieldInitializer,
+                        span,
+                        format!("Field '{}' is initialized more than once", name).into(),
+                    ))?;
+                }
+
+                self.expect(TokenType::Equals)?;
+                fields.push((symbol, self.expect_expression()?));
+
+                if !self.accept(TokenType::Comma) {
+                    break;
+                }
+            }
+
+            self.expect(TokenType::ClosingCurlyBrace)?;
+
+            return Ok(Some(
+                self.ast.replace_node(
+                    node,
+                    ast::nodes::StructConstruction {
+                        expr: *head,
+                        fields,
                     }
                     .into(),
                 ),
@@ -190,6 +508,8 @@ impl<'a> Parser<'a> {
                 self.ast
                     .add_node(ast::nodes::BooleanLiteral { value: false }.into()),
             ));
+        } else if self.accept(TokenType::Null) {
+            return Ok(Some(self.ast.add_node(ast::nodes::NullLiteral {}.into())));
         } else if self.accept(TokenType::StringLiteral) {
             let text = self.get_last_token_text();
 
@@ -206,42 +526,150 @@ impl<'a> Parser<'a> {
                     .into(),
                 ),
             ));
+        } else if self.accept(TokenType::CharacterLiteral) {
+            return match parse_character_literal(self.get_last_token_text()) {
+                Ok(value) => Ok(Some(
+                    self.ast
+                        .add_node(ast::nodes::CharacterLiteral { value }.into()),
+                )),
+                Err(message) => Err(self.log_error(error::Error::at_span(
+                    errors::InvalidCharacterLiteral,
+                    self.last_token.as_ref().unwrap().source_span,
+                    message,
+                ))?),
+            };
         } else if self.accept(TokenType::NumericLiteral) {
-            let text = self.get_last_token_text();
-            // TODO: Support for other numericals
-            return Ok(Some(
-                self.ast.add_node(
-                    ast::nodes::IntegerLiteral {
-                        value: text.parse::<u64>().unwrap(),
-                        signed: false,
-                    }
-                    .into(),
-                ),
-            ));
+            let literal = parse_numeric_literal(self.get_last_token_text());
+
+            return match literal {
+                Ok(NumericLiteral::Integer(value)) => Ok(Some(
+                    self.ast.add_node(
+                        ast::nodes::IntegerLiteral {
+                            value,
+                            signed: false,
+                        }
+                        .into(),
+                    ),
+                )),
+                Ok(NumericLiteral::Float(value)) => Ok(Some(
+                    self.ast.add_node(ast::nodes::FloatLiteral { value }.into()),
+                )),
+                Err(message) => Err(self.log_error(error::Error::at_span(
+                    errors::InvalidNumericLiteral,
+                    self.last_token.as_ref().unwrap().source_span,
+                    message,
+                ))?),
+            };
         } else if self.accept(TokenType::OpeningParenthesis) {
             let expr = self.parse_expression()?;
 
+            // A comma turns the parenthesized expression into a tuple
+            if let Some(first) = expr.filter(|_| self.accept(TokenType::Comma)) {
+                let mut elements = vec![first];
+
+                loop {
+                    if let Some(n) = self.parse_expression()? {
+                        elements.push(n);
+                    } else {
+                        return Err(self.log_error(error::Error::at_span(
+                            errors::ExpectedExpression,
+                            self.last_token.as_ref().unwrap().source_span,
+                            "Expected tuple element".into(),
+                        ))?);
+                    }
+
+                    if !self.accept(TokenType::Comma) {
+                        break;
+                    }
+                }
+
+                self.expect(TokenType::ClosingParenthesis)?;
+                return Ok(Some(
+                    self.ast
+                        .add_node(ast::nodes::TupleLiteral { elements }.into()),
+                ));
+            }
+
             self.expect(TokenType::ClosingParenthesis)?;
             return Ok(expr);
         } else if self.accept(TokenType::Identifier) {
             let s = self.get_last_token_symbol();
+            let location = self
+                .tokens
+                .get_token_location(self.last_token.as_ref().unwrap());
+
+            return Ok(Some(self.ast.add_node(
+                ast::nodes::SymbolReference {
+                    symbol: s,
+                    location,
+                }
+                .into(),
+            )));
+        } else if self.accept(TokenType::Dollar) {
+            self.expect(TokenType::Identifier)?;
+            let symbol = self.get_last_token_symbol();
 
             return Ok(Some(
                 self.ast
-                    .add_node(ast::nodes::SymbolReference { symbol: s }.into()),
+                    .add_node(ast::nodes::TypeParameter { symbol }.into()),
             ));
         } else if let Some(n) = self.parse_if_expression()? {
             return Ok(Some(n));
+        } else if let Some(n) = self.parse_match_expression()? {
+            return Ok(Some(n));
         } else if let Some(n) = self.parse_function_literal_or_type()? {
             return Ok(Some(n));
         } else if let Some(n) = self.parse_struct_literal()? {
             return Ok(Some(n));
+        } else if let Some(n) = self.parse_enum_literal()? {
+            return Ok(Some(n));
+        } else if let Some(n) = self.parse_array_literal()? {
+            return Ok(Some(n));
         } else if let Some(n) = self.parse_builtin_expression()? {
             return Ok(Some(n));
         }
         return Ok(None);
     }
 
+    fn parse_array_literal(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        if self.accept(TokenType::OpeningSquareBracket) {
+            let node = self.ast.reserve_node();
+            let mut elements = Vec::new();
+            let mut dynamic = false;
+
+            // A lone or trailing ".." marks the array as dynamic
+            if self.accept(TokenType::DotDot) {
+                dynamic = true;
+            } else if let Some(n) = self.parse_expression()? {
+                elements.push(n);
+
+                while self.accept(TokenType::Comma) {
+                    if self.accept(TokenType::DotDot) {
+                        dynamic = true;
+                        break;
+                    } else if let Some(n) = self.parse_expression()? {
+                        elements.push(n);
+                    } else {
+                        return Err(self.log_error(error::Error::at_span(
+                            errors::ExpectedExpression,
+                            self.last_token.as_ref().unwrap().source_span,
+                            "Expected array element".into(),
+                        ))?);
+                    }
+                }
+            }
+
+            self.expect(TokenType::ClosingSquareBracket)?;
+
+            return Ok(Some(self.ast.replace_node(
+                node,
+                ast::nodes::ArrayLiteral { elements, dynamic }.into(),
+            )));
+        }
+
+        return Ok(None);
+    }
+
     fn parse_if_expression(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
         if self.accept(TokenType::If) {
             let node = self.ast.reserve_node();
@@ -287,4 +715,66 @@ impl<'a> Parser<'a> {
 
         return Ok(None);
     }
+
+    fn parse_match_expression(&mut self) -> Result<Option<ast::NodeRef>, error::ErrorId> {
+        if self.accept(TokenType::Match) {
+            let node = self.ast.reserve_node();
+            let location = self
+                .tokens
+                .get_token_location(self.last_token.as_ref().unwrap());
+
+            let expr = self.expect_expression()?;
+            let location = location.extended_to(&self.last_token.as_ref().unwrap().source_span);
+
+            let mut arms = Vec::new();
+            let mut elsebranch: Option<ast::NodeRef> = None;
+
+            while self.accept_with_layout(TokenType::Case, TokenLayoutType::BlockLinker) {
+                let arm = self.ast.reserve_node();
+                let armlocation = self
+                    .tokens
+                    .get_token_location(self.last_token.as_ref().unwrap());
+                let pattern = self.parse_pattern()?;
+                let guard = self.parse_match_guard()?;
+                let armlocation =
+                    armlocation.extended_to(&self.last_token.as_ref().unwrap().source_span);
+
+                self.expect_with_layout(TokenType::FatArrow, TokenLayoutType::BlockKeyword)?;
+                let body = self.expect_expression()?;
+
+                arms.push(
+                    self.ast.replace_node(
+                        arm,
+                        ast::nodes::MatchArm {
+                            pattern,
+                            guard,
+                            body,
+                            location: armlocation,
+                        }
+                        .into(),
+                    ),
+                );
+            }
+
+            // Arms not matched by any case
+            if self.accept_with_layout(TokenType::Else, TokenLayoutType::BlockLinker) {
+                elsebranch = Some(self.expect_expression()?);
+            }
+
+            return Ok(Some(
+                self.ast.replace_node(
+                    node,
+                    ast::nodes::MatchExpression {
+                        expr,
+                        arms,
+                        elsebranch,
+                        location,
+                    }
+                    .into(),
+                ),
+            ));
+        }
+
+        return Ok(None);
+    }
 }
@@ -2,6 +2,7 @@ use super::utils::*;
 
 use crate::ast;
 use crate::ast::NodeId::*;
+use crate::error::*;
 
 use phf::phf_map;
 
@@ -13,11 +14,20 @@ static BINOPS: phf::Map<&'static str, BinaryOperationType> = phf_map! {
     "-" => BinaryOperationType::Sub,
     "*" => BinaryOperationType::Mul,
     "/" => BinaryOperationType::Div,
+    "%" => BinaryOperationType::Mod,
+    "&" => BinaryOperationType::BitAnd,
+    "|" => BinaryOperationType::BitOr,
+    "^" => BinaryOperationType::BitXor,
+    "<<" => BinaryOperationType::ShiftLeft,
+    ">>" => BinaryOperationType::ShiftRight,
     "==" => BinaryOperationType::Equals,
+    "!=" => BinaryOperationType::NotEquals,
     ">" => BinaryOperationType::GreaterThan,
     ">=" => BinaryOperationType::GreaterThanOrEq,
     "<" => BinaryOperationType::LessThan,
     "<=" => BinaryOperationType::LessThanOrEq,
+    "and" => BinaryOperationType::And,
+    "or" => BinaryOperationType::Or,
 };
 
 #[test]
@@ -146,3 +156,106 @@ fn test_binary_operation_order_comparison() {
 
     assert_eq!(generate_nodeid_tree(&ast1), generate_nodeid_tree(&ast2));
 }
+
+#[test]
+fn test_binary_operation_order_modulo() {
+    let ast1 = generate_ast("a + b % c * d");
+    let ast2 = generate_ast("a + ((b % c) * d)");
+
+    assert_eq!(generate_nodeid_tree(&ast1), generate_nodeid_tree(&ast2));
+}
+
+#[test]
+fn test_binary_operation_order_shift() {
+    let ast1 = generate_ast("a + b << c - d");
+    let ast2 = generate_ast("(a + b) << (c - d)");
+
+    assert_eq!(generate_nodeid_tree(&ast1), generate_nodeid_tree(&ast2));
+}
+
+#[test]
+fn test_binary_operation_order_bitwise() {
+    let ast1 = generate_ast("a | b ^ c & d << e");
+    let ast2 = generate_ast("a | (b ^ (c & (d << e)))");
+
+    assert_eq!(generate_nodeid_tree(&ast1), generate_nodeid_tree(&ast2));
+}
+
+#[test]
+fn test_binary_operation_order_bitwise_comparison() {
+    let ast1 = generate_ast("a & b != c | d");
+    let ast2 = generate_ast("(a & b) != (c | d)");
+
+    assert_eq!(generate_nodeid_tree(&ast1), generate_nodeid_tree(&ast2));
+}
+
+#[test]
+fn test_binary_operation_order_logical() {
+    let ast1 = generate_ast("a or b and c == d");
+    let ast2 = generate_ast("a or (b and (c == d))");
+
+    assert_eq!(generate_nodeid_tree(&ast1), generate_nodeid_tree(&ast2));
+}
+
+#[test]
+fn test_unary_operation_not() {
+    verify_ast(
+        "not a",
+        &entrypoint_wrapper_tree(&[tree(UnaryOperation, &[leaf(SymbolReference)])]),
+    );
+}
+
+#[test]
+fn test_unary_operation_order_not_comparison() {
+    let ast1 = generate_ast("not a == b");
+    let ast2 = generate_ast("not (a == b)");
+
+    assert_eq!(generate_nodeid_tree(&ast1), generate_nodeid_tree(&ast2));
+}
+
+#[test]
+fn test_unary_operation_order_not_logical() {
+    let ast1 = generate_ast("not a and not b or c");
+    let ast2 = generate_ast("((not a) and (not b)) or c");
+
+    assert_eq!(generate_nodeid_tree(&ast1), generate_nodeid_tree(&ast2));
+}
+
+#[test]
+fn test_unary_operation_missing_operand() {
+    let result = generate_ast_with_errors("not", false);
+    expect_error_ids(&result.1, &[new_error_id(errors::ExpectedExpression)]);
+}
+
+#[test]
+fn test_unary_operation_negation() {
+    verify_ast(
+        "-a",
+        &entrypoint_wrapper_tree(&[tree(UnaryOperation, &[leaf(SymbolReference)])]),
+    );
+    verify_ast(
+        "~a",
+        &entrypoint_wrapper_tree(&[tree(UnaryOperation, &[leaf(SymbolReference)])]),
+    );
+}
+
+#[test]
+fn test_unary_operation_order_negation() {
+    let ast1 = generate_ast("-a * b - ~c");
+    let ast2 = generate_ast("((-a) * b) - (~c)");
+
+    assert_eq!(generate_nodeid_tree(&ast1), generate_nodeid_tree(&ast2));
+}
+
+#[test]
+fn test_unary_operation_order_nested() {
+    let ast1 = generate_ast("a - -b");
+    let ast2 = generate_ast("a - (-b)");
+
+    assert_eq!(generate_nodeid_tree(&ast1), generate_nodeid_tree(&ast2));
+
+    let ast1 = generate_ast("- ~a");
+    let ast2 = generate_ast("-(~a)");
+
+    assert_eq!(generate_nodeid_tree(&ast1), generate_nodeid_tree(&ast2));
+}
@@ -0,0 +1,146 @@
+use super::utils::*;
+use crate::ast::NodeId::*;
+use crate::error::*;
+
+#[test]
+fn test_array_literal() {
+    verify_ast(
+        "[1, a, 2 + 3]",
+        &entrypoint_wrapper_tree(&[tree(
+            ArrayLiteral,
+            &[
+                leaf(IntegerLiteral),
+                leaf(SymbolReference),
+                tree(
+                    BinaryOperation,
+                    &[leaf(IntegerLiteral), leaf(IntegerLiteral)],
+                ),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_array_literal_empty() {
+    verify_ast("[]", &entrypoint_wrapper_tree(&[leaf(ArrayLiteral)]));
+}
+
+#[test]
+fn test_array_literal_nested() {
+    verify_ast(
+        "[[1], [2]]",
+        &entrypoint_wrapper_tree(&[tree(
+            ArrayLiteral,
+            &[
+                tree(ArrayLiteral, &[leaf(IntegerLiteral)]),
+                tree(ArrayLiteral, &[leaf(IntegerLiteral)]),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_index_expression() {
+    verify_ast(
+        "a[i + 1]",
+        &entrypoint_wrapper_tree(&[tree(
+            IndexExpression,
+            &[
+                leaf(SymbolReference),
+                tree(
+                    BinaryOperation,
+                    &[leaf(SymbolReference), leaf(IntegerLiteral)],
+                ),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_index_expression_nested() {
+    verify_ast(
+        "a[1][2]",
+        &entrypoint_wrapper_tree(&[tree(
+            IndexExpression,
+            &[
+                tree(
+                    IndexExpression,
+                    &[leaf(SymbolReference), leaf(IntegerLiteral)],
+                ),
+                leaf(IntegerLiteral),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_index_expression_location() {
+    let ast = verify_ast(
+        "a[1]",
+        &entrypoint_wrapper_tree(&[tree(
+            IndexExpression,
+            &[leaf(SymbolReference), leaf(IntegerLiteral)],
+        )]),
+    );
+
+    let noderef = ast.find_first_node(IndexExpression).unwrap();
+    if let crate::ast::Node::IndexExpression(n) = ast.get_node(&noderef) {
+        assert_eq!(n.location.to_string(), "strsource:1:2");
+    } else {
+        panic!("Expected index expression");
+    }
+}
+
+#[test]
+fn test_typed_array_declaration() {
+    verify_ast(
+        "var a : #primitives.u32[4] = [5, 6, 7, 8]",
+        &entrypoint_wrapper_tree(&[tree(
+            SymbolDeclaration,
+            &[
+                tree(
+                    IndexExpression,
+                    &[leaf(BuiltInObjectReference), leaf(IntegerLiteral)],
+                ),
+                tree(
+                    ArrayLiteral,
+                    &[
+                        leaf(IntegerLiteral),
+                        leaf(IntegerLiteral),
+                        leaf(IntegerLiteral),
+                        leaf(IntegerLiteral),
+                    ],
+                ),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_index_assignment() {
+    verify_ast(
+        "a[0] = b",
+        &entrypoint_wrapper_tree(&[tree(
+            AssignStatement,
+            &[
+                tree(
+                    IndexExpression,
+                    &[leaf(SymbolReference), leaf(IntegerLiteral)],
+                ),
+                leaf(SymbolReference),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_index_expression_missing_bracket() {
+    let result = generate_ast_with_errors("a[1", false);
+    expect_error_ids(&result.1, &[new_error_id(errors::UnexpectedEOF)]);
+}
+
+#[test]
+fn test_array_literal_missing_element() {
+    let result = generate_ast_with_errors("[1, ]", false);
+    expect_error_ids(&result.1, &[new_error_id(errors::ExpectedExpression)]);
+}
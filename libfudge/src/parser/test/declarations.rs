@@ -8,6 +8,7 @@ use phf::phf_map;
 // Map with all declaration types
 static DECLTYPES: phf::Map<&'static str, ast::SymbolDeclarationType> = phf_map! {
     "def" => ast::SymbolDeclarationType::Def,
+    "const" => ast::SymbolDeclarationType::Const,
     "var" => ast::SymbolDeclarationType::Var,
 };
 
@@ -29,3 +30,25 @@ fn test_all_simple_declarations() {
         test_simple_declaration(decl, DECLTYPES[decl]);
     }
 }
+
+#[test]
+fn test_inferred_declarations() {
+    fn test_inferred_declaration(source: &str, expected: &NodeIdTree) {
+        let ast = verify_ast(source, &entrypoint_wrapper_tree(&[expected.clone()]));
+
+        if let Some(noderef) = ast.find_first_node(SymbolDeclaration) {
+            if let ast::Node::SymbolDeclaration(n) = ast.get_node(&noderef) {
+                assert_eq!(ast::SymbolDeclarationType::Inferred, n.decltype);
+            }
+        }
+    }
+
+    test_inferred_declaration("a := 0", &tree(SymbolDeclaration, &[leaf(IntegerLiteral)]));
+    test_inferred_declaration(
+        "a : b = 0",
+        &tree(
+            SymbolDeclaration,
+            &[leaf(SymbolReference), leaf(IntegerLiteral)],
+        ),
+    );
+}
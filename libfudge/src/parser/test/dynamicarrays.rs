@@ -0,0 +1,98 @@
+use super::utils::*;
+use crate::ast::NodeId::*;
+use crate::error::*;
+
+fn is_dynamic_literal(source: &str) -> bool {
+    let ast = generate_ast_with_errors(source, false).0;
+    let noderef = ast.find_first_node(ArrayLiteral).unwrap();
+    match ast.get_node(&noderef) {
+        crate::ast::Node::ArrayLiteral(n) => n.dynamic,
+        _ => panic!("Expected array literal"),
+    }
+}
+
+#[test]
+fn test_dynamic_array_literal() {
+    verify_ast(
+        "[1, 2, ..]",
+        &entrypoint_wrapper_tree(&[tree(
+            ArrayLiteral,
+            &[leaf(IntegerLiteral), leaf(IntegerLiteral)],
+        )]),
+    );
+    assert!(is_dynamic_literal("[1, 2, ..]"));
+    assert!(!is_dynamic_literal("[1, 2]"));
+}
+
+#[test]
+fn test_dynamic_array_literal_empty() {
+    verify_ast("[..]", &entrypoint_wrapper_tree(&[leaf(ArrayLiteral)]));
+    assert!(is_dynamic_literal("[..]"));
+}
+
+#[test]
+fn test_dynamic_array_type() {
+    verify_ast(
+        "var a : #primitives.u32[..] = [5, 6]",
+        &entrypoint_wrapper_tree(&[tree(
+            SymbolDeclaration,
+            &[
+                tree(DynamicArrayType, &[leaf(BuiltInObjectReference)]),
+                tree(ArrayLiteral, &[leaf(IntegerLiteral), leaf(IntegerLiteral)]),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_dynamic_array_inferred_declaration() {
+    verify_ast(
+        "var a : [..] = [5, 6]",
+        &entrypoint_wrapper_tree(&[tree(
+            SymbolDeclaration,
+            &[
+                leaf(ArrayLiteral),
+                tree(ArrayLiteral, &[leaf(IntegerLiteral), leaf(IntegerLiteral)]),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_array_builtins() {
+    for builtin in ["len", "pop", "clear"] {
+        verify_ast(
+            format!("#array.{}(a)", builtin).as_str(),
+            &entrypoint_wrapper_tree(&[tree(
+                CallOperation,
+                &[
+                    leaf(BuiltInObjectReference),
+                    tree(ArgumentList, &[leaf(SymbolReference)]),
+                ],
+            )]),
+        );
+    }
+
+    verify_ast(
+        "#array.push(a, 5)",
+        &entrypoint_wrapper_tree(&[tree(
+            CallOperation,
+            &[
+                leaf(BuiltInObjectReference),
+                tree(ArgumentList, &[leaf(SymbolReference), leaf(IntegerLiteral)]),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_unknown_array_builtin() {
+    let result = generate_ast_with_errors("#array.sort(a)", false);
+    expect_error_ids(&result.1, &[new_error_id(errors::UnknownCompilerDirective)]);
+}
+
+#[test]
+fn test_dynamic_array_type_missing_bracket() {
+    let result = generate_ast_with_errors("var a : #primitives.u32[.. = [5]", false);
+    expect_error_ids(&result.1, &[new_error_id(errors::UnexpectedToken)]);
+}
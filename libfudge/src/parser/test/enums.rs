@@ -0,0 +1,66 @@
+use super::utils::*;
+use crate::ast::NodeId::*;
+
+#[test]
+fn test_declare_empty_enum() {
+    verify_ast(
+        "\
+        enum begin\n\
+        end",
+        &entrypoint_wrapper_tree(&[leaf(EnumLiteral)]),
+    );
+}
+
+#[test]
+fn test_declare_simple_enum() {
+    verify_ast(
+        "\
+        enum begin\n\
+            \tcase a\n\
+            \tcase b\n\
+        end",
+        &entrypoint_wrapper_tree(&[tree(EnumLiteral, &[leaf(EnumVariant), leaf(EnumVariant)])]),
+    );
+}
+
+#[test]
+fn test_declare_enum_with_payload() {
+    verify_ast(
+        "\
+        enum begin\n\
+            \tcase none\n\
+            \tcase rect(w : u32, h : u32)\n\
+        end",
+        &entrypoint_wrapper_tree(&[tree(
+            EnumLiteral,
+            &[
+                leaf(EnumVariant),
+                tree(
+                    EnumVariant,
+                    &[
+                        tree(StructField, &[leaf(SymbolReference)]),
+                        tree(StructField, &[leaf(SymbolReference)]),
+                    ],
+                ),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_declare_generic_enum() {
+    verify_ast(
+        "\
+        enum begin\n\
+            \tcase some(value : $T)\n\
+            \tcase none\n\
+        end",
+        &entrypoint_wrapper_tree(&[tree(
+            EnumLiteral,
+            &[
+                tree(EnumVariant, &[tree(StructField, &[leaf(TypeParameter)])]),
+                leaf(EnumVariant),
+            ],
+        )]),
+    );
+}
@@ -0,0 +1,88 @@
+use super::utils::*;
+use crate::ast::NodeId::*;
+use crate::error::*;
+
+#[test]
+fn test_statement_for_range_empty() {
+    verify_ast(
+        "for i in 0..n do\n\
+        end",
+        &entrypoint_wrapper_tree(&[tree(
+            ForStatement,
+            &[
+                tree(
+                    RangeExpression,
+                    &[leaf(IntegerLiteral), leaf(SymbolReference)],
+                ),
+                leaf(StatementBody),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_statement_for_range_operator_bounds() {
+    verify_ast(
+        "for i in a + 1..b * 2 do end",
+        &entrypoint_wrapper_tree(&[tree(
+            ForStatement,
+            &[
+                tree(
+                    RangeExpression,
+                    &[
+                        tree(
+                            BinaryOperation,
+                            &[leaf(SymbolReference), leaf(IntegerLiteral)],
+                        ),
+                        tree(
+                            BinaryOperation,
+                            &[leaf(SymbolReference), leaf(IntegerLiteral)],
+                        ),
+                    ],
+                ),
+                leaf(StatementBody),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_statement_for_with_break_and_continue() {
+    verify_ast(
+        "\
+        for i in 0..10 do\n\
+            \tbreak\n\
+            \tcontinue\n\
+        end",
+        &entrypoint_wrapper_tree(&[tree(
+            ForStatement,
+            &[
+                tree(
+                    RangeExpression,
+                    &[leaf(IntegerLiteral), leaf(IntegerLiteral)],
+                ),
+                tree(
+                    StatementBody,
+                    &[leaf(BreakStatement), leaf(ContinueStatement)],
+                ),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_wrong_statement_for_empty() {
+    let s = "\
+        for i in 0..10\n\
+        do\n\
+        end";
+
+    let result = generate_ast_with_errors(s, false);
+    expect_error_ids(&result.1, &[new_error_id(errors::MismatchedAlignment)]);
+}
+
+#[test]
+fn test_wrong_statement_for_missing_range_end() {
+    let result = generate_ast_with_errors("for i in 0.. do end", false);
+    expect_error_ids(&result.1, &[new_error_id(errors::ExpectedExpression)]);
+}
@@ -1,5 +1,8 @@
 use super::utils::*;
 use crate::ast::NodeId::*;
+use crate::error::*;
+
+use crate::ast;
 
 fn wrap_in_simple_function_literal(body: &str) -> String {
     return format!("func() do\n{}\nend", body);
@@ -57,3 +60,200 @@ fn test_function_with_primitive_return() {
 
     verify_ast(source.as_str(), &expected);
 }
+
+#[test]
+fn test_function_with_multiple_returns() {
+    let source = wrap_in_function_literal_with_outparams(
+        "(#primitives.u32, #primitives.bool)",
+        "\treturn 42, true",
+    );
+    let expected = function_literal_with_outparams_wrapper_tree(
+        &[leaf(BuiltInObjectReference), leaf(BuiltInObjectReference)],
+        &[tree(
+            ReturnStatement,
+            &[leaf(IntegerLiteral), leaf(BooleanLiteral)],
+        )],
+    );
+
+    verify_ast(source.as_str(), &expected);
+}
+
+#[test]
+fn test_function_with_default_argument() {
+    verify_ast(
+        "func(a : #primitives.u32, b : #primitives.u32 = 2) do\nend",
+        &entrypoint_wrapper_tree(&[tree(
+            FunctionLiteral,
+            &[
+                tree(InputParameter, &[leaf(BuiltInObjectReference)]),
+                tree(
+                    InputParameter,
+                    &[leaf(BuiltInObjectReference), leaf(IntegerLiteral)],
+                ),
+                leaf(StatementBody),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_call_with_named_arguments() {
+    verify_ast(
+        "f(x, scale = 2, offset = a + 1)",
+        &entrypoint_wrapper_tree(&[tree(
+            CallOperation,
+            &[
+                leaf(SymbolReference),
+                tree(
+                    ArgumentList,
+                    &[
+                        leaf(SymbolReference),
+                        tree(NamedArgument, &[leaf(IntegerLiteral)]),
+                        tree(
+                            NamedArgument,
+                            &[tree(
+                                BinaryOperation,
+                                &[leaf(SymbolReference), leaf(IntegerLiteral)],
+                            )],
+                        ),
+                    ],
+                ),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_wrong_positional_argument_after_named() {
+    let result = generate_ast_with_errors("f(scale = 2, x)", false);
+    expect_error_ids(
+        &result.1,
+        &[new_error_id(errors::PositionalArgumentAfterNamed)],
+    );
+}
+
+#[test]
+fn test_wrong_duplicate_named_argument() {
+    let result = generate_ast_with_errors("f(scale = 2, scale = 3)", false);
+    expect_error_ids(&result.1, &[new_error_id(errors::DuplicateNamedArgument)]);
+}
+
+#[test]
+fn test_function_with_capture_list() {
+    let ast = verify_ast(
+        "func[x, &y]() do\nend",
+        &entrypoint_wrapper_tree(&[tree(FunctionLiteral, &[leaf(StatementBody)])]),
+    );
+
+    let noderef = ast.find_first_node(FunctionLiteral).unwrap();
+    if let ast::Node::FunctionLiteral(n) = ast.get_node(&noderef) {
+        let captures: Vec<_> = n
+            .captures
+            .iter()
+            .map(|(symbol, capturetype)| (ast.get_symbol(symbol).unwrap().as_str(), *capturetype))
+            .collect();
+        assert_eq!(
+            captures,
+            [
+                ("x", ast::CaptureType::Value),
+                ("y", ast::CaptureType::Reference)
+            ]
+        );
+    }
+}
+
+#[test]
+fn test_function_type_parameter() {
+    verify_ast(
+        "func(f : func(a : #primitives.u32) -> #primitives.u32) do\nend",
+        &entrypoint_wrapper_tree(&[tree(
+            FunctionLiteral,
+            &[
+                tree(
+                    InputParameter,
+                    &[tree(
+                        FunctionType,
+                        &[
+                            tree(InputParameter, &[leaf(BuiltInObjectReference)]),
+                            tree(OutputParameter, &[leaf(BuiltInObjectReference)]),
+                        ],
+                    )],
+                ),
+                leaf(StatementBody),
+            ],
+        )]),
+    );
+}
+
+// The body after "do" belongs to the function literal, not to the function type it returns
+#[test]
+fn test_function_returning_function_type() {
+    verify_ast(
+        "\
+            func(f : func(x : #primitives.u32) -> #primitives.u32) -> func(x : #primitives.u32) -> #primitives.u32 do\n\
+                \treturn f\n\
+            end",
+        &entrypoint_wrapper_tree(&[tree(
+            FunctionLiteral,
+            &[
+                tree(
+                    InputParameter,
+                    &[tree(
+                        FunctionType,
+                        &[
+                            tree(InputParameter, &[leaf(BuiltInObjectReference)]),
+                            tree(OutputParameter, &[leaf(BuiltInObjectReference)]),
+                        ],
+                    )],
+                ),
+                tree(
+                    OutputParameter,
+                    &[tree(
+                        FunctionType,
+                        &[
+                            tree(InputParameter, &[leaf(BuiltInObjectReference)]),
+                            tree(OutputParameter, &[leaf(BuiltInObjectReference)]),
+                        ],
+                    )],
+                ),
+                tree(
+                    StatementBody,
+                    &[tree(ReturnStatement, &[leaf(SymbolReference)])],
+                ),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_wrong_function_type_with_captures() {
+    let result = generate_ast_with_errors("var f : func[x]()", false);
+    expect_error_ids(&result.1, &[new_error_id(errors::CapturesOnFunctionType)]);
+}
+
+#[test]
+fn test_function_with_type_parameters() {
+    let ast = verify_ast(
+        "func(a : $T, b : $T[..]) -> $T do\nend",
+        &entrypoint_wrapper_tree(&[tree(
+            FunctionLiteral,
+            &[
+                tree(InputParameter, &[leaf(TypeParameter)]),
+                tree(
+                    InputParameter,
+                    &[tree(DynamicArrayType, &[leaf(TypeParameter)])],
+                ),
+                tree(OutputParameter, &[leaf(TypeParameter)]),
+                leaf(StatementBody),
+            ],
+        )]),
+    );
+
+    let mut typeparams = Vec::new();
+    ast.collect_type_parameters(&ast.get_root().unwrap(), &mut typeparams);
+    let typeparams: Vec<_> = typeparams
+        .iter()
+        .map(|symbol| ast.get_symbol(symbol).unwrap().as_str())
+        .collect();
+    assert_eq!(typeparams, ["T"]);
+}
@@ -0,0 +1,237 @@
+use super::utils::*;
+use crate::ast::NodeId::*;
+use crate::error::*;
+
+#[test]
+fn test_statement_match_empty() {
+    verify_ast(
+        "match a\n\
+        end",
+        &entrypoint_wrapper_tree(&[tree(MatchStatement, &[leaf(SymbolReference)])]),
+    );
+}
+
+#[test]
+fn test_statement_match_simple() {
+    verify_ast(
+        "\
+        match a\n\
+        case b then\n\
+            \tc()\n\
+        case d(e, _) then\n\
+        end",
+        &entrypoint_wrapper_tree(&[tree(
+            MatchStatement,
+            &[
+                leaf(SymbolReference),
+                tree(
+                    MatchArm,
+                    &[
+                        leaf(VariantPattern),
+                        tree(
+                            StatementBody,
+                            &[tree(
+                                CallOperation,
+                                &[leaf(SymbolReference), leaf(ArgumentList)],
+                            )],
+                        ),
+                    ],
+                ),
+                tree(MatchArm, &[leaf(VariantPattern), leaf(StatementBody)]),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_statement_match_else() {
+    verify_ast(
+        "\
+        match a\n\
+        case b then\n\
+        else\n\
+        end",
+        &entrypoint_wrapper_tree(&[tree(
+            MatchStatement,
+            &[
+                leaf(SymbolReference),
+                tree(MatchArm, &[leaf(VariantPattern), leaf(StatementBody)]),
+                leaf(StatementBody),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_wrong_statement_match_case() {
+    let s = "\
+        match a\n\
+            \tcase b then\n\
+        end";
+
+    // The end keyword is misaligned to the case as well
+    let result = generate_ast_with_errors(s, false);
+    expect_error_ids(
+        &result.1,
+        &[
+            new_error_id(errors::MismatchedAlignment),
+            new_error_id(errors::MismatchedAlignment),
+        ],
+    );
+}
+
+#[test]
+fn test_expression_match_oneline() {
+    verify_ast(
+        "def x = match a case b => c case d(e) => e else f",
+        &entrypoint_wrapper_tree(&[tree(
+            SymbolDeclaration,
+            &[tree(
+                MatchExpression,
+                &[
+                    leaf(SymbolReference),
+                    tree(MatchArm, &[leaf(VariantPattern), leaf(SymbolReference)]),
+                    tree(MatchArm, &[leaf(VariantPattern), leaf(SymbolReference)]),
+                    leaf(SymbolReference),
+                ],
+            )],
+        )]),
+    );
+}
+
+#[test]
+fn test_expression_match_multiline() {
+    verify_ast(
+        "def x =\n\
+            \tmatch a\n\
+            \tcase b => c\n\
+            \tcase d =>\n\
+                \t\te",
+        &entrypoint_wrapper_tree(&[tree(
+            SymbolDeclaration,
+            &[tree(
+                MatchExpression,
+                &[
+                    leaf(SymbolReference),
+                    tree(MatchArm, &[leaf(VariantPattern), leaf(SymbolReference)]),
+                    tree(MatchArm, &[leaf(VariantPattern), leaf(SymbolReference)]),
+                ],
+            )],
+        )]),
+    );
+}
+
+#[test]
+fn test_wrong_expression_match_multiline() {
+    let s = "\
+        def x = match a\n\
+            \tcase b => c";
+
+    let result = generate_ast_with_errors(s, false);
+    expect_error_ids(&result.1, &[new_error_id(errors::MismatchedAlignment)]);
+}
+
+#[test]
+fn test_statement_match_guard() {
+    verify_ast(
+        "\
+        match a\n\
+        case b(c) if c then\n\
+        end",
+        &entrypoint_wrapper_tree(&[tree(
+            MatchStatement,
+            &[
+                leaf(SymbolReference),
+                tree(
+                    MatchArm,
+                    &[
+                        leaf(VariantPattern),
+                        leaf(SymbolReference),
+                        leaf(StatementBody),
+                    ],
+                ),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_expression_match_literal_and_range_patterns() {
+    verify_ast(
+        "def x = match a case 1 => b case -2..3 => c case _ => d",
+        &entrypoint_wrapper_tree(&[tree(
+            SymbolDeclaration,
+            &[tree(
+                MatchExpression,
+                &[
+                    leaf(SymbolReference),
+                    tree(
+                        MatchArm,
+                        &[
+                            tree(LiteralPattern, &[leaf(IntegerLiteral)]),
+                            leaf(SymbolReference),
+                        ],
+                    ),
+                    tree(
+                        MatchArm,
+                        &[
+                            tree(
+                                RangePattern,
+                                &[
+                                    tree(UnaryOperation, &[leaf(IntegerLiteral)]),
+                                    leaf(IntegerLiteral),
+                                ],
+                            ),
+                            leaf(SymbolReference),
+                        ],
+                    ),
+                    tree(MatchArm, &[leaf(WildcardPattern), leaf(SymbolReference)]),
+                ],
+            )],
+        )]),
+    );
+}
+
+#[test]
+fn test_expression_match_struct_and_tuple_patterns() {
+    verify_ast(
+        "def x = match a case { b = c, d = (_, 1) } if c => c",
+        &entrypoint_wrapper_tree(&[tree(
+            SymbolDeclaration,
+            &[tree(
+                MatchExpression,
+                &[
+                    leaf(SymbolReference),
+                    tree(
+                        MatchArm,
+                        &[
+                            tree(
+                                StructPattern,
+                                &[
+                                    leaf(BindingPattern),
+                                    tree(
+                                        TuplePattern,
+                                        &[
+                                            leaf(WildcardPattern),
+                                            tree(LiteralPattern, &[leaf(IntegerLiteral)]),
+                                        ],
+                                    ),
+                                ],
+                            ),
+                            leaf(SymbolReference),
+                            leaf(SymbolReference),
+                        ],
+                    ),
+                ],
+            )],
+        )]),
+    );
+}
+
+#[test]
+fn test_wrong_struct_pattern() {
+    let s = "def x = match a case { b } => c";
+
+    let result = generate_ast_with_errors(s, false);
+    expect_error_ids(&result.1, &[new_error_id(errors::UnexpectedToken)]);
+}
@@ -1,11 +1,22 @@
 pub mod arithmetics;
+pub mod arrays;
 pub mod basic;
 pub mod declarations;
+pub mod dynamicarrays;
+pub mod enums;
+pub mod forstatements;
 pub mod functions;
 pub mod ifexpressions;
 pub mod ifstatements;
 pub mod layout;
+pub mod matches;
+pub mod optionals;
 pub mod primitives;
+pub mod references;
+pub mod results;
+pub mod slices;
 pub mod structs;
 pub mod subscripts;
+pub mod tuples;
 pub mod utils;
+pub mod whilestatements;
@@ -0,0 +1,30 @@
+use super::utils::*;
+use crate::ast::NodeId::*;
+
+#[test]
+fn test_optional_declaration() {
+    verify_ast(
+        "var x : ?#primitives.u32 = null",
+        &entrypoint_wrapper_tree(&[tree(
+            SymbolDeclaration,
+            &[
+                tree(OptionalType, &[leaf(BuiltInObjectReference)]),
+                leaf(NullLiteral),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_null_comparison() {
+    verify_ast(
+        "if x != null then\nend",
+        &entrypoint_wrapper_tree(&[tree(
+            IfStatement,
+            &[
+                tree(BinaryOperation, &[leaf(SymbolReference), leaf(NullLiteral)]),
+                leaf(StatementBody),
+            ],
+        )]),
+    );
+}
@@ -2,6 +2,7 @@ use super::utils::*;
 use crate::typesystem::*;
 
 use crate::ast;
+use crate::error::*;
 use crate::ast::NodeId::*;
 
 fn wrap_in_simple_declaration(typename: &str) -> String {
@@ -41,3 +42,110 @@ fn test_boolean_literals() {
         &entrypoint_wrapper_tree(&[leaf(BooleanLiteral), leaf(BooleanLiteral)]),
     );
 }
+
+fn first_integer_literal_value(source: &str) -> u64 {
+    let ast = verify_ast(
+        source,
+        &simple_declaration_wrapper_tree(&[leaf(IntegerLiteral)]),
+    );
+    let noderef = ast.find_first_node(IntegerLiteral).unwrap();
+    if let ast::Node::IntegerLiteral(n) = ast.get_node(&noderef) {
+        return n.value;
+    }
+    unreachable!();
+}
+
+fn first_float_literal_value(source: &str) -> f64 {
+    let ast = verify_ast(
+        source,
+        &simple_declaration_wrapper_tree(&[leaf(FloatLiteral)]),
+    );
+    let noderef = ast.find_first_node(FloatLiteral).unwrap();
+    if let ast::Node::FloatLiteral(n) = ast.get_node(&noderef) {
+        return n.value;
+    }
+    unreachable!();
+}
+
+#[test]
+fn test_integer_literals() {
+    assert_eq!(first_integer_literal_value("def _ = 1234"), 1234);
+    assert_eq!(first_integer_literal_value("def _ = 1_000_000"), 1000000);
+    assert_eq!(first_integer_literal_value("def _ = 0xFF"), 255);
+    assert_eq!(first_integer_literal_value("def _ = 0xdead_beef"), 0xdeadbeef);
+    assert_eq!(first_integer_literal_value("def _ = 0b1010"), 10);
+    assert_eq!(first_integer_literal_value("def _ = 0o777"), 511);
+    assert_eq!(
+        first_integer_literal_value("def _ = 18446744073709551615"),
+        u64::MAX
+    );
+}
+
+#[test]
+fn test_negated_integer_literals() {
+    fn is_signed(source: &str) -> bool {
+        let ast = generate_ast(source);
+        let noderef = ast.find_first_node(IntegerLiteral).unwrap();
+        if let ast::Node::IntegerLiteral(n) = ast.get_node(&noderef) {
+            return n.signed;
+        }
+        unreachable!();
+    }
+
+    assert!(!is_signed("def _ = 5"));
+    assert!(is_signed("def _ = -5"));
+    assert!(is_signed("def _ = -(5)"));
+    assert!(!is_signed("def _ = ~5"));
+    assert!(!is_signed("def _ = 1 - 5"));
+}
+
+#[test]
+fn test_float_literals() {
+    assert_eq!(first_float_literal_value("def _ = 1.5"), 1.5);
+    assert_eq!(first_float_literal_value("def _ = 1_000.25"), 1000.25);
+    assert_eq!(first_float_literal_value("def _ = 2e3"), 2000.0);
+    assert_eq!(first_float_literal_value("def _ = 2.5e-1"), 0.25);
+}
+
+#[test]
+fn test_malformed_numeric_literals() {
+    for source in [
+        "def _ = 0x",
+        "def _ = 0b102",
+        "def _ = 0o8",
+        "def _ = 1__0",
+        "def _ = 1_",
+        "def _ = 18446744073709551616",
+    ] {
+        let result = generate_ast_with_errors(source, false);
+        expect_error_ids(&result.1, &[new_error_id(errors::InvalidNumericLiteral)]);
+    }
+}
+
+#[test]
+fn test_character_literals() {
+    fn first_character_literal_value(source: &str) -> char {
+        let ast = verify_ast(
+            source,
+            &simple_declaration_wrapper_tree(&[leaf(CharacterLiteral)]),
+        );
+        let noderef = ast.find_first_node(CharacterLiteral).unwrap();
+        if let ast::Node::CharacterLiteral(n) = ast.get_node(&noderef) {
+            return n.value;
+        }
+        unreachable!();
+    }
+
+    assert_eq!(first_character_literal_value("def _ = 'a'"), 'a');
+    assert_eq!(first_character_literal_value("def _ = '\\t'"), '\t');
+    assert_eq!(first_character_literal_value("def _ = '\\''"), '\'');
+    assert_eq!(first_character_literal_value("def _ = '\\u{e5}'"), 'å');
+}
+
+#[test]
+fn test_malformed_character_literals() {
+    for source in ["def _ = ''", "def _ = 'ab'", "def _ = '\\q'"] {
+        let result = generate_ast_with_errors(source, false);
+        expect_error_ids(&result.1, &[new_error_id(errors::InvalidCharacterLiteral)]);
+    }
+}
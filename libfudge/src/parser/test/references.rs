@@ -0,0 +1,56 @@
+use super::utils::*;
+use crate::ast::NodeId::*;
+
+#[test]
+fn test_reference_expression() {
+    verify_ast(
+        "var r : &#primitives.u32 = &a",
+        &entrypoint_wrapper_tree(&[tree(
+            SymbolDeclaration,
+            &[
+                tree(ReferenceExpression, &[leaf(BuiltInObjectReference)]),
+                tree(ReferenceExpression, &[leaf(SymbolReference)]),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_dereference_assignment() {
+    verify_ast(
+        "*r = *r + 1",
+        &entrypoint_wrapper_tree(&[tree(
+            AssignStatement,
+            &[
+                tree(DereferenceExpression, &[leaf(SymbolReference)]),
+                tree(
+                    BinaryOperation,
+                    &[
+                        tree(DereferenceExpression, &[leaf(SymbolReference)]),
+                        leaf(IntegerLiteral),
+                    ],
+                ),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_dereference_on_new_line() {
+    verify_ast(
+        "a = b\n*r = 2",
+        &entrypoint_wrapper_tree(&[
+            tree(
+                AssignStatement,
+                &[leaf(SymbolReference), leaf(SymbolReference)],
+            ),
+            tree(
+                AssignStatement,
+                &[
+                    tree(DereferenceExpression, &[leaf(SymbolReference)]),
+                    leaf(IntegerLiteral),
+                ],
+            ),
+        ]),
+    );
+}
@@ -0,0 +1,48 @@
+use super::utils::*;
+use crate::ast::NodeId::*;
+
+#[test]
+fn test_result_type() {
+    verify_ast(
+        "def R = #result(#primitives.u32, #primitives.u8)",
+        &entrypoint_wrapper_tree(&[tree(
+            SymbolDeclaration,
+            &[tree(
+                CallOperation,
+                &[
+                    leaf(BuiltInObjectReference),
+                    tree(
+                        ArgumentList,
+                        &[leaf(BuiltInObjectReference), leaf(BuiltInObjectReference)],
+                    ),
+                ],
+            )],
+        )]),
+    );
+}
+
+#[test]
+fn test_try_expression() {
+    verify_ast(
+        "var x = try f(a) + 1",
+        &entrypoint_wrapper_tree(&[tree(
+            SymbolDeclaration,
+            &[tree(
+                BinaryOperation,
+                &[
+                    tree(
+                        TryExpression,
+                        &[tree(
+                            CallOperation,
+                            &[
+                                leaf(SymbolReference),
+                                tree(ArgumentList, &[leaf(SymbolReference)]),
+                            ],
+                        )],
+                    ),
+                    leaf(IntegerLiteral),
+                ],
+            )],
+        )]),
+    );
+}
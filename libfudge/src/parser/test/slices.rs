@@ -0,0 +1,59 @@
+use super::utils::*;
+use crate::ast::NodeId::*;
+
+#[test]
+fn test_slice_expression() {
+    verify_ast(
+        "a[1..n]",
+        &entrypoint_wrapper_tree(&[tree(
+            IndexExpression,
+            &[
+                leaf(SymbolReference),
+                tree(
+                    RangeExpression,
+                    &[leaf(IntegerLiteral), leaf(SymbolReference)],
+                ),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_slice_type() {
+    verify_ast(
+        "var s : #primitives.u32[&] = a[0..2]",
+        &entrypoint_wrapper_tree(&[tree(
+            SymbolDeclaration,
+            &[
+                tree(SliceType, &[leaf(BuiltInObjectReference)]),
+                tree(
+                    IndexExpression,
+                    &[
+                        leaf(SymbolReference),
+                        tree(
+                            RangeExpression,
+                            &[leaf(IntegerLiteral), leaf(IntegerLiteral)],
+                        ),
+                    ],
+                ),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_slice_type_parameter() {
+    verify_ast(
+        "func(s : #primitives.u8[&]) do\nend",
+        &entrypoint_wrapper_tree(&[tree(
+            FunctionLiteral,
+            &[
+                tree(
+                    InputParameter,
+                    &[tree(SliceType, &[leaf(BuiltInObjectReference)])],
+                ),
+                leaf(StatementBody),
+            ],
+        )]),
+    );
+}
@@ -1,5 +1,6 @@
 use super::utils::*;
 use crate::ast::NodeId::*;
+use crate::error::*;
 
 #[test]
 fn test_declare_empty_struct() {
@@ -24,3 +25,65 @@ fn test_declare_simple_struct() {
         )]),
     );
 }
+
+#[test]
+fn test_declare_generic_struct() {
+    verify_ast(
+        "\
+        struct begin\n\
+            \tvar first : $T\n\
+            \tvar second : Pair($T)\n\
+        end",
+        &entrypoint_wrapper_tree(&[tree(
+            StructLiteral,
+            &[
+                tree(StructField, &[leaf(TypeParameter)]),
+                tree(
+                    StructField,
+                    &[tree(
+                        CallOperation,
+                        &[
+                            leaf(SymbolReference),
+                            tree(ArgumentList, &[leaf(TypeParameter)]),
+                        ],
+                    )],
+                ),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_struct_construction() {
+    verify_ast(
+        "A { a = 1, b = c }",
+        &entrypoint_wrapper_tree(&[tree(
+            StructConstruction,
+            &[
+                leaf(SymbolReference),
+                leaf(IntegerLiteral),
+                leaf(SymbolReference),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_struct_construction_empty() {
+    verify_ast(
+        "std.A {}",
+        &entrypoint_wrapper_tree(&[tree(
+            StructConstruction,
+            &[tree(SubScript, &[leaf(SymbolReference)])],
+        )]),
+    );
+}
+
+#[test]
+fn test_wrong_struct_construction_duplicate_field() {
+    let result = generate_ast_with_errors("A { a = 1, a = 2 }", false);
+    expect_error_ids(
+        &result.1,
+        &[new_error_id(errors::DuplicateFieldInitializer)],
+    );
+}
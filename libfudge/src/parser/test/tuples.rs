@@ -0,0 +1,68 @@
+use super::utils::*;
+use crate::ast::NodeId::*;
+
+fn get_destructured_symbols(source: &str) -> Vec<Option<String>> {
+    let ast = generate_ast(source);
+    let noderef = ast.find_first_node(DestructuringDeclaration).unwrap();
+    match ast.get_node(&noderef) {
+        crate::ast::Node::DestructuringDeclaration(n) => n
+            .symbols
+            .iter()
+            .map(|s| s.as_ref().map(|s| ast.get_symbol(s).unwrap().clone()))
+            .collect(),
+        _ => panic!("Expected destructuring declaration"),
+    }
+}
+
+#[test]
+fn test_tuple_literal() {
+    verify_ast(
+        "(1, a, true)",
+        &entrypoint_wrapper_tree(&[tree(
+            TupleLiteral,
+            &[
+                leaf(IntegerLiteral),
+                leaf(SymbolReference),
+                leaf(BooleanLiteral),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_parenthesized_expression_is_not_tuple() {
+    verify_ast("(a)", &entrypoint_wrapper_tree(&[leaf(SymbolReference)]));
+}
+
+#[test]
+fn test_tuple_type() {
+    verify_ast(
+        "var t : (#primitives.u32, #primitives.bool)",
+        &entrypoint_wrapper_tree(&[tree(
+            SymbolDeclaration,
+            &[tree(
+                TupleLiteral,
+                &[leaf(BuiltInObjectReference), leaf(BuiltInObjectReference)],
+            )],
+        )]),
+    );
+}
+
+#[test]
+fn test_destructuring_declaration() {
+    let source = "var a, _, c = f()";
+    verify_ast(
+        source,
+        &entrypoint_wrapper_tree(&[tree(
+            DestructuringDeclaration,
+            &[tree(
+                CallOperation,
+                &[leaf(SymbolReference), leaf(ArgumentList)],
+            )],
+        )]),
+    );
+    assert_eq!(
+        get_destructured_symbols(source),
+        vec![Some("a".into()), None, Some("c".into())]
+    );
+}
@@ -0,0 +1,115 @@
+use super::utils::*;
+use crate::ast::NodeId::*;
+use crate::error::*;
+
+#[test]
+fn test_statement_while_empty() {
+    verify_ast(
+        "while a do\n\
+        end",
+        &entrypoint_wrapper_tree(&[tree(
+            WhileStatement,
+            &[leaf(SymbolReference), leaf(StatementBody)],
+        )]),
+    );
+}
+
+#[test]
+fn test_statement_while_empty_2() {
+    verify_ast(
+        "while a do end",
+        &entrypoint_wrapper_tree(&[tree(
+            WhileStatement,
+            &[leaf(SymbolReference), leaf(StatementBody)],
+        )]),
+    );
+}
+
+#[test]
+fn test_wrong_statement_while_empty() {
+    let s = "\
+        while a\n\
+        do\n\
+        end";
+
+    let result = generate_ast_with_errors(s, false);
+    expect_error_ids(&result.1, &[new_error_id(errors::MismatchedAlignment)]);
+}
+
+#[test]
+fn test_statement_while_with_break_and_continue() {
+    verify_ast(
+        "\
+        while a do\n\
+            \tbreak\n\
+            \tcontinue\n\
+        end",
+        &entrypoint_wrapper_tree(&[tree(
+            WhileStatement,
+            &[
+                leaf(SymbolReference),
+                tree(
+                    StatementBody,
+                    &[leaf(BreakStatement), leaf(ContinueStatement)],
+                ),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_statement_nested_while_with_break() {
+    verify_ast(
+        "\
+        while a do\n\
+            \twhile b do\n\
+                \t\tbreak\n\
+            \tend\n\
+            \tbreak\n\
+        end",
+        &entrypoint_wrapper_tree(&[tree(
+            WhileStatement,
+            &[
+                leaf(SymbolReference),
+                tree(
+                    StatementBody,
+                    &[
+                        tree(
+                            WhileStatement,
+                            &[
+                                leaf(SymbolReference),
+                                tree(StatementBody, &[leaf(BreakStatement)]),
+                            ],
+                        ),
+                        leaf(BreakStatement),
+                    ],
+                ),
+            ],
+        )]),
+    );
+}
+
+#[test]
+fn test_wrong_break_outside_loop() {
+    let result = generate_ast_with_errors("break", false);
+    expect_error_ids(&result.1, &[new_error_id(errors::BreakOutsideLoop)]);
+}
+
+#[test]
+fn test_wrong_continue_outside_loop() {
+    let result = generate_ast_with_errors("continue", false);
+    expect_error_ids(&result.1, &[new_error_id(errors::ContinueOutsideLoop)]);
+}
+
+#[test]
+fn test_wrong_break_in_function_inside_loop() {
+    let s = "\
+        while a do\n\
+            \tfunc() do\n\
+                \t\tbreak\n\
+            \tend\n\
+        end";
+
+    let result = generate_ast_with_errors(s, false);
+    expect_error_ids(&result.1, &[new_error_id(errors::BreakOutsideLoop)]);
+}
@@ -20,6 +20,10 @@ impl<'a> TokenStream<'a> {
         return self.source.name();
     }
 
+    pub fn get_token_location(&self, token: &Token) -> source::SourceLocation {
+        return self.source.get_location(&token.source_span).unwrap();
+    }
+
     pub fn read_token(&mut self) -> Option<&Token> {
         self.count += 1;
         return self.tokens.get(self.count - 1);
@@ -17,6 +17,7 @@ pub static KEYWORDS: phf::Map<&'static str, TokenType> = phf_map! {
     "true" => TokenType::True,
     "false" => TokenType::False,
     "def" => TokenType::Def,
+    "const" => TokenType::Const,
     "var" => TokenType::Var,
     "func" => TokenType::Func,
     "struct" => TokenType::Struct,
@@ -24,6 +25,19 @@ pub static KEYWORDS: phf::Map<&'static str, TokenType> = phf_map! {
     "begin" => TokenType::Begin,
     "end" => TokenType::End,
     "return" => TokenType::Return,
+    "while" => TokenType::While,
+    "break" => TokenType::Break,
+    "continue" => TokenType::Continue,
+    "for" => TokenType::For,
+    "in" => TokenType::In,
+    "and" => TokenType::And,
+    "or" => TokenType::Or,
+    "not" => TokenType::Not,
+    "enum" => TokenType::Enum,
+    "match" => TokenType::Match,
+    "case" => TokenType::Case,
+    "null" => TokenType::Null,
+    "try" => TokenType::Try,
 };
 
 pub struct ScannerResult {
@@ -110,7 +124,12 @@ impl<'a> Scanner<'a> {
 
             // Produce token
             match n {
-                b'.' => return Some(self.produce_token_and_advance(TokenType::Dot)),
+                b'.' => match self.reader.lookahead() {
+                    Some(b'.') => {
+                        return Some(self.produce_token_and_advance_n(TokenType::DotDot, 2))
+                    }
+                    _ => return Some(self.produce_token_and_advance(TokenType::Dot)),
+                },
                 b',' => return Some(self.produce_token_and_advance(TokenType::Comma)),
                 b':' => return Some(self.produce_token_and_advance(TokenType::Colon)),
                 b'=' => match self.reader.lookahead() {
@@ -123,6 +142,19 @@ impl<'a> Scanner<'a> {
                     _ => return Some(self.produce_token_and_advance(TokenType::Equals)),
                 },
                 b'+' => return Some(self.produce_token_and_advance(TokenType::Plus)),
+                b'~' => return Some(self.produce_token_and_advance(TokenType::Tilde)),
+                b'%' => return Some(self.produce_token_and_advance(TokenType::Percent)),
+                b'&' => return Some(self.produce_token_and_advance(TokenType::Ampersand)),
+                b'|' => return Some(self.produce_token_and_advance(TokenType::Pipe)),
+                b'^' => return Some(self.produce_token_and_advance(TokenType::Caret)),
+                b'?' => return Some(self.produce_token_and_advance(TokenType::Question)),
+                b'!' => match self.reader.lookahead() {
+                    Some(b'=') => {
+                        return Some(self.produce_token_and_advance_n(TokenType::CompareNotEq, 2))
+                    }
+                    // Lone '!' is not a valid token
+                    _ => (),
+                },
                 b'-' => match self.reader.lookahead() {
                     Some(b'>') => {
                         return Some(self.produce_token_and_advance_n(TokenType::Arrow, 2))
@@ -141,18 +173,25 @@ impl<'a> Scanner<'a> {
                 b'}' => return Some(self.produce_token_and_advance(TokenType::ClosingCurlyBrace)),
                 b';' => return Some(self.produce_token_and_advance(TokenType::SemiColon)),
                 b'#' => return Some(self.produce_token_and_advance(TokenType::Hash)),
+                b'$' => return Some(self.produce_token_and_advance(TokenType::Dollar)),
                 b'>' => match self.reader.lookahead() {
                     Some(b'=') => {
                         return Some(
                             self.produce_token_and_advance_n(TokenType::GreaterThanOrEq, 2),
                         )
                     }
+                    Some(b'>') => {
+                        return Some(self.produce_token_and_advance_n(TokenType::RightShift, 2))
+                    }
                     _ => return Some(self.produce_token_and_advance(TokenType::GreaterThan)),
                 },
                 b'<' => match self.reader.lookahead() {
                     Some(b'=') => {
                         return Some(self.produce_token_and_advance_n(TokenType::LessThanOrEq, 2))
                     }
+                    Some(b'<') => {
+                        return Some(self.produce_token_and_advance_n(TokenType::LeftShift, 2))
+                    }
                     _ => return Some(self.produce_token_and_advance(TokenType::LessThan)),
                 },
                 b'/' => match self.reader.lookahead() {
@@ -495,8 +534,13 @@ impl<'a> Scanner<'a> {
         debug_assert!(self.reader.peek().unwrap() == b'\'');
         self.reader.advance();
 
-        while self.reader.peek().filter(|c| *c != b'\'').is_some() {
+        while let Some(c) = self.reader.peek().filter(|c| *c != b'\'') {
             self.reader.advance();
+
+            // Skip escaped character, so that '\'' does not end the literal
+            if c == b'\\' && self.reader.peek().is_some() {
+                self.reader.advance();
+            }
         }
 
         // TODO: Add error reference to start of literal
@@ -519,6 +563,10 @@ impl<'a> Scanner<'a> {
     fn produce_numericliteral(&mut self) -> Token {
         let startpos = self.reader.pos();
         debug_assert!(self.reader.peek().unwrap().is_ascii_digit());
+
+        // Hexadecimal digits include 'e', which should not be treated as an exponent
+        let is_hex = self.reader.peek() == Some(b'0') && self.reader.lookahead() == Some(b'x');
+
         self.reader.advance();
 
         // Note: we eat all trailing alphanumericals in this function, parsing of the
@@ -529,10 +577,15 @@ impl<'a> Scanner<'a> {
             .peek()
             .filter(|c| c.is_ascii_alphanumeric() || *c == b'.' || *c == b'_')
         {
+            // A range operator ends the literal, as in 0..10
+            if c == b'.' && self.reader.lookahead() == Some(b'.') {
+                break;
+            }
+
             self.reader.advance();
 
             // Allow for signed exponents
-            if c == b'e' || c == b'E' {
+            if !is_hex && (c == b'e' || c == b'E') {
                 let n = self.reader.peek();
                 if n == Some(b'-') || n == Some(b'+') {
                     self.reader.advance();
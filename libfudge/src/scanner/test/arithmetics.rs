@@ -9,6 +9,11 @@ fn test_single_character_operators() {
     verify_exact_scan("=", &[Token::new(TokenType::Equals, 0, 1)]);
     verify_exact_scan(">", &[Token::new(TokenType::GreaterThan, 0, 1)]);
     verify_exact_scan("<", &[Token::new(TokenType::LessThan, 0, 1)]);
+    verify_exact_scan("~", &[Token::new(TokenType::Tilde, 0, 1)]);
+    verify_exact_scan("%", &[Token::new(TokenType::Percent, 0, 1)]);
+    verify_exact_scan("&", &[Token::new(TokenType::Ampersand, 0, 1)]);
+    verify_exact_scan("|", &[Token::new(TokenType::Pipe, 0, 1)]);
+    verify_exact_scan("^", &[Token::new(TokenType::Caret, 0, 1)]);
 }
 
 #[test]
@@ -16,4 +21,7 @@ fn test_multi_character_operators() {
     verify_exact_scan("==", &[Token::new(TokenType::CompareEq, 0, 2)]);
     verify_exact_scan(">=", &[Token::new(TokenType::GreaterThanOrEq, 0, 2)]);
     verify_exact_scan("<=", &[Token::new(TokenType::LessThanOrEq, 0, 2)]);
+    verify_exact_scan("!=", &[Token::new(TokenType::CompareNotEq, 0, 2)]);
+    verify_exact_scan("<<", &[Token::new(TokenType::LeftShift, 0, 2)]);
+    verify_exact_scan(">>", &[Token::new(TokenType::RightShift, 0, 2)]);
 }
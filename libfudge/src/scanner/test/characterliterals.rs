@@ -14,3 +14,8 @@ fn test_basic() {
 fn test_escape() {
     verify_exact_scan("\'\\0\'", &[Token::new(TokenType::CharacterLiteral, 0, 4)]);
 }
+
+#[test]
+fn test_escaped_quote() {
+    verify_exact_scan("\'\\\'\'", &[Token::new(TokenType::CharacterLiteral, 0, 4)]);
+}
@@ -41,3 +41,18 @@ fn test_all_keywords() {
         test_keyword(key, KEYWORDS[key]);
     }
 }
+
+#[test]
+fn test_logical_operators() {
+    verify_exact_scan(
+        "not a and b or c",
+        &[
+            Token::new(TokenType::Not, 0, 3),
+            Token::new(TokenType::Identifier, 4, 1),
+            Token::new(TokenType::And, 6, 3),
+            Token::new(TokenType::Identifier, 10, 1),
+            Token::new(TokenType::Or, 12, 2),
+            Token::new(TokenType::Identifier, 15, 1),
+        ],
+    );
+}
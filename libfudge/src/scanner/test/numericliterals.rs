@@ -92,3 +92,39 @@ fn test_float_and_operator() {
         ],
     );
 }
+
+#[test]
+fn test_hexadecimal_and_operator() {
+    verify_exact_scan(
+        "0xFE+5",
+        &[
+            Token::new(TokenType::NumericLiteral, 0, 4),
+            Token::new(TokenType::Plus, 4, 1),
+            Token::new(TokenType::NumericLiteral, 5, 1),
+        ],
+    );
+}
+
+#[test]
+fn test_range_of_literals() {
+    verify_exact_scan(
+        "0..10",
+        &[
+            Token::new(TokenType::NumericLiteral, 0, 1),
+            Token::new(TokenType::DotDot, 1, 2),
+            Token::new(TokenType::NumericLiteral, 3, 2),
+        ],
+    );
+}
+
+#[test]
+fn test_range_of_floats() {
+    verify_exact_scan(
+        "0.5..1.5",
+        &[
+            Token::new(TokenType::NumericLiteral, 0, 3),
+            Token::new(TokenType::DotDot, 3, 2),
+            Token::new(TokenType::NumericLiteral, 5, 3),
+        ],
+    );
+}
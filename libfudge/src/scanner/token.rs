@@ -11,6 +11,7 @@ pub enum TokenType {
     SemiColon,
     Equals,
     Hash,
+    Dollar,
     OpeningParenthesis,
     ClosingParenthesis,
     OpeningSquareBracket,
@@ -25,14 +26,24 @@ pub enum TokenType {
     Star,
     GreaterThan,
     LessThan,
+    Tilde,
+    Percent,
+    Ampersand,
+    Pipe,
+    Caret,
+    Question,
 
     // n-char tokens
     LineBreak,
     Indentation,
     Comment,
     CompareEq,
+    CompareNotEq,
     GreaterThanOrEq,
     LessThanOrEq,
+    LeftShift,
+    RightShift,
+    DotDot,
 
     // Keywords
     Module,
@@ -43,6 +54,7 @@ pub enum TokenType {
     True,
     False,
     Def,
+    Const,
     Var,
     Func,
     Struct,
@@ -50,6 +62,19 @@ pub enum TokenType {
     Begin,
     End,
     Return,
+    While,
+    Break,
+    Continue,
+    For,
+    In,
+    And,
+    Or,
+    Not,
+    Enum,
+    Match,
+    Case,
+    Null,
+    Try,
 
     // Tokens with significant data
     Identifier,
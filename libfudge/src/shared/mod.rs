@@ -4,9 +4,25 @@ pub enum BinaryOperationType {
     Sub,
     Mul,
     Div,
+    Mod,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
     Equals,
+    NotEquals,
     LessThan,
     LessThanOrEq,
     GreaterThan,
     GreaterThanOrEq,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOperationType {
+    Not,
+    Neg,
+    BitNot,
 }
@@ -1,3 +1,4 @@
+use std::fmt;
 use std::fs;
 use std::path::*;
 
@@ -6,7 +7,7 @@ pub use lookaheadreader::*;
 
 use std::io::BufRead;
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 pub struct SourceSpan {
     pub pos: u64,
     pub len: usize,
@@ -18,6 +19,35 @@ pub struct LineInfo {
     pub row: u32,
 }
 
+// Human readable position in a source, kept for errors that are reported after parsing.
+//  The span is kept along with it, so these errors can point into the source.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SourceLocation {
+    pub source_name: String,
+    pub row: u32,
+    pub col: u32,
+    pub span: SourceSpan,
+}
+
+impl SourceLocation {
+    // Location of the same start, spanning up to the end of the given span
+    pub fn extended_to(&self, end: &SourceSpan) -> SourceLocation {
+        SourceLocation {
+            span: SourceSpan {
+                pos: self.span.pos,
+                len: (end.pos + end.len as u64 - self.span.pos) as usize,
+            },
+            ..self.clone()
+        }
+    }
+}
+
+impl fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.source_name, self.row, self.col)
+    }
+}
+
 pub struct Source {
     name: String,
     data: Vec<u8>,
@@ -102,6 +132,24 @@ impl Source {
         }
         return None;
     }
+
+    pub fn get_location(&self, span: &SourceSpan) -> Option<SourceLocation> {
+        let lineinfo = self.get_line_info(span.pos)?;
+
+        // Columns count characters rather than bytes
+        let linepos = span.pos as usize - lineinfo.line_start;
+        let col = String::from_utf8_lossy(&self.data[lineinfo.line_start..][..linepos])
+            .chars()
+            .count() as u32
+            + 1;
+
+        Some(SourceLocation {
+            source_name: self.name.clone(),
+            row: lineinfo.row,
+            col,
+            span: *span,
+        })
+    }
 }
 
 #[cfg(test)]
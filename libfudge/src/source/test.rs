@@ -69,3 +69,20 @@ fn test_get_line_info_complex() {
     assert_eq!(lineinfo.text.trim(), "row3");
     assert_eq!(lineinfo.row, 3);
 }
+
+#[test]
+fn test_get_location() {
+    let source = Source::from_str("row1\n  row2(ö) x\nrow3");
+
+    let location = source
+        .get_location(&SourceSpan { pos: 15, len: 1 })
+        .unwrap();
+    assert_eq!(location.row, 2);
+    assert_eq!(location.col, 10);
+    assert_eq!(location.span, SourceSpan { pos: 15, len: 1 });
+    assert_eq!(location.to_string(), "strsource:2:10");
+
+    assert!(source
+        .get_location(&SourceSpan { pos: 100, len: 1 })
+        .is_none());
+}
@@ -1,4 +1,5 @@
 use phf::phf_map;
+use std::collections::HashMap;
 
 use crate::utils::*;
 
@@ -11,6 +12,7 @@ pub enum PrimitiveType {
     // built-ins and primitives
     StaticStringUtf8,
     Bool,
+    Char,
     U8,
     U16,
     U32,
@@ -28,6 +30,7 @@ impl PrimitiveType {
         match self {
             PrimitiveType::StaticStringUtf8 => "str",
             PrimitiveType::Bool => "bool",
+            PrimitiveType::Char => "char",
             PrimitiveType::U8 => "u8",
             PrimitiveType::U16 => "u16",
             PrimitiveType::U32 => "u32",
@@ -45,6 +48,7 @@ impl PrimitiveType {
         match self {
             PrimitiveType::StaticStringUtf8 => format!("@c{}", data as u64),
             PrimitiveType::Bool => format!("{}", data != 0),
+            PrimitiveType::Char => format!("{:?}", char::from_u32(data as u32).unwrap_or('?')),
             PrimitiveType::U8 => format!("{}", data as u8),
             PrimitiveType::U16 => format!("{}", data as u16),
             PrimitiveType::U32 => format!("{}", data as u32),
@@ -53,8 +57,8 @@ impl PrimitiveType {
             PrimitiveType::S16 => format!("{}", data as i16),
             PrimitiveType::S32 => format!("{}", data as i32),
             PrimitiveType::S64 => format!("{}", data as i64),
-            PrimitiveType::F32 => format!("{}", data as f32),
-            PrimitiveType::F64 => format!("{}", data as f64),
+            PrimitiveType::F32 => format!("{}", f32::from_bits(data as u32)),
+            PrimitiveType::F64 => format!("{}", f64::from_bits(data)),
         }
     }
 
@@ -62,6 +66,7 @@ impl PrimitiveType {
         match self {
             PrimitiveType::StaticStringUtf8 => 8, // u64 address, length is stored at address
             PrimitiveType::Bool => 1,
+            PrimitiveType::Char => 4, // Unicode scalar value
             PrimitiveType::U8 => 1,
             PrimitiveType::U16 => 2,
             PrimitiveType::U32 => 4,
@@ -80,6 +85,7 @@ impl PrimitiveType {
 pub static PRIMITIVES: phf::Map<&'static str, PrimitiveType> = phf_map! {
     "ssutf8" => PrimitiveType::StaticStringUtf8,
     "bool" => PrimitiveType::Bool,
+    "char" => PrimitiveType::Char,
     "u8" => PrimitiveType::U8,
     "u16" => PrimitiveType::U16,
     "u32" => PrimitiveType::U32,
@@ -94,15 +100,258 @@ pub static PRIMITIVES: phf::Map<&'static str, PrimitiveType> = phf_map! {
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct StructDefinition {
+    // Type parameters the fields refer to, in order of first use, nested uses included.
+    //  Generic structs are instantiated with one type argument for each, bound in that
+    //  order, so "Entry(A, B)" binds $V to A and $K to B when the first field uses $V.
+    pub typeparams: Vec<String>,
     pub fields: Vec<(SymbolKey, TypeId)>,
 }
 
+impl StructDefinition {
+    pub fn new(fields: Vec<(SymbolKey, TypeId)>) -> Self {
+        let mut typeparams = Vec::new();
+        for (_, typeid) in &fields {
+            typeid.collect_type_parameters(&mut typeparams);
+        }
+
+        Self { typeparams, fields }
+    }
+
+    pub fn is_generic(&self) -> bool {
+        !self.typeparams.is_empty()
+    }
+
+    pub fn get_field(&self, symbol: &SymbolKey) -> Option<&TypeId> {
+        self.fields
+            .iter()
+            .find(|(fieldsymbol, _)| fieldsymbol == symbol)
+            .map(|(_, typeid)| typeid)
+    }
+
+    pub fn size(&self) -> u64 {
+        self.fields.iter().map(|(_, typeid)| typeid.size()).sum()
+    }
+
+    // Fields are stored back to back, in the order of the definition
+    pub fn field_offset(&self, symbol: &SymbolKey) -> Option<u64> {
+        let index = self
+            .fields
+            .iter()
+            .position(|(fieldsymbol, _)| fieldsymbol == symbol)?;
+        Some(
+            self.fields[..index]
+                .iter()
+                .map(|(_, typeid)| typeid.size())
+                .sum(),
+        )
+    }
+
+    // Binds the type parameters to the arguments in order. Arguments can be type
+    //  parameters themselves, which makes the instance generic again.
+    pub fn instantiate(&self, arguments: &[TypeId]) -> Result<StructDefinition, String> {
+        if arguments.len() != self.typeparams.len() {
+            return Err(format!(
+                "Expected {} type arguments, got {}",
+                self.typeparams.len(),
+                arguments.len()
+            ));
+        }
+
+        let bindings = self
+            .typeparams
+            .iter()
+            .cloned()
+            .zip(arguments.iter().cloned())
+            .collect();
+        Ok(self.substitute(&bindings))
+    }
+
+    fn substitute(&self, arguments: &HashMap<String, TypeId>) -> StructDefinition {
+        StructDefinition::new(
+            self.fields
+                .iter()
+                .map(|(symbol, typeid)| (symbol.clone(), typeid.substitute(arguments)))
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EnumVariant {
+    pub name: SymbolKey,
+    pub fields: Vec<(SymbolKey, TypeId)>,
+}
+
+impl EnumVariant {
+    pub fn payload_size(&self) -> u64 {
+        self.fields.iter().map(|(_, typeid)| typeid.size()).sum()
+    }
+}
+
+// Tagged union, values are stored as the index of the variant followed by the fields
+//  of the variant back to back. The payload is sized to fit the largest variant.
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EnumDefinition {
+    // Type parameters the fields of all variants refer to, in order of first use
+    pub typeparams: Vec<String>,
+    pub variants: Vec<EnumVariant>,
+}
+
+impl EnumDefinition {
+    pub fn new(variants: Vec<EnumVariant>) -> Self {
+        let mut typeparams = Vec::new();
+        for variant in &variants {
+            for (_, typeid) in &variant.fields {
+                typeid.collect_type_parameters(&mut typeparams);
+            }
+        }
+
+        Self {
+            typeparams,
+            variants,
+        }
+    }
+
+    // Built-in result, either the value of an operation that can fail or its error
+    pub fn new_result(value: TypeId, error: TypeId) -> Self {
+        Self::new(vec![
+            EnumVariant {
+                name: SymbolKey::from_str("ok"),
+                fields: vec![(SymbolKey::from_str("value"), value)],
+            },
+            EnumVariant {
+                name: SymbolKey::from_str("error"),
+                fields: vec![(SymbolKey::from_str("error"), error)],
+            },
+        ])
+    }
+
+    // Result as "#result" refers to it, generic over the value and the error
+    pub fn new_generic_result() -> Self {
+        Self::new_result(
+            TypeId::TypeParameter("T".to_string()),
+            TypeId::TypeParameter("E".to_string()),
+        )
+    }
+
+    // Value and error types, if this is an instance of the built-in result
+    pub fn get_result_types(&self) -> Option<(&TypeId, &TypeId)> {
+        match self.variants.as_slice() {
+            [ok, error] if ok.fields.len() == 1 && error.fields.len() == 1 => {
+                let (value, error) = (&ok.fields[0].1, &error.fields[0].1);
+                let result = Self::new_result(value.clone(), error.clone());
+                (*self == result).then(|| (value, error))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn is_generic(&self) -> bool {
+        !self.typeparams.is_empty()
+    }
+
+    // Variants are identified by their index, which is also the value of the tag
+    pub fn get_variant(&self, symbol: &SymbolKey) -> Option<(usize, &EnumVariant)> {
+        self.variants
+            .iter()
+            .enumerate()
+            .find(|(_, variant)| variant.name == *symbol)
+    }
+
+    // Smallest unsigned type that can hold the index of every variant
+    pub fn tag_type(&self) -> PrimitiveType {
+        match self.variants.len() {
+            n if n <= u8::MAX as usize + 1 => PrimitiveType::U8,
+            n if n <= u16::MAX as usize + 1 => PrimitiveType::U16,
+            _ => PrimitiveType::U32,
+        }
+    }
+
+    pub fn field_offset(&self, variant: usize, field: usize) -> u64 {
+        let preceding: u64 = self.variants[variant].fields[..field]
+            .iter()
+            .map(|(_, typeid)| typeid.size())
+            .sum();
+        self.tag_type().size() + preceding
+    }
+
+    pub fn size(&self) -> u64 {
+        let payload = self
+            .variants
+            .iter()
+            .map(|v| v.payload_size())
+            .max()
+            .unwrap_or(0);
+        self.tag_type().size() + payload
+    }
+
+    // Binds the type parameters to the arguments in order, like generic structs
+    pub fn instantiate(&self, arguments: &[TypeId]) -> Result<EnumDefinition, String> {
+        if arguments.len() != self.typeparams.len() {
+            return Err(format!(
+                "Expected {} type arguments, got {}",
+                self.typeparams.len(),
+                arguments.len()
+            ));
+        }
+
+        let bindings = self
+            .typeparams
+            .iter()
+            .cloned()
+            .zip(arguments.iter().cloned())
+            .collect();
+        Ok(self.substitute(&bindings))
+    }
+
+    fn substitute(&self, arguments: &HashMap<String, TypeId>) -> EnumDefinition {
+        EnumDefinition::new(
+            self.variants
+                .iter()
+                .map(|variant| EnumVariant {
+                    name: variant.name.clone(),
+                    fields: variant
+                        .fields
+                        .iter()
+                        .map(|(symbol, typeid)| (symbol.clone(), typeid.substitute(arguments)))
+                        .collect(),
+                })
+                .collect(),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct FunctionSignature {
     pub inputparams: Vec<(SymbolKey, TypeId)>,
     pub outputparams: Vec<TypeId>,
 }
 
+// Parameter names are only used to match named arguments, functions taking and returning
+//  the same types are interchangeable
+impl PartialEq for FunctionSignature {
+    fn eq(&self, other: &Self) -> bool {
+        self.inputparams.len() == other.inputparams.len()
+            && self
+                .inputparams
+                .iter()
+                .zip(&other.inputparams)
+                .all(|(a, b)| a.1 == b.1)
+            && self.outputparams == other.outputparams
+    }
+}
+
+impl Eq for FunctionSignature {}
+
+impl std::hash::Hash for FunctionSignature {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        for (_, typeid) in &self.inputparams {
+            typeid.hash(state);
+        }
+        self.outputparams.hash(state);
+    }
+}
+
 impl FunctionSignature {
     pub fn new_simple() -> Self {
         Self {
@@ -110,6 +359,15 @@ impl FunctionSignature {
             outputparams: Vec::new(),
         }
     }
+
+    // Type of the value produced by a call, multiple output parameters are returned as a tuple
+    pub fn return_type(&self) -> TypeId {
+        match self.outputparams.as_slice() {
+            [] => TypeId::Null,
+            [single] => single.clone(),
+            multiple => TypeId::Tuple(multiple.to_vec()),
+        }
+    }
 }
 
 // Cheat a bit and treat all built-ins as their own unique types
@@ -118,12 +376,26 @@ impl FunctionSignature {
 #[repr(u8)]
 pub enum BuiltInFunction {
     PrintFormat,
+    ArrayLen,
+    ArrayPush,
+    ArrayPop,
+    ArrayClear,
+    // Not addressable from code, dynamic array literals are lowered to it
+    ArrayNew,
+    // Not addressable from code, closures are lowered to it
+    Allocate,
 }
 
 impl BuiltInFunction {
     pub fn to_str(&self) -> &str {
         match self {
             BuiltInFunction::PrintFormat => "#output.print_format",
+            BuiltInFunction::ArrayLen => "#array.len",
+            BuiltInFunction::ArrayPush => "#array.push",
+            BuiltInFunction::ArrayPop => "#array.pop",
+            BuiltInFunction::ArrayClear => "#array.clear",
+            BuiltInFunction::ArrayNew => "#array.new",
+            BuiltInFunction::Allocate => "#memory.allocate",
         }
     }
 }
@@ -137,16 +409,61 @@ pub enum TypeId {
     BuiltInFunction(BuiltInFunction),
     Function(FunctionSignature),
     Struct(StructDefinition),
+    Enum(EnumDefinition),
+    // Fixed size array, elements are stored back to back
+    Array { element: Box<TypeId>, length: u64 },
+    // Growable array, elements live on the heap and the value only refers to them
+    DynamicArray { element: Box<TypeId> },
+    // Borrowed view into a range of elements of another array
+    Slice { element: Box<TypeId> },
+    // Ordered, fixed set of values of possibly different types, i.e. multiple return values
+    Tuple(Vec<TypeId>),
+    // Address of a value stored elsewhere, i.e. a variable captured by reference
+    Reference(Box<TypeId>),
+    // Either a value of the type or null, a u8 tag telling them apart followed by the value
+    Optional(Box<TypeId>),
+    // Placeholder in generic functions, bound to a type per call
+    TypeParameter(String),
     Module,
     // Hm, this is a bit awkward, perhaps this can be a core struct instead?
     TypedValue,
 }
 
 impl TypeId {
+    // Offset of the value in an optional, after the tag
+    pub const OPTIONAL_VALUE_OFFSET: u64 = 1;
+
     pub fn new_primitive(ptype: PrimitiveType) -> Self {
         TypeId::Primitive(ptype)
     }
 
+    pub fn new_array(element: TypeId, length: u64) -> Self {
+        TypeId::Array {
+            element: Box::new(element),
+            length,
+        }
+    }
+
+    pub fn new_dynamic_array(element: TypeId) -> Self {
+        TypeId::DynamicArray {
+            element: Box::new(element),
+        }
+    }
+
+    pub fn new_slice(element: TypeId) -> Self {
+        TypeId::Slice {
+            element: Box::new(element),
+        }
+    }
+
+    pub fn new_reference(target: TypeId) -> Self {
+        TypeId::Reference(Box::new(target))
+    }
+
+    pub fn new_optional(target: TypeId) -> Self {
+        TypeId::Optional(Box::new(target))
+    }
+
     pub fn type_id(&self) -> u64 {
         match self {
             TypeId::Primitive(n) => return *n as u64,
@@ -161,6 +478,15 @@ impl TypeId {
         match self {
             TypeId::Primitive(n) => return n.size(),
             TypeId::TypedValue => return 2 * 8, // u64 typeid, u64 value
+            TypeId::Array { element, length } => return element.size() * length,
+            TypeId::DynamicArray { .. } => return 8, // u64 address of the array header
+            TypeId::Slice { .. } => return 2 * 8,    // u64 address of first element, u64 length
+            TypeId::Tuple(elements) => return elements.iter().map(|e| e.size()).sum(),
+            TypeId::Function(_) => return 8, // u64 address of the closure
+            TypeId::Reference(_) => return 8, // u64 address of the value
+            TypeId::Optional(target) => return TypeId::OPTIONAL_VALUE_OFFSET + target.size(),
+            TypeId::Enum(definition) => return definition.size(),
+            TypeId::Struct(definition) => return definition.size(),
             _ => panic!(
                 "Size is only supported for primitives currently, not {:?}",
                 self
@@ -175,6 +501,23 @@ impl TypeId {
         }
     }
 
+    // Whether values of the type can be created without initializing them, i.e. omitted
+    //  fields of a struct construction
+    pub fn has_default_value(&self) -> bool {
+        match self {
+            // Optionals are null by default
+            TypeId::Primitive(_) | TypeId::DynamicArray { .. } | TypeId::Optional(_) => true,
+            TypeId::Array { element, .. } => element.has_default_value(),
+            TypeId::Tuple(elements) => elements.iter().all(|e| e.has_default_value()),
+            TypeId::Struct(definition) => definition.fields.iter().all(|f| f.1.has_default_value()),
+            TypeId::Enum(definition) => match definition.variants.first() {
+                Some(variant) => variant.fields.iter().all(|f| f.1.has_default_value()),
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
     pub fn to_string(&self) -> String {
         match self {
             TypeId::Null => format!("null"),
@@ -182,9 +525,257 @@ impl TypeId {
             TypeId::Primitive(n) => n.to_str().into(),
             TypeId::BuiltInFunction(n) => n.to_str().into(),
             TypeId::Function(_) => format!("func"),
+            TypeId::Struct(n) if n.is_generic() => format!(
+                "struct({})",
+                n.typeparams
+                    .iter()
+                    .map(|p| format!("${}", p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
             TypeId::Struct(_) => format!("struct"),
+            TypeId::Enum(n) if n.is_generic() => format!(
+                "enum({})",
+                n.typeparams
+                    .iter()
+                    .map(|p| format!("${}", p))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TypeId::Enum(_) => format!("enum"),
+            TypeId::Array { element, length } => format!("{}[{}]", element.to_string(), length),
+            TypeId::DynamicArray { element } => format!("{}[..]", element.to_string()),
+            TypeId::Slice { element } => format!("{}[&]", element.to_string()),
+            TypeId::Tuple(elements) => format!(
+                "({})",
+                elements
+                    .iter()
+                    .map(|e| e.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TypeId::Reference(n) => format!("&{}", n.to_string()),
+            TypeId::Optional(n) => format!("?{}", n.to_string()),
+            TypeId::TypeParameter(n) => format!("${}", n),
             TypeId::Module => format!("module"),
             TypeId::TypedValue => format!("typedval"),
         }
     }
+
+    // Whether a type is bound to every type parameter the type refers to
+    pub fn is_bound(&self, arguments: &HashMap<String, TypeId>) -> bool {
+        match self {
+            TypeId::TypeParameter(n) => arguments.contains_key(n),
+            TypeId::Array { element, .. }
+            | TypeId::DynamicArray { element }
+            | TypeId::Slice { element }
+            | TypeId::Reference(element)
+            | TypeId::Optional(element) => element.is_bound(arguments),
+            TypeId::Tuple(elements) => elements.iter().all(|e| e.is_bound(arguments)),
+            TypeId::Function(signature) => {
+                signature
+                    .inputparams
+                    .iter()
+                    .all(|p| p.1.is_bound(arguments))
+                    && signature.outputparams.iter().all(|p| p.is_bound(arguments))
+            }
+            TypeId::Struct(definition) => definition
+                .typeparams
+                .iter()
+                .all(|p| arguments.contains_key(p)),
+            TypeId::Enum(definition) => definition
+                .typeparams
+                .iter()
+                .all(|p| arguments.contains_key(p)),
+            _ => true,
+        }
+    }
+
+    // Appends the type parameters the type refers to that are not in the list yet
+    pub fn collect_type_parameters(&self, typeparams: &mut Vec<String>) {
+        match self {
+            TypeId::TypeParameter(n) => {
+                if !typeparams.contains(n) {
+                    typeparams.push(n.clone());
+                }
+            }
+            TypeId::Array { element, .. }
+            | TypeId::DynamicArray { element }
+            | TypeId::Slice { element }
+            | TypeId::Reference(element)
+            | TypeId::Optional(element) => element.collect_type_parameters(typeparams),
+            TypeId::Tuple(elements) => {
+                for element in elements {
+                    element.collect_type_parameters(typeparams);
+                }
+            }
+            TypeId::Function(signature) => {
+                for (_, typeid) in &signature.inputparams {
+                    typeid.collect_type_parameters(typeparams);
+                }
+                for typeid in &signature.outputparams {
+                    typeid.collect_type_parameters(typeparams);
+                }
+            }
+            TypeId::Struct(definition) => {
+                for (_, typeid) in &definition.fields {
+                    typeid.collect_type_parameters(typeparams);
+                }
+            }
+            TypeId::Enum(definition) => {
+                for variant in &definition.variants {
+                    for (_, typeid) in &variant.fields {
+                        typeid.collect_type_parameters(typeparams);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // Replaces type parameters with the types they are bound to, unbound ones are kept
+    pub fn substitute(&self, arguments: &HashMap<String, TypeId>) -> TypeId {
+        match self {
+            TypeId::TypeParameter(n) => arguments.get(n).cloned().unwrap_or(self.clone()),
+            TypeId::Array { element, length } => {
+                TypeId::new_array(element.substitute(arguments), *length)
+            }
+            TypeId::DynamicArray { element } => {
+                TypeId::new_dynamic_array(element.substitute(arguments))
+            }
+            TypeId::Slice { element } => TypeId::new_slice(element.substitute(arguments)),
+            TypeId::Reference(target) => TypeId::new_reference(target.substitute(arguments)),
+            TypeId::Optional(target) => TypeId::new_optional(target.substitute(arguments)),
+            TypeId::Tuple(elements) => {
+                TypeId::Tuple(elements.iter().map(|e| e.substitute(arguments)).collect())
+            }
+            TypeId::Function(signature) => TypeId::Function(FunctionSignature {
+                inputparams: signature
+                    .inputparams
+                    .iter()
+                    .map(|(symbol, typeid)| (symbol.clone(), typeid.substitute(arguments)))
+                    .collect(),
+                outputparams: signature
+                    .outputparams
+                    .iter()
+                    .map(|typeid| typeid.substitute(arguments))
+                    .collect(),
+            }),
+            TypeId::Struct(definition) => TypeId::Struct(definition.substitute(arguments)),
+            TypeId::Enum(definition) => TypeId::Enum(definition.substitute(arguments)),
+            _ => self.clone(),
+        }
+    }
+
+    // Matches a type containing type parameters against the type of an argument, binding
+    //  each type parameter the first time it is matched
+    pub fn bind_type_parameters(
+        &self,
+        argument: &TypeId,
+        arguments: &mut HashMap<String, TypeId>,
+    ) -> Result<(), String> {
+        match (self, argument) {
+            (TypeId::TypeParameter(n), _) => match arguments.get(n) {
+                Some(bound) if bound != argument => Err(format!(
+                    "Type parameter ${} is bound to both {} and {}",
+                    n,
+                    bound.to_string(),
+                    argument.to_string()
+                )),
+                Some(_) => Ok(()),
+                None => {
+                    arguments.insert(n.clone(), argument.clone());
+                    Ok(())
+                }
+            },
+            (
+                TypeId::Array { element, length },
+                TypeId::Array {
+                    element: argelement,
+                    length: arglength,
+                },
+            ) if length == arglength => element.bind_type_parameters(argelement, arguments),
+            (
+                TypeId::DynamicArray { element },
+                TypeId::DynamicArray {
+                    element: argelement,
+                },
+            )
+            | (
+                TypeId::Slice { element },
+                TypeId::Slice {
+                    element: argelement,
+                },
+            )
+            | (TypeId::Reference(element), TypeId::Reference(argelement))
+            | (TypeId::Optional(element), TypeId::Optional(argelement)) => {
+                element.bind_type_parameters(argelement, arguments)
+            }
+            // Null is a value of any optional
+            (TypeId::Optional(_), TypeId::Null) => Ok(()),
+            (TypeId::Tuple(elements), TypeId::Tuple(argelements))
+                if elements.len() == argelements.len() =>
+            {
+                for (element, argelement) in elements.iter().zip(argelements) {
+                    element.bind_type_parameters(argelement, arguments)?;
+                }
+                Ok(())
+            }
+            (TypeId::Function(signature), TypeId::Function(argsignature))
+                if signature.inputparams.len() == argsignature.inputparams.len()
+                    && signature.outputparams.len() == argsignature.outputparams.len() =>
+            {
+                for (param, argparam) in signature.inputparams.iter().zip(&argsignature.inputparams)
+                {
+                    param.1.bind_type_parameters(&argparam.1, arguments)?;
+                }
+                for (param, argparam) in signature
+                    .outputparams
+                    .iter()
+                    .zip(&argsignature.outputparams)
+                {
+                    param.bind_type_parameters(argparam, arguments)?;
+                }
+                Ok(())
+            }
+            // Instances of the same generic struct have fields of the same names
+            (TypeId::Struct(definition), TypeId::Struct(argdefinition))
+                if definition.fields.len() == argdefinition.fields.len()
+                    && definition
+                        .fields
+                        .iter()
+                        .zip(&argdefinition.fields)
+                        .all(|(field, argfield)| field.0 == argfield.0) =>
+            {
+                for (field, argfield) in definition.fields.iter().zip(&argdefinition.fields) {
+                    field.1.bind_type_parameters(&argfield.1, arguments)?;
+                }
+                Ok(())
+            }
+            // Likewise, instances of the same generic enum have variants of the same shape
+            (TypeId::Enum(definition), TypeId::Enum(argdefinition))
+                if definition.variants.len() == argdefinition.variants.len()
+                    && definition.variants.iter().zip(&argdefinition.variants).all(
+                        |(variant, argvariant)| {
+                            variant.name == argvariant.name
+                                && variant.fields.len() == argvariant.fields.len()
+                        },
+                    ) =>
+            {
+                for (variant, argvariant) in definition.variants.iter().zip(&argdefinition.variants)
+                {
+                    for (field, argfield) in variant.fields.iter().zip(&argvariant.fields) {
+                        field.1.bind_type_parameters(&argfield.1, arguments)?;
+                    }
+                }
+                Ok(())
+            }
+            _ if self == argument => Ok(()),
+            _ => Err(format!(
+                "Type mismatch: expected {}, got {}",
+                self.to_string(),
+                argument.to_string()
+            )),
+        }
+    }
 }
@@ -10,10 +10,18 @@ pub enum Op {
     StoreReg, // Store value in register at address in register
     MoveReg,  // Move value from register to register
     CallBuiltIn, // Call specified built-in function
-    Call,     // Call function at address in register
+    Call,     // Call function at address in register, with a new stack frame and saved registers
+    Jump,     // Set pc to instruction address
+    JumpIfZero, // Set pc to instruction address if register is zero
+    LogicalNot, // Set register to 1 if source register is zero, otherwise 0
+    Negate,   // Set register to two's complement negation of source register
+    FloatNegate, // Set register to float negation of source register
+    BitwiseNot, // Set register to bitwise complement of source register
+    BinaryOp, // Set register to result of binary operation on two source registers
+    Panic,    // Abort program with the string message at address in register
 
     // Keep return as last instruction
-    Return, // Set pc to instruction address in return register.
+    Return, // Restore the calling frame, except for the registers holding returned values
 }
 
 // Make sure op fits into 6 bits
@@ -59,6 +67,36 @@ pub enum LoadAddressMode {
     InstructionAddress,
 }
 
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[repr(u8)]
+pub enum BinaryOpType {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    BitAnd,
+    BitOr,
+    BitXor,
+    ShiftLeft,
+    ShiftRight,
+    Equals,
+    NotEquals,
+    LessThan,
+    LessThanOrEq,
+    GreaterThan,
+    GreaterThanOrEq,
+}
+
+// How the operand registers of a binary operation are interpreted
+#[derive(Debug, PartialEq, Copy, Clone)]
+#[repr(u8)]
+pub enum OperandKind {
+    Unsigned,
+    Signed,
+    Float,
+}
+
 pub mod abstractvm {
     use super::*;
     use vm::abstractvm::Config;
@@ -80,6 +118,14 @@ pub mod abstractvm {
         MoveReg(instructions::MoveReg<Config>),
         CallBuiltIn(instructions::CallBuiltIn),
         Call(instructions::Call<Config>),
+        Jump(instructions::Jump<Config>),
+        JumpIfZero(instructions::JumpIfZero<Config>),
+        LogicalNot(instructions::LogicalNot<Config>),
+        Negate(instructions::Negate<Config>),
+        FloatNegate(instructions::FloatNegate<Config>),
+        BitwiseNot(instructions::BitwiseNot<Config>),
+        BinaryOp(instructions::BinaryOp<Config>),
+        Panic(instructions::Panic<Config>),
         Return(instructions::Return),
     }
 
@@ -95,6 +141,14 @@ pub mod abstractvm {
                 Instruction::MoveReg(n) => n.bytecode_size(),
                 Instruction::CallBuiltIn(n) => n.bytecode_size(),
                 Instruction::Call(n) => n.bytecode_size(),
+                Instruction::Jump(n) => n.bytecode_size(),
+                Instruction::JumpIfZero(n) => n.bytecode_size(),
+                Instruction::LogicalNot(n) => n.bytecode_size(),
+                Instruction::Negate(n) => n.bytecode_size(),
+                Instruction::FloatNegate(n) => n.bytecode_size(),
+                Instruction::BitwiseNot(n) => n.bytecode_size(),
+                Instruction::BinaryOp(n) => n.bytecode_size(),
+                Instruction::Panic(n) => n.bytecode_size(),
                 Instruction::Return(n) => n.bytecode_size(),
             }
         }
@@ -110,6 +164,14 @@ pub mod abstractvm {
                 Instruction::MoveReg(n) => n.encode(data, context),
                 Instruction::CallBuiltIn(n) => n.encode(data, context),
                 Instruction::Call(n) => n.encode(data, context),
+                Instruction::Jump(n) => n.encode(data, context),
+                Instruction::JumpIfZero(n) => n.encode(data, context),
+                Instruction::LogicalNot(n) => n.encode(data, context),
+                Instruction::Negate(n) => n.encode(data, context),
+                Instruction::FloatNegate(n) => n.encode(data, context),
+                Instruction::BitwiseNot(n) => n.encode(data, context),
+                Instruction::BinaryOp(n) => n.encode(data, context),
+                Instruction::Panic(n) => n.encode(data, context),
                 Instruction::Return(n) => n.encode(data, context),
             }
         }
@@ -125,6 +187,14 @@ pub mod abstractvm {
                 Instruction::MoveReg(n) => n.to_string(),
                 Instruction::CallBuiltIn(n) => n.to_string(),
                 Instruction::Call(n) => n.to_string(),
+                Instruction::Jump(n) => n.to_string(),
+                Instruction::JumpIfZero(n) => n.to_string(),
+                Instruction::LogicalNot(n) => n.to_string(),
+                Instruction::Negate(n) => n.to_string(),
+                Instruction::FloatNegate(n) => n.to_string(),
+                Instruction::BitwiseNot(n) => n.to_string(),
+                Instruction::BinaryOp(n) => n.to_string(),
+                Instruction::Panic(n) => n.to_string(),
                 Instruction::Return(n) => n.to_string(),
             }
         }
@@ -468,12 +538,15 @@ pub mod instructions {
     #[derive(Debug)]
     pub struct Call<Config: VmConfig> {
         pub instruction_address_target: Config::RegisterType,
+        // Stack size of the calling function, the called function's frame starts after it
+        pub stackframe: Config::ValueType,
     }
     impl<Config: VmConfig> Call<Config> {
         pub fn to_string(&self) -> String {
-            columnize_output2(
+            columnize_output3(
                 &format!("Call"),
                 &format!("*r{}", self.instruction_address_target),
+                &format!("sp+{}", self.stackframe),
             )
         }
     }
@@ -484,6 +557,7 @@ pub mod instructions {
             data.skip_op(pc);
             Self {
                 instruction_address_target: data.read_u8(pc),
+                stackframe: data.read_u64(pc),
             }
         }
     }
@@ -491,20 +565,332 @@ pub mod instructions {
         const OP: Op = Op::Call;
 
         fn bytecode_size(&self) -> usize {
-            1 + 1
+            1 + 1 + 8
         }
         fn encode<'a>(&self, data: &mut ByteCodeWriter<'a>, context: &ByteCodeGenContext) {
             data.write_op(Self::OP);
             data.write_register(context.resolve_register(&self.instruction_address_target));
+            data.write_u64(context.resolve_value(&self.stackframe));
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct Jump<Config: VmConfig> {
+        pub address: Config::ValueType,
+    }
+    impl<Config: VmConfig> Jump<Config> {
+        pub fn to_string(&self) -> String {
+            columnize_output2(&format!("Jump"), &format!("{}", self.address))
+        }
+    }
+    impl Instruction for Jump<ByteCodeVm> {
+        const OP: Op = Op::Jump;
+
+        fn decode(data: &ByteCodeChunk, pc: &mut usize) -> Self {
+            data.skip_op(pc);
+            Self {
+                address: data.read_u64(pc),
+            }
+        }
+    }
+    impl abstractvm::InstructionTrait for Jump<AbstractVm> {
+        const OP: Op = Op::Jump;
+
+        fn bytecode_size(&self) -> usize {
+            1 + 8
+        }
+        fn encode<'a>(&self, data: &mut ByteCodeWriter<'a>, context: &ByteCodeGenContext) {
+            data.write_op(Self::OP);
+            data.write_u64(context.resolve_value(&self.address));
         }
     }
 
     #[derive(Debug)]
-    pub struct Return {}
+    pub struct JumpIfZero<Config: VmConfig> {
+        pub condition: Config::RegisterType,
+        pub address: Config::ValueType,
+    }
+    impl<Config: VmConfig> JumpIfZero<Config> {
+        pub fn to_string(&self) -> String {
+            columnize_output3(
+                &format!("JumpIfZero"),
+                &format!("r{}", self.condition),
+                &format!("{}", self.address),
+            )
+        }
+    }
+    impl Instruction for JumpIfZero<ByteCodeVm> {
+        const OP: Op = Op::JumpIfZero;
+
+        fn decode(data: &ByteCodeChunk, pc: &mut usize) -> Self {
+            data.skip_op(pc);
+            Self {
+                condition: data.read_register(pc),
+                address: data.read_u64(pc),
+            }
+        }
+    }
+    impl abstractvm::InstructionTrait for JumpIfZero<AbstractVm> {
+        const OP: Op = Op::JumpIfZero;
+
+        fn bytecode_size(&self) -> usize {
+            1 + 1 + 8
+        }
+        fn encode<'a>(&self, data: &mut ByteCodeWriter<'a>, context: &ByteCodeGenContext) {
+            data.write_op(Self::OP);
+            data.write_register(context.resolve_register(&self.condition));
+            data.write_u64(context.resolve_value(&self.address));
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct LogicalNot<Config: VmConfig> {
+        pub target: Config::RegisterType,
+        pub source: Config::RegisterType,
+    }
+    impl<Config: VmConfig> LogicalNot<Config> {
+        pub fn to_string(&self) -> String {
+            columnize_output3(
+                &format!("LogicalNot"),
+                &format!("r{}", self.target),
+                &format!("r{}", self.source),
+            )
+        }
+    }
+    impl Instruction for LogicalNot<ByteCodeVm> {
+        const OP: Op = Op::LogicalNot;
+
+        fn decode(data: &ByteCodeChunk, pc: &mut usize) -> Self {
+            data.skip_op(pc);
+            Self {
+                target: data.read_register(pc),
+                source: data.read_register(pc),
+            }
+        }
+    }
+    impl abstractvm::InstructionTrait for LogicalNot<AbstractVm> {
+        const OP: Op = Op::LogicalNot;
+
+        fn bytecode_size(&self) -> usize {
+            1 + 1 + 1
+        }
+        fn encode<'a>(&self, data: &mut ByteCodeWriter<'a>, context: &ByteCodeGenContext) {
+            data.write_op(Self::OP);
+            data.write_register(context.resolve_register(&self.target));
+            data.write_register(context.resolve_register(&self.source));
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct Negate<Config: VmConfig> {
+        pub opsize: OpSize,
+        pub target: Config::RegisterType,
+        pub source: Config::RegisterType,
+    }
+    impl<Config: VmConfig> Negate<Config> {
+        pub fn to_string(&self) -> String {
+            columnize_output3(
+                &format!("Negate{}", self.opsize.size_bits()),
+                &format!("r{}", self.target),
+                &format!("r{}", self.source),
+            )
+        }
+    }
+    impl Instruction for Negate<ByteCodeVm> {
+        const OP: Op = Op::Negate;
+
+        fn decode(data: &ByteCodeChunk, pc: &mut usize) -> Self {
+            Self {
+                opsize: data.read_opsize(pc),
+                target: data.read_register(pc),
+                source: data.read_register(pc),
+            }
+        }
+    }
+    impl abstractvm::InstructionTrait for Negate<AbstractVm> {
+        const OP: Op = Op::Negate;
+
+        fn bytecode_size(&self) -> usize {
+            1 + 1 + 1
+        }
+        fn encode<'a>(&self, data: &mut ByteCodeWriter<'a>, context: &ByteCodeGenContext) {
+            data.write_sized_op(Self::OP, self.opsize);
+            data.write_register(context.resolve_register(&self.target));
+            data.write_register(context.resolve_register(&self.source));
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct FloatNegate<Config: VmConfig> {
+        pub opsize: OpSize,
+        pub target: Config::RegisterType,
+        pub source: Config::RegisterType,
+    }
+    impl<Config: VmConfig> FloatNegate<Config> {
+        pub fn to_string(&self) -> String {
+            columnize_output3(
+                &format!("FloatNegate{}", self.opsize.size_bits()),
+                &format!("r{}", self.target),
+                &format!("r{}", self.source),
+            )
+        }
+    }
+    impl Instruction for FloatNegate<ByteCodeVm> {
+        const OP: Op = Op::FloatNegate;
+
+        fn decode(data: &ByteCodeChunk, pc: &mut usize) -> Self {
+            Self {
+                opsize: data.read_opsize(pc),
+                target: data.read_register(pc),
+                source: data.read_register(pc),
+            }
+        }
+    }
+    impl abstractvm::InstructionTrait for FloatNegate<AbstractVm> {
+        const OP: Op = Op::FloatNegate;
+
+        fn bytecode_size(&self) -> usize {
+            1 + 1 + 1
+        }
+        fn encode<'a>(&self, data: &mut ByteCodeWriter<'a>, context: &ByteCodeGenContext) {
+            data.write_sized_op(Self::OP, self.opsize);
+            data.write_register(context.resolve_register(&self.target));
+            data.write_register(context.resolve_register(&self.source));
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct BitwiseNot<Config: VmConfig> {
+        pub opsize: OpSize,
+        pub target: Config::RegisterType,
+        pub source: Config::RegisterType,
+    }
+    impl<Config: VmConfig> BitwiseNot<Config> {
+        pub fn to_string(&self) -> String {
+            columnize_output3(
+                &format!("BitwiseNot{}", self.opsize.size_bits()),
+                &format!("r{}", self.target),
+                &format!("r{}", self.source),
+            )
+        }
+    }
+    impl Instruction for BitwiseNot<ByteCodeVm> {
+        const OP: Op = Op::BitwiseNot;
+
+        fn decode(data: &ByteCodeChunk, pc: &mut usize) -> Self {
+            Self {
+                opsize: data.read_opsize(pc),
+                target: data.read_register(pc),
+                source: data.read_register(pc),
+            }
+        }
+    }
+    impl abstractvm::InstructionTrait for BitwiseNot<AbstractVm> {
+        const OP: Op = Op::BitwiseNot;
+
+        fn bytecode_size(&self) -> usize {
+            1 + 1 + 1
+        }
+        fn encode<'a>(&self, data: &mut ByteCodeWriter<'a>, context: &ByteCodeGenContext) {
+            data.write_sized_op(Self::OP, self.opsize);
+            data.write_register(context.resolve_register(&self.target));
+            data.write_register(context.resolve_register(&self.source));
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct BinaryOp<Config: VmConfig> {
+        pub opsize: OpSize,
+        pub optype: BinaryOpType,
+        pub kind: OperandKind,
+        pub target: Config::RegisterType,
+        pub lhs: Config::RegisterType,
+        pub rhs: Config::RegisterType,
+    }
+    impl<Config: VmConfig> BinaryOp<Config> {
+        pub fn to_string(&self) -> String {
+            columnize_output3(
+                &format!(
+                    "{:?}{:?}{}",
+                    self.optype,
+                    self.kind,
+                    self.opsize.size_bits()
+                ),
+                &format!("r{}", self.target),
+                &format!("r{} r{}", self.lhs, self.rhs),
+            )
+        }
+    }
+    impl Instruction for BinaryOp<ByteCodeVm> {
+        const OP: Op = Op::BinaryOp;
+
+        fn decode(data: &ByteCodeChunk, pc: &mut usize) -> Self {
+            Self {
+                opsize: data.read_opsize(pc),
+                optype: unsafe { std::mem::transmute(data.read_u8(pc)) },
+                kind: unsafe { std::mem::transmute(data.read_u8(pc)) },
+                target: data.read_register(pc),
+                lhs: data.read_register(pc),
+                rhs: data.read_register(pc),
+            }
+        }
+    }
+    impl abstractvm::InstructionTrait for BinaryOp<AbstractVm> {
+        const OP: Op = Op::BinaryOp;
+
+        fn bytecode_size(&self) -> usize {
+            1 + 1 + 1 + 1 + 1 + 1
+        }
+        fn encode<'a>(&self, data: &mut ByteCodeWriter<'a>, context: &ByteCodeGenContext) {
+            data.write_sized_op(Self::OP, self.opsize);
+            data.write_u8(self.optype as u8);
+            data.write_u8(self.kind as u8);
+            data.write_register(context.resolve_register(&self.target));
+            data.write_register(context.resolve_register(&self.lhs));
+            data.write_register(context.resolve_register(&self.rhs));
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct Panic<Config: VmConfig> {
+        pub message: Config::RegisterType,
+    }
+    impl<Config: VmConfig> Panic<Config> {
+        pub fn to_string(&self) -> String {
+            columnize_output2(&format!("Panic"), &format!("*r{}", self.message))
+        }
+    }
+    impl Instruction for Panic<ByteCodeVm> {
+        const OP: Op = Op::Panic;
+
+        fn decode(data: &ByteCodeChunk, pc: &mut usize) -> Self {
+            data.skip_op(pc);
+            Self {
+                message: data.read_register(pc),
+            }
+        }
+    }
+    impl abstractvm::InstructionTrait for Panic<AbstractVm> {
+        const OP: Op = Op::Panic;
+
+        fn bytecode_size(&self) -> usize {
+            1 + 1
+        }
+        fn encode<'a>(&self, data: &mut ByteCodeWriter<'a>, context: &ByteCodeGenContext) {
+            data.write_op(Self::OP);
+            data.write_register(context.resolve_register(&self.message));
+        }
+    }
+
+    // Values are returned in the first registers, which are not restored
+    #[derive(Debug)]
+    pub struct Return {
+        pub values: u8,
+    }
     impl Return {
         const __OP: Op = Op::Return;
         pub fn to_string(&self) -> String {
-            columnize_output1(&format!("Return"))
+            columnize_output2(&format!("Return"), &format!("{}", self.values))
         }
     }
     impl Instruction for Return {
@@ -512,17 +898,20 @@ pub mod instructions {
 
         fn decode(data: &ByteCodeChunk, pc: &mut usize) -> Self {
             data.skip_op(pc);
-            Self {}
+            Self {
+                values: data.read_u8(pc),
+            }
         }
     }
     impl abstractvm::InstructionTrait for Return {
         const OP: Op = Op::Return;
 
         fn bytecode_size(&self) -> usize {
-            1
+            1 + 1
         }
         fn encode<'a>(&self, data: &mut ByteCodeWriter<'a>, context: &ByteCodeGenContext) {
             data.write_op(Self::__OP);
+            data.write_u8(self.values);
         }
     }
 
@@ -4,7 +4,7 @@ use instructions::instructions;
 use dyn_fmt::AsStrFormatExt;
 
 type Config = bytecodevm::Config;
-use bytecodevm::Vm;
+use bytecodevm::{CallFrame, Vm};
 use program::bytecodevm::Program;
 
 pub struct Interpreter<'a> {
@@ -12,6 +12,141 @@ pub struct Interpreter<'a> {
     program: &'a Program,
 }
 
+// Registers hold sized values zero-extended to 64 bits
+fn truncate(value: u64, opsize: OpSize) -> u64 {
+    match opsize {
+        OpSize::Size8 => value as u8 as u64,
+        OpSize::Size16 => value as u16 as u64,
+        OpSize::Size32 => value as u32 as u64,
+        OpSize::Size64 => value,
+    }
+}
+
+fn sign_extend(value: u64, opsize: OpSize) -> i64 {
+    match opsize {
+        OpSize::Size8 => value as i8 as i64,
+        OpSize::Size16 => value as i16 as i64,
+        OpSize::Size32 => value as i32 as i64,
+        OpSize::Size64 => value as i64,
+    }
+}
+
+fn float_from_bits(value: u64, opsize: OpSize) -> f64 {
+    match opsize {
+        OpSize::Size32 => f32::from_bits(value as u32) as f64,
+        OpSize::Size64 => f64::from_bits(value),
+        _ => panic!("No float type of size {}", opsize.size_bits()),
+    }
+}
+
+fn float_to_bits(value: f64, opsize: OpSize) -> u64 {
+    match opsize {
+        OpSize::Size32 => (value as f32).to_bits() as u64,
+        OpSize::Size64 => value.to_bits(),
+        _ => panic!("No float type of size {}", opsize.size_bits()),
+    }
+}
+
+fn perform_binaryop(
+    optype: BinaryOpType,
+    kind: OperandKind,
+    opsize: OpSize,
+    lhs: u64,
+    rhs: u64,
+) -> u64 {
+    use std::cmp::Ordering;
+
+    match optype {
+        BinaryOpType::BitAnd => return lhs & rhs,
+        BinaryOpType::BitOr => return lhs | rhs,
+        BinaryOpType::BitXor => return lhs ^ rhs,
+        BinaryOpType::ShiftLeft | BinaryOpType::ShiftRight => {
+            // The shift amount is unsigned and may be of any size
+            if rhs >= opsize.size_bits() as u64 {
+                panic!(
+                    "Shift amount {} out of range for {} bits",
+                    rhs,
+                    opsize.size_bits()
+                );
+            }
+            let value = match (optype, kind) {
+                (BinaryOpType::ShiftLeft, _) => lhs << rhs,
+                (_, OperandKind::Signed) => (sign_extend(lhs, opsize) >> rhs) as u64,
+                _ => lhs >> rhs,
+            };
+            return truncate(value, opsize);
+        }
+        BinaryOpType::Equals
+        | BinaryOpType::NotEquals
+        | BinaryOpType::LessThan
+        | BinaryOpType::LessThanOrEq
+        | BinaryOpType::GreaterThan
+        | BinaryOpType::GreaterThanOrEq => {
+            let ordering = match kind {
+                OperandKind::Unsigned => lhs.partial_cmp(&rhs),
+                OperandKind::Signed => {
+                    sign_extend(lhs, opsize).partial_cmp(&sign_extend(rhs, opsize))
+                }
+                OperandKind::Float => {
+                    float_from_bits(lhs, opsize).partial_cmp(&float_from_bits(rhs, opsize))
+                }
+            };
+            let result = match optype {
+                BinaryOpType::Equals => ordering == Some(Ordering::Equal),
+                BinaryOpType::NotEquals => ordering != Some(Ordering::Equal),
+                BinaryOpType::LessThan => ordering == Some(Ordering::Less),
+                BinaryOpType::LessThanOrEq => {
+                    matches!(ordering, Some(Ordering::Less | Ordering::Equal))
+                }
+                BinaryOpType::GreaterThan => ordering == Some(Ordering::Greater),
+                _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            };
+            return result as u64;
+        }
+        _ => (),
+    }
+
+    if kind == OperandKind::Float {
+        let (lhs, rhs) = (float_from_bits(lhs, opsize), float_from_bits(rhs, opsize));
+        let value = match optype {
+            BinaryOpType::Add => lhs + rhs,
+            BinaryOpType::Sub => lhs - rhs,
+            BinaryOpType::Mul => lhs * rhs,
+            BinaryOpType::Div => lhs / rhs,
+            BinaryOpType::Mod => lhs % rhs,
+            _ => unreachable!(),
+        };
+        return float_to_bits(value, opsize);
+    }
+
+    if rhs == 0 && (optype == BinaryOpType::Div || optype == BinaryOpType::Mod) {
+        panic!("Integer division by zero");
+    }
+
+    // Integer arithmetic wraps around at the operand size
+    let value = if kind == OperandKind::Signed {
+        let (lhs, rhs) = (sign_extend(lhs, opsize), sign_extend(rhs, opsize));
+        (match optype {
+            BinaryOpType::Add => lhs.wrapping_add(rhs),
+            BinaryOpType::Sub => lhs.wrapping_sub(rhs),
+            BinaryOpType::Mul => lhs.wrapping_mul(rhs),
+            BinaryOpType::Div => lhs.wrapping_div(rhs),
+            BinaryOpType::Mod => lhs.wrapping_rem(rhs),
+            _ => unreachable!(),
+        }) as u64
+    } else {
+        match optype {
+            BinaryOpType::Add => lhs.wrapping_add(rhs),
+            BinaryOpType::Sub => lhs.wrapping_sub(rhs),
+            BinaryOpType::Mul => lhs.wrapping_mul(rhs),
+            BinaryOpType::Div => lhs / rhs,
+            BinaryOpType::Mod => lhs % rhs,
+            _ => unreachable!(),
+        }
+    };
+    truncate(value, opsize)
+}
+
 impl<'a> Interpreter<'a> {
     pub fn new(program: &'a Program) -> Self {
         Self {
@@ -68,12 +203,109 @@ impl<'a> Interpreter<'a> {
         u64::from_be_bytes(bytes)
     }
 
+    // Static strings are stored as [bytelen: u64, data: &[u8]]
+    fn reg_to_staticstring(&self, reg: Register) -> &str {
+        unsafe {
+            let strptr = self.reg_to_memptr(reg);
+            let strlen = self.read_u64(strptr);
+
+            std::str::from_utf8(std::slice::from_raw_parts(
+                strptr.offset(8),
+                strlen as usize,
+            ))
+            .unwrap()
+        }
+    }
+
     fn call_builtin(&mut self, builtin: &crate::typesystem::BuiltInFunction) {
+        use crate::typesystem::BuiltInFunction;
+
         match builtin {
-            crate::typesystem::BuiltInFunction::PrintFormat => self.builtin_printformat(),
+            BuiltInFunction::PrintFormat => self.builtin_printformat(),
+            BuiltInFunction::ArrayNew => self.builtin_array_new(),
+            BuiltInFunction::ArrayLen => self.builtin_array_len(),
+            BuiltInFunction::ArrayPush => self.builtin_array_push(),
+            BuiltInFunction::ArrayPop => self.builtin_array_pop(),
+            BuiltInFunction::ArrayClear => self.builtin_array_clear(),
+            BuiltInFunction::Allocate => self.builtin_allocate(),
         }
     }
 
+    // Dynamic arrays are referred to by the address of a header:
+    //  [data: u64, length: u64, capacity: u64, elementsize: u64]
+    // Array built-ins take the header in the first register and return results in it.
+    //  Elements are accessed directly through the data address by generated code.
+    const ARRAY_REG: Register = 0;
+    const ARRAY_DATA: isize = 0;
+    const ARRAY_LENGTH: isize = 8;
+    const ARRAY_CAPACITY: isize = 16;
+    const ARRAY_ELEMENTSIZE: isize = 24;
+
+    fn array_field(&self, field: isize) -> MutMemPtr {
+        unsafe { self.reg_to_memptr(Self::ARRAY_REG).offset(field) as MutMemPtr }
+    }
+
+    fn builtin_array_new(&mut self) {
+        let elementsize = self.vm.registers[Self::ARRAY_REG as usize];
+        let header = self.vm.allocate(32);
+        self.store_u64(
+            unsafe { header.offset(Self::ARRAY_ELEMENTSIZE) },
+            elementsize,
+        );
+        self.vm.registers[Self::ARRAY_REG as usize] = header as u64;
+    }
+
+    fn builtin_array_len(&mut self) {
+        let length = self.read_u64(self.array_field(Self::ARRAY_LENGTH));
+        self.vm.registers[Self::ARRAY_REG as usize] = length;
+    }
+
+    // Makes room for one more element, returns its index
+    fn builtin_array_push(&mut self) {
+        let length = self.read_u64(self.array_field(Self::ARRAY_LENGTH));
+        let capacity = self.read_u64(self.array_field(Self::ARRAY_CAPACITY));
+
+        if length == capacity {
+            let elementsize = self.read_u64(self.array_field(Self::ARRAY_ELEMENTSIZE));
+            let newcapacity = std::cmp::max(4, capacity * 2);
+            let newdata = self.vm.allocate(newcapacity * elementsize);
+
+            let data = self.read_u64(self.array_field(Self::ARRAY_DATA)) as ConstMemPtr;
+            if length > 0 {
+                unsafe {
+                    std::ptr::copy_nonoverlapping(data, newdata, (length * elementsize) as usize)
+                };
+            }
+
+            self.store_u64(self.array_field(Self::ARRAY_DATA), newdata as u64);
+            self.store_u64(self.array_field(Self::ARRAY_CAPACITY), newcapacity);
+        }
+
+        self.store_u64(self.array_field(Self::ARRAY_LENGTH), length + 1);
+        self.vm.registers[Self::ARRAY_REG as usize] = length;
+    }
+
+    // Removes the last element, returns its index so it can still be read
+    fn builtin_array_pop(&mut self) {
+        let length = self.read_u64(self.array_field(Self::ARRAY_LENGTH));
+        if length == 0 {
+            panic!("Cannot pop from an empty array");
+        }
+
+        self.store_u64(self.array_field(Self::ARRAY_LENGTH), length - 1);
+        self.vm.registers[Self::ARRAY_REG as usize] = length - 1;
+    }
+
+    fn builtin_array_clear(&mut self) {
+        self.store_u64(self.array_field(Self::ARRAY_LENGTH), 0);
+    }
+
+    // Takes the size in the first register, returns the address of the zeroed memory in it
+    fn builtin_allocate(&mut self) {
+        let size = self.vm.registers[0];
+        self.vm.registers[0] = self.vm.allocate(size) as u64;
+    }
+
     fn builtin_printformat(&mut self) {
         use crate::typesystem::PrimitiveType;
 
@@ -83,6 +315,7 @@ impl<'a> Interpreter<'a> {
                     panic!("String typed-value arguments to print_format not yet supported!")
                 }
                 PrimitiveType::Bool => format!("{}", (val != 0) as bool),
+                PrimitiveType::Char => format!("{}", char::from_u32(val as u32).unwrap_or('?')),
                 PrimitiveType::U8 => format!("{}", val as u8),
                 PrimitiveType::U16 => format!("{}", val as u16),
                 PrimitiveType::U32 => format!("{}", val as u32),
@@ -91,16 +324,8 @@ impl<'a> Interpreter<'a> {
                 PrimitiveType::S16 => format!("{}", val as i16),
                 PrimitiveType::S32 => format!("{}", val as i32),
                 PrimitiveType::S64 => format!("{}", val as i64),
-                PrimitiveType::F32 => {
-                    // TODO: This is not correct, because of endianess, among other things
-                    format!("{}", unsafe {
-                        *((&(val as u32) as *const u32) as *const f32)
-                    })
-                }
-                PrimitiveType::F64 => {
-                    // TODO: This is not correct, because of endianess
-                    format!("{}", unsafe { *((val as *const u64) as *const f64) })
-                }
+                PrimitiveType::F32 => format!("{}", f32::from_bits(val as u32)),
+                PrimitiveType::F64 => format!("{}", f64::from_bits(val)),
             }
         }
 
@@ -108,16 +333,7 @@ impl<'a> Interpreter<'a> {
         const DYNARG_COUNT_REG: Register = 1;
         const DYNARG_START_REG: Register = 2;
 
-        let fmtstr: &str = unsafe {
-            let fmtstrptr = self.reg_to_memptr(FMTSTR_REG);
-            let fmtstrlen = self.read_u64(fmtstrptr);
-
-            std::str::from_utf8(std::slice::from_raw_parts(
-                fmtstrptr.offset(8),
-                fmtstrlen as usize,
-            ))
-            .unwrap()
-        };
+        let fmtstr = self.reg_to_staticstring(FMTSTR_REG);
 
         let argcount = self.vm.registers[DYNARG_COUNT_REG as usize] as u64;
 
@@ -168,10 +384,11 @@ impl<'a> Interpreter<'a> {
                     let instr = self.read_instruction::<instructions::LoadAddress<Config>>();
                     let addr: u64 = match instr.mode {
                         LoadAddressMode::StackOffset => {
-                            // TODO: This should be a stack window base
-                            let stack_base: usize =
-                                unsafe { std::mem::transmute(&self.vm.stack[0]) };
-                            stack_base as u64 + instr.value
+                            let offset = self.vm.stack_base + instr.value as usize;
+                            assert!(offset < self.vm.stack.len(), "Stack overflow");
+                            let stack_address: usize =
+                                unsafe { std::mem::transmute(&self.vm.stack[offset]) };
+                            stack_address as u64
                         }
                         LoadAddressMode::ConstantAddress => {
                             let const_base: usize =
@@ -231,13 +448,68 @@ impl<'a> Interpreter<'a> {
                 }
                 Op::Call => {
                     let instr = self.read_instruction::<instructions::Call<Config>>();
-                    self.vm.registers[RETURN_REGISTER as usize] = self.vm.pc as u64;
+                    self.vm.callstack.push(CallFrame {
+                        return_pc: self.vm.pc,
+                        registers: self.vm.registers.clone(),
+                        stack_base: self.vm.stack_base,
+                    });
+                    self.vm.stack_base += instr.stackframe as usize;
                     let new_pc = self.vm.registers[instr.instruction_address_target as usize];
                     self.vm.pc = new_pc as usize;
                 }
+                Op::Jump => {
+                    let instr = self.read_instruction::<instructions::Jump<Config>>();
+                    self.vm.pc = instr.address as usize;
+                }
+                Op::JumpIfZero => {
+                    let instr = self.read_instruction::<instructions::JumpIfZero<Config>>();
+                    if self.vm.registers[instr.condition as usize] == 0 {
+                        self.vm.pc = instr.address as usize;
+                    }
+                }
+                Op::LogicalNot => {
+                    let instr = self.read_instruction::<instructions::LogicalNot<Config>>();
+                    self.vm.registers[instr.target as usize] =
+                        (self.vm.registers[instr.source as usize] == 0) as u64;
+                }
+                Op::Negate => {
+                    let instr = self.read_instruction::<instructions::Negate<Config>>();
+                    let value = self.vm.registers[instr.source as usize].wrapping_neg();
+                    self.vm.registers[instr.target as usize] = truncate(value, instr.opsize);
+                }
+                Op::FloatNegate => {
+                    let instr = self.read_instruction::<instructions::FloatNegate<Config>>();
+                    // Flip the sign bit
+                    let signbit = 1u64 << (instr.opsize.size_bits() - 1);
+                    self.vm.registers[instr.target as usize] =
+                        self.vm.registers[instr.source as usize] ^ signbit;
+                }
+                Op::BitwiseNot => {
+                    let instr = self.read_instruction::<instructions::BitwiseNot<Config>>();
+                    let value = !self.vm.registers[instr.source as usize];
+                    self.vm.registers[instr.target as usize] = truncate(value, instr.opsize);
+                }
+                Op::BinaryOp => {
+                    let instr = self.read_instruction::<instructions::BinaryOp<Config>>();
+                    self.vm.registers[instr.target as usize] = perform_binaryop(
+                        instr.optype,
+                        instr.kind,
+                        instr.opsize,
+                        self.vm.registers[instr.lhs as usize],
+                        self.vm.registers[instr.rhs as usize],
+                    );
+                }
+                Op::Panic => {
+                    let instr = self.read_instruction::<instructions::Panic<Config>>();
+                    panic!("{}", self.reg_to_staticstring(instr.message));
+                }
                 Op::Return => {
-                    self.read_instruction::<instructions::Return>();
-                    self.vm.pc = self.vm.registers[RETURN_REGISTER as usize] as usize;
+                    let instr = self.read_instruction::<instructions::Return>();
+                    let frame = self.vm.callstack.pop().expect("Return without a caller");
+                    let values = instr.values as usize;
+                    self.vm.registers[values..].copy_from_slice(&frame.registers[values..]);
+                    self.vm.stack_base = frame.stack_base;
+                    self.vm.pc = frame.return_pc;
                 }
                 Op::Halt => {
                     self.read_instruction::<instructions::Halt>();
@@ -227,14 +227,81 @@ pub mod abstractvm {
             }));
         }
 
-        pub fn call(&mut self, instruction_address_target: Register) {
+        // The caller is needed to place the stack frame of the called function after its own
+        pub fn call(&mut self, instruction_address_target: Register, caller: FunctionKey) {
             self.push_instr(Instruction::Call(instructions::Call {
                 instruction_address_target,
+                stackframe: vm::abstractvm::Value::StackFrameSize(caller),
             }));
         }
 
-        pub fn do_return(&mut self) {
-            self.push_instr(Instruction::Return(instructions::Return {}));
+        pub fn jump(&mut self, address: vm::abstractvm::Value) {
+            self.push_instr(Instruction::Jump(instructions::Jump { address }));
+        }
+
+        pub fn jump_if_zero(&mut self, condition: Register, address: vm::abstractvm::Value) {
+            self.push_instr(Instruction::JumpIfZero(instructions::JumpIfZero {
+                condition,
+                address,
+            }));
+        }
+
+        pub fn logical_not(&mut self, target: Register, source: Register) {
+            self.push_instr(Instruction::LogicalNot(instructions::LogicalNot {
+                target,
+                source,
+            }));
+        }
+
+        pub fn negate(&mut self, opsize: OpSize, target: Register, source: Register) {
+            self.push_instr(Instruction::Negate(instructions::Negate {
+                opsize,
+                target,
+                source,
+            }));
+        }
+
+        pub fn float_negate(&mut self, opsize: OpSize, target: Register, source: Register) {
+            self.push_instr(Instruction::FloatNegate(instructions::FloatNegate {
+                opsize,
+                target,
+                source,
+            }));
+        }
+
+        pub fn bitwise_not(&mut self, opsize: OpSize, target: Register, source: Register) {
+            self.push_instr(Instruction::BitwiseNot(instructions::BitwiseNot {
+                opsize,
+                target,
+                source,
+            }));
+        }
+
+        pub fn binary_op(
+            &mut self,
+            opsize: OpSize,
+            optype: BinaryOpType,
+            kind: OperandKind,
+            target: Register,
+            lhs: Register,
+            rhs: Register,
+        ) {
+            self.push_instr(Instruction::BinaryOp(instructions::BinaryOp {
+                opsize,
+                optype,
+                kind,
+                target,
+                lhs,
+                rhs,
+            }));
+        }
+
+        pub fn panic(&mut self, message: Register) {
+            self.push_instr(Instruction::Panic(instructions::Panic { message }));
+        }
+
+        pub fn do_return(&mut self, values: u8) {
+            self.push_instr(Instruction::Return(instructions::Return { values }));
         }
 
         pub fn halt(&mut self) {
@@ -260,6 +327,10 @@ pub mod abstractvm {
         pub fn edit_chunk(&mut self, key: ChunkKey) -> ChunkEditor {
             ChunkEditor::new(self.function.chunkstore.get_mut(&key))
         }
+
+        pub fn set_stack_size(&mut self, size: u64) {
+            self.function.stacksize = size;
+        }
     }
 
     pub struct ProgramBuilder {
@@ -308,6 +379,8 @@ pub mod abstractvm {
     pub struct Function {
         pub name: String,
         pub chunkstore: ChunkStore,
+        // Size of the stack frame, known once all chunks are generated
+        pub stacksize: u64,
     }
 
     impl Function {
@@ -315,6 +388,7 @@ pub mod abstractvm {
             Self {
                 name,
                 chunkstore: ChunkStore::new(),
+                stacksize: 0,
             }
         }
     }
@@ -376,7 +450,10 @@ pub mod abstractvm {
             for fkey in program.functionstore.keys() {
                 let function = program.functionstore.get(&fkey);
 
-                println!("    f{} - {}", fkey, function.name);
+                println!(
+                    "    f{} - {}, stack size: {}",
+                    fkey, function.name, function.stacksize
+                );
 
                 for chunkkey in function.chunkstore.keys() {
                     let chunk = function.chunkstore.get(&chunkkey);
@@ -475,6 +552,38 @@ pub mod bytecodevm {
                         "{}",
                         instructions::Call::decode(&bc, &mut index).to_string()
                     ),
+                    Op::Jump => format!(
+                        "{}",
+                        instructions::Jump::decode(&bc, &mut index).to_string()
+                    ),
+                    Op::JumpIfZero => format!(
+                        "{}",
+                        instructions::JumpIfZero::decode(&bc, &mut index).to_string()
+                    ),
+                    Op::LogicalNot => format!(
+                        "{}",
+                        instructions::LogicalNot::decode(&bc, &mut index).to_string()
+                    ),
+                    Op::Negate => format!(
+                        "{}",
+                        instructions::Negate::decode(&bc, &mut index).to_string()
+                    ),
+                    Op::FloatNegate => format!(
+                        "{}",
+                        instructions::FloatNegate::decode(&bc, &mut index).to_string()
+                    ),
+                    Op::BitwiseNot => format!(
+                        "{}",
+                        instructions::BitwiseNot::decode(&bc, &mut index).to_string()
+                    ),
+                    Op::BinaryOp => format!(
+                        "{}",
+                        instructions::BinaryOp::decode(&bc, &mut index).to_string()
+                    ),
+                    Op::Panic => format!(
+                        "{}",
+                        instructions::Panic::decode(&bc, &mut index).to_string()
+                    ),
                     Op::Return => {
                         format!(
                             "{}",
@@ -528,6 +637,7 @@ pub mod bytecodevm {
 pub struct ByteCodeGenFunctionInfo {
     pub address: InstrAddr,
     pub chunk_offset_map: HashMap<ChunkKey, (usize, usize)>,
+    pub stacksize: u64,
 }
 
 pub struct ByteCodeGenContext {
@@ -558,10 +668,21 @@ impl ByteCodeGenContext {
         self.functioninfo_lookup[&funckey].address
     }
 
+    pub fn get_chunk_address(
+        &self,
+        funckey: crate::vm::program::abstractvm::FunctionKey,
+        chunkkey: ChunkKey,
+    ) -> InstrAddr {
+        let functioninfo = &self.functioninfo_lookup[&funckey];
+        functioninfo.address + functioninfo.chunk_offset_map[&chunkkey].0 as InstrAddr
+    }
+
     pub fn resolve_value(&self, value: &vm::abstractvm::Value) -> u64 {
         match value {
             vm::abstractvm::Value::ConstantAddress(n) => self.get_constdata_address(*n),
             vm::abstractvm::Value::FunctionAddress(n) => self.get_function_address(*n),
+            vm::abstractvm::Value::ChunkAddress(f, c) => self.get_chunk_address(*f, *c),
+            vm::abstractvm::Value::StackFrameSize(n) => self.functioninfo_lookup[n].stacksize,
             vm::abstractvm::Value::Static(n) => *n,
         }
     }
@@ -612,6 +733,7 @@ pub fn generate_bytecode(avmprogram: &abstractvm::Program) -> bytecodevm::Progra
             ByteCodeGenFunctionInfo {
                 address: address as InstrAddr,
                 chunk_offset_map,
+                stacksize: function.stacksize,
             },
         );
         total_instr_size += chunk_offset;
@@ -40,9 +40,22 @@ pub mod bytecodevm {
         type StackOffsetType = u64;
     }
 
+    // State of a calling function, restored when the called function returns
+    pub struct CallFrame {
+        pub return_pc: usize,
+        pub registers: Vec<u64>,
+        pub stack_base: usize,
+    }
+
     pub struct Vm {
         pub registers: Vec<u64>,
         pub stack: Vec<u8>,
+        // Stack offsets are relative to the frame of the current function
+        pub stack_base: usize,
+        pub callstack: Vec<CallFrame>,
+        // Allocations never move, so their addresses can be kept in registers
+        // TODO: Nothing is freed until the program ends
+        pub heap: Vec<Vec<u8>>,
         pub pc: usize,
     }
 
@@ -51,9 +64,17 @@ pub mod bytecodevm {
             Self {
                 registers: vec![0; 256],
                 stack: vec![0; 10000], // TODO: make this more sensible
+                stack_base: 0,
+                callstack: Vec::new(),
+                heap: Vec::new(),
                 pc,
             }
         }
+
+        pub fn allocate(&mut self, size: u64) -> MutMemPtr {
+            self.heap.push(vec![0; size as usize]);
+            self.heap.last_mut().unwrap().as_mut_ptr()
+        }
     }
 }
 
@@ -64,6 +85,11 @@ pub mod abstractvm {
     pub enum Value {
         ConstantAddress(crate::vm::program::abstractvm::ConstantKey),
         FunctionAddress(crate::vm::program::abstractvm::FunctionKey),
+        ChunkAddress(
+            crate::vm::program::abstractvm::FunctionKey,
+            crate::vm::program::abstractvm::ChunkKey,
+        ),
+        StackFrameSize(crate::vm::program::abstractvm::FunctionKey),
         Static(u64),
     }
 
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::hash::Hash;
 
 use crate::asg::objectstore::IndexedObjectStore;
@@ -55,6 +55,31 @@ impl CodeGenContext {
         self.functioninfo_lookup[&irfunckey].vmkey
     }
 
+    pub fn register_basicblock(
+        &mut self,
+        irfunckey: ir::FunctionKey,
+        irblockkey: ir::BasicBlockKey,
+        vmchunkkey: crate::vm::program::abstractvm::ChunkKey,
+    ) {
+        self.functioninfo_lookup
+            .get_mut(&irfunckey)
+            .unwrap()
+            .basicblock_lookup
+            .insert(irblockkey, vmchunkkey);
+    }
+
+    pub fn get_vmchunk_address(
+        &self,
+        irfunckey: ir::FunctionKey,
+        irblockkey: ir::BasicBlockKey,
+    ) -> crate::vm::vm::abstractvm::Value {
+        let functioninfo = &self.functioninfo_lookup[&irfunckey];
+        crate::vm::vm::abstractvm::Value::ChunkAddress(
+            functioninfo.vmkey,
+            functioninfo.basicblock_lookup[&irblockkey],
+        )
+    }
+
     pub fn register_function(
         &mut self,
         irfunckey: ir::FunctionKey,
@@ -129,6 +154,8 @@ struct AbstractStorageManager {
     current_variable_storage: HashMap<ir::VariableKey, Storage>,
     register_allocator: AbstractRegisterAllocator,
     current_stack_offset: u64, // TODO: Handle re-using stack "holes"
+    // Variables whose address is taken have to live on the stack
+    addressed_variables: HashSet<ir::VariableKey>,
 }
 
 fn resolve_variablekey(function: &ir::Function, variablekey: ir::VariableKey) -> ir::VariableKey {
@@ -145,6 +172,7 @@ impl AbstractStorageManager {
             current_variable_storage: HashMap::new(),
             register_allocator: AbstractRegisterAllocator::new(),
             current_stack_offset: 0,
+            addressed_variables: HashSet::new(),
         }
     }
 
@@ -183,9 +211,23 @@ impl AbstractStorageManager {
 
         assert!(!self.current_variable_storage.contains_key(&variablekey));
         let variable = irfunction.variablestore.get(&variablekey);
-        let size = variable.get_type(&irfunction.variablestore).size();
+        let typeid = variable.get_type(&irfunction.variablestore);
+        let size = typeid.size();
+
+        // Arrays, enums, optionals, structs and tuples always live on the stack, so elements
+        //  and fields can be addressed
+        let is_array = matches!(
+            typeid,
+            crate::typesystem::TypeId::Array { .. }
+                | crate::typesystem::TypeId::Enum(_)
+                | crate::typesystem::TypeId::Optional(_)
+                | crate::typesystem::TypeId::Struct(_)
+                | crate::typesystem::TypeId::Tuple(_)
+        );
+
+        let is_addressed = self.addressed_variables.contains(&variablekey);
 
-        let storage = if (size <= 8) {
+        let storage = if (size <= 8 && !is_array && !is_addressed) {
             // TODO: Handle out-of-registers
             let register = self.register_allocator.acquire();
             Storage::Register { register, size }
@@ -199,6 +241,324 @@ impl AbstractStorageManager {
         storage
     }
 
+    // Variables assigned in several basic blocks share the same storage
+    pub fn get_or_acquire_variable_storage(
+        &mut self,
+        irfunction: &ir::Function,
+        variablekey: &ir::VariableKey,
+    ) -> Storage {
+        let resolvedkey = resolve_variablekey(irfunction, *variablekey);
+
+        match self.current_variable_storage.get(&resolvedkey) {
+            Some(storage) => storage.clone(),
+            None => self.acquire_variable_storage(irfunction, variablekey),
+        }
+    }
+
+    // Make sure variable value is in a register, temp registers are released by caller
+    pub fn load_variable_to_register<'a>(
+        &mut self,
+        chunkeditor: &mut crate::vm::program::abstractvm::ChunkEditor<'a>,
+        irfunction: &ir::Function,
+        variablekey: &ir::VariableKey,
+    ) -> (AbstractRegister, bool) {
+        match self.get_current_variable_storage(irfunction, variablekey) {
+            Storage::Register { register, size: _ } => (register, false),
+            Storage::Stack { offset, size } => {
+                assert!(size <= 8);
+                let temp = self.acquire_register();
+                chunkeditor.load_stack_address(temp, offset);
+                chunkeditor.load_reg_sized(vm::size_to_opsize(size), temp, temp);
+                (temp, true)
+            }
+        }
+    }
+
+    // Copies memory between the addresses in the registers, in chunks of at most 8 bytes
+    pub fn copy_memory<'a>(
+        &mut self,
+        chunkeditor: &mut crate::vm::program::abstractvm::ChunkEditor<'a>,
+        target: AbstractRegister,
+        source: AbstractRegister,
+        size: u64,
+    ) {
+        let offset = self.acquire_register();
+        let targetaddress = self.acquire_register();
+        let sourceaddress = self.acquire_register();
+        let value = self.acquire_register();
+
+        let mut copied = 0;
+        while copied < size {
+            let chunksize = [8, 4, 2, 1]
+                .iter()
+                .copied()
+                .find(|chunksize| size - copied >= *chunksize)
+                .unwrap();
+            let opsize = vm::size_to_opsize(chunksize);
+
+            chunkeditor.load_u64(offset, copied);
+            chunkeditor.binary_op(
+                vm::OpSize::Size64,
+                vm::BinaryOpType::Add,
+                vm::OperandKind::Unsigned,
+                targetaddress,
+                target,
+                offset,
+            );
+            chunkeditor.binary_op(
+                vm::OpSize::Size64,
+                vm::BinaryOpType::Add,
+                vm::OperandKind::Unsigned,
+                sourceaddress,
+                source,
+                offset,
+            );
+            chunkeditor.load_reg_sized(opsize, value, sourceaddress);
+            chunkeditor.store_reg_sized(opsize, targetaddress, value);
+
+            copied += chunksize;
+        }
+
+        self.release_register(value);
+        self.release_register(sourceaddress);
+        self.release_register(targetaddress);
+        self.release_register(offset);
+    }
+
+    // Loads the address of the first array element into a temp register, released by caller
+    pub fn load_array_data_address<'a>(
+        &mut self,
+        chunkeditor: &mut crate::vm::program::abstractvm::ChunkEditor<'a>,
+        irfunction: &ir::Function,
+        array: &ir::VariableKey,
+    ) -> AbstractRegister {
+        let arraytype = irfunction
+            .variablestore
+            .get(array)
+            .get_type(&irfunction.variablestore);
+
+        let address = self.acquire_register();
+        match (
+            arraytype,
+            self.get_current_variable_storage(irfunction, array),
+        ) {
+            (crate::typesystem::TypeId::Array { .. }, Storage::Stack { offset, size: _ }) => {
+                chunkeditor.load_stack_address(address, offset);
+            }
+            // Dynamic arrays refer to a header, starting with the address of the elements
+            (
+                crate::typesystem::TypeId::DynamicArray { .. },
+                Storage::Register { register, size: _ },
+            ) => {
+                chunkeditor.load_reg64(address, register);
+            }
+            // Slices start with the address of their first element
+            (crate::typesystem::TypeId::Slice { .. }, Storage::Stack { offset, size: _ }) => {
+                chunkeditor.load_stack_address(address, offset);
+                chunkeditor.load_reg64(address, address);
+            }
+            (n, _) => panic!("Unexpected storage for array of type {:?}", n),
+        }
+
+        address
+    }
+
+    // Calculates the address of an array element into a temp register, released by caller
+    pub fn load_element_address<'a>(
+        &mut self,
+        chunkeditor: &mut crate::vm::program::abstractvm::ChunkEditor<'a>,
+        irfunction: &ir::Function,
+        array: &ir::VariableKey,
+        index: &ir::VariableKey,
+    ) -> AbstractRegister {
+        let elementsize = match irfunction
+            .variablestore
+            .get(array)
+            .get_type(&irfunction.variablestore)
+        {
+            crate::typesystem::TypeId::Array { element, length: _ }
+            | crate::typesystem::TypeId::DynamicArray { element }
+            | crate::typesystem::TypeId::Slice { element } => element.size(),
+            n => panic!("Cannot index type {:?}", n),
+        };
+
+        let (index, index_is_temp) = self.load_variable_to_register(chunkeditor, irfunction, index);
+
+        let address = self.load_array_data_address(chunkeditor, irfunction, array);
+        let temp = self.acquire_register();
+        chunkeditor.load_u64(temp, elementsize);
+        chunkeditor.binary_op(
+            vm::OpSize::Size64,
+            vm::BinaryOpType::Mul,
+            vm::OperandKind::Unsigned,
+            temp,
+            index,
+            temp,
+        );
+        chunkeditor.binary_op(
+            vm::OpSize::Size64,
+            vm::BinaryOpType::Add,
+            vm::OperandKind::Unsigned,
+            address,
+            address,
+            temp,
+        );
+
+        self.release_register(temp);
+        if index_is_temp {
+            self.release_register(index);
+        }
+
+        address
+    }
+
+    // Address of the field at the offset into a variable on the stack, into a temp register
+    //  released by caller
+    pub fn load_field_address<'a>(
+        &mut self,
+        chunkeditor: &mut crate::vm::program::abstractvm::ChunkEditor<'a>,
+        irfunction: &ir::Function,
+        target: &ir::VariableKey,
+        offset: u64,
+    ) -> AbstractRegister {
+        let base = self.acquire_register();
+        match self.get_current_variable_storage(irfunction, target) {
+            Storage::Stack {
+                offset: stackoffset,
+                size: _,
+            } => chunkeditor.load_stack_address(base, stackoffset),
+            Storage::Register { .. } => panic!("Fields can only be addressed on the stack!"),
+        }
+
+        let address = self.load_offset_address(chunkeditor, base, offset);
+        self.release_register(base);
+        address
+    }
+
+    // Calculates the address at an offset from the address in a register, into a temp
+    //  register released by caller
+    pub fn load_offset_address<'a>(
+        &mut self,
+        chunkeditor: &mut crate::vm::program::abstractvm::ChunkEditor<'a>,
+        base: AbstractRegister,
+        offset: u64,
+    ) -> AbstractRegister {
+        let address = self.acquire_register();
+        chunkeditor.load_u64(address, offset);
+        chunkeditor.binary_op(
+            vm::OpSize::Size64,
+            vm::BinaryOpType::Add,
+            vm::OperandKind::Unsigned,
+            address,
+            base,
+            address,
+        );
+        address
+    }
+
+    // Loads the value at the address in the register into a variable
+    pub fn load_variable_from_address<'a>(
+        &mut self,
+        chunkeditor: &mut crate::vm::program::abstractvm::ChunkEditor<'a>,
+        irfunction: &ir::Function,
+        variable: &ir::VariableKey,
+        address: AbstractRegister,
+    ) {
+        match self.get_or_acquire_variable_storage(irfunction, variable) {
+            Storage::Register { register, size } => {
+                chunkeditor.load_reg_sized(vm::size_to_opsize(size), register, address);
+            }
+            Storage::Stack { offset, size } => {
+                let target = self.acquire_register();
+                chunkeditor.load_stack_address(target, offset);
+                self.copy_memory(chunkeditor, target, address, size);
+                self.release_register(target);
+            }
+        }
+    }
+
+    // Stores the value of a variable at the address in the register
+    pub fn store_variable_at_address<'a>(
+        &mut self,
+        chunkeditor: &mut crate::vm::program::abstractvm::ChunkEditor<'a>,
+        irfunction: &ir::Function,
+        variable: &ir::VariableKey,
+        address: AbstractRegister,
+    ) {
+        match self.get_current_variable_storage(irfunction, variable) {
+            Storage::Register { register, size } => {
+                chunkeditor.store_reg_sized(vm::size_to_opsize(size), address, register);
+            }
+            Storage::Stack { offset, size } => {
+                let source = self.acquire_register();
+                chunkeditor.load_stack_address(source, offset);
+                self.copy_memory(chunkeditor, address, source, size);
+                self.release_register(source);
+            }
+        }
+    }
+
+    // Arguments are passed like call params, values bigger than a register by address
+    pub fn receive_params<'a>(
+        &mut self,
+        chunkeditor: &mut crate::vm::program::abstractvm::ChunkEditor<'a>,
+        irfunction: &ir::Function,
+    ) {
+        assert!(irfunction.params.len() < 255);
+        for (paramindex, variable) in irfunction.params.iter().enumerate() {
+            let paramreg = paramindex as AbstractRegister;
+            match self.acquire_variable_storage(irfunction, variable) {
+                Storage::Register { register, size: _ } => {
+                    chunkeditor.move_reg(register, paramreg);
+                }
+                Storage::Stack { offset, size } if is_register_sized(size) => {
+                    let target = self.acquire_register();
+                    chunkeditor.load_stack_address(target, offset);
+                    chunkeditor.store_reg_sized(vm::size_to_opsize(size), target, paramreg);
+                    self.release_register(target);
+                }
+                Storage::Stack { offset, size } => {
+                    let target = self.acquire_register();
+                    chunkeditor.load_stack_address(target, offset);
+                    self.copy_memory(chunkeditor, target, paramreg, size);
+                    self.release_register(target);
+                }
+            }
+        }
+    }
+
+    // Returned values are passed in the call param registers, in order
+    pub fn store_returned_values<'a>(
+        &mut self,
+        chunkeditor: &mut crate::vm::program::abstractvm::ChunkEditor<'a>,
+        irfunction: &ir::Function,
+        variables: &[ir::VariableKey],
+    ) {
+        assert!(variables.len() < 255);
+        for (returnindex, var) in variables.iter().enumerate() {
+            let returnreg = returnindex as AbstractRegister;
+            match self.get_or_acquire_variable_storage(irfunction, var) {
+                Storage::Register { register, size: _ } => {
+                    chunkeditor.move_reg(register, returnreg)
+                }
+                Storage::Stack { offset, size } if is_register_sized(size) => {
+                    let temp = self.acquire_register();
+                    chunkeditor.load_stack_address(temp, offset);
+                    chunkeditor.store_reg_sized(vm::size_to_opsize(size), temp, returnreg);
+                    self.release_register(temp);
+                }
+                // Other values are returned by the address of a dead stack slot of the called
+                //  function, copied before the stack is used again
+                Storage::Stack { offset, size } => {
+                    let temp = self.acquire_register();
+                    chunkeditor.load_stack_address(temp, offset);
+                    self.copy_memory(chunkeditor, temp, returnreg, size);
+                    self.release_register(temp);
+                }
+            }
+        }
+    }
+
     pub fn move_param_register_if_needed<'a>(
         &mut self,
         chunkeditor: &mut crate::vm::program::abstractvm::ChunkEditor<'a>,
@@ -232,7 +592,6 @@ impl AbstractStorageManager {
         }
 
         chunkeditor.move_reg(target_register, source_register);
-        self.register_allocator.release(source_register);
 
         target_register
     }
@@ -240,30 +599,38 @@ impl AbstractStorageManager {
     pub fn set_up_variable_as_call_param<'a>(
         &mut self,
         chunkeditor: &mut crate::vm::program::abstractvm::ChunkEditor<'a>,
+        irfunction: &ir::Function,
         variable: &ir::VariableKey,
         paramindex: usize,
     ) {
-        let storage = &self.current_variable_storage[variable];
+        // Variable registers are only copied, they still hold the variable after the call
+        let storage = self.get_current_variable_storage(irfunction, variable);
         match storage {
             Storage::Register { register, size: _ } => {
-                self.move_param_register_if_needed(chunkeditor, paramindex, *register);
+                self.move_param_register_if_needed(chunkeditor, paramindex, register);
             }
             Storage::Stack { offset, size } => {
                 let temp = self.register_allocator.acquire();
-                chunkeditor.load_stack_address(temp, *offset);
+                chunkeditor.load_stack_address(temp, offset);
                 // TODO: This is ABI stuff, how to pass parameters bigger than a register
                 //  This should be handled more formally.
-                if *size <= 8 {
+                if is_register_sized(size) {
                     // If value is a register or less, send actual value instead of address
-                    chunkeditor.load_reg_sized(vm::size_to_opsize(*size), temp, temp);
+                    chunkeditor.load_reg_sized(vm::size_to_opsize(size), temp, temp);
                 }
 
                 self.move_param_register_if_needed(chunkeditor, paramindex, temp);
+                self.register_allocator.release(temp);
             }
         }
     }
 }
 
+// Values that can be loaded into a register as a whole, others are passed by address
+fn is_register_sized(size: u64) -> bool {
+    matches!(size, 1 | 2 | 4 | 8)
+}
+
 #[derive(Clone)]
 enum Storage {
     Register {
@@ -278,21 +645,51 @@ enum Storage {
 fn populate_function(
     context: &mut CodeGenContext,
     functioneditor: &mut crate::vm::program::abstractvm::FunctionEditor,
+    irfunctionkey: ir::FunctionKey,
     irfunction: &ir::Function,
 ) {
     let mut storagemanager = AbstractStorageManager::new();
+    let vmfunctionkey = context.get_vmfunction(irfunctionkey);
 
-    for blockkey in irfunction.basicblockstore.keys() {
-        let block = irfunction.basicblockstore.get(&blockkey);
+    for block in irfunction.basicblockstore.values() {
+        for instr in &block.instructions {
+            if let ir::Instruction::AddressOf(n) = instr {
+                let target = resolve_variablekey(irfunction, n.target);
+                storagemanager.addressed_variables.insert(target);
+            }
+        }
+    }
 
+    // Function address is the start of the first chunk, so the entry block goes first
+    let mut blockkeys = vec![irfunction.entry];
+    blockkeys.extend(
+        irfunction
+            .basicblockstore
+            .keys()
+            .filter(|key| *key != irfunction.entry),
+    );
+
+    // Create all chunks up front, so jumps can refer to blocks not yet generated
+    for blockkey in &blockkeys {
         let chunkkey = functioneditor.create_chunk();
+        context.register_basicblock(irfunctionkey, *blockkey, chunkkey);
+    }
+
+    for blockkey in &blockkeys {
+        let block = irfunction.basicblockstore.get(blockkey);
+
+        let chunkkey = context.functioninfo_lookup[&irfunctionkey].basicblock_lookup[blockkey];
         let mut chunkeditor = functioneditor.edit_chunk(chunkkey);
 
+        if *blockkey == irfunction.entry {
+            storagemanager.receive_params(&mut chunkeditor, irfunction);
+        }
+
         for instr in &block.instructions {
             match instr {
                 ir::Instruction::Assign(n) => {
                     let targetstorage =
-                        storagemanager.acquire_variable_storage(irfunction, &n.variable);
+                        storagemanager.get_or_acquire_variable_storage(irfunction, &n.variable);
 
                     // TODO: Exhaust expression to non-compound
 
@@ -372,18 +769,20 @@ fn populate_function(
                                         offset: source_offset,
                                         size,
                                     } => {
-                                        assert!(size <= 8);
                                         let sourcereg = storagemanager.acquire_register();
-
                                         let targetreg = storagemanager.acquire_register();
 
                                         chunkeditor.load_stack_address(sourcereg, source_offset);
-                                        chunkeditor.load_stack_address(targetreg, source_offset);
-                                        chunkeditor.store_reg_sized(
-                                            vm::size_to_opsize(size),
+                                        chunkeditor.load_stack_address(targetreg, offset);
+                                        storagemanager.copy_memory(
+                                            &mut chunkeditor,
                                             targetreg,
                                             sourcereg,
+                                            size,
                                         );
+
+                                        storagemanager.release_register(targetreg);
+                                        storagemanager.release_register(sourcereg);
                                     }
                                 }
                             }
@@ -434,6 +833,7 @@ fn populate_function(
                     for var in &n.args {
                         storagemanager.set_up_variable_as_call_param(
                             &mut chunkeditor,
+                            irfunction,
                             var,
                             paramindex,
                         );
@@ -446,7 +846,23 @@ fn populate_function(
                     // Will parse call param registers internally
                     chunkeditor.call_builtin(n.builtin);
 
-                    // TODO: Deal with return values
+                    // Built-ins return values in the first param register
+                    let returntype = irfunction
+                        .variablestore
+                        .get(&n.variable)
+                        .get_type(&irfunction.variablestore);
+                    if *returntype != crate::typesystem::TypeId::Null {
+                        match storagemanager
+                            .get_or_acquire_variable_storage(irfunction, &n.variable)
+                        {
+                            Storage::Register { register, size: _ } => {
+                                chunkeditor.move_reg(register, 0)
+                            }
+                            Storage::Stack { .. } => {
+                                panic!("Built-in return values must be stored in registers!")
+                            }
+                        }
+                    }
                 }
                 ir::Instruction::CallStatic(n) => {
                     let mut paramindex = 0;
@@ -455,6 +871,7 @@ fn populate_function(
                     for var in &n.args {
                         storagemanager.set_up_variable_as_call_param(
                             &mut chunkeditor,
+                            irfunction,
                             var,
                             paramindex,
                         );
@@ -471,14 +888,478 @@ fn populate_function(
                     chunkeditor.load_function_address(callreg, vmfunctionkey);
 
                     // Will parse call param registers internally
-                    chunkeditor.call(callreg);
+                    chunkeditor.call(callreg, vmfunctionkey);
+
+                    storagemanager.store_returned_values(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.variables,
+                    );
+                }
+                ir::Instruction::CallClosure(n) => {
+                    // The closure is passed first, so the called function can load its captures
+                    assert!(n.args.len() < 254);
+                    storagemanager.set_up_variable_as_call_param(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.closure,
+                        0,
+                    );
+                    for (argindex, var) in n.args.iter().enumerate() {
+                        storagemanager.set_up_variable_as_call_param(
+                            &mut chunkeditor,
+                            irfunction,
+                            var,
+                            argindex + 1,
+                        );
+                    }
+
+                    // Closures start with the address of their function
+                    let callreg = storagemanager.acquire_register();
+                    chunkeditor.load_reg64(callreg, 0);
+                    chunkeditor.call(callreg, vmfunctionkey);
+                    storagemanager.release_register(callreg);
+
+                    storagemanager.store_returned_values(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.variables,
+                    );
+                }
+                ir::Instruction::MakeClosure(n) => {
+                    // Closures are the address of the function followed by the captured values
+                    let sizes: Vec<u64> = n
+                        .captures
+                        .iter()
+                        .map(|v| {
+                            irfunction
+                                .variablestore
+                                .get(v)
+                                .get_type(&irfunction.variablestore)
+                                .size()
+                        })
+                        .collect();
+
+                    let size = storagemanager.acquire_register();
+                    chunkeditor.load_u64(size, 8 + sizes.iter().sum::<u64>());
+                    storagemanager.move_param_register_if_needed(&mut chunkeditor, 0, size);
+                    storagemanager.release_register(size);
+                    chunkeditor.call_builtin(crate::typesystem::BuiltInFunction::Allocate);
+
+                    let closure = match storagemanager
+                        .get_or_acquire_variable_storage(irfunction, &n.variable)
+                    {
+                        Storage::Register { register, size: _ } => register,
+                        Storage::Stack { .. } => {
+                            panic!("Closures must be stored in registers!")
+                        }
+                    };
+                    chunkeditor.move_reg(closure, 0);
+
+                    let function = storagemanager.acquire_register();
+                    chunkeditor.load_function_address(function, context.get_vmfunction(n.function));
+                    chunkeditor.store_reg64(closure, function);
+                    storagemanager.release_register(function);
+
+                    let mut offset = 8;
+                    for (capture, size) in n.captures.iter().zip(sizes) {
+                        let address =
+                            storagemanager.load_offset_address(&mut chunkeditor, closure, offset);
+                        storagemanager.store_variable_at_address(
+                            &mut chunkeditor,
+                            irfunction,
+                            capture,
+                            address,
+                        );
+                        storagemanager.release_register(address);
+                        offset += size;
+                    }
+                }
+                ir::Instruction::LoadCapture(n) => {
+                    let offset = 8 + irfunction.captures[..n.index]
+                        .iter()
+                        .map(|typeid| typeid.size())
+                        .sum::<u64>();
+
+                    let (closure, is_temp) = storagemanager.load_variable_to_register(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.closure,
+                    );
+                    let address =
+                        storagemanager.load_offset_address(&mut chunkeditor, closure, offset);
+                    storagemanager.load_variable_from_address(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.variable,
+                        address,
+                    );
+
+                    storagemanager.release_register(address);
+                    if is_temp {
+                        storagemanager.release_register(closure);
+                    }
+                }
+                ir::Instruction::AddressOf(n) => {
+                    let offset = match storagemanager
+                        .get_or_acquire_variable_storage(irfunction, &n.target)
+                    {
+                        Storage::Stack { offset, size: _ } => offset,
+                        Storage::Register { .. } => {
+                            panic!("Addressed variables must be stored on the stack!")
+                        }
+                    };
+                    match storagemanager.get_or_acquire_variable_storage(irfunction, &n.variable) {
+                        Storage::Register { register, size: _ } => {
+                            chunkeditor.load_stack_address(register, offset)
+                        }
+                        Storage::Stack { .. } => panic!("Addresses must be stored in registers!"),
+                    }
+                }
+                ir::Instruction::Load(n) => {
+                    let (address, is_temp) = storagemanager.load_variable_to_register(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.address,
+                    );
+                    storagemanager.load_variable_from_address(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.variable,
+                        address,
+                    );
+                    if is_temp {
+                        storagemanager.release_register(address);
+                    }
+                }
+                ir::Instruction::Store(n) => {
+                    let (address, is_temp) = storagemanager.load_variable_to_register(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.address,
+                    );
+                    storagemanager.store_variable_at_address(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.value,
+                        address,
+                    );
+                    if is_temp {
+                        storagemanager.release_register(address);
+                    }
+                }
+                ir::Instruction::UnaryOp(n) => {
+                    let (source, is_temp) = storagemanager.load_variable_to_register(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.operand,
+                    );
+
+                    let targetstorage =
+                        storagemanager.get_or_acquire_variable_storage(irfunction, &n.variable);
+
+                    let target = match targetstorage {
+                        Storage::Register { register, size: _ } => register,
+                        Storage::Stack { offset: _, size: _ } => {
+                            panic!("Unary operation result must be stored in register!")
+                        }
+                    };
+
+                    let ptype = match irfunction
+                        .variablestore
+                        .get(&n.variable)
+                        .get_type(&irfunction.variablestore)
+                    {
+                        crate::typesystem::TypeId::Primitive(n) => n,
+                        n => panic!("Unary operations not supported for type {:?}", n),
+                    };
+                    let opsize = vm::size_to_opsize(ptype.size());
+
+                    match n.op {
+                        crate::shared::UnaryOperationType::Not => {
+                            chunkeditor.logical_not(target, source)
+                        }
+                        crate::shared::UnaryOperationType::Neg => match ptype {
+                            crate::typesystem::PrimitiveType::F32
+                            | crate::typesystem::PrimitiveType::F64 => {
+                                chunkeditor.float_negate(opsize, target, source)
+                            }
+                            _ => chunkeditor.negate(opsize, target, source),
+                        },
+                        crate::shared::UnaryOperationType::BitNot => {
+                            chunkeditor.bitwise_not(opsize, target, source)
+                        }
+                    }
 
-                    // TODO: Deal with return values
+                    if is_temp {
+                        storagemanager.release_register(source);
+                    }
+                }
+                ir::Instruction::BinOp(n) => {
+                    let (lhs, lhs_is_temp) = storagemanager.load_variable_to_register(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.lhs,
+                    );
+                    let (rhs, rhs_is_temp) = storagemanager.load_variable_to_register(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.rhs,
+                    );
+
+                    let targetstorage =
+                        storagemanager.get_or_acquire_variable_storage(irfunction, &n.variable);
+
+                    let target = match targetstorage {
+                        Storage::Register { register, size: _ } => register,
+                        Storage::Stack { offset: _, size: _ } => {
+                            panic!("Binary operation result must be stored in register!")
+                        }
+                    };
+
+                    // Operand type decides the operation, i.e. comparisons result in bools
+                    let ptype = match irfunction
+                        .variablestore
+                        .get(&n.lhs)
+                        .get_type(&irfunction.variablestore)
+                    {
+                        crate::typesystem::TypeId::Primitive(n) => n,
+                        n => panic!("Binary operations not supported for type {:?}", n),
+                    };
+                    let opsize = vm::size_to_opsize(ptype.size());
+
+                    let kind = {
+                        use crate::typesystem::PrimitiveType::*;
+                        match ptype {
+                            S8 | S16 | S32 | S64 => vm::OperandKind::Signed,
+                            F32 | F64 => vm::OperandKind::Float,
+                            _ => vm::OperandKind::Unsigned,
+                        }
+                    };
+
+                    let optype = {
+                        use crate::shared::BinaryOperationType;
+                        match n.op {
+                            BinaryOperationType::Add => vm::BinaryOpType::Add,
+                            BinaryOperationType::Sub => vm::BinaryOpType::Sub,
+                            BinaryOperationType::Mul => vm::BinaryOpType::Mul,
+                            BinaryOperationType::Div => vm::BinaryOpType::Div,
+                            BinaryOperationType::Mod => vm::BinaryOpType::Mod,
+                            BinaryOperationType::BitAnd => vm::BinaryOpType::BitAnd,
+                            BinaryOperationType::BitOr => vm::BinaryOpType::BitOr,
+                            BinaryOperationType::BitXor => vm::BinaryOpType::BitXor,
+                            BinaryOperationType::ShiftLeft => vm::BinaryOpType::ShiftLeft,
+                            BinaryOperationType::ShiftRight => vm::BinaryOpType::ShiftRight,
+                            BinaryOperationType::Equals => vm::BinaryOpType::Equals,
+                            BinaryOperationType::NotEquals => vm::BinaryOpType::NotEquals,
+                            BinaryOperationType::LessThan => vm::BinaryOpType::LessThan,
+                            BinaryOperationType::LessThanOrEq => vm::BinaryOpType::LessThanOrEq,
+                            BinaryOperationType::GreaterThan => vm::BinaryOpType::GreaterThan,
+                            BinaryOperationType::GreaterThanOrEq => {
+                                vm::BinaryOpType::GreaterThanOrEq
+                            }
+                            BinaryOperationType::And | BinaryOperationType::Or => {
+                                panic!("Logical operations are lowered to branches")
+                            }
+                        }
+                    };
+
+                    chunkeditor.binary_op(opsize, optype, kind, target, lhs, rhs);
+
+                    if lhs_is_temp {
+                        storagemanager.release_register(lhs);
+                    }
+                    if rhs_is_temp {
+                        storagemanager.release_register(rhs);
+                    }
+                }
+                ir::Instruction::LoadElement(n) => {
+                    let address = storagemanager.load_element_address(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.array,
+                        &n.index,
+                    );
+
+                    storagemanager.load_variable_from_address(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.variable,
+                        address,
+                    );
+
+                    storagemanager.release_register(address);
+                }
+                ir::Instruction::StoreElement(n) => {
+                    // The array may be built up element by element
+                    storagemanager.get_or_acquire_variable_storage(irfunction, &n.array);
+
+                    let address = storagemanager.load_element_address(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.array,
+                        &n.index,
+                    );
+
+                    storagemanager.store_variable_at_address(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.value,
+                        address,
+                    );
+
+                    storagemanager.release_register(address);
+                }
+                ir::Instruction::LoadField(n) => {
+                    let address = storagemanager.load_field_address(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.target,
+                        n.offset,
+                    );
+
+                    storagemanager.load_variable_from_address(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.variable,
+                        address,
+                    );
+
+                    storagemanager.release_register(address);
+                }
+                ir::Instruction::StoreField(n) => {
+                    // The target is built up field by field
+                    storagemanager.get_or_acquire_variable_storage(irfunction, &n.target);
+
+                    let address = storagemanager.load_field_address(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.target,
+                        n.offset,
+                    );
+
+                    storagemanager.store_variable_at_address(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.value,
+                        address,
+                    );
+
+                    storagemanager.release_register(address);
+                }
+                ir::Instruction::Slice(n) => {
+                    // Slices are stored as the address of the first element and the length
+                    let offset = match storagemanager
+                        .get_or_acquire_variable_storage(irfunction, &n.variable)
+                    {
+                        Storage::Stack { offset, size: _ } => offset,
+                        Storage::Register { .. } => panic!("Slices must be stored on the stack!"),
+                    };
+
+                    let address = storagemanager.load_element_address(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.array,
+                        &n.start,
+                    );
+                    let (start, start_is_temp) = storagemanager.load_variable_to_register(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.start,
+                    );
+                    let (end, end_is_temp) = storagemanager.load_variable_to_register(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.end,
+                    );
+
+                    let target = storagemanager.acquire_register();
+                    chunkeditor.load_stack_address(target, offset);
+                    chunkeditor.store_reg64(target, address);
+
+                    chunkeditor.binary_op(
+                        vm::OpSize::Size64,
+                        vm::BinaryOpType::Sub,
+                        vm::OperandKind::Unsigned,
+                        address,
+                        end,
+                        start,
+                    );
+                    chunkeditor.load_stack_address(target, offset + 8);
+                    chunkeditor.store_reg64(target, address);
+
+                    storagemanager.release_register(target);
+                    if end_is_temp {
+                        storagemanager.release_register(end);
+                    }
+                    if start_is_temp {
+                        storagemanager.release_register(start);
+                    }
+                    storagemanager.release_register(address);
+                }
+                ir::Instruction::SliceLength(n) => {
+                    let offset = match storagemanager
+                        .get_current_variable_storage(irfunction, &n.slice)
+                    {
+                        Storage::Stack { offset, size: _ } => offset,
+                        Storage::Register { .. } => panic!("Slices must be stored on the stack!"),
+                    };
+
+                    match storagemanager.get_or_acquire_variable_storage(irfunction, &n.variable) {
+                        Storage::Register { register, size: _ } => {
+                            chunkeditor.load_stack_address(register, offset + 8);
+                            chunkeditor.load_reg64(register, register);
+                        }
+                        Storage::Stack { .. } => {
+                            panic!("Slice length must be stored in register!")
+                        }
+                    }
+                }
+                ir::Instruction::Jump(n) => {
+                    chunkeditor.jump(context.get_vmchunk_address(irfunctionkey, n.target));
+                }
+                ir::Instruction::Branch(n) => {
+                    let (condition, is_temp) = storagemanager.load_variable_to_register(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.condition,
+                    );
+
+                    chunkeditor.jump_if_zero(
+                        condition,
+                        context.get_vmchunk_address(irfunctionkey, n.on_false),
+                    );
+                    chunkeditor.jump(context.get_vmchunk_address(irfunctionkey, n.on_true));
+
+                    if is_temp {
+                        storagemanager.release_register(condition);
+                    }
                 }
                 ir::Instruction::Return(n) => {
-                    // TODO
-                    assert!(n.values.len() == 0);
-                    chunkeditor.do_return();
+                    // Values are returned in the call param registers, like arguments are passed
+                    assert!(n.values.len() < 255);
+                    for (returnindex, var) in n.values.iter().enumerate() {
+                        storagemanager.set_up_variable_as_call_param(
+                            &mut chunkeditor,
+                            irfunction,
+                            var,
+                            returnindex,
+                        );
+                    }
+                    chunkeditor.do_return(n.values.len() as u8);
+                }
+                ir::Instruction::Panic(n) => {
+                    let (message, is_temp) = storagemanager.load_variable_to_register(
+                        &mut chunkeditor,
+                        irfunction,
+                        &n.message,
+                    );
+
+                    chunkeditor.panic(message);
+
+                    if is_temp {
+                        storagemanager.release_register(message);
+                    }
                 }
                 ir::Instruction::Halt => {
                     chunkeditor.halt();
@@ -487,6 +1368,8 @@ fn populate_function(
             }
         }
     }
+
+    functioneditor.set_stack_size(storagemanager.current_stack_offset);
 }
 
 pub fn generate_program(irprogram: &ir::Program) -> crate::vm::program::abstractvm::Program {
@@ -521,7 +1404,12 @@ pub fn generate_program(irprogram: &ir::Program) -> crate::vm::program::abstract
 
         let mut functioneeditor = programbuilder.edit_function(vmfunctionkey);
 
-        populate_function(&mut context, &mut functioneeditor, irfunction);
+        populate_function(
+            &mut context,
+            &mut functioneeditor,
+            irfunctionkey,
+            irfunction,
+        );
     }
 
     programbuilder.finish(context.get_vmfunction(irprogram.init))